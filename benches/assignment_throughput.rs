@@ -0,0 +1,130 @@
+// Jobs per second the assigner matches with claims when a burst of jobs arrives at
+// once, with simulated workers that claim every job and acknowledge their assignments.
+//
+//     cargo bench --bench assignment_throughput
+//
+// BENCH_JOBS and BENCH_WORKERS override the burst size (2000) and fleet size (8).
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use corebrum_examples::assigner::Assigner;
+use corebrum_examples::events::{EventBus, LifecycleEvent};
+use corebrum_examples::keyspace::Keyspace;
+use corebrum_examples::schema::{Assign, AssignAck, Claim, Job};
+use corebrum_examples::zenoh_utils::{deserialize_from_sample_with_context, publish_json};
+
+const CLAIM_WINDOW: Duration = Duration::from_millis(20);
+const SETTLE: Duration = Duration::from_secs(1);
+const BENCH_TIMEOUT: Duration = Duration::from_secs(300);
+
+fn env_count(name: &str, default: usize) -> usize {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+async fn open_session() -> Result<zenoh::Session> {
+    zenoh::open(zenoh::Config::default()).await.map_err(|e| anyhow::anyhow!("Failed to open Zenoh session: {}", e))
+}
+
+// Claims every announced job and acknowledges the ones assigned to it
+async fn simulated_worker(keyspace: Keyspace, worker_id: String, shutdown: CancellationToken) -> Result<()> {
+    let session = open_session().await?;
+    let announcements = session.declare_subscriber(keyspace.announce()).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    let assignments = session.declare_subscriber(keyspace.all_assigns()).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => break,
+            sample = announcements.recv_async() => {
+                let Ok(sample) = sample else { break };
+                let Ok(job) = deserialize_from_sample_with_context::<Job>(&sample, "job") else { continue };
+                let claim = Claim {
+                    task_id: job.task_id.clone(),
+                    worker_id: worker_id.clone(),
+                    claimed_at: chrono::Utc::now(),
+                    estimated_duration_seconds: Some(1),
+                    running_tasks: 0,
+                    queue_depth: 0,
+                    fleet: None,
+                    cpu_score: None,
+                };
+                publish_json(&session, &keyspace.claim(&job.task_id), &claim).await?;
+            }
+            sample = assignments.recv_async() => {
+                let Ok(sample) = sample else { break };
+                let Ok(assign) = deserialize_from_sample_with_context::<Assign>(&sample, "assign") else { continue };
+                if assign.worker_id == worker_id {
+                    let ack = AssignAck {
+                        task_id: assign.task_id.clone(),
+                        worker_id: worker_id.clone(),
+                        acked_at: chrono::Utc::now(),
+                    };
+                    publish_json(&session, &keyspace.ack(&assign.task_id), &ack).await?;
+                }
+            }
+        }
+    }
+    session.close().await.map_err(|e| anyhow::anyhow!("{}", e))?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let jobs = env_count("BENCH_JOBS", 2000);
+    let workers = env_count("BENCH_WORKERS", 8);
+    let queue = format!("bench-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let keyspace = Keyspace::for_queue(&queue);
+    let shutdown = CancellationToken::new();
+    let events = EventBus::with_capacity(jobs * 4);
+    let mut assigned = events.subscribe();
+
+    let assigner = Assigner::new(keyspace.clone()).with_claim_window(CLAIM_WINDOW).with_events(events);
+    let assigner_session = open_session().await?;
+    let assigner_shutdown = shutdown.clone();
+    let assigner_task = tokio::spawn(async move { assigner.run(&assigner_session, assigner_shutdown).await });
+    let worker_tasks: Vec<_> = (0..workers)
+        .map(|index| tokio::spawn(simulated_worker(keyspace.clone(), format!("bench-worker-{}", index), shutdown.clone())))
+        .collect();
+    tokio::time::sleep(SETTLE).await;
+
+    let submitter = open_session().await?;
+    let started = Instant::now();
+    for index in 0..jobs {
+        let job = Job::new(queue.clone(), serde_json::json!({ "index": index }));
+        publish_json(&submitter, &keyspace.announce(), &job).await?;
+    }
+    let submitted = started.elapsed();
+
+    let mut count = 0;
+    let outcome = tokio::time::timeout(BENCH_TIMEOUT, async {
+        while count < jobs {
+            match assigned.recv().await {
+                Ok(LifecycleEvent::Assigned { .. }) => count += 1,
+                Ok(_) => {}
+                Err(e) => anyhow::bail!("Lost lifecycle events: {}", e),
+            }
+        }
+        Ok(())
+    })
+    .await;
+    let elapsed = started.elapsed();
+
+    shutdown.cancel();
+    let _ = assigner_task.await;
+    for task in worker_tasks {
+        let _ = task.await;
+    }
+    submitter.close().await.map_err(|e| anyhow::anyhow!("{}", e))?;
+
+    match outcome {
+        Ok(result) => result?,
+        Err(_) => anyhow::bail!("Only {} of {} jobs were assigned within {:?}", count, jobs, BENCH_TIMEOUT),
+    }
+    println!(
+        "assignment_throughput: {} jobs, {} workers: submitted in {:.2?}, all assigned in {:.2?} ({:.0} jobs/s)",
+        jobs,
+        workers,
+        submitted,
+        elapsed,
+        jobs as f64 / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
@@ -0,0 +1,38 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zenoh::bytes::ZBytes;
+use corebrum_examples::envelope::Message;
+use corebrum_examples::schema::{Assign, AssignAck, Claim, Heartbeat, Job, QueueMetrics, Result as TaskResult, Status};
+use corebrum_examples::zenoh_utils::deserialize_payload_with_context;
+
+fn deserialize<T: Message>(payload: &ZBytes, attachment: Option<&ZBytes>) {
+    let _ = deserialize_payload_with_context::<T>("corebrum/fuzz", payload, attachment, "message");
+}
+
+// The first byte picks the message type and whether the sample carries an attachment,
+// whose length is the next byte; the rest is the payload
+fuzz_target!(|data: &[u8]| {
+    let Some((&kind, rest)) = data.split_first() else {
+        return;
+    };
+    let (attachment, payload) = match rest.split_first() {
+        Some((&len, rest)) if kind & 0x80 != 0 => {
+            let (attachment, payload) = rest.split_at((len as usize).min(rest.len()));
+            (Some(ZBytes::from(attachment.to_vec())), payload)
+        }
+        _ => (None, rest),
+    };
+    let payload = ZBytes::from(payload.to_vec());
+    let attachment = attachment.as_ref();
+    match (kind & 0x7f) % 8 {
+        0 => deserialize::<Job>(&payload, attachment),
+        1 => deserialize::<Claim>(&payload, attachment),
+        2 => deserialize::<Assign>(&payload, attachment),
+        3 => deserialize::<AssignAck>(&payload, attachment),
+        4 => deserialize::<Status>(&payload, attachment),
+        5 => deserialize::<TaskResult>(&payload, attachment),
+        6 => deserialize::<Heartbeat>(&payload, attachment),
+        _ => deserialize::<QueueMetrics>(&payload, attachment),
+    }
+});
@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use corebrum_examples::schema::TaskDefinition;
+
+// The first byte picks JSON or YAML; the rest is the file's content
+fuzz_target!(|data: &[u8]| {
+    let Some((&kind, rest)) = data.split_first() else {
+        return;
+    };
+    if let Ok(content) = std::str::from_utf8(rest) {
+        let _ = TaskDefinition::from_task_file(content, kind % 2 == 0);
+    }
+});
@@ -0,0 +1,158 @@
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use crate::client::TaskSubmitter;
+use crate::replay::ResultDiff;
+use crate::schema::{Result as TaskResult, TaskDefinition};
+
+// How two versions of a task are compared. `ignore_outputs` names outputs that
+// legitimately differ between runs; with a `tolerance`, numeric outputs match when
+// no further apart than it, since e.g. a WASM port may print 3 where Python printed 3.0.
+#[derive(Debug, Clone)]
+pub struct AbOptions {
+    pub concurrency: usize,
+    pub timeout: Duration,
+    pub ignore_outputs: Vec<String>,
+    pub tolerance: Option<f64>,
+}
+
+impl Default for AbOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            timeout: Duration::from_secs(300),
+            ignore_outputs: Vec::new(),
+            tolerance: None,
+        }
+    }
+}
+
+// One version's run of one set of inputs
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionRun {
+    pub task_id: Option<String>,
+    pub result: Option<TaskResult>,
+    // Why there is no result, e.g. no worker answered in time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure: Option<String>,
+    // From submission to result, as seen by the submitter
+    pub wall_seconds: f64,
+}
+
+impl VersionRun {
+    pub fn execution_time_seconds(&self) -> Option<f64> {
+        self.result.as_ref()?.execution_time_seconds
+    }
+}
+
+// Both versions' runs of one set of inputs. The diff reads A as the archived side
+// and B as the replayed one, and is missing when either run produced no result.
+#[derive(Debug, Clone, Serialize)]
+pub struct AbCase {
+    pub inputs: serde_json::Value,
+    pub a: VersionRun,
+    pub b: VersionRun,
+    pub diff: Option<ResultDiff>,
+}
+
+impl AbCase {
+    pub fn matched(&self) -> bool {
+        self.diff.as_ref().is_some_and(ResultDiff::is_empty)
+    }
+
+    // How many times faster B executed than A; above 1 when B is quicker
+    pub fn speedup(&self) -> Option<f64> {
+        let (a, b) = (self.a.execution_time_seconds()?, self.b.execution_time_seconds()?);
+        (b > 0.0).then(|| a / b)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AbReport {
+    // "name (backend)" of each version
+    pub a: String,
+    pub b: String,
+    pub cases: Vec<AbCase>,
+}
+
+impl AbReport {
+    pub fn matched(&self) -> usize {
+        self.cases.iter().filter(|case| case.matched()).count()
+    }
+
+    pub fn differing(&self) -> usize {
+        self.cases.iter().filter(|case| case.diff.as_ref().is_some_and(|diff| !diff.is_empty())).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.cases.iter().filter(|case| case.diff.is_none()).count()
+    }
+
+    // True when B reproduced A's result for every set of inputs
+    pub fn is_clean(&self) -> bool {
+        self.cases.iter().all(AbCase::matched)
+    }
+
+    // Mean execution times of A and B over the cases both completed
+    pub fn mean_execution_seconds(&self) -> Option<(f64, f64)> {
+        let timed: Vec<(f64, f64)> = self
+            .cases
+            .iter()
+            .filter_map(|case| Some((case.a.execution_time_seconds()?, case.b.execution_time_seconds()?)))
+            .collect();
+        if timed.is_empty() {
+            return None;
+        }
+        let (a, b) = timed.iter().fold((0.0, 0.0), |(a, b), (case_a, case_b)| (a + case_a, b + case_b));
+        Some((a / timed.len() as f64, b / timed.len() as f64))
+    }
+}
+
+// Submits every set of `inputs` to both versions of a task through `submitter` (a
+// TaskClient or a LocalRuntime), the two runs of a set side by side, and diffs the
+// results' statuses, errors and outputs
+pub async fn compare_versions<S: TaskSubmitter + Sync>(
+    submitter: &S,
+    a: &TaskDefinition,
+    b: &TaskDefinition,
+    inputs: &[serde_json::Value],
+    options: &AbOptions,
+) -> AbReport {
+    let cases = stream::iter(inputs)
+        .map(|inputs| async move {
+            let (run_a, run_b) = tokio::join!(
+                run_version(submitter, a, inputs, options.timeout),
+                run_version(submitter, b, inputs, options.timeout)
+            );
+            let diff = match (&run_a.result, &run_b.result) {
+                (Some(a), Some(b)) => Some(ResultDiff::between_within(a, b, &options.ignore_outputs, options.tolerance)),
+                _ => None,
+            };
+            AbCase { inputs: inputs.clone(), a: run_a, b: run_b, diff }
+        })
+        // Cases come back in the order of their inputs
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+    AbReport { a: label(a), b: label(b), cases }
+}
+
+async fn run_version<S: TaskSubmitter>(submitter: &S, definition: &TaskDefinition, inputs: &serde_json::Value, timeout: Duration) -> VersionRun {
+    let started = Instant::now();
+    let run = |task_id, outcome: anyhow::Result<TaskResult>| VersionRun {
+        task_id,
+        wall_seconds: started.elapsed().as_secs_f64(),
+        failure: outcome.as_ref().err().map(|e| format!("{:#}", e)),
+        result: outcome.ok(),
+    };
+    let handle = match submitter.submit(definition.clone(), inputs.clone()).await {
+        Ok(handle) => handle,
+        Err(e) => return run(None, Err(e)),
+    };
+    let task_id = Some(handle.task_id().to_string());
+    run(task_id, handle.await_result_timeout(timeout).await)
+}
+
+fn label(definition: &TaskDefinition) -> String {
+    format!("{} ({})", definition.name, definition.backend())
+}
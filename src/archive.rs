@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+
+// Unpacks a tar, tar.gz or zip archive (told apart by their magic bytes) into
+// `dest`. Entries escaping `dest` are rejected, and so is an archive that expands
+// to more than `limit_bytes`.
+pub fn extract_archive(bytes: &[u8], dest: &Path, limit_bytes: Option<u64>) -> Result<()> {
+    let limit = limit_bytes.unwrap_or(u64::MAX);
+    if bytes.starts_with(ZIP_MAGIC) {
+        extract_zip(bytes, dest, limit)
+    } else if bytes.starts_with(GZIP_MAGIC) {
+        extract_tar(flate2::read::GzDecoder::new(bytes), dest, limit)
+    } else {
+        extract_tar(bytes, dest, limit)
+    }
+}
+
+fn extract_tar(reader: impl Read, dest: &Path, limit: u64) -> Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    let mut total = 0u64;
+    for entry in archive.entries().context("Failed to read tar archive")? {
+        let mut entry = entry.context("Corrupt tar archive")?;
+        total = total.saturating_add(entry.header().size()?);
+        if total > limit {
+            anyhow::bail!("Archive expands to more than {} bytes", limit);
+        }
+        let path = entry.path()?.into_owned();
+        if !entry.unpack_in(dest)? {
+            anyhow::bail!("Archive entry {} points outside the project", path.display());
+        }
+    }
+    Ok(())
+}
+
+fn extract_zip(bytes: &[u8], dest: &Path, limit: u64) -> Result<()> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).context("Failed to read zip archive")?;
+    let mut total = 0u64;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).context("Corrupt zip archive")?;
+        let Some(relative) = file.enclosed_name().map(Path::to_path_buf) else {
+            anyhow::bail!("Archive entry {} points outside the project", file.name());
+        };
+        let path = dest.join(relative);
+        if file.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // The declared size can't be trusted, so the limit is enforced while copying
+        let mut out = std::fs::File::create(&path)?;
+        let remaining = limit.saturating_sub(total);
+        let written = std::io::copy(&mut (&mut file).take(remaining.saturating_add(1)), &mut out)?;
+        total += written;
+        if total > limit {
+            anyhow::bail!("Archive expands to more than {} bytes", limit);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,743 @@
+use anyhow::Result;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use zenoh::sample::Sample;
+use zenoh::Session;
+use crate::events::{EventBus, LifecycleEvent};
+use crate::keyspace::Keyspace;
+use crate::metrics::{QueueGauges, VersionCounters};
+use crate::qos::{MessageClass, QosPolicy};
+use crate::receipts::ReceiptSigner;
+use crate::status::StatusSequencer;
+use crate::scheduling::{FleetChoice, FleetRouting, SchedulingStrategy, DEFAULT_CLAIM_WINDOW};
+use crate::estimation::{estimate, DurationHistory};
+use crate::schema::{Assign, AssignAck, Claim, EstimateRequest, Heartbeat, Isolation, Job, Receipt, Result as TaskResult, Status, TaskStatus, WorkerInfo};
+use crate::batching::unbatch;
+use crate::envelope::seal;
+use crate::verbosity::Verbosity;
+use crate::{detail, summary};
+use crate::zenoh_utils::{declare_history_subscriber, deserialize_from_sample_with_context, deserialize_payload_with_context, ready_samples, Compression, MessageMetadata, PublisherCache};
+
+// How long an assignee has to acknowledge before the next claimant is tried
+pub const DEFAULT_ACK_TIMEOUT: Duration = Duration::from_millis(500);
+// An acknowledged task is presumed lost if its worker sends neither a heartbeat
+// nor a status update for this long
+pub const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(5);
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+// How often queue depth gauges are published
+const METRICS_INTERVAL: Duration = Duration::from_secs(1);
+// Most messages of one kind handled in a single pass of the loop
+const MAX_BATCH: usize = 512;
+// Wait before retrying an assignment or lapsed lease whose messages failed to publish
+const PUBLISH_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+// Matches announced jobs with worker claims: claims are collected for a short window
+// after the first one arrives, then the scheduling strategy picks the winner. If the
+// winner never acknowledges, the job falls back to the next-best claimant, and once
+// none is left it is re-announced as another attempt. Once acknowledged the assignee
+// holds a lease that its heartbeats and status updates renew; if the lease lapses
+// before a result arrives the job is re-announced too.
+// Jobs not yet acknowledged are served on the pending queryable, so workers that
+// start after an announcement can still claim them. Claims from workers whose
+// heartbeats report a failed self-test for the job's backend are ignored, as are
+// claims from outside the fleet a task requires; fleet routing prefers the claimants
+// of a fleet picked by weight. The estimate queryable answers how long a job would
+// wait and run if submitted now, from the queue depth, the live workers and the
+// durations of completed runs.
+pub struct Assigner {
+    keyspace: Keyspace,
+    claim_window: Duration,
+    ack_timeout: Duration,
+    lease_duration: Duration,
+    max_attempts: u32,
+    strategy: SchedulingStrategy,
+    fleet_routing: FleetRouting,
+    qos: QosPolicy,
+    compression: Compression,
+    sequencer: StatusSequencer,
+    gauges: Arc<QueueGauges>,
+    versions: Arc<VersionCounters>,
+    events: Option<EventBus>,
+    receipt_signer: Option<ReceiptSigner>,
+    verbosity: Verbosity,
+}
+
+struct PendingJob {
+    job: Job,
+    fleet: FleetChoice,
+    // Carries the submitter's trace into everything published about the job
+    metadata: MessageMetadata,
+    // Claimants that have not been tried yet
+    claims: Vec<Claim>,
+    assignee: Option<String>,
+    // End of the claim window, or of the ack wait once assigned
+    deadline: Option<Instant>,
+}
+
+impl PendingJob {
+    fn new(job: Job, fleet: FleetChoice, metadata: MessageMetadata) -> Self {
+        Self {
+            job,
+            fleet,
+            metadata,
+            claims: Vec::new(),
+            assignee: None,
+            deadline: None,
+        }
+    }
+
+    // The first claim opens the claim window
+    fn add_claim(&mut self, claim: Claim, claim_window: Duration, timers: &mut Timers) {
+        if self.deadline.is_none() {
+            let deadline = Instant::now() + claim_window;
+            self.deadline = Some(deadline);
+            timers.schedule(deadline, &self.job.task_id);
+        }
+        self.claims.push(claim);
+    }
+}
+
+// Deadlines of pending jobs and leases, soonest first, so the loop never scans every
+// job to find the next one. Moving a deadline adds a timer rather than updating the
+// old one; timers that no longer match their job are dropped when they fall due.
+#[derive(Default)]
+struct Timers {
+    heap: BinaryHeap<Reverse<(Instant, String)>>,
+}
+
+impl Timers {
+    fn schedule(&mut self, at: Instant, task_id: &str) {
+        self.heap.push(Reverse((at, task_id.to_string())));
+    }
+
+    fn next(&self) -> Option<Instant> {
+        self.heap.peek().map(|Reverse((at, _))| *at)
+    }
+
+    fn due(&mut self, now: Instant) -> Vec<(Instant, String)> {
+        let mut due = Vec::new();
+        while self.heap.peek().is_some_and(|Reverse((at, _))| *at <= now) {
+            if let Some(Reverse(timer)) = self.heap.pop() {
+                due.push(timer);
+            }
+        }
+        due
+    }
+}
+
+struct Lease {
+    job: Job,
+    metadata: MessageMetadata,
+    worker_id: String,
+    expires_at: Instant,
+}
+
+impl Assigner {
+    pub fn new(keyspace: Keyspace) -> Self {
+        Self {
+            gauges: QueueGauges::new(keyspace.queue()),
+            versions: VersionCounters::new(keyspace.queue()),
+            keyspace,
+            claim_window: DEFAULT_CLAIM_WINDOW,
+            ack_timeout: DEFAULT_ACK_TIMEOUT,
+            lease_duration: DEFAULT_LEASE_DURATION,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            strategy: SchedulingStrategy::default(),
+            fleet_routing: FleetRouting::default(),
+            qos: QosPolicy::default(),
+            compression: Compression::default(),
+            sequencer: StatusSequencer::new(),
+            events: None,
+            receipt_signer: None,
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Reports announced, assigned and abandoned jobs on `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    // Signs the receipts published for accepted jobs; unsigned without a signer
+    pub fn with_receipt_signer(mut self, signer: ReceiptSigner) -> Self {
+        self.receipt_signer = Some(signer);
+        self
+    }
+
+    pub fn with_claim_window(mut self, claim_window: Duration) -> Self {
+        self.claim_window = claim_window;
+        self
+    }
+
+    pub fn with_ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+
+    pub fn with_lease_duration(mut self, lease_duration: Duration) -> Self {
+        self.lease_duration = lease_duration;
+        self
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: SchedulingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    // Steers each job to a worker fleet picked by weight, e.g. to send a share of the
+    // queue to workers running a canary build
+    pub fn with_fleet_routing(mut self, fleet_routing: FleetRouting) -> Self {
+        self.fleet_routing = fleet_routing;
+        self
+    }
+
+    pub fn with_qos_policy(mut self, qos: QosPolicy) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn keyspace(&self) -> &Keyspace {
+        &self.keyspace
+    }
+
+    // Kept up to date while running, e.g. for a Prometheus endpoint
+    pub fn gauges(&self) -> Arc<QueueGauges> {
+        self.gauges.clone()
+    }
+
+    // Results of jobs submitted by registry reference, per task version
+    pub fn version_counters(&self) -> Arc<VersionCounters> {
+        self.versions.clone()
+    }
+
+    pub async fn run(&self, session: &Session, shutdown: CancellationToken) -> Result<()> {
+        let jobs = session
+            .declare_subscriber(self.keyspace.announce())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare announce subscriber: {}", e))?;
+        // Claims are retained by their publishers, so ones sent before this
+        // subscription existed are fetched on declaration
+        let claims = declare_history_subscriber(session, self.keyspace.all_claims()).await?;
+        let acks = session
+            .declare_subscriber(self.keyspace.all_acks())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare ack subscriber: {}", e))?;
+        let heartbeats = session
+            .declare_subscriber(self.keyspace.all_heartbeats())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare heartbeat subscriber: {}", e))?;
+        let statuses = session
+            .declare_subscriber(self.keyspace.all_statuses())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare status subscriber: {}", e))?;
+        let results = session
+            .declare_subscriber(self.keyspace.all_results())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        let batches = session
+            .declare_subscriber(self.keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+        // Late-joining workers query this for jobs announced before they subscribed
+        let pending_queries = session
+            .declare_queryable(self.keyspace.all_pending())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare pending job queryable: {}", e))?;
+        let estimate_queries = session
+            .declare_queryable(self.keyspace.estimate())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare estimate queryable: {}", e))?;
+        summary!(self.verbosity, "🤖 Assigner started on queue {}", self.keyspace.queue());
+        let publishers = PublisherCache::new(session.clone())
+            .with_qos_policy(self.qos)
+            .with_compression(self.compression.clone());
+
+        let mut pending: HashMap<String, PendingJob> = HashMap::new();
+        let mut leases: HashMap<String, Lease> = HashMap::new();
+        let mut timers = Timers::default();
+        // Claims that arrived before their job's announcement, with arrival time
+        let mut early_claims: HashMap<String, Vec<(Instant, Claim)>> = HashMap::new();
+        // Worker -> backends whose self-test failed, as of its latest heartbeat
+        let mut failed_probes: HashMap<String, Vec<String>> = HashMap::new();
+        // Worker -> its latest heartbeat's info and when that arrived
+        let mut workers: HashMap<String, (WorkerInfo, Instant)> = HashMap::new();
+        let mut durations = DurationHistory::new();
+        let mut metrics = tokio::time::interval(METRICS_INTERVAL);
+        loop {
+            self.gauges.set(pending.len(), leases.len());
+            let next_deadline = timers.next();
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = metrics.tick() => {
+                    self.prune_early_claims(&mut early_claims);
+                    // Workers stop counting as candidates once their heartbeats would
+                    // have let a lease lapse
+                    workers.retain(|_, (_, seen)| seen.elapsed() < self.lease_duration);
+                    let snapshot = self.gauges.snapshot();
+                    if let Err(e) = publishers.publish_json(MessageClass::Status, &self.keyspace.metrics(), &snapshot).await {
+                        summary!(self.verbosity, "⚠️  Failed to publish queue metrics: {}", e);
+                    }
+                }
+                sample = jobs.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    // Whatever else already arrived is handled in the same pass, so a
+                    // bulk submission doesn't go around the loop once per job
+                    for sample in std::iter::once(sample).chain(ready_samples(&jobs, MAX_BATCH)) {
+                        self.accept_job(&publishers, &sample, &mut pending, &mut early_claims, &mut timers).await;
+                    }
+                }
+                sample = claims.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    for sample in std::iter::once(sample).chain(ready_samples(&claims, MAX_BATCH)) {
+                        match deserialize_from_sample_with_context::<Claim>(&sample, "claim") {
+                            Ok(claim) => self.accept_claim(claim, &mut pending, &leases, &mut early_claims, &failed_probes, &mut timers),
+                            Err(e) => summary!(self.verbosity, "⚠️  Assigner skipping malformed claim: {}", e),
+                        }
+                    }
+                }
+                sample = acks.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    for sample in std::iter::once(sample).chain(ready_samples(&acks, MAX_BATCH)) {
+                        match deserialize_from_sample_with_context::<AssignAck>(&sample, "ack") {
+                            Ok(ack) => {
+                                let acked = pending
+                                    .get(&ack.task_id)
+                                    .is_some_and(|entry| entry.assignee.as_deref() == Some(ack.worker_id.as_str()));
+                                if !acked {
+                                    continue;
+                                }
+                                if let Some(entry) = pending.remove(&ack.task_id) {
+                                    detail!(self.verbosity, "🤝 {} acknowledged job {}", ack.worker_id, ack.task_id);
+                                    let expires_at = Instant::now() + self.lease_duration;
+                                    timers.schedule(expires_at, &ack.task_id);
+                                    leases.insert(ack.task_id, Lease {
+                                        job: entry.job,
+                                        metadata: entry.metadata,
+                                        worker_id: ack.worker_id,
+                                        expires_at,
+                                    });
+                                }
+                            }
+                            Err(e) => summary!(self.verbosity, "⚠️  Assigner skipping malformed ack: {}", e),
+                        }
+                    }
+                }
+                query = pending_queries.recv_async() => {
+                    let Ok(query) = query else { break };
+                    for entry in pending.values() {
+                        let key = self.keyspace.pending(&entry.job.task_id);
+                        let reply = match (seal(publishers.sender(), &entry.job), entry.metadata.to_attachment()) {
+                            (Ok(payload), Ok(attachment)) => query.reply(key, payload).attachment(attachment).await,
+                            (Err(e), _) | (_, Err(e)) => {
+                                summary!(self.verbosity, "⚠️  Failed to encode pending job {}: {}", entry.job.task_id, e);
+                                continue;
+                            }
+                        };
+                        if let Err(e) = reply {
+                            summary!(self.verbosity, "⚠️  Failed to reply with pending job {}: {}", entry.job.task_id, e);
+                        }
+                    }
+                }
+                query = estimate_queries.recv_async() => {
+                    let Ok(query) = query else { break };
+                    let request = match query.payload() {
+                        Some(payload) => deserialize_payload_with_context::<EstimateRequest>(
+                            query.key_expr().as_str(),
+                            payload,
+                            query.attachment(),
+                            "estimate request",
+                        ),
+                        None => Err(anyhow::anyhow!("Estimate query carries no job")),
+                    };
+                    let reply = match request {
+                        Ok(request) => {
+                            let live: Vec<WorkerInfo> = workers.values().map(|(info, _)| info.clone()).collect();
+                            let estimate = estimate(&request.job, &durations, pending.len(), leases.len(), &live, chrono::Utc::now());
+                            match seal(publishers.sender(), &estimate) {
+                                Ok(payload) => query.reply(self.keyspace.estimate(), payload).await,
+                                Err(e) => query.reply_err(format!("Failed to encode the estimate: {:#}", e)).await,
+                            }
+                        }
+                        Err(e) => query.reply_err(format!("{:#}", e)).await,
+                    };
+                    if let Err(e) = reply {
+                        summary!(self.verbosity, "⚠️  Failed to reply to estimate query: {}", e);
+                    }
+                }
+                sample = heartbeats.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(heartbeat) = deserialize_from_sample_with_context::<Heartbeat>(&sample, "heartbeat") {
+                        for task_id in &heartbeat.task_ids {
+                            self.renew(&mut leases, &mut timers, task_id, &heartbeat.worker_id);
+                        }
+                        match &heartbeat.info {
+                            Some(info) if !info.failed_probes.is_empty() => {
+                                failed_probes.insert(heartbeat.worker_id.clone(), info.failed_probes.clone());
+                            }
+                            _ => {
+                                failed_probes.remove(&heartbeat.worker_id);
+                            }
+                        }
+                        if let Some(info) = heartbeat.info {
+                            workers.insert(heartbeat.worker_id, (info, Instant::now()));
+                        }
+                    }
+                }
+                sample = statuses.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(status) = deserialize_from_sample_with_context::<Status>(&sample, "status") {
+                        self.renew(&mut leases, &mut timers, &status.task_id, &status.worker_id);
+                    }
+                }
+                sample = results.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(result) = deserialize_from_sample_with_context::<TaskResult>(&sample, "result") {
+                        self.record_result(&mut durations, leases.remove(&result.task_id), &result);
+                    }
+                }
+                sample = batches.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    let Ok(entries) = unbatch(&sample, &self.keyspace.all_tasks()) else { continue };
+                    for entry in entries {
+                        if entry.matches(&self.keyspace.all_statuses()) {
+                            if let Ok(status) = entry.decode::<Status>("status") {
+                                self.renew(&mut leases, &mut timers, &status.task_id, &status.worker_id);
+                            }
+                        } else if entry.matches(&self.keyspace.all_results()) {
+                            if let Ok(result) = entry.decode::<TaskResult>("result") {
+                                self.record_result(&mut durations, leases.remove(&result.task_id), &result);
+                            }
+                        }
+                    }
+                }
+                _ = tokio::time::sleep_until(next_deadline.unwrap_or_else(Instant::now)), if next_deadline.is_some() => {
+                    // Every timer that fell due is handled in this pass
+                    for (at, task_id) in timers.due(Instant::now()) {
+                        if let Some(entry) = pending.get_mut(&task_id).filter(|entry| entry.deadline == Some(at)) {
+                            if let Some(assignee) = &entry.assignee {
+                                detail!(self.verbosity, "⚠️  {} did not acknowledge job {}, trying next claimant", assignee, task_id);
+                            }
+                            match self.assign_next(&publishers, entry).await {
+                                // Nobody claimed it (e.g. every claim was outside its fleet):
+                                // the next claim opens a fresh window
+                                Ok(false) if entry.assignee.is_none() => {
+                                    entry.deadline = None;
+                                }
+                                // Every claimant was tried and none acknowledged: another
+                                // attempt with fresh claims, or a failure once out of attempts
+                                Ok(false) => {
+                                    let assignee = entry.assignee.clone().unwrap_or_default();
+                                    let reason = format!("{} never acknowledged the assignment", assignee);
+                                    match self.retry_or_give_up(&publishers, &entry.job, &entry.metadata, &assignee, &reason).await {
+                                        Ok(Some(retry)) => *entry = retry,
+                                        Ok(None) => {
+                                            pending.remove(&task_id);
+                                        }
+                                        Err(e) => {
+                                            summary!(self.verbosity, "⚠️  Failed to re-announce job {}, retrying: {}", task_id, e);
+                                            let retry_at = Instant::now() + PUBLISH_RETRY_BACKOFF;
+                                            entry.deadline = Some(retry_at);
+                                            timers.schedule(retry_at, &task_id);
+                                        }
+                                    }
+                                }
+                                Ok(true) => {
+                                    if let Some(deadline) = entry.deadline {
+                                        timers.schedule(deadline, &task_id);
+                                    }
+                                }
+                                Err(e) => {
+                                    summary!(self.verbosity, "⚠️  Failed to assign job {}, retrying: {}", task_id, e);
+                                    let retry_at = Instant::now() + PUBLISH_RETRY_BACKOFF;
+                                    entry.deadline = Some(retry_at);
+                                    timers.schedule(retry_at, &task_id);
+                                }
+                            }
+                        } else if let Some(lease) = leases.get_mut(&task_id).filter(|lease| lease.expires_at == at) {
+                            match self.handle_lapsed_lease(&publishers, lease).await {
+                                Ok(entry) => {
+                                    leases.remove(&task_id);
+                                    if let Some(entry) = entry {
+                                        pending.insert(task_id, entry);
+                                    }
+                                }
+                                Err(e) => {
+                                    summary!(self.verbosity, "⚠️  Failed to handle the lapsed lease of job {}, retrying: {}", task_id, e);
+                                    lease.expires_at = Instant::now() + PUBLISH_RETRY_BACKOFF;
+                                    timers.schedule(lease.expires_at, &task_id);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        summary!(self.verbosity, "🤖 Assigner stopped");
+        Ok(())
+    }
+
+    async fn accept_job(
+        &self,
+        publishers: &PublisherCache,
+        sample: &Sample,
+        pending: &mut HashMap<String, PendingJob>,
+        early_claims: &mut HashMap<String, Vec<(Instant, Claim)>>,
+        timers: &mut Timers,
+    ) {
+        let job = match deserialize_from_sample_with_context::<Job>(sample, "job") {
+            Ok(job) => job,
+            Err(e) => {
+                summary!(self.verbosity, "⚠️  Assigner skipping malformed job: {}", e);
+                return;
+            }
+        };
+        // Our own re-announcements echo back here; the entry already exists
+        if pending.contains_key(&job.task_id) {
+            return;
+        }
+        detail!(self.verbosity, "📋 Assigner received job: {} ({})", job.task_id, job.name());
+        self.emit(LifecycleEvent::JobAnnounced {
+            queue: job.queue.clone(),
+            task_id: job.task_id.clone(),
+            name: job.name().to_string(),
+            attempt: job.attempt,
+        });
+        let fleet = self.fleet_routing.choose(&job, rand::random());
+        let mut entry = PendingJob::new(job, fleet, publishers.metadata().continue_trace(sample));
+        for (_, claim) in early_claims.remove(&entry.job.task_id).unwrap_or_default() {
+            if !entry.job.failed_on(&claim.worker_id) && entry.fleet.admits(&claim) {
+                entry.add_claim(claim, self.claim_window, timers);
+            }
+        }
+        self.publish_receipt(publishers, &entry, pending.len()).await;
+        pending.insert(entry.job.task_id.clone(), entry);
+    }
+
+    fn accept_claim(
+        &self,
+        claim: Claim,
+        pending: &mut HashMap<String, PendingJob>,
+        leases: &HashMap<String, Lease>,
+        early_claims: &mut HashMap<String, Vec<(Instant, Claim)>>,
+        failed_probes: &HashMap<String, Vec<String>>,
+        timers: &mut Timers,
+    ) {
+        if let Some(entry) = pending.get_mut(&claim.task_id) {
+            if entry.job.failed_on(&claim.worker_id) {
+                detail!(self.verbosity, "🚫 Ignoring claim for {} from {}, which already failed it", claim.task_id, claim.worker_id);
+                return;
+            }
+            if let Some(backend) = failed_probe(failed_probes, &entry.job, &claim.worker_id) {
+                detail!(self.verbosity, "🩺 Ignoring claim for {} from {}, whose {} self-test failed", claim.task_id, claim.worker_id, backend);
+                return;
+            }
+            if !entry.fleet.admits(&claim) {
+                detail!(self.verbosity, "🚫 Ignoring claim for {} from {}, which is in the {} fleet", claim.task_id, claim.worker_id, claim.fleet());
+                return;
+            }
+            detail!(
+                self.verbosity,
+                "📝 Assigner received claim for {} from {} (running {}, queued {})",
+                claim.task_id, claim.worker_id, claim.running_tasks, claim.queue_depth
+            );
+            entry.add_claim(claim, self.claim_window, timers);
+        } else if !leases.contains_key(&claim.task_id) {
+            // Either the announcement is still in flight or the task is long
+            // finished; keep it briefly in case it is the former
+            early_claims.entry(claim.task_id.clone()).or_default().push((Instant::now(), claim));
+        }
+    }
+
+    // Drops early claims whose job never showed up
+    fn prune_early_claims(&self, early_claims: &mut HashMap<String, Vec<(Instant, Claim)>>) {
+        let now = Instant::now();
+        let horizon = self.claim_window + self.ack_timeout;
+        early_claims.retain(|_, claims| {
+            claims.retain(|(at, _)| now.duration_since(*at) < horizon);
+            !claims.is_empty()
+        });
+    }
+
+    // Tells the submitter when the job was accepted and how many jobs wait ahead of it
+    async fn publish_receipt(&self, publishers: &PublisherCache, entry: &PendingJob, queue_position: usize) {
+        let mut receipt = Receipt {
+            task_id: entry.job.task_id.clone(),
+            queue: self.keyspace.queue().to_string(),
+            accepted_at: chrono::Utc::now(),
+            queue_position: queue_position as u64,
+            assigner_id: publishers.sender().to_string(),
+            signature: None,
+        };
+        if let Some(signer) = &self.receipt_signer {
+            signer.sign(&mut receipt);
+        }
+        let key = self.keyspace.receipt(&entry.job.task_id);
+        if let Err(e) = publishers.publish_json_with_metadata(MessageClass::Control, &key, &receipt, &entry.metadata).await {
+            summary!(self.verbosity, "⚠️  Failed to publish receipt for job {}: {}", entry.job.task_id, e);
+        }
+    }
+
+    fn emit(&self, event: LifecycleEvent) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
+
+    // Completed runs of leased jobs feed the estimates of later ones, and results of
+    // registry-pinned jobs the per-version counters
+    fn record_result(&self, durations: &mut DurationHistory, lease: Option<Lease>, result: &TaskResult) {
+        let Some(lease) = lease else { return };
+        if let Some(seconds) = result.execution_time_seconds.filter(|_| result.status == TaskStatus::Completed) {
+            durations.record(lease.job.name(), seconds);
+        }
+        if let Some(pin) = &lease.job.definition_pin {
+            match result.status {
+                TaskStatus::Completed => self.versions.record(&pin.reference, true),
+                TaskStatus::Failed | TaskStatus::Timeout => self.versions.record(&pin.reference, false),
+                _ => {}
+            }
+        }
+    }
+
+    fn renew(&self, leases: &mut HashMap<String, Lease>, timers: &mut Timers, task_id: &str, worker_id: &str) {
+        if let Some(lease) = leases.get_mut(task_id).filter(|lease| lease.worker_id == worker_id) {
+            lease.expires_at = Instant::now() + self.lease_duration;
+            timers.schedule(lease.expires_at, task_id);
+        }
+    }
+
+    // Re-announces the job of a worker presumed dead, or gives up after max_attempts;
+    // returns the job to collect fresh claims for. On error the lease is left to retry.
+    async fn handle_lapsed_lease(&self, publishers: &PublisherCache, lease: &Lease) -> Result<Option<PendingJob>> {
+        let reason = format!("lease expired on {}", lease.worker_id);
+        self.retry_or_give_up(publishers, &lease.job, &lease.metadata, &lease.worker_id, &reason).await
+    }
+
+    // Re-announces `job` as its next attempt, recording why `worker_id`'s attempt
+    // ended, or publishes a Failed status and result once out of attempts
+    async fn retry_or_give_up(
+        &self,
+        publishers: &PublisherCache,
+        job: &Job,
+        metadata: &MessageMetadata,
+        worker_id: &str,
+        reason: &str,
+    ) -> Result<Option<PendingJob>> {
+        let task_id = job.task_id.clone();
+        summary!(self.verbosity, "💀 Job {} attempt {}: {}", task_id, job.attempt, reason);
+
+        let max_attempts = job.max_attempts().unwrap_or(self.max_attempts).max(1);
+        if job.attempt >= max_attempts {
+            let error = format!("Giving up after {} attempts; last {}", job.attempt, reason);
+            summary!(self.verbosity, "❌ Job {}: {}", task_id, error);
+            let status = self.sequencer.stamp(Status::new(&task_id, worker_id, TaskStatus::Failed, &error, 1.0));
+            publishers
+                .publish_json_with_metadata(MessageClass::Status, &self.keyspace.status(&task_id), &status, metadata)
+                .await?;
+            self.emit(LifecycleEvent::Failed {
+                task_id: task_id.clone(),
+                worker_id: worker_id.to_string(),
+                status: TaskStatus::Failed,
+                error: Some(error.clone()),
+            });
+            let result = TaskResult::failure(task_id.clone(), worker_id.to_string(), error);
+            publishers
+                .publish_json_with_metadata(MessageClass::Data, &self.keyspace.result(&task_id), &result, metadata)
+                .await?;
+            return Ok(None);
+        }
+
+        let job = job.retry(worker_id, reason);
+        publishers
+            .publish_json_with_metadata(MessageClass::Control, &self.keyspace.announce(), &job, metadata)
+            .await?;
+        summary!(self.verbosity, "🔁 Re-announced job {} (attempt {}/{})", task_id, job.attempt, max_attempts);
+        let fleet = self.fleet_routing.choose(&job, rand::random());
+        Ok(Some(PendingJob::new(job, fleet, metadata.clone())))
+    }
+
+    // Assigns the job to the best untried claimant; false once nobody is left. A
+    // claimant whose assignment failed to publish stays untried.
+    async fn assign_next(&self, publishers: &PublisherCache, entry: &mut PendingJob) -> Result<bool> {
+        let Some(best) = self.strategy.select(&entry.fleet.narrow(&entry.claims)).cloned() else {
+            match entry.assignee {
+                Some(_) => summary!(self.verbosity, "❌ No claimant acknowledged job {}", entry.job.task_id),
+                None => summary!(self.verbosity, "❌ No claims for job {}", entry.job.task_id),
+            }
+            return Ok(false);
+        };
+        detail!(
+            self.verbosity,
+            "🏆 Picked {} for job {} out of {} claim(s) ({:?})",
+            best.worker_id,
+            entry.job.task_id,
+            entry.claims.len(),
+            self.strategy
+        );
+        self.assign(publishers, &entry.job, &entry.metadata, &best).await?;
+        entry.claims.retain(|claim| claim.worker_id != best.worker_id);
+        entry.assignee = Some(best.worker_id);
+        entry.deadline = Some(Instant::now() + self.ack_timeout);
+        Ok(true)
+    }
+
+    async fn assign(&self, publishers: &PublisherCache, job: &Job, metadata: &MessageMetadata, claim: &Claim) -> Result<()> {
+        let task_id = &job.task_id;
+        let assign = Assign {
+            task_id: task_id.clone(),
+            worker_id: claim.worker_id.clone(),
+            assigned_at: chrono::Utc::now(),
+            task_definition: job.task_definition.clone(),
+            inputs: job.inputs.clone(),
+            attempt: job.attempt,
+        };
+        publishers
+            .publish_json_retained(MessageClass::Control, &self.keyspace.assign(task_id), &assign, metadata)
+            .await?;
+
+        let status = self.sequencer.stamp(Status::new(task_id, &claim.worker_id, TaskStatus::Assigned, "Task assigned to worker", 0.0));
+        publishers
+            .publish_json_with_metadata(MessageClass::Status, &self.keyspace.status(task_id), &status, metadata)
+            .await?;
+        detail!(self.verbosity, "✅ Assigned job {} to {}", task_id, claim.worker_id);
+        self.emit(LifecycleEvent::Assigned {
+            task_id: task_id.clone(),
+            worker_id: claim.worker_id.clone(),
+        });
+        Ok(())
+    }
+}
+
+// The backend `job` needs that failed `worker_id`'s self-test, if it did
+fn failed_probe<'a>(failed_probes: &'a HashMap<String, Vec<String>>, job: &Job, worker_id: &str) -> Option<&'a str> {
+    let task_definition = job.task_definition.as_ref()?;
+    if task_definition.isolation() != Isolation::Process {
+        return None;
+    }
+    failed_probes
+        .get(worker_id)?
+        .iter()
+        .find(|backend| *backend == task_definition.backend())
+        .map(String::as_str)
+}
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use zenoh::key_expr::KeyExpr;
+use zenoh::sample::Sample;
+use crate::envelope::{unseal, Message};
+use crate::zenoh_utils::{deserialize_from_sample_with_context, Codec, MessageMetadata, JSON_CONTENT_ENCODING};
+
+// Micro-batching of a worker's status updates and results: instead of one put per
+// message they go out together on the worker's batch key once `max_messages` are
+// waiting or every `flush_interval_ms`, whichever comes first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Batching {
+    pub max_messages: usize,
+    pub flush_interval_ms: u64,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self {
+            max_messages: 64,
+            flush_interval_ms: 20,
+        }
+    }
+}
+
+impl Batching {
+    pub fn flush_interval(&self) -> Duration {
+        Duration::from_millis(self.flush_interval_ms.max(1))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Batch {
+    pub entries: Vec<BatchEntry>,
+}
+
+impl Message for Batch {
+    const MSG_TYPE: &'static str = "batch";
+}
+
+// One message of a batch, as it would have been published on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub key: String,
+    pub metadata: MessageMetadata,
+    // The sealed envelope
+    pub payload: String,
+}
+
+impl BatchEntry {
+    pub fn matches(&self, key_expr: &str) -> bool {
+        match (KeyExpr::try_from(self.key.as_str()), KeyExpr::try_from(key_expr)) {
+            (Ok(key), Ok(pattern)) => key.intersects(&pattern),
+            _ => false,
+        }
+    }
+
+    pub fn decode<T: Message>(&self, context: &str) -> Result<T> {
+        if self.metadata.content_encoding != JSON_CONTENT_ENCODING {
+            anyhow::bail!("Unsupported content encoding {} for batched {} on {}", self.metadata.content_encoding, context, self.key);
+        }
+        unseal(&self.payload)
+            .and_then(|envelope| envelope.open::<T>())
+            .map(|envelope| envelope.body)
+            .with_context(|| format!("Failed to deserialize batched {} from {}", context, self.key))
+    }
+}
+
+// The entries of a batch sample whose keys fall under `key_expr`, in publishing order
+pub fn unbatch(sample: &Sample, key_expr: &str) -> Result<Vec<BatchEntry>> {
+    let batch: Batch = deserialize_from_sample_with_context(sample, "batch")?;
+    Ok(batch.entries.into_iter().filter(|entry| entry.matches(key_expr)).collect())
+}
+
+// Decodes the matching entries of a batch sample as `T`; a malformed batch yields a single error
+pub fn unbatch_messages<T: Message>(sample: &Sample, key_expr: &str, context: &str) -> Vec<Result<T>> {
+    match unbatch(sample, key_expr) {
+        Ok(entries) => entries.iter().map(|entry| entry.decode(context)).collect(),
+        Err(e) => vec![Err(e)],
+    }
+}
+
+// Messages waiting to go out in the next batch
+pub(crate) struct PendingBatch {
+    pub key: String,
+    pub settings: Batching,
+    pub entries: Mutex<Vec<BatchEntry>>,
+}
+
+impl PendingBatch {
+    pub fn new(key: String, settings: Batching) -> Self {
+        Self {
+            key,
+            settings,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Returns true once the batch is full
+    pub fn push(&self, entry: BatchEntry) -> bool {
+        let mut entries = self.lock();
+        entries.push(entry);
+        entries.len() >= self.settings.max_messages.max(1)
+    }
+
+    pub fn take(&self) -> Vec<BatchEntry> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<BatchEntry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Codecs every entry's trace accepts; the batch as a whole may only be compressed with those
+pub(crate) fn common_codecs(entries: &[BatchEntry]) -> Vec<Codec> {
+    let mut codecs = entries.first().map(|entry| entry.metadata.accept_compression.clone()).unwrap_or_default();
+    codecs.retain(|codec| entries.iter().all(|entry| entry.metadata.accept_compression.contains(codec)));
+    codecs
+}
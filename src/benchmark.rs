@@ -0,0 +1,99 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::time::Instant;
+use crate::schema::BenchmarkScores;
+
+// Enough to get past the CPU caches without keeping a small device busy for long
+pub const DEFAULT_BENCHMARK_MB: u64 = 64;
+pub const MAX_BENCHMARK_MB: u64 = 1024;
+const MB: usize = 1024 * 1024;
+
+// Measures CPU, memory and disk throughput with `size_mb` of data, writing the disk
+// test's file under `dir`. Each test runs once on one thread, so scores compare
+// machines rather than how many cores they have.
+pub fn run_benchmark(dir: &Path, size_mb: u64) -> Result<BenchmarkScores> {
+    if !(1..=MAX_BENCHMARK_MB).contains(&size_mb) {
+        anyhow::bail!("Benchmark size is {}MB, outside 1 to {}MB", size_mb, MAX_BENCHMARK_MB);
+    }
+    let bytes = size_mb as usize * MB;
+    // Not all zeroes, so nothing along the way can take a shortcut
+    let data: Vec<u8> = (0..bytes).map(|i| (i % 251) as u8).collect();
+
+    let started = Instant::now();
+    let digest = Sha256::digest(&data);
+    let cpu_seconds = started.elapsed().as_secs_f64();
+    std::hint::black_box(digest);
+
+    let mut copy = vec![0u8; bytes];
+    let started = Instant::now();
+    copy.copy_from_slice(&data);
+    std::hint::black_box(&copy);
+    let memory_seconds = started.elapsed().as_secs_f64();
+
+    let path = dir.join("benchmark.bin");
+    let started = Instant::now();
+    let mut file = std::fs::File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(&data).context("Failed to write the benchmark file")?;
+    file.sync_all().context("Failed to sync the benchmark file")?;
+    let write_seconds = started.elapsed().as_secs_f64();
+    drop(file);
+
+    // Likely served from the page cache, so this is the best case for reads
+    let started = Instant::now();
+    let mut file = std::fs::File::open(&path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut chunk = vec![0u8; MB];
+    let mut read = 0;
+    loop {
+        let n = file.read(&mut chunk).context("Failed to read the benchmark file")?;
+        if n == 0 {
+            break;
+        }
+        read += n;
+    }
+    let read_seconds = started.elapsed().as_secs_f64();
+    let _ = std::fs::remove_file(&path);
+    if read != bytes {
+        anyhow::bail!("Read back {} of the {} bytes written", read, bytes);
+    }
+
+    let mb = size_mb as f64;
+    Ok(BenchmarkScores {
+        cpu_mb_per_second: rate(mb, cpu_seconds),
+        memory_gb_per_second: rate(mb / 1024.0, memory_seconds),
+        disk_write_mb_per_second: rate(mb, write_seconds),
+        disk_read_mb_per_second: rate(mb, read_seconds),
+        measured_at: chrono::Utc::now(),
+    })
+}
+
+// Rounded to one decimal; a test too quick for the clock scores what one tick allows
+fn rate(amount: f64, seconds: f64) -> f64 {
+    (amount / seconds.max(1e-6) * 10.0).round() / 10.0
+}
+
+// Size of the benchmark built-in's data, from its `size_mb` input
+pub fn benchmark_size(inputs: &serde_json::Value) -> Result<u64> {
+    match inputs.get("size_mb") {
+        None | Some(serde_json::Value::Null) => Ok(DEFAULT_BENCHMARK_MB),
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Input size_mb must be a positive integer, got {}", value)),
+    }
+}
+
+// The benchmark built-in's outputs: one per score
+pub fn benchmark_outputs(scores: &BenchmarkScores) -> Result<HashMap<String, serde_json::Value>> {
+    match serde_json::to_value(scores)? {
+        serde_json::Value::Object(fields) => Ok(fields.into_iter().collect()),
+        other => anyhow::bail!("Benchmark scores serialized to {}", other),
+    }
+}
+
+// The scores back from a benchmark run's outputs
+pub fn scores_from_outputs(outputs: &HashMap<String, serde_json::Value>) -> Result<BenchmarkScores> {
+    let fields = outputs.iter().map(|(name, value)| (name.clone(), value.clone())).collect();
+    serde_json::from_value(serde_json::Value::Object(fields)).context("Benchmark outputs are not scores")
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::time::Duration;
+use crate::benchmark::DEFAULT_BENCHMARK_MB;
+use crate::schema::{InputType, TaskDefinition, TaskInput, TaskOutput, TaskSource};
+
+// Tasks implemented natively in the worker, for smoke tests and benchmarks that
+// shouldn't depend on an interpreter or a code download
+pub const BUILTIN_TASKS: &[&str] = &["factorial", "fibonacci", "hash", "sleep", "echo", "benchmark"];
+// Published in the task registry as `builtin/<name>@<version>`
+pub const BUILTIN_NAMESPACE: &str = "builtin";
+pub const BUILTIN_VERSION: &str = "1";
+
+// 10000! has 35660 digits
+const MAX_FACTORIAL: u64 = 10_000;
+// The 95th term no longer fits in a u64
+const MAX_FIBONACCI_TERMS: u64 = 94;
+const MAX_HASH_ROUNDS: u64 = 10_000_000;
+const MAX_SLEEP_SECONDS: f64 = 3600.0;
+// Limbs of the big numbers factorial computes
+const LIMB: u64 = 1_000_000_000;
+
+// The definition of a built-in, with its inputs and their defaults declared
+pub fn builtin_task(name: &str) -> Option<TaskDefinition> {
+    let input = |name: &str, data_type: InputType, default_value: serde_json::Value, description: &str| TaskInput {
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        required: false,
+        default_value: Some(default_value),
+        data_type: Some(data_type),
+        choices: Vec::new(),
+    };
+    let output = |name: &str, data_type: &str| TaskOutput {
+        name: name.to_string(),
+        description: None,
+        data_type: data_type.to_string(),
+    };
+    let (description, inputs, outputs) = match name {
+        "factorial" => (
+            "number! as an integer, or a string of digits when it doesn't fit in 64 bits",
+            vec![input("number", InputType::Integer, 10.into(), "Up to 10000")],
+            vec![output("factorial", "json"), output("input_number", "integer")],
+        ),
+        "fibonacci" => (
+            "The first `terms` Fibonacci numbers, starting from 0",
+            vec![input("terms", InputType::Integer, 10.into(), "Up to 94")],
+            vec![output("sequence", "array")],
+        ),
+        "hash" => (
+            "SHA-256 of `data`, hashed again `rounds` times in all; strings are hashed as UTF-8, anything else as JSON",
+            vec![
+                input("data", InputType::Json, "".into(), "What to hash"),
+                input("rounds", InputType::Integer, 1.into(), "Up to 10000000"),
+            ],
+            vec![output("sha256", "string"), output("rounds", "integer")],
+        ),
+        "sleep" => (
+            "Waits for `seconds` without using the CPU",
+            vec![input("seconds", InputType::Duration, 1.into(), "Up to an hour")],
+            vec![output("slept_seconds", "number")],
+        ),
+        "echo" => ("Returns its inputs as its outputs", Vec::new(), Vec::new()),
+        "benchmark" => (
+            "Measures the worker's CPU, memory and disk throughput",
+            vec![input("size_mb", InputType::Integer, DEFAULT_BENCHMARK_MB.into(), "Data per test, up to 1024")],
+            vec![
+                output("cpu_mb_per_second", "number"),
+                output("memory_gb_per_second", "number"),
+                output("disk_write_mb_per_second", "number"),
+                output("disk_read_mb_per_second", "number"),
+                output("measured_at", "string"),
+            ],
+        ),
+        _ => return None,
+    };
+    Some(TaskDefinition {
+        name: format!("{}/{}", BUILTIN_NAMESPACE, name),
+        description: Some(description.to_string()),
+        language: "builtin".to_string(),
+        source: TaskSource::Builtin { name: name.to_string() },
+        inputs,
+        outputs,
+        requirements: None,
+        checks: Vec::new(),
+    })
+}
+
+// Runs a built-in other than `sleep` and `benchmark`, which need the executor's
+// runtime and workspace. Inputs left out take the defaults builtin_task declares.
+pub fn run_builtin(name: &str, inputs: &serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+    let outputs = match name {
+        "factorial" => {
+            let number = integer(inputs, "number", 10, MAX_FACTORIAL)?;
+            vec![("factorial", factorial(number)), ("input_number", number.into())]
+        }
+        "fibonacci" => {
+            let terms = integer(inputs, "terms", 10, MAX_FIBONACCI_TERMS)?;
+            let (mut a, mut b) = (0u64, 1u64);
+            let mut sequence = Vec::new();
+            for _ in 0..terms {
+                sequence.push(serde_json::Value::from(a));
+                // Only terms past the last one kept can overflow
+                (a, b) = (b, a.checked_add(b).unwrap_or(u64::MAX));
+            }
+            vec![("sequence", sequence.into())]
+        }
+        "hash" => {
+            let data = match inputs.get("data") {
+                Some(serde_json::Value::String(text)) => text.clone().into_bytes(),
+                Some(value) => value.to_string().into_bytes(),
+                None => Vec::new(),
+            };
+            let rounds = integer(inputs, "rounds", 1, MAX_HASH_ROUNDS)?.max(1);
+            let mut digest = Sha256::digest(&data);
+            for _ in 1..rounds {
+                digest = Sha256::digest(digest);
+            }
+            vec![("sha256", format!("{:x}", digest).into()), ("rounds", rounds.into())]
+        }
+        "echo" => match inputs {
+            serde_json::Value::Object(inputs) => return Ok(inputs.clone().into_iter().collect()),
+            serde_json::Value::Null => return Ok(HashMap::new()),
+            other => vec![("inputs", other.clone())],
+        },
+        "sleep" | "benchmark" => anyhow::bail!("The {} built-in is run by the executor", name),
+        _ => anyhow::bail!("Unknown built-in task {:?}; this worker has {}", name, BUILTIN_TASKS.join(", ")),
+    };
+    Ok(outputs.into_iter().map(|(name, value)| (name.to_string(), value)).collect())
+}
+
+// How long the sleep built-in waits
+pub fn sleep_duration(inputs: &serde_json::Value) -> Result<Duration> {
+    let seconds = match inputs.get("seconds") {
+        None | Some(serde_json::Value::Null) => 1.0,
+        Some(value) => value
+            .as_f64()
+            .ok_or_else(|| anyhow::anyhow!("Input seconds must be a number, got {}", value))?,
+    };
+    if !(0.0..=MAX_SLEEP_SECONDS).contains(&seconds) {
+        anyhow::bail!("Input seconds is {}, outside 0 to {}", seconds, MAX_SLEEP_SECONDS);
+    }
+    Ok(Duration::from_secs_f64(seconds))
+}
+
+fn integer(inputs: &serde_json::Value, name: &str, default: u64, max: u64) -> Result<u64> {
+    let value = match inputs.get(name) {
+        None | Some(serde_json::Value::Null) => default,
+        Some(value) => value
+            .as_u64()
+            .ok_or_else(|| anyhow::anyhow!("Input {} must be a non-negative integer, got {}", name, value))?,
+    };
+    if value > max {
+        anyhow::bail!("Input {} is {}, over the limit of {}", name, value, max);
+    }
+    Ok(value)
+}
+
+// JSON numbers can't hold the large ones exactly, so those come back as digits
+fn factorial(number: u64) -> serde_json::Value {
+    // Least significant limb first
+    let mut limbs = vec![1u64];
+    for factor in 2..=number {
+        let mut carry = 0;
+        for limb in &mut limbs {
+            let product = *limb * factor + carry;
+            *limb = product % LIMB;
+            carry = product / LIMB;
+        }
+        while carry > 0 {
+            limbs.push(carry % LIMB);
+            carry /= LIMB;
+        }
+    }
+    let mut digits = limbs.last().map(u64::to_string).unwrap_or_default();
+    for limb in limbs.iter().rev().skip(1) {
+        digits.push_str(&format!("{:09}", limb));
+    }
+    digits.parse::<u64>().map(serde_json::Value::from).unwrap_or(serde_json::Value::String(digits))
+}
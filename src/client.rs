@@ -0,0 +1,573 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use zenoh::bytes::ZBytes;
+use crate::batching::unbatch_messages;
+use crate::config::QueueDefaults;
+use crate::dry_run::{worker_unfit, DryRunReport, WorkerMatch};
+use crate::fan_in::validate_stream_inputs;
+use crate::keyspace::{validate_key_segment, Keyspace};
+use crate::large_inputs::InputsOffload;
+use crate::envelope::{seal, Message};
+use crate::receipts::ReceiptSigner;
+#[cfg(feature = "http")]
+use crate::registry::TaskRegistry;
+use crate::scheduling::Coordination;
+use crate::schema::{
+    Assign, Claim, Estimate, EstimateRequest, Heartbeat, Job, Receipt, Result as TaskResult, TaskDefinition, TaskFilter, TaskListRequest,
+    TaskPage, TaskTree, TaskTreeRequest, WorkerInfo,
+};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::{deserialize_from_sample_with_context, deserialize_payload_with_context, publish_json_with_metadata, Codec, MessageMetadata};
+use crate::{detail, summary};
+
+const TASK_LIST_TIMEOUT: Duration = Duration::from_secs(5);
+// How long submit waits for the assigner's receipt
+const RECEIPT_TIMEOUT: Duration = Duration::from_secs(2);
+// How long a work-stealing job's lock, or its offloaded inputs, are hosted without a
+// result, past the job's own timeout (or this alone for jobs without one)
+const LOCK_GRACE: Duration = Duration::from_secs(60);
+
+fn hosting_lifetime(job: &Job) -> Duration {
+    job.timeout_seconds.map_or(Duration::ZERO, Duration::from_secs) + LOCK_GRACE
+}
+
+// Common submit/await API shared by the networked client and the in-process LocalRuntime
+pub trait TaskSubmitter {
+    fn submit(
+        &self,
+        task_definition: TaskDefinition,
+        inputs: serde_json::Value,
+    ) -> impl Future<Output = Result<TaskHandle>> + Send;
+}
+
+pub struct TaskHandle {
+    task_id: String,
+    result_rx: oneshot::Receiver<TaskResult>,
+    receipt: Option<Receipt>,
+}
+
+impl TaskHandle {
+    pub(crate) fn new(task_id: String, result_rx: oneshot::Receiver<TaskResult>) -> Self {
+        Self {
+            task_id,
+            result_rx,
+            receipt: None,
+        }
+    }
+
+    pub fn task_id(&self) -> &str {
+        &self.task_id
+    }
+
+    // The assigner's receipt for the submission; None when no assigner answered in
+    // time, and always for in-process runtimes and reattached handles
+    pub fn receipt(&self) -> Option<&Receipt> {
+        self.receipt.as_ref()
+    }
+
+    pub async fn await_result(self) -> Result<TaskResult> {
+        self.result_rx
+            .await
+            .with_context(|| format!("Result channel for task {} closed", self.task_id))
+    }
+
+    pub async fn await_result_timeout(self, timeout: Duration) -> Result<TaskResult> {
+        let task_id = self.task_id.clone();
+        tokio::time::timeout(timeout, self.await_result())
+            .await
+            .with_context(|| format!("Timed out waiting for result of task {}", task_id))?
+    }
+}
+
+pub struct TaskClient {
+    session: zenoh::Session,
+    keyspace: Keyspace,
+    accept_compression: Vec<Codec>,
+    receipt_signer: Option<ReceiptSigner>,
+    coordination: Coordination,
+    inputs_offload: InputsOffload,
+    queue_defaults: QueueDefaults,
+    verbosity: Verbosity,
+}
+
+impl TaskClient {
+    pub fn new(session: zenoh::Session, queue: &str) -> Self {
+        Self {
+            session,
+            keyspace: Keyspace::for_queue(queue),
+            accept_compression: Vec::new(),
+            receipt_signer: None,
+            coordination: Coordination::default(),
+            inputs_offload: InputsOffload::default(),
+            queue_defaults: QueueDefaults::default(),
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    // A client on a queue outside the default namespace, e.g. a worker submitting child jobs
+    pub fn for_keyspace(session: zenoh::Session, keyspace: Keyspace) -> Self {
+        Self {
+            keyspace,
+            ..Self::new(session, "")
+        }
+    }
+
+    // With WorkStealing this client hosts the lock of every job it submits, for
+    // queues whose workers run without an assigner
+    pub fn with_coordination(mut self, coordination: Coordination) -> Self {
+        self.coordination = coordination;
+        self
+    }
+
+    // Applied to every job this client submits, e.g. `QueueSettings::for_queue`
+    pub fn with_queue_defaults(mut self, defaults: QueueDefaults) -> Self {
+        self.queue_defaults = defaults;
+        self
+    }
+
+    // Inputs over the threshold are served by this client rather than announced with
+    // the job, so it has to stay up until a worker fetched them
+    pub fn with_inputs_offload(mut self, offload: InputsOffload) -> Self {
+        self.inputs_offload = offload;
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Lets workers compress large results of jobs submitted from here with one of
+    // `codecs`. Every assigner and listener on the queue must be able to decode them
+    // too, so leave this off while older peers are around.
+    pub fn with_accept_compression(mut self, codecs: Vec<Codec>) -> Self {
+        self.accept_compression = codecs;
+        self
+    }
+
+    // Requires receipts to be signed with the assigners' secret; submit fails on a
+    // receipt that doesn't verify
+    pub fn with_receipt_signer(mut self, signer: ReceiptSigner) -> Self {
+        self.receipt_signer = Some(signer);
+        self
+    }
+
+    pub fn keyspace(&self) -> &Keyspace {
+        &self.keyspace
+    }
+
+    // One page of the tasks a status store on `queue` knows about; pass the returned
+    // `next_page_token` back in to fetch the next one
+    pub async fn list_tasks(&self, queue: &str, filter: TaskFilter, page_token: Option<String>) -> Result<TaskPage> {
+        let request = TaskListRequest {
+            filter,
+            page_token,
+            ..TaskListRequest::default()
+        };
+        self.list_tasks_with(queue, &request).await
+    }
+
+    pub async fn list_tasks_with(&self, queue: &str, request: &TaskListRequest) -> Result<TaskPage> {
+        validate_key_segment("Queue name", queue)?;
+        let key = Keyspace::new(self.keyspace.namespace(), queue).task_list();
+        self.query_queue(&key, request, "status store", "Task listing", "task page").await
+    }
+
+    // The parent/child tree `task_id` belongs to on `queue`, with statuses and
+    // timings, from a status store on the queue. Render it with `TaskTree::to_dot`.
+    pub async fn task_tree(&self, queue: &str, task_id: &str) -> Result<TaskTree> {
+        validate_key_segment("Queue name", queue)?;
+        let key = Keyspace::new(self.keyspace.namespace(), queue).task_tree();
+        let request = TaskTreeRequest { task_id: task_id.to_string() };
+        self.query_queue(&key, &request, "status store", "Task tree", "task tree").await
+    }
+
+    // When `job` would likely start and how long it would run if submitted now, from
+    // the assigner on its queue. Queue defaults and input coercion are applied first,
+    // as on submission.
+    pub async fn estimate(&self, job: &Job) -> Result<Estimate> {
+        let mut job = job.clone();
+        self.prepare(&mut job)?;
+        // The estimate doesn't depend on them, and large ones needn't travel
+        job.inputs = serde_json::Value::Null;
+        let key = Keyspace::new(self.keyspace.namespace(), &job.queue).estimate();
+        self.query_queue(&key, &EstimateRequest { job }, "assigner", "Estimate", "estimate").await
+    }
+
+    async fn query_queue<Req: Message, Resp: Message>(&self, key: &str, request: &Req, answerer: &str, what: &str, context: &str) -> Result<Resp> {
+        let replies = self.session
+            .get(key)
+            .payload(seal(&self.session.zid().to_string(), request)?)
+            .timeout(TASK_LIST_TIMEOUT)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to query {}: {}", key, e))?;
+        // Any status store (or assigner) on the queue can answer; the first reply wins
+        match replies.recv_async().await {
+            Ok(reply) => match reply.result() {
+                Ok(sample) => deserialize_from_sample_with_context(sample, context),
+                Err(e) => anyhow::bail!("{} on {} failed: {}", what, key, String::from_utf8_lossy(&e.payload().to_bytes())),
+            },
+            Err(_) => anyhow::bail!("No {} answered on {}", answerer, key),
+        }
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.session
+            .close()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to close client session: {}", e))
+    }
+
+    // Queue defaults and input coercion, as applied to every job before it is announced
+    fn prepare(&self, job: &mut Job) -> Result<()> {
+        validate_key_segment("Task ID", &job.task_id)?;
+        validate_key_segment("Queue name", &job.queue)?;
+        self.queue_defaults.apply(job);
+        if let Some(requirements) = job.task_definition.as_ref().and_then(|task_definition| task_definition.requirements.as_ref()) {
+            requirements.check()?;
+        }
+        if let Some(stream_inputs) = &job.stream_inputs {
+            validate_stream_inputs(stream_inputs)?;
+        }
+        // Children get exactly what their parent handed them
+        if job.parent_task_id.is_none() {
+            if let Some(task_definition) = &job.task_definition {
+                let inputs = std::mem::take(&mut job.inputs);
+                job.inputs = match &job.stream_inputs {
+                    Some(stream_inputs) => task_definition.coerce_inputs_except(inputs, &stream_inputs.names())?,
+                    None => task_definition.coerce_inputs(inputs)?,
+                };
+            }
+        }
+        Ok(())
+    }
+
+    // Everything submit_job checks, plus the task's source and which live workers
+    // could take it, without announcing anything. Listens for heartbeats for `window`.
+    pub async fn dry_run(&self, mut job: Job, window: Duration) -> Result<DryRunReport> {
+        let mut problems = Vec::new();
+        if let Err(e) = self.prepare(&mut job) {
+            problems.push(format!("{:#}", e));
+        }
+        let inputs_offloaded = match self.inputs_offload.offload(&mut job.clone(), &self.keyspace) {
+            Ok(offloaded) => offloaded.is_some(),
+            Err(e) => {
+                problems.push(format!("{:#}", e));
+                false
+            }
+        };
+        if let Some(task_definition) = &job.task_definition {
+            if let Some(pin) = &job.definition_pin {
+                if let Err(e) = pin.verify(task_definition) {
+                    problems.push(format!("{:#}", e));
+                }
+            }
+            if let Err(e) = task_definition.source.check() {
+                problems.push(format!("{:#}", e));
+            }
+        }
+
+        let workers: Vec<WorkerMatch> = self
+            .live_workers(window)
+            .await?
+            .into_iter()
+            .map(|(worker_id, info)| WorkerMatch {
+                unfit: worker_unfit(info.as_ref(), job.task_definition.as_ref()),
+                status: info.map(|info| info.status),
+                worker_id,
+            })
+            .collect();
+        if workers.is_empty() {
+            problems.push(format!("No worker heartbeats on queue {}", self.keyspace.queue()));
+        } else if workers.iter().all(|worker| worker.unfit.is_some()) {
+            problems.push(format!("None of the {} live worker(s) on queue {} can take the job", workers.len(), self.keyspace.queue()));
+        }
+
+        Ok(DryRunReport {
+            backend: job.task_definition.as_ref().map(|task_definition| task_definition.backend().to_string()),
+            max_attempts: job.max_attempts(),
+            timeout_seconds: job.timeout_seconds,
+            task_id: job.task_id,
+            queue: job.queue,
+            inputs: job.inputs,
+            inputs_offloaded,
+            workers,
+            problems,
+        })
+    }
+
+    // Workers heard heartbeating on this client's queue within `window`, with the
+    // info they advertise, if any
+    pub async fn live_workers(&self, window: Duration) -> Result<Vec<(String, Option<WorkerInfo>)>> {
+        let heartbeats = self.session
+            .declare_subscriber(self.keyspace.all_heartbeats())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare heartbeat subscriber: {}", e))?;
+        let mut workers = BTreeMap::new();
+        let deadline = tokio::time::sleep(window);
+        tokio::pin!(deadline);
+        loop {
+            let sample = tokio::select! {
+                _ = &mut deadline => break,
+                sample = heartbeats.recv_async() => match sample {
+                    Ok(sample) => sample,
+                    Err(_) => break,
+                },
+            };
+            match deserialize_from_sample_with_context::<Heartbeat>(&sample, "heartbeat") {
+                Ok(heartbeat) => {
+                    workers.insert(heartbeat.worker_id, heartbeat.info);
+                }
+                Err(e) => detail!(self.verbosity, "⚠️  Skipping malformed heartbeat: {}", e),
+            }
+        }
+        Ok(workers.into_iter().collect())
+    }
+
+    // Announces a pre-built job, e.g. one without a task definition for handler-based workers
+    pub async fn submit_job(&self, mut job: Job) -> Result<TaskHandle> {
+        self.prepare(&mut job)?;
+        let offloaded = self.inputs_offload.offload(&mut job, &self.keyspace)?;
+
+        // Watch before announcing so a fast worker's result can't be missed
+        let mut handle = self.watch_result(&job.task_id).await?;
+        let receipts = self.session
+            .declare_subscriber(self.keyspace.receipt(&job.task_id))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare receipt subscriber: {}", e))?;
+
+        // Every message about this job carries the trace started here
+        let metadata = MessageMetadata::new_trace().with_accept_compression(self.accept_compression.clone());
+        if let Some(payload) = offloaded {
+            self.host_inputs(&job, payload).await?;
+        }
+        if self.coordination == Coordination::WorkStealing {
+            self.host_lock(&job, &metadata).await?;
+        }
+        publish_json_with_metadata(&self.session, &self.keyspace.announce(), &job, &metadata)
+            .await
+            .context("Failed to announce job")?;
+        // No assigner, no receipt
+        if self.coordination == Coordination::WorkStealing {
+            return Ok(handle);
+        }
+
+        handle.receipt = match tokio::time::timeout(RECEIPT_TIMEOUT, receipts.recv_async()).await {
+            Ok(Ok(sample)) => {
+                let receipt = deserialize_from_sample_with_context::<Receipt>(&sample, "receipt")?;
+                if let Some(signer) = &self.receipt_signer {
+                    signer.verify(&receipt)?;
+                }
+                Some(receipt)
+            }
+            _ => None,
+        };
+        Ok(handle)
+    }
+
+    // Hands `job` to the first worker querying its lock key and tells later ones who
+    // has it. Until then it is also served to workers asking for pending jobs. Lasts
+    // until the result shows up or the job's timeout has long passed.
+    async fn host_lock(&self, job: &Job, metadata: &MessageMetadata) -> Result<()> {
+        let lock_key = self.keyspace.lock(&job.task_id);
+        let pending_key = self.keyspace.pending(&job.task_id);
+        let locks = self.session
+            .declare_queryable(lock_key.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare lock queryable: {}", e))?;
+        let pending = self.session
+            .declare_queryable(pending_key.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare pending job queryable: {}", e))?;
+        let results = self.session
+            .declare_subscriber(self.keyspace.result(&job.task_id))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        let sender = self.session.zid().to_string();
+        let job_message = seal(&sender, job)?;
+        let attachment = metadata.to_attachment()?;
+        let lifetime = hosting_lifetime(job);
+        let job = job.clone();
+        let verbosity = self.verbosity;
+        tokio::spawn(async move {
+            let mut grant: Option<Assign> = None;
+            let deadline = tokio::time::sleep(lifetime);
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    _ = results.recv_async() => break,
+                    query = pending.recv_async(), if grant.is_none() => {
+                        let Ok(query) = query else { break };
+                        if let Err(e) = query.reply(pending_key.clone(), job_message.clone()).attachment(attachment.clone()).await {
+                            summary!(verbosity, "⚠️  Failed to reply with pending job {}: {}", job.task_id, e);
+                        }
+                    }
+                    query = locks.recv_async() => {
+                        let Ok(query) = query else { break };
+                        let claim = match query.payload() {
+                            Some(payload) => deserialize_payload_with_context::<Claim>(&lock_key, payload, query.attachment(), "lock request"),
+                            None => Err(anyhow::anyhow!("Lock requests carry the worker's claim")),
+                        };
+                        let reply = match claim {
+                            Ok(claim) => {
+                                let grant = grant.get_or_insert_with(|| {
+                                    detail!(verbosity, "🔒 Job {} taken by {}", job.task_id, claim.worker_id);
+                                    Assign {
+                                        task_id: job.task_id.clone(),
+                                        worker_id: claim.worker_id.clone(),
+                                        assigned_at: chrono::Utc::now(),
+                                        task_definition: job.task_definition.clone(),
+                                        inputs: job.inputs.clone(),
+                                        attempt: job.attempt,
+                                    }
+                                });
+                                match seal(&sender, &*grant) {
+                                    Ok(message) => query.reply(lock_key.clone(), message).attachment(attachment.clone()).await,
+                                    Err(e) => query.reply_err(format!("{:#}", e)).await,
+                                }
+                            }
+                            Err(e) => query.reply_err(format!("{:#}", e)).await,
+                        };
+                        if let Err(e) = reply {
+                            summary!(verbosity, "⚠️  Failed to answer lock request for job {}: {}", job.task_id, e);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    // Serves the offloaded inputs of `job` to every worker asking, e.g. again after a
+    // retry, until its result shows up or its timeout has long passed
+    async fn host_inputs(&self, job: &Job, payload: Vec<u8>) -> Result<()> {
+        let key = self.keyspace.inputs(&job.task_id);
+        let queries = self.session
+            .declare_queryable(key.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare inputs queryable: {}", e))?;
+        let results = self.session
+            .declare_subscriber(self.keyspace.result(&job.task_id))
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        detail!(self.verbosity, "📦 Serving {} bytes of inputs for job {} at {}", payload.len(), job.task_id, key);
+        let payload = ZBytes::from(payload);
+        let lifetime = hosting_lifetime(job);
+        let task_id = job.task_id.clone();
+        let verbosity = self.verbosity;
+        tokio::spawn(async move {
+            let deadline = tokio::time::sleep(lifetime);
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    _ = results.recv_async() => break,
+                    query = queries.recv_async() => {
+                        let Ok(query) = query else { break };
+                        if let Err(e) = query.reply(key.clone(), payload.clone()).await {
+                            summary!(verbosity, "⚠️  Failed to serve inputs of job {}: {}", task_id, e);
+                        }
+                    }
+                }
+            }
+        });
+        Ok(())
+    }
+
+    // Submits the definition the registry reference `name@version` resolves to, pinned
+    // so that workers run exactly the definition this client resolved. A bare `name`
+    // gets the version its rollout in the registry picks.
+    #[cfg(feature = "http")]
+    pub async fn submit_ref(&self, registry: &TaskRegistry, reference: &str, inputs: serde_json::Value) -> Result<TaskHandle> {
+        let reference = registry.route(reference, rand::random())?;
+        let (task_definition, pin) = registry.resolve(&reference).await?;
+        let mut job = Job::new_user_task(self.keyspace.queue().to_string(), task_definition, inputs);
+        job.definition_pin = Some(pin);
+        self.submit_job(job).await
+    }
+
+    // A handle on a task submitted earlier, e.g. by this client before it restarted.
+    // If the result was published in the meantime it is fetched from a storage on the
+    // queue's task keys; without one, only a result still to come is seen.
+    pub async fn attach(&self, task_id: &str) -> Result<TaskHandle> {
+        validate_key_segment("Task ID", task_id)?;
+        self.watch_result(task_id).await
+    }
+
+    // Subscribes first and queries the result store second, so a result published in
+    // between is caught by one or the other
+    async fn watch_result(&self, task_id: &str) -> Result<TaskHandle> {
+        let result_key = self.keyspace.result(task_id);
+        let subscriber = self.session
+            .declare_subscriber(result_key.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        // Workers that batch send the result on their batch key instead
+        let batches = self.session
+            .declare_subscriber(self.keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+        let session = self.session.clone();
+        let (result_tx, result_rx) = oneshot::channel();
+        let verbosity = self.verbosity;
+        tokio::spawn(async move {
+            match stored_result(&session, &result_key).await {
+                Ok(Some(result)) => {
+                    let _ = result_tx.send(result);
+                    return;
+                }
+                Ok(None) => {}
+                Err(e) => summary!(verbosity, "⚠️  {:#}", e),
+            }
+            loop {
+                let received = tokio::select! {
+                    sample = subscriber.recv_async() => match sample {
+                        Ok(sample) => vec![deserialize_from_sample_with_context::<TaskResult>(&sample, "result")],
+                        Err(_) => break,
+                    },
+                    sample = batches.recv_async() => match sample {
+                        Ok(sample) => unbatch_messages::<TaskResult>(&sample, &result_key, "result"),
+                        Err(_) => break,
+                    },
+                };
+                for result in received {
+                    match result {
+                        Ok(result) => {
+                            let _ = result_tx.send(result);
+                            return;
+                        }
+                        Err(e) => summary!(verbosity, "⚠️  Ignoring malformed result: {}", e),
+                    }
+                }
+            }
+        });
+        Ok(TaskHandle::new(task_id.to_string(), result_rx))
+    }
+}
+
+// The result held by storages (or any queryable) on `key`, if one answers
+async fn stored_result(session: &zenoh::Session, key: &str) -> Result<Option<TaskResult>> {
+    let replies = session
+        .get(key.to_string())
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to query stored result on {}: {}", key, e))?;
+    while let Ok(reply) = replies.recv_async().await {
+        if let Ok(sample) = reply.result() {
+            if let Ok(result) = deserialize_from_sample_with_context::<TaskResult>(sample, "stored result") {
+                return Ok(Some(result));
+            }
+        }
+    }
+    Ok(None)
+}
+
+impl TaskSubmitter for TaskClient {
+    async fn submit(&self, task_definition: TaskDefinition, inputs: serde_json::Value) -> Result<TaskHandle> {
+        let job = Job::new_user_task(self.keyspace.queue().to_string(), task_definition, inputs);
+        self.submit_job(job).await
+    }
+}
@@ -0,0 +1,409 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::execution_pool::default_execution_threads;
+use crate::workspace::default_workspace_root;
+use crate::batching::Batching;
+use crate::scheduling::{ClaimPolicy, Coordination};
+use crate::schema::{Job, DEFAULT_TIMEOUT_SECONDS};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::Compression;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkerConfig {
+    pub worker_id: String,
+    pub capabilities: Vec<String>,
+    // Build or rollout group, e.g. "canary", that fleet routing steers jobs by;
+    // DEFAULT_FLEET when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fleet: Option<String>,
+    pub backends: BackendSettings,
+    pub concurrency: ConcurrencySettings,
+    pub claims: ClaimPolicy,
+    // Must match the queue's clients: work_stealing only works with clients that
+    // host their jobs' locks
+    pub coordination: Coordination,
+    pub cache: CacheSettings,
+    pub sources: SourceSettings,
+    pub sandbox: SandboxSettings,
+    pub docker: DockerSettings,
+    pub microvm: MicrovmSettings,
+    pub zenoh: ZenohSettings,
+    pub verbosity: Verbosity,
+    // Run a built-in task through each enabled backend before taking work
+    pub self_test: bool,
+    // Run the benchmark built-in at startup and report its scores in WorkerInfo and
+    // claims, for SchedulingStrategy::Fastest
+    pub benchmark: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    // Languages / source kinds this worker is willing to execute
+    pub enabled: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencySettings {
+    pub max_concurrent_tasks: usize,
+    pub execution_threads: usize,
+    // How long tasks still running at shutdown get to finish before they are killed
+    pub shutdown_grace_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    pub dir: PathBuf,
+}
+
+// How task sources (URLs, gists, git repositories) are fetched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SourceSettings {
+    // Used for the GitHub API (gists); falls back to the GITHUB_TOKEN environment variable
+    pub github_token: Option<String>,
+    // Applies to connecting and, separately, to the whole download
+    pub download_timeout_seconds: u64,
+    pub max_download_bytes: u64,
+    // Retries of timeouts, connection failures and 5xx/429 responses
+    pub download_retries: u32,
+    // Proxy for URL, gist and git fetches, e.g. "http://proxy.local:3128". The
+    // standard HTTPS_PROXY/NO_PROXY variables are honoured as well when this is unset.
+    pub proxy: Option<String>,
+    // Comma-separated hosts and domains that bypass the proxy
+    pub no_proxy: Option<String>,
+    // PEM bundle of extra root certificates, e.g. for a TLS-inspecting proxy
+    pub ca_bundle: Option<PathBuf>,
+    // Air-gapped mode: nothing is fetched while executing tasks; remote sources must
+    // already be in the cache
+    pub offline: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxSettings {
+    pub workspace_root: PathBuf,
+    // Applied to tasks whose requirements leave these unset
+    pub default_timeout_seconds: Option<u64>,
+    pub default_disk_mb: Option<u64>,
+    pub dependency_policy: DependencyPolicy,
+    // Attach a debug bundle (workspace listing, inputs, runtimes, environment minus
+    // secrets, stderr) to the results of failed tasks
+    pub debug_bundles: bool,
+}
+
+// Whether dependency manifests of git and archive sources get installed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyPolicy {
+    // Tasks asking for it are not accepted
+    Never,
+    // Only for tasks that set `install_dependencies`
+    #[default]
+    OnRequest,
+    // Whenever a manifest is found
+    Always,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockerSettings {
+    // Image -> number of pre-started containers to keep ready for it
+    pub warm_pool: HashMap<String, usize>,
+    // Keep the last container alive this long for a next task with the same
+    // image and command
+    pub reuse_seconds: Option<u64>,
+}
+
+// Experimental Firecracker backend for tasks requiring `isolation: microvm`; both
+// the kernel and the rootfs template must be set for it to be used
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MicrovmSettings {
+    pub firecracker_binary: PathBuf,
+    pub kernel_image: Option<PathBuf>,
+    pub rootfs_template: Option<PathBuf>,
+    pub vcpus: u32,
+    pub memory_mb: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZenohSettings {
+    pub mode: String,
+    pub connect: Vec<String>,
+    pub listen: Vec<String>,
+    // For results and other messages this worker publishes
+    pub compression: Compression,
+    // Publish statuses and results in micro-batches on the worker's batch key;
+    // only consumers that unbatch (this crate's) see them, so it is opt-in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batching: Option<Batching>,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            worker_id: format!("worker-{}", &uuid::Uuid::new_v4().to_string()[..8]),
+            capabilities: Vec::new(),
+            fleet: None,
+            backends: BackendSettings::default(),
+            concurrency: ConcurrencySettings::default(),
+            claims: ClaimPolicy::default(),
+            coordination: Coordination::default(),
+            cache: CacheSettings::default(),
+            sources: SourceSettings::default(),
+            sandbox: SandboxSettings::default(),
+            docker: DockerSettings::default(),
+            microvm: MicrovmSettings::default(),
+            zenoh: ZenohSettings::default(),
+            verbosity: Verbosity::default(),
+            self_test: true,
+            benchmark: false,
+        }
+    }
+}
+
+impl Default for BackendSettings {
+    fn default() -> Self {
+        let mut enabled = vec!["python".to_string(), "javascript".to_string(), "builtin".to_string()];
+        if cfg!(feature = "wasm") {
+            enabled.push("wasm".to_string());
+        }
+        if cfg!(feature = "docker") {
+            enabled.push("docker".to_string());
+        }
+        if cfg!(feature = "expression") {
+            enabled.push("expression".to_string());
+        }
+        if cfg!(feature = "firecracker") {
+            enabled.push("microvm".to_string());
+        }
+        Self { enabled }
+    }
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_tasks: 1,
+            execution_threads: default_execution_threads(),
+            shutdown_grace_seconds: 30,
+        }
+    }
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        Self {
+            dir: std::env::temp_dir().join("corebrum-cache"),
+        }
+    }
+}
+
+impl Default for SandboxSettings {
+    fn default() -> Self {
+        Self {
+            workspace_root: default_workspace_root(),
+            default_timeout_seconds: Some(DEFAULT_TIMEOUT_SECONDS),
+            default_disk_mb: None,
+            dependency_policy: DependencyPolicy::default(),
+            debug_bundles: false,
+        }
+    }
+}
+
+impl Default for MicrovmSettings {
+    fn default() -> Self {
+        Self {
+            firecracker_binary: PathBuf::from("firecracker"),
+            kernel_image: None,
+            rootfs_template: None,
+            vcpus: 1,
+            memory_mb: 256,
+        }
+    }
+}
+
+impl Default for SourceSettings {
+    fn default() -> Self {
+        Self {
+            github_token: None,
+            download_timeout_seconds: 30,
+            max_download_bytes: 16 * 1024 * 1024,
+            download_retries: 3,
+            proxy: None,
+            no_proxy: None,
+            ca_bundle: None,
+            offline: false,
+        }
+    }
+}
+
+impl SourceSettings {
+    pub fn github_token(&self) -> Option<String> {
+        self.github_token
+            .clone()
+            .or_else(|| std::env::var("GITHUB_TOKEN").ok())
+            .filter(|token| !token.is_empty())
+    }
+
+    // Also enabled by COREBRUM_OFFLINE=1
+    pub fn offline(&self) -> bool {
+        self.offline || non_empty_env("COREBRUM_OFFLINE").is_some_and(|value| value != "0" && value != "false")
+    }
+
+    // The settings below fall back to COREBRUM_PROXY, COREBRUM_NO_PROXY and
+    // COREBRUM_CA_BUNDLE
+
+    pub fn proxy(&self) -> Option<String> {
+        self.proxy.clone().or_else(|| non_empty_env("COREBRUM_PROXY"))
+    }
+
+    pub fn no_proxy(&self) -> Option<String> {
+        self.no_proxy.clone().or_else(|| non_empty_env("COREBRUM_NO_PROXY"))
+    }
+
+    pub fn ca_bundle(&self) -> Option<PathBuf> {
+        self.ca_bundle.clone().or_else(|| non_empty_env("COREBRUM_CA_BUNDLE").map(PathBuf::from))
+    }
+}
+
+impl MicrovmSettings {
+    pub fn is_configured(&self) -> bool {
+        self.kernel_image.is_some() && self.rootfs_template.is_some()
+    }
+}
+
+impl Default for ZenohSettings {
+    fn default() -> Self {
+        Self {
+            mode: "peer".to_string(),
+            connect: Vec::new(),
+            listen: Vec::new(),
+            compression: Compression::default(),
+            batching: None,
+        }
+    }
+}
+
+// Format is picked from the extension: .toml, or .yaml/.yml
+fn load_config<T: serde::de::DeserializeOwned>(path: &Path, kind: &str) -> Result<T> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {} config {}", kind, path.display()))?;
+    let config = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&content)
+            .with_context(|| format!("Invalid TOML in {}", path.display()))?,
+        Some("yaml") | Some("yml") => serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid YAML in {}", path.display()))?,
+        _ => anyhow::bail!("Unsupported {} config format: {}", kind, path.display()),
+    };
+    Ok(config)
+}
+
+impl WorkerConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        load_config(path.as_ref(), "worker")
+    }
+
+    pub fn backend_enabled(&self, backend: &str) -> bool {
+        self.backends.enabled.iter().any(|enabled| enabled == backend)
+    }
+}
+
+// Requirements a queue's tasks get at submission when they leave them unset; values
+// the task sets itself always win
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueDefaults {
+    pub timeout_seconds: Option<u64>,
+    pub memory_mb: Option<u64>,
+    pub cpu_cores: Option<u32>,
+    pub disk_mb: Option<u64>,
+    // Announcements before the assigner gives up, retries included
+    pub max_attempts: Option<u32>,
+}
+
+// Operator-wide `defaults`, overridden field by field under `queues.<name>`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueueSettings {
+    pub defaults: QueueDefaults,
+    pub queues: HashMap<String, QueueDefaults>,
+}
+
+impl QueueSettings {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        load_config(path.as_ref(), "queue")
+    }
+
+    pub fn for_queue(&self, queue: &str) -> QueueDefaults {
+        match self.queues.get(queue) {
+            Some(overrides) => overrides.or(&self.defaults),
+            None => self.defaults.clone(),
+        }
+    }
+}
+
+impl QueueDefaults {
+    // These values, falling back to `base` for the ones left unset
+    pub fn or(&self, base: &QueueDefaults) -> QueueDefaults {
+        QueueDefaults {
+            timeout_seconds: self.timeout_seconds.or(base.timeout_seconds),
+            memory_mb: self.memory_mb.or(base.memory_mb),
+            cpu_cores: self.cpu_cores.or(base.cpu_cores),
+            disk_mb: self.disk_mb.or(base.disk_mb),
+            max_attempts: self.max_attempts.or(base.max_attempts),
+        }
+    }
+
+    // Fills in what the job's task definition leaves unset. Pinned definitions run
+    // exactly as resolved and handler jobs have no requirements, so both keep theirs.
+    pub fn apply(&self, job: &mut Job) {
+        if *self == QueueDefaults::default() || job.definition_pin.is_some() {
+            return;
+        }
+        let Some(task_definition) = job.task_definition.as_mut() else {
+            return;
+        };
+        let requirements = task_definition.requirements.get_or_insert_with(Default::default);
+        requirements.timeout_seconds = requirements.timeout_seconds.or(self.timeout_seconds);
+        requirements.memory_mb = requirements.memory_mb.or(self.memory_mb);
+        requirements.cpu_cores = requirements.cpu_cores.or(self.cpu_cores);
+        requirements.disk_mb = requirements.disk_mb.or(self.disk_mb);
+        requirements.max_attempts = requirements.max_attempts.or(self.max_attempts);
+        if let Some(timeout_seconds) = requirements.timeout_seconds {
+            job.timeout_seconds = Some(timeout_seconds);
+        }
+    }
+}
+
+impl ZenohSettings {
+    pub fn to_zenoh_config(&self) -> Result<zenoh::Config> {
+        let mut config = zenoh::Config::default();
+        config
+            .insert_json5("mode", &serde_json::to_string(&self.mode)?)
+            .map_err(|e| anyhow::anyhow!("Invalid Zenoh mode: {}", e))?;
+        if !self.connect.is_empty() {
+            config
+                .insert_json5("connect/endpoints", &serde_json::to_string(&self.connect)?)
+                .map_err(|e| anyhow::anyhow!("Invalid Zenoh connect endpoints: {}", e))?;
+        }
+        if !self.listen.is_empty() {
+            config
+                .insert_json5("listen/endpoints", &serde_json::to_string(&self.listen)?)
+                .map_err(|e| anyhow::anyhow!("Invalid Zenoh listen endpoints: {}", e))?;
+        }
+        Ok(config)
+    }
+}
+
+fn non_empty_env(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
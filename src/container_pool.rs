@@ -0,0 +1,561 @@
+use anyhow::{Context, Result};
+use bollard::container::{Config, CreateContainerOptions, LogOutput, LogsOptions, RemoveContainerOptions, WaitContainerOptions};
+use bollard::exec::{CreateExecOptions, StartExecResults};
+use bollard::image::CreateImageOptions;
+use bollard::models::HostConfig;
+use bollard::Docker;
+use futures::StreamExt;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use crate::process::{CappedOutput, MAX_PROCESS_OUTPUT_BYTES};
+use crate::verbosity::Verbosity;
+use crate::{detail, summary};
+
+// Where a container sees its host workdir, and the file task inputs are written to
+pub const CONTAINER_WORKDIR: &str = "/workspace";
+pub const CONTAINER_INPUTS_FILE: &str = "inputs.json";
+const POOL_LABEL: &str = "corebrum.pool";
+// On containers started for a single task
+const TASK_LABEL: &str = "corebrum.task";
+
+// A started container idling on `sleep infinity`, with a host directory bind
+// mounted at CONTAINER_WORKDIR. Force-removed if dropped rather than discarded or
+// parked, e.g. when the task running in it is cancelled.
+#[derive(Debug)]
+pub struct WarmContainer {
+    id: String,
+    image: String,
+    workdir: PathBuf,
+    // What it was started with; only tasks asking for the same get it
+    resources: ContainerResources,
+    docker: Docker,
+    verbosity: Verbosity,
+}
+
+// Caps for the container a task runs in, from the task's requirements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ContainerResources {
+    pub memory_mb: Option<u64>,
+    pub cpu_cores: Option<u32>,
+}
+
+// Force-removes a single-task container however its run ends, including by being
+// dropped when the task is cancelled
+struct OneShotContainer {
+    docker: Docker,
+    id: String,
+    verbosity: Verbosity,
+}
+
+#[derive(Debug)]
+pub struct ContainerOutput {
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    // Past MAX_PROCESS_OUTPUT_BYTES, like a process's output
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    pub exit_code: Option<i64>,
+    pub timed_out: bool,
+}
+
+// The most recently used container, kept around for a follow-up task with the
+// same image and command
+struct ParkedContainer {
+    key: u64,
+    container: WarmContainer,
+}
+
+// Pre-started containers per image that Docker tasks exec into instead of paying
+// for a cold start. By default every container serves a single task and is then
+// discarded and replaced in the background, so tasks never see each other's
+// leftovers. With reuse enabled the last container is instead parked for a while
+// and handed to the next task with the same image and command, after its workdir
+// has been wiped; the rest of its filesystem is not reset.
+pub struct ContainerPool {
+    docker: Docker,
+    workdir_root: PathBuf,
+    // Image -> number of idle containers to keep ready
+    targets: HashMap<String, usize>,
+    idle: Mutex<HashMap<String, Vec<WarmContainer>>>,
+    reuse_for: Option<Duration>,
+    parked: Mutex<Option<ParkedContainer>>,
+    // Never pull; images must already be present
+    offline: bool,
+    verbosity: Verbosity,
+}
+
+fn reuse_key(image: &str, command: &[String], resources: ContainerResources) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image.hash(&mut hasher);
+    command.hash(&mut hasher);
+    resources.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl ContainerPool {
+    pub fn new(docker: Docker, workdir_root: PathBuf, targets: HashMap<String, usize>) -> Self {
+        Self {
+            docker,
+            workdir_root,
+            targets,
+            idle: Mutex::new(HashMap::new()),
+            reuse_for: None,
+            parked: Mutex::new(None),
+            offline: false,
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    pub fn with_reuse(mut self, reuse_for: Duration) -> Self {
+        self.reuse_for = Some(reuse_for);
+        self
+    }
+
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn connect(workdir_root: PathBuf, targets: HashMap<String, usize>) -> Result<Self> {
+        let docker = Docker::connect_with_local_defaults().context("Failed to connect to the Docker daemon")?;
+        Ok(Self::new(docker, workdir_root, targets))
+    }
+
+    pub fn docker(&self) -> &Docker {
+        &self.docker
+    }
+
+    fn idle_lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Vec<WarmContainer>>> {
+        self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn idle_count(&self, image: &str) -> usize {
+        self.idle_lock().get(image).map_or(0, Vec::len)
+    }
+
+    // Pulls the pooled images and starts containers until every target is met
+    pub async fn fill(&self) -> Result<()> {
+        for (image, &target) in &self.targets {
+            self.ensure_image(image).await?;
+            while self.idle_count(image) < target {
+                let container = self.start_container(image, ContainerResources::default()).await?;
+                self.idle_lock().entry(image.clone()).or_default().push(container);
+            }
+            detail!(self.verbosity, "🐳 {} warm container(s) ready for {}", target, image);
+        }
+        Ok(())
+    }
+
+    fn parked_lock(&self) -> std::sync::MutexGuard<'_, Option<ParkedContainer>> {
+        self.parked.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // A container to run `command` of `image` in under `resources`: the parked one if
+    // it matches, else a warm one, else (with reuse enabled) a freshly started one.
+    // Warm containers are started without caps, so tasks with memory or CPU
+    // requirements never get one; without reuse they are cold-started instead.
+    pub async fn acquire(self: &Arc<Self>, image: &str, command: &[String], resources: ContainerResources) -> Result<Option<WarmContainer>> {
+        let key = reuse_key(image, command, resources);
+        let parked = {
+            let mut parked = self.parked_lock();
+            match parked.as_ref() {
+                Some(candidate) if candidate.key == key => parked.take().map(|parked| parked.container),
+                _ => None,
+            }
+        };
+        if let Some(container) = parked {
+            return Ok(Some(container));
+        }
+        if resources == ContainerResources::default() {
+            if let Some(container) = self.take(image) {
+                return Ok(Some(container));
+            }
+        }
+        if self.reuse_for.is_none() {
+            return Ok(None);
+        }
+        self.ensure_image(image).await?;
+        self.start_container(image, resources).await.map(Some)
+    }
+
+    // Hands a container back after a task: parked for reuse if enabled and the task
+    // ended cleanly, discarded otherwise
+    pub async fn release(self: &Arc<Self>, container: WarmContainer, command: &[String], clean: bool) {
+        let Some(reuse_for) = self.reuse_for.filter(|_| clean) else {
+            self.discard(container).await;
+            return;
+        };
+        // Isolation between tasks: nothing written to the workdir survives
+        if let Err(e) = reset_dir(&container.workdir) {
+            summary!(self.verbosity, "⚠️  Failed to reset workdir of container {}: {}", container.id, e);
+            self.discard(container).await;
+            return;
+        }
+
+        let id = container.id.clone();
+        let key = reuse_key(&container.image, command, container.resources);
+        let previous = self.parked_lock().replace(ParkedContainer { key, container });
+        if let Some(previous) = previous {
+            self.discard(previous.container).await;
+        }
+
+        let pool = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(reuse_for).await;
+            let expired = {
+                let mut parked = pool.parked_lock();
+                match parked.as_ref() {
+                    Some(candidate) if candidate.container.id == id => parked.take(),
+                    _ => None,
+                }
+            };
+            if let Some(expired) = expired {
+                pool.discard(expired.container).await;
+            }
+        });
+    }
+
+    // An idle container for `image`, if one is ready; a replacement is started in
+    // the background
+    pub fn take(self: &Arc<Self>, image: &str) -> Option<WarmContainer> {
+        let container = self.idle_lock().get_mut(image)?.pop()?;
+        let pool = self.clone();
+        let image = image.to_string();
+        tokio::spawn(async move {
+            match pool.start_container(&image, ContainerResources::default()).await {
+                Ok(replacement) => pool.idle_lock().entry(image).or_default().push(replacement),
+                Err(e) => summary!(pool.verbosity, "⚠️  Failed to replenish warm container for {}: {}", image, e),
+            }
+        });
+        Some(container)
+    }
+
+    // Runs `command` in the container with `inputs` written to CONTAINER_INPUTS_FILE,
+    // then releases the container
+    pub async fn run(
+        self: &Arc<Self>,
+        container: WarmContainer,
+        command: &[String],
+        inputs: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<ContainerOutput> {
+        let outcome = self.exec(&container, command, inputs, timeout).await;
+        // A timed-out exec may still be running in there
+        let clean = outcome.as_ref().is_ok_and(|output| !output.timed_out);
+        self.release(container, command, clean).await;
+        outcome
+    }
+
+    // Cold start: pulls `image` if needed and runs `command` in a fresh container
+    // that is removed afterwards. `workdir` is bind mounted at CONTAINER_WORKDIR with
+    // `inputs` written to CONTAINER_INPUTS_FILE in it. An empty command runs the
+    // image's own entrypoint and command.
+    pub async fn run_once(
+        &self,
+        image: &str,
+        command: &[String],
+        workdir: &Path,
+        inputs: &serde_json::Value,
+        resources: ContainerResources,
+        timeout: Option<Duration>,
+    ) -> Result<ContainerOutput> {
+        self.ensure_image(image).await?;
+        std::fs::write(workdir.join(CONTAINER_INPUTS_FILE), serde_json::to_vec(inputs)?)?;
+
+        let config = Config {
+            image: Some(image.to_string()),
+            // Replacing the entrypoint also drops the image's command
+            entrypoint: (!command.is_empty()).then(|| command.to_vec()),
+            working_dir: Some(CONTAINER_WORKDIR.to_string()),
+            env: Some(vec![inputs_env()]),
+            labels: Some(HashMap::from([(TASK_LABEL.to_string(), "true".to_string())])),
+            host_config: Some(host_config(workdir, resources)),
+            ..Default::default()
+        };
+        let options = CreateContainerOptions {
+            name: format!("corebrum-task-{}", uuid::Uuid::new_v4()),
+            platform: None,
+        };
+        let created = self
+            .docker
+            .create_container(Some(options), config)
+            .await
+            .with_context(|| format!("Failed to create container from {}", image))?;
+        let container = OneShotContainer {
+            docker: self.docker.clone(),
+            id: created.id,
+            verbosity: self.verbosity,
+        };
+        self.docker
+            .start_container::<String>(&container.id, None)
+            .await
+            .with_context(|| format!("Failed to start container {}", container.id))?;
+        detail!(self.verbosity, "🐳 Started container {} from {}", container.id, image);
+
+        let mut stdout = CappedOutput::new(MAX_PROCESS_OUTPUT_BYTES);
+        let mut stderr = CappedOutput::new(MAX_PROCESS_OUTPUT_BYTES);
+        let collect = async {
+            let options = LogsOptions::<String> {
+                follow: true,
+                stdout: true,
+                stderr: true,
+                ..Default::default()
+            };
+            // Ends once the container has exited
+            let mut logs = self.docker.logs(&container.id, Some(options));
+            while let Some(chunk) = logs.next().await {
+                match chunk? {
+                    LogOutput::StdOut { message } => stdout.push(&message),
+                    LogOutput::StdErr { message } => stderr.push(&message),
+                    _ => {}
+                }
+            }
+            // bollard reports a non-zero exit as an error
+            let mut wait = self.docker.wait_container(&container.id, None::<WaitContainerOptions<String>>);
+            match wait.next().await {
+                Some(Ok(exited)) => anyhow::Ok(Some(exited.status_code)),
+                Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. })) => Ok(Some(code)),
+                Some(Err(e)) => Err(e).with_context(|| format!("Failed to wait for container {}", container.id)),
+                None => Ok(None),
+            }
+        };
+        let (exit_code, timed_out) = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, collect).await {
+                Ok(exit_code) => (exit_code?, false),
+                Err(_) => (None, true),
+            },
+            None => (collect.await?, false),
+        };
+        let (stdout, stdout_truncated) = stdout.finish();
+        let (stderr, stderr_truncated) = stderr.finish();
+        Ok(ContainerOutput {
+            stdout,
+            stderr,
+            stdout_truncated,
+            stderr_truncated,
+            exit_code,
+            timed_out,
+        })
+    }
+
+    async fn exec(
+        &self,
+        container: &WarmContainer,
+        command: &[String],
+        inputs: &serde_json::Value,
+        timeout: Option<Duration>,
+    ) -> Result<ContainerOutput> {
+        std::fs::write(container.workdir.join(CONTAINER_INPUTS_FILE), serde_json::to_vec(inputs)?)?;
+
+        let exec = self
+            .docker
+            .create_exec(
+                &container.id,
+                CreateExecOptions {
+                    cmd: Some(command.to_vec()),
+                    attach_stdout: Some(true),
+                    attach_stderr: Some(true),
+                    working_dir: Some(CONTAINER_WORKDIR.to_string()),
+                    env: Some(vec![inputs_env()]),
+                    ..Default::default()
+                },
+            )
+            .await
+            .with_context(|| format!("Failed to create exec in container {}", container.id))?;
+
+        let mut stdout = CappedOutput::new(MAX_PROCESS_OUTPUT_BYTES);
+        let mut stderr = CappedOutput::new(MAX_PROCESS_OUTPUT_BYTES);
+        let collect = async {
+            if let StartExecResults::Attached { mut output, .. } = self.docker.start_exec(&exec.id, None).await? {
+                while let Some(chunk) = output.next().await {
+                    match chunk? {
+                        LogOutput::StdOut { message } => stdout.push(&message),
+                        LogOutput::StdErr { message } => stderr.push(&message),
+                        _ => {}
+                    }
+                }
+            }
+            anyhow::Ok(())
+        };
+        let timed_out = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, collect).await {
+                Ok(collected) => {
+                    collected?;
+                    false
+                }
+                Err(_) => true,
+            },
+            None => {
+                collect.await?;
+                false
+            }
+        };
+
+        let exit_code = if timed_out {
+            None
+        } else {
+            self.docker.inspect_exec(&exec.id).await?.exit_code
+        };
+        let (stdout, stdout_truncated) = stdout.finish();
+        let (stderr, stderr_truncated) = stderr.finish();
+        Ok(ContainerOutput {
+            stdout,
+            stderr,
+            stdout_truncated,
+            stderr_truncated,
+            exit_code,
+            timed_out,
+        })
+    }
+
+    async fn discard(&self, mut container: WarmContainer) {
+        let options = RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        };
+        if let Err(e) = self.docker.remove_container(&container.id, Some(options)).await {
+            summary!(self.verbosity, "⚠️  Failed to remove container {}: {}", container.id, e);
+        }
+        let _ = std::fs::remove_dir_all(&container.workdir);
+        // Already gone; nothing left for drop to do
+        container.id.clear();
+    }
+
+    // Removes every idle and parked container; called when the worker stops
+    pub async fn shutdown(&self) {
+        let mut containers: Vec<WarmContainer> = self.idle_lock().drain().flat_map(|(_, containers)| containers).collect();
+        containers.extend(self.parked_lock().take().map(|parked| parked.container));
+        for container in containers {
+            self.discard(container).await;
+        }
+    }
+
+    async fn ensure_image(&self, image: &str) -> Result<()> {
+        if self.docker.inspect_image(image).await.is_ok() {
+            return Ok(());
+        }
+        if self.offline {
+            anyhow::bail!("Offline mode: image {} is not available locally", image);
+        }
+        detail!(self.verbosity, "📥 Pulling image {}", image);
+        let options = CreateImageOptions {
+            from_image: image.to_string(),
+            ..Default::default()
+        };
+        let mut pull = self.docker.create_image(Some(options), None, None);
+        while let Some(progress) = pull.next().await {
+            progress.with_context(|| format!("Failed to pull image {}", image))?;
+        }
+        Ok(())
+    }
+
+    async fn start_container(&self, image: &str, resources: ContainerResources) -> Result<WarmContainer> {
+        let name = format!("corebrum-warm-{}", uuid::Uuid::new_v4());
+        let workdir = self.workdir_root.join(&name);
+        std::fs::create_dir_all(&workdir)?;
+
+        let config = Config {
+            image: Some(image.to_string()),
+            entrypoint: Some(vec!["sleep".to_string()]),
+            cmd: Some(vec!["infinity".to_string()]),
+            working_dir: Some(CONTAINER_WORKDIR.to_string()),
+            labels: Some(HashMap::from([(POOL_LABEL.to_string(), "true".to_string())])),
+            host_config: Some(host_config(&workdir, resources)),
+            ..Default::default()
+        };
+        let options = CreateContainerOptions {
+            name: name.clone(),
+            platform: None,
+        };
+        let created = self
+            .docker
+            .create_container(Some(options), config)
+            .await
+            .with_context(|| format!("Failed to create container from {}", image))?;
+        self.docker
+            .start_container::<String>(&created.id, None)
+            .await
+            .with_context(|| format!("Failed to start container {}", created.id))?;
+        Ok(WarmContainer {
+            id: created.id,
+            image: image.to_string(),
+            workdir,
+            resources,
+            docker: self.docker.clone(),
+            verbosity: self.verbosity,
+        })
+    }
+}
+
+impl WarmContainer {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn image(&self) -> &str {
+        &self.image
+    }
+}
+
+impl Drop for WarmContainer {
+    fn drop(&mut self) {
+        if self.id.is_empty() {
+            return;
+        }
+        let _ = std::fs::remove_dir_all(&self.workdir);
+        remove_in_background(&self.docker, std::mem::take(&mut self.id), self.verbosity);
+    }
+}
+
+impl Drop for OneShotContainer {
+    fn drop(&mut self) {
+        remove_in_background(&self.docker, std::mem::take(&mut self.id), self.verbosity);
+    }
+}
+
+// Force-removes a container from a destructor, which can't wait for it; a no-op
+// outside of a Tokio runtime
+fn remove_in_background(docker: &Docker, id: String, verbosity: Verbosity) {
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let docker = docker.clone();
+    runtime.spawn(async move {
+        let options = RemoveContainerOptions {
+            force: true,
+            ..Default::default()
+        };
+        if let Err(e) = docker.remove_container(&id, Some(options)).await {
+            summary!(verbosity, "⚠️  Failed to remove container {}: {}", id, e);
+        }
+    });
+}
+
+// `workdir` bind mounted at CONTAINER_WORKDIR, under `resources`
+fn host_config(workdir: &Path, resources: ContainerResources) -> HostConfig {
+    HostConfig {
+        binds: Some(vec![format!("{}:{}", workdir.display(), CONTAINER_WORKDIR)]),
+        memory: resources.memory_mb.map(|mb| i64::try_from(mb.saturating_mul(1024 * 1024)).unwrap_or(i64::MAX)),
+        nano_cpus: resources.cpu_cores.map(|cores| i64::from(cores).saturating_mul(1_000_000_000)),
+        ..Default::default()
+    }
+}
+
+// Tells the task's command where its inputs are
+fn inputs_env() -> String {
+    format!("COREBRUM_INPUTS={}/{}", CONTAINER_WORKDIR, CONTAINER_INPUTS_FILE)
+}
+
+fn reset_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    std::fs::remove_dir_all(dir)?;
+    std::fs::create_dir_all(dir)
+}
@@ -0,0 +1,165 @@
+use anyhow::{Context, Result};
+use polars::prelude::*;
+use std::fs::File;
+use std::path::Path;
+use crate::schema::{AggFunc, Aggregation, Comparison, DatasetFormat, FrameOperation};
+use crate::task_archive::ArchivedTask;
+
+// Shape of a transform's result
+#[derive(Debug, Clone)]
+pub struct FrameSummary {
+    pub rows: usize,
+    pub columns: Vec<String>,
+}
+
+// Reads the dataset at `input`, applies the operations in order and writes the result
+// to `output`. Blocking, so call this from the execution pool.
+pub fn transform_dataset(
+    input: &Path,
+    input_format: DatasetFormat,
+    operations: &[FrameOperation],
+    output: &Path,
+    output_format: DatasetFormat,
+) -> Result<FrameSummary> {
+    let mut frame = read_dataset(input, input_format)?;
+    for (index, operation) in operations.iter().enumerate() {
+        frame = apply(frame, operation).with_context(|| format!("Invalid operation {} ({:?})", index + 1, operation))?;
+    }
+    let mut df = frame.collect().context("DataFrame transform failed")?;
+
+    let file = File::create(output).with_context(|| format!("Failed to create {}", output.display()))?;
+    match output_format {
+        DatasetFormat::Parquet => ParquetWriter::new(file).finish(&mut df).map(|_| ())?,
+        DatasetFormat::Csv => CsvWriter::new(file).include_header(true).finish(&mut df)?,
+        DatasetFormat::Json => JsonWriter::new(file).with_json_format(JsonFormat::Json).finish(&mut df)?,
+    }
+    Ok(FrameSummary {
+        rows: df.height(),
+        columns: df.get_column_names().iter().map(|name| name.to_string()).collect(),
+    })
+}
+
+// One row per task; nested values (definition, inputs, outputs) are kept as JSON
+// strings and timestamps as Unix milliseconds
+pub fn write_task_archive(path: &Path, tasks: &[ArchivedTask]) -> Result<()> {
+    let millis = |at: Option<chrono::DateTime<chrono::Utc>>| at.map(|at| at.timestamp_millis());
+    let mut df = DataFrame::new(vec![
+        Series::new("task_id", tasks.iter().map(|t| t.task_id.as_str()).collect::<Vec<_>>()),
+        Series::new("queue", tasks.iter().map(|t| t.queue.as_str()).collect::<Vec<_>>()),
+        Series::new("name", tasks.iter().map(|t| t.name()).collect::<Vec<_>>()),
+        Series::new("attempt", tasks.iter().map(|t| t.attempt).collect::<Vec<_>>()),
+        Series::new("status", tasks.iter().map(|t| format!("{:?}", t.status)).collect::<Vec<_>>()),
+        Series::new("worker_id", tasks.iter().map(|t| t.worker_id.as_deref()).collect::<Vec<_>>()),
+        Series::new("submitted_at_ms", tasks.iter().map(|t| millis(t.submitted_at)).collect::<Vec<_>>()),
+        Series::new("started_at_ms", tasks.iter().map(|t| millis(t.started_at)).collect::<Vec<_>>()),
+        Series::new("finished_at_ms", tasks.iter().map(|t| t.finished_at.timestamp_millis()).collect::<Vec<_>>()),
+        Series::new("total_seconds", tasks.iter().map(|t| t.total_seconds()).collect::<Vec<_>>()),
+        Series::new("execution_seconds", tasks.iter().map(|t| t.execution_seconds()).collect::<Vec<_>>()),
+        Series::new(
+            "error",
+            tasks.iter().map(|t| t.result.as_ref().and_then(|r| r.error.as_deref())).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "task_definition",
+            tasks.iter().map(|t| t.task_definition.as_ref().and_then(|td| serde_json::to_string(td).ok())).collect::<Vec<_>>(),
+        ),
+        Series::new("inputs", tasks.iter().map(|t| t.inputs.to_string()).collect::<Vec<_>>()),
+        Series::new(
+            "outputs",
+            tasks.iter().map(|t| t.result.as_ref().and_then(|r| serde_json::to_string(&r.outputs).ok())).collect::<Vec<_>>(),
+        ),
+    ])?;
+
+    let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    ParquetWriter::new(file)
+        .finish(&mut df)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+fn read_dataset(path: &Path, format: DatasetFormat) -> Result<LazyFrame> {
+    let frame = match format {
+        DatasetFormat::Csv => LazyCsvReader::new(path).with_has_header(true).finish()?,
+        DatasetFormat::Parquet => LazyFrame::scan_parquet(path, ScanArgsParquet::default())?,
+        // A JSON array of rows, or one object per line
+        DatasetFormat::Json => {
+            let text = std::fs::read(path)?;
+            let json_format = match text.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'[') => JsonFormat::Json,
+                _ => JsonFormat::JsonLines,
+            };
+            JsonReader::new(std::io::Cursor::new(text)).with_json_format(json_format).finish()?.lazy()
+        }
+    };
+    Ok(frame)
+}
+
+fn apply(frame: LazyFrame, operation: &FrameOperation) -> Result<LazyFrame> {
+    let columns = |names: &[String]| names.iter().map(|name| col(name.as_str())).collect::<Vec<_>>();
+    let frame = match operation {
+        FrameOperation::Select { columns: names } => frame.select(columns(names)),
+        FrameOperation::Drop { columns: names } => frame.drop(names),
+        FrameOperation::Rename { columns: names } => frame.rename(names.keys(), names.values()),
+        FrameOperation::Filter { column, cmp, value } => frame.filter(predicate(col(column.as_str()), *cmp, value)?),
+        FrameOperation::DropNulls { columns: names } => frame.drop_nulls((!names.is_empty()).then(|| columns(names))),
+        FrameOperation::GroupBy { by, aggs } => {
+            if aggs.is_empty() {
+                anyhow::bail!("group_by needs at least one aggregation");
+            }
+            // Stable, so groups come out in order of first appearance
+            frame.group_by_stable(columns(by)).agg(aggs.iter().map(aggregation).collect::<Vec<_>>())
+        }
+        FrameOperation::Sort { by, descending } => {
+            frame.sort_by_exprs(columns(by), SortMultipleOptions::default().with_order_descending(*descending))
+        }
+        FrameOperation::Limit { n } => frame.limit(*n as IdxSize),
+    };
+    Ok(frame)
+}
+
+fn predicate(column: Expr, cmp: Comparison, value: &serde_json::Value) -> Result<Expr> {
+    // Only equality makes sense against null
+    let value = match value {
+        serde_json::Value::Null => {
+            return match cmp {
+                Comparison::Eq => Ok(column.is_null()),
+                Comparison::Ne => Ok(column.is_not_null()),
+                _ => anyhow::bail!("Only eq and ne can compare with null"),
+            };
+        }
+        serde_json::Value::Bool(flag) => lit(*flag),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => lit(integer),
+            None => lit(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        serde_json::Value::String(text) => lit(text.as_str()),
+        other => anyhow::bail!("Can't compare a column with {}", other),
+    };
+    Ok(match cmp {
+        Comparison::Eq => column.eq(value),
+        Comparison::Ne => column.neq(value),
+        Comparison::Gt => column.gt(value),
+        Comparison::Ge => column.gt_eq(value),
+        Comparison::Lt => column.lt(value),
+        Comparison::Le => column.lt_eq(value),
+    })
+}
+
+fn aggregation(aggregation: &Aggregation) -> Expr {
+    let column = col(aggregation.column.as_str());
+    let expr = match aggregation.func {
+        AggFunc::Sum => column.sum(),
+        AggFunc::Mean => column.mean(),
+        AggFunc::Min => column.min(),
+        AggFunc::Max => column.max(),
+        AggFunc::Count => column.count(),
+        AggFunc::First => column.first(),
+        AggFunc::Last => column.last(),
+        AggFunc::NUnique => column.n_unique(),
+    };
+    let alias = match &aggregation.alias {
+        Some(alias) => alias.clone(),
+        None => format!("{}_{}", aggregation.column, aggregation.func.name()),
+    };
+    expr.alias(&alias)
+}
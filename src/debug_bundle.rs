@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::schema::Result as TaskResult;
+
+// Artifact holding the DebugBundle of a failed task, as JSON
+pub const DEBUG_BUNDLE_ARTIFACT: &str = "debug-bundle.json";
+// Caps keeping a bundle small enough to travel inside the result
+const MAX_BUNDLE_FILES: usize = 500;
+const MAX_BUNDLE_INPUTS_BYTES: usize = 4 * 1024;
+const MAX_BUNDLE_STDERR_BYTES: usize = 16 * 1024;
+const REDACTED: &str = "<redacted>";
+// Environment variables whose names contain any of these have their values redacted
+const SECRET_MARKERS: &[&str] = &["SECRET", "TOKEN", "PASSWORD", "PASSWD", "KEY", "CREDENTIAL", "AUTH", "COOKIE", "SESSION"];
+
+// What a failed task's environment looked like on the worker: enough to reproduce
+// it elsewhere without asking the worker's operator
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebugBundle {
+    pub captured_at: chrono::DateTime<chrono::Utc>,
+    pub language: String,
+    pub source_kind: String,
+    pub error: Option<String>,
+    // Relative to the task workspace, with sizes, in path order
+    pub workspace_files: Vec<WorkspaceFile>,
+    // Set when the listing stopped at MAX_BUNDLE_FILES
+    #[serde(default)]
+    pub workspace_truncated: bool,
+    // JSON of the task inputs, cut after a few KiB
+    pub inputs: String,
+    // Interpreter the task ran with when it wasn't the one on the PATH, e.g. a venv
+    pub interpreter: Option<String>,
+    // `<backend>=<version>` of the worker's runtimes
+    #[serde(default)]
+    pub runtimes: Vec<String>,
+    // Environment the task process saw, with secrets redacted
+    pub env: BTreeMap<String, String>,
+    // The end of stderr, where the actual failure usually is
+    pub stderr_tail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceFile {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+impl DebugBundle {
+    pub fn from_result(result: &TaskResult) -> Option<Result<Self>> {
+        let content = result.artifacts.get(DEBUG_BUNDLE_ARTIFACT)?;
+        Some(serde_json::from_str(content).with_context(|| format!("Malformed {} of task {}", DEBUG_BUNDLE_ARTIFACT, result.task_id)))
+    }
+
+    // Rewrites the bundle already attached to `result`, if any
+    pub fn update(result: &mut TaskResult, change: impl FnOnce(&mut DebugBundle)) -> Result<()> {
+        let Some(bundle) = DebugBundle::from_result(result) else {
+            return Ok(());
+        };
+        let mut bundle = bundle?;
+        change(&mut bundle);
+        result.artifacts.insert(DEBUG_BUNDLE_ARTIFACT.to_string(), serde_json::to_string(&bundle)?);
+        Ok(())
+    }
+}
+
+pub(crate) fn list_workspace(root: &Path) -> (Vec<WorkspaceFile>, bool) {
+    let mut files = Vec::new();
+    let truncated = collect_files(root, root, &mut files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    (files, truncated)
+}
+
+// True once the cap was hit; symlinks are listed, not followed
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<WorkspaceFile>) -> bool {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return false;
+    };
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let Ok(metadata) = fs::symlink_metadata(&path) else { continue };
+        if metadata.is_dir() {
+            if collect_files(root, &path, files) {
+                return true;
+            }
+            continue;
+        }
+        if files.len() >= MAX_BUNDLE_FILES {
+            return true;
+        }
+        files.push(WorkspaceFile {
+            path: path.strip_prefix(root).unwrap_or(&path).to_string_lossy().into_owned(),
+            size_bytes: metadata.len(),
+        });
+    }
+    false
+}
+
+pub(crate) fn truncated_inputs(inputs: &serde_json::Value) -> String {
+    crate::dynamic_executor::truncate_output(inputs.to_string().as_bytes(), MAX_BUNDLE_INPUTS_BYTES)
+}
+
+pub(crate) fn stderr_tail(stderr: &[u8]) -> String {
+    let start = stderr.len().saturating_sub(MAX_BUNDLE_STDERR_BYTES);
+    let tail = String::from_utf8_lossy(&stderr[start..]).into_owned();
+    if start > 0 {
+        format!("[truncated] ...{}", tail)
+    } else {
+        tail
+    }
+}
+
+// The worker's environment overlaid with the task's own variables
+pub(crate) fn task_env(task_env: &[(String, String)]) -> BTreeMap<String, String> {
+    std::env::vars()
+        .chain(task_env.iter().cloned())
+        .map(|(name, value)| {
+            let value = if is_secret(&name) { REDACTED.to_string() } else { value };
+            (name, value)
+        })
+        .collect()
+}
+
+fn is_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    SECRET_MARKERS.iter().any(|marker| name.contains(marker))
+}
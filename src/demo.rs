@@ -0,0 +1,618 @@
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use corebrum_examples::ab_test::{compare_versions, AbOptions, AbReport};
+use corebrum_examples::client::TaskClient;
+use corebrum_examples::config::{QueueSettings, WorkerConfig};
+use corebrum_examples::debug_bundle::DebugBundle;
+use corebrum_examples::determinism::check_reproducible;
+use corebrum_examples::dry_run::{DryRunReport, WORKER_DISCOVERY_WINDOW};
+use corebrum_examples::gc::TaskGc;
+use corebrum_examples::keyspace::Keyspace;
+use corebrum_examples::replay::{replay, replay_locally, OutputChange, ReplayOptions, ReplayOutcome, ResultDiff};
+use corebrum_examples::task_archive::{ArchivedTask, TaskArchive, TaskArchiver};
+use corebrum_examples::verbosity::Verbosity;
+use corebrum_examples::worker::Worker;
+use corebrum_examples::schema::{Determinism, Estimate, Job, TaskDefinition, TaskFilter, TaskListRequest, TaskStatus, DEFAULT_TASK_PAGE_SIZE, MAX_TASK_TREE_NODES};
+use corebrum_examples::zenoh_utils::{create_zenoh_session, EmbeddedRouter, EMBEDDED_ROUTER_ENDPOINT};
+
+mod simple_zenoh_demo;
+mod user_demo;
+mod working_user_zenoh_demo;
+
+use simple_zenoh_demo::SimpleZenohDemo;
+use user_demo::UserDefinedDemo;
+use working_user_zenoh_demo::{DemoArgs, WorkingUserZenohDemo};
+
+#[derive(Debug, Parser)]
+#[command(name = "demo", about = "Corebrum mesh computing demos")]
+struct Cli {
+    #[arg(long, global = true, default_value = "debug", help = "off, summary or debug")]
+    verbosity: Verbosity,
+    #[command(subcommand)]
+    command: DemoCommand,
+}
+
+#[derive(Debug, Subcommand)]
+enum DemoCommand {
+    #[command(about = "Perception jobs on the 'perception' queue with simulated workers")]
+    Simple,
+    #[command(about = "User-defined factorial/fibonacci tasks executed by the dynamic executor")]
+    User {
+        #[arg(long, help = "Host a Zenoh router in-process instead of relying on scouting")]
+        embedded_router: bool,
+    },
+    #[command(about = "Tasks loaded from YAML/JSON files with URL, Gist, WASM and Docker sources")]
+    ExternalSources(DemoArgs),
+    #[command(about = "Submit a task file and wait for its result, or check what submitting it would do and how long it would take")]
+    Submit(SubmitArgs),
+    #[command(about = "Browse the tasks a running status store knows about, one page at a time")]
+    Tasks(TasksArgs),
+    #[command(about = "Print the parent/child tree of a task as JSON, or as Graphviz DOT")]
+    Tree(TreeArgs),
+    #[command(about = "Archive tasks finishing on a queue to JSONL or Parquet until Ctrl-C")]
+    Export(ExportArgs),
+    #[command(about = "Re-submit archived tasks and diff the new results against the archived ones, or re-run one task locally")]
+    Replay(ReplayArgs),
+    #[command(about = "Run the same inputs through two versions of a task and diff their results and timings")]
+    Compare(CompareArgs),
+    #[command(about = "Download the debug bundle of a failed task from the queue's result storage")]
+    DebugBundle(DebugBundleArgs),
+    #[command(about = "Worker utilities", subcommand)]
+    Worker(WorkerCommand),
+}
+
+#[derive(Debug, Subcommand)]
+enum WorkerCommand {
+    #[command(about = "Run a built-in task through each enabled backend and report whether the worker is ready")]
+    Selftest {
+        #[arg(long, help = "Worker configuration file; defaults are used without one")]
+        config: Option<PathBuf>,
+    },
+    #[command(about = "Run a task file several times in deterministic mode and check that its outputs are byte-identical")]
+    Reproduce {
+        #[arg(long, help = "YAML or JSON task file")]
+        task_file: PathBuf,
+        #[arg(long, default_value = "{}", help = "JSON inputs")]
+        inputs: String,
+        #[arg(long, default_value_t = 3)]
+        runs: usize,
+        #[arg(long, help = "Random seed; the task's own, or 0, without one")]
+        seed: Option<u64>,
+        #[arg(long, help = "Worker configuration file; defaults are used without one")]
+        config: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct DebugBundleArgs {
+    #[arg(long)]
+    queue: String,
+    #[arg(long)]
+    task_id: String,
+    #[arg(long, help = "Write the bundle here instead of printing it")]
+    out: Option<PathBuf>,
+    #[arg(long, default_value_t = 5, help = "Seconds to wait for the result")]
+    timeout_secs: u64,
+}
+
+#[derive(Debug, clap::Args)]
+struct ReplayArgs {
+    #[arg(help = "Re-run just this task on this machine instead of submitting the archive")]
+    task_id: Option<String>,
+    #[arg(long, help = "JSONL archive written by the export command")]
+    archive: PathBuf,
+    #[arg(long, required_unless_present = "task_id", help = "Queue to submit to; point it at a new worker fleet to compare against it")]
+    queue: Option<String>,
+    #[arg(long, help = "Worker configuration for the local re-run; defaults are used without one")]
+    config: Option<PathBuf>,
+    #[arg(long, default_value_t = 8)]
+    concurrency: usize,
+    #[arg(long, default_value_t = 300)]
+    timeout_secs: u64,
+    #[arg(long = "ignore-output", help = "Output expected to differ between runs (repeatable)")]
+    ignore_outputs: Vec<String>,
+}
+
+#[derive(Debug, clap::Args)]
+struct CompareArgs {
+    #[arg(long)]
+    queue: String,
+    #[arg(long, help = "YAML or JSON task file of the current version")]
+    a: PathBuf,
+    #[arg(long, help = "YAML or JSON task file of the version to compare against it")]
+    b: PathBuf,
+    #[arg(long, default_value = "{}", help = "JSON inputs (repeatable, one comparison each)")]
+    inputs: Vec<String>,
+    #[arg(long = "ignore-output", help = "Output expected to differ between versions (repeatable)")]
+    ignore_outputs: Vec<String>,
+    #[arg(long, help = "Numbers in the outputs match when no further apart than this")]
+    tolerance: Option<f64>,
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    #[arg(long, default_value_t = 300, help = "Seconds to wait for each result")]
+    timeout_secs: u64,
+    #[arg(long, help = "Print the report as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportArgs {
+    #[arg(long, help = "Queue to archive, e.g. user_tasks or perception")]
+    queue: String,
+    #[arg(long, help = "Archive file; .jsonl is appended to, .parquet is written in numbered parts")]
+    out: PathBuf,
+    #[arg(long, help = "Also delete task keys this many seconds after they finish, once archived")]
+    gc_ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, clap::Args)]
+struct SubmitArgs {
+    #[arg(long)]
+    queue: String,
+    #[arg(long, help = "YAML or JSON task file")]
+    task_file: PathBuf,
+    #[arg(long, default_value = "{}", help = "JSON inputs")]
+    inputs: String,
+    #[arg(long, help = "Queue defaults applied to the task, see queues.example.toml")]
+    queue_config: Option<PathBuf>,
+    #[arg(long, help = "Validate the task and list the workers that could take it without announcing it")]
+    dry_run: bool,
+    #[arg(long, conflicts_with = "dry_run", help = "Ask the queue's assigner when the task would start and how long it would run")]
+    estimate: bool,
+    #[arg(long, default_value_t = 300, help = "Seconds to wait for the result")]
+    timeout_secs: u64,
+}
+
+#[derive(Debug, clap::Args)]
+struct TreeArgs {
+    #[arg(long)]
+    queue: String,
+    #[arg(long, help = "Any task of the tree; it is printed from its root")]
+    task_id: String,
+    #[arg(long, help = "Print Graphviz DOT instead of JSON")]
+    dot: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct TasksArgs {
+    #[arg(long, help = "Queue to list, e.g. user_tasks or perception")]
+    queue: String,
+    #[arg(long, value_parser = parse_task_status, help = "Only tasks in this status; repeatable")]
+    status: Vec<TaskStatus>,
+    #[arg(long)]
+    worker: Option<String>,
+    #[arg(long)]
+    prefix: Option<String>,
+    #[arg(long, help = "Token printed with the previous page")]
+    page_token: Option<String>,
+    #[arg(long, default_value_t = DEFAULT_TASK_PAGE_SIZE)]
+    page_size: usize,
+}
+
+fn parse_task_status(value: &str) -> Result<TaskStatus, String> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|_| format!("unknown task status {:?}", value))
+}
+
+async fn export_tasks(args: ExportArgs, verbosity: Verbosity) -> Result<()> {
+    let session = create_zenoh_session().await?;
+    let keyspace = Keyspace::for_queue(&args.queue);
+    let archiver = Arc::new(TaskArchiver::new(keyspace.clone(), TaskArchive::open(&args.out)?).with_verbosity(verbosity));
+    let shutdown = CancellationToken::new();
+
+    let gc = args.gc_ttl_secs.map(|ttl| {
+        let gc = TaskGc::new(keyspace.clone())
+            .with_ttl(Duration::from_secs(ttl))
+            .with_archiver(archiver.clone())
+            .with_verbosity(verbosity);
+        let session = session.clone();
+        let shutdown = shutdown.child_token();
+        tokio::spawn(async move { gc.run(&session, shutdown).await })
+    });
+    let archiving = archiver.run(&session, shutdown.child_token());
+    tokio::pin!(archiving);
+    let result = tokio::select! {
+        result = &mut archiving => result,
+        signal = tokio::signal::ctrl_c() => {
+            signal?;
+            shutdown.cancel();
+            archiving.await
+        }
+    };
+    shutdown.cancel();
+    if let Some(gc) = gc {
+        gc.await??;
+    }
+    session.close().await.map_err(|e| anyhow::anyhow!("Failed to close Zenoh session: {}", e))?;
+    result
+}
+
+async fn replay_archive(args: ReplayArgs, verbosity: Verbosity) -> Result<()> {
+    let archived = ArchivedTask::read_jsonl(&args.archive)?;
+    if let Some(task_id) = &args.task_id {
+        return replay_task_locally(&args, &archived, task_id, verbosity).await;
+    }
+    let queue = args.queue.as_deref().unwrap_or_default();
+    let client = TaskClient::new(create_zenoh_session().await?, queue).with_verbosity(verbosity);
+    let options = ReplayOptions {
+        concurrency: args.concurrency,
+        timeout: Duration::from_secs(args.timeout_secs),
+        ignore_outputs: args.ignore_outputs,
+    };
+    println!("🔁 Replaying {} archived task(s) on queue {}", archived.len(), queue);
+    let report = replay(&client, &archived, &options).await;
+    client.close().await?;
+
+    for task in &report.tasks {
+        match &task.outcome {
+            ReplayOutcome::Matched => {}
+            ReplayOutcome::Skipped(reason) => println!("⏭️  {} ({}): {}", task.archived_task_id, task.name, reason),
+            ReplayOutcome::Failed(error) => println!("❌ {} ({}): {}", task.archived_task_id, task.name, error),
+            ReplayOutcome::Differs(diff) => {
+                println!("≠  {} ({}) replayed as {}", task.archived_task_id, task.name, task.replay_task_id.as_deref().unwrap_or("?"));
+                print_diff(diff);
+            }
+        }
+    }
+    println!(
+        "📊 {} matched, {} differ, {} failed, {} skipped",
+        report.matched(),
+        report.differing(),
+        report.failed(),
+        report.skipped()
+    );
+    if !report.is_clean() {
+        anyhow::bail!("Replay found regressions");
+    }
+    Ok(())
+}
+
+fn read_task_file(path: &Path) -> Result<TaskDefinition> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let json = path.extension().is_some_and(|extension| extension == "json");
+    TaskDefinition::from_task_file(&content, json)
+}
+
+async fn compare_task_versions(args: CompareArgs, verbosity: Verbosity) -> Result<()> {
+    let (a, b) = (read_task_file(&args.a)?, read_task_file(&args.b)?);
+    let inputs = args
+        .inputs
+        .iter()
+        .map(|inputs| serde_json::from_str(inputs).with_context(|| format!("--inputs {} is not JSON", inputs)))
+        .collect::<Result<Vec<serde_json::Value>>>()?;
+    let client = TaskClient::new(create_zenoh_session().await?, &args.queue).with_verbosity(verbosity);
+    let options = AbOptions {
+        concurrency: args.concurrency,
+        timeout: Duration::from_secs(args.timeout_secs),
+        ignore_outputs: args.ignore_outputs,
+        tolerance: args.tolerance,
+    };
+    let report = compare_versions(&client, &a, &b, &inputs, &options).await;
+    client.close().await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_comparison(&report);
+    }
+    if !report.is_clean() {
+        anyhow::bail!("{} and {} disagree on {} of {} input(s)", report.a, report.b, report.cases.len() - report.matched(), report.cases.len());
+    }
+    Ok(())
+}
+
+fn print_comparison(report: &AbReport) {
+    println!("🆚 A: {}  B: {}", report.a, report.b);
+    for case in &report.cases {
+        let timing = |seconds: Option<f64>| seconds.map(|seconds| format!("{:.3}s", seconds)).unwrap_or_else(|| "-".to_string());
+        let speedup = case.speedup().map(|speedup| format!(" ({:.2}x)", speedup)).unwrap_or_default();
+        let mark = if case.matched() { "✅" } else if case.diff.is_some() { "≠ " } else { "❌" };
+        println!(
+            "{} {}  A {}  B {}{}",
+            mark,
+            case.inputs,
+            timing(case.a.execution_time_seconds()),
+            timing(case.b.execution_time_seconds()),
+            speedup
+        );
+        for (side, run) in [("A", &case.a), ("B", &case.b)] {
+            if let Some(failure) = &run.failure {
+                println!("   {}: {}", side, failure);
+            }
+        }
+        if let Some(diff) = &case.diff {
+            print_diff(diff);
+        }
+    }
+    let means = report
+        .mean_execution_seconds()
+        .map(|(a, b)| format!(", mean execution A {:.3}s B {:.3}s", a, b))
+        .unwrap_or_default();
+    println!("📊 {} matched, {} differ, {} failed{}", report.matched(), report.differing(), report.failed(), means);
+}
+
+async fn replay_task_locally(args: &ReplayArgs, archived: &[ArchivedTask], task_id: &str, verbosity: Verbosity) -> Result<()> {
+    // The latest attempt, if the task was archived more than once
+    let task = archived
+        .iter()
+        .rev()
+        .find(|task| task.task_id == task_id)
+        .ok_or_else(|| anyhow::anyhow!("Task {} is not in {}", task_id, args.archive.display()))?;
+    let mut config = match &args.config {
+        Some(path) => WorkerConfig::from_file(path)?,
+        None => WorkerConfig::default(),
+    };
+    config.verbosity = verbosity;
+    let worker = Worker::from_config(config)?;
+    println!("🔁 Re-running {} ({}) locally, as archived from {}", task.task_id, task.name(), task.worker_id.as_deref().unwrap_or("an unknown worker"));
+    let replay = replay_locally(worker.executor(), task, &args.ignore_outputs).await?;
+
+    println!("{}", serde_json::to_string_pretty(&replay.result)?);
+    if let Some((archived, local)) = &replay.source_drift {
+        println!("⚠️  The source changed since: the worker ran sha256 {}, this run {}", archived, local);
+    }
+    match &replay.diff {
+        None => println!("⏭️  No result was archived to compare with"),
+        Some(diff) if diff.is_empty() => println!("✅ Reproduced the archived result"),
+        Some(diff) => {
+            println!("≠  Differs from the archived result");
+            print_diff(diff);
+        }
+    }
+    Ok(())
+}
+
+fn print_diff(diff: &ResultDiff) {
+    if let Some((before, after)) = &diff.status {
+        println!("   status: {:?} -> {:?}", before, after);
+    }
+    if let Some((before, after)) = &diff.error {
+        println!("   error: {:?} -> {:?}", before, after);
+    }
+    for change in &diff.outputs {
+        match change {
+            OutputChange::Missing { name, archived } => println!("   - {}: {}", name, archived),
+            OutputChange::Added { name, replayed } => println!("   + {}: {}", name, replayed),
+            OutputChange::Changed { name, archived, replayed } => println!("   ~ {}: {} -> {}", name, archived, replayed),
+        }
+    }
+}
+
+async fn download_debug_bundle(args: DebugBundleArgs) -> Result<()> {
+    let client = TaskClient::new(create_zenoh_session().await?, &args.queue);
+    let result = async {
+        let handle = client.attach(&args.task_id).await?;
+        handle.await_result_timeout(Duration::from_secs(args.timeout_secs)).await
+    }
+    .await;
+    client.close().await?;
+    let result = result.context("No result found; is a storage configured for the queue's task keys?")?;
+
+    let Some(bundle) = DebugBundle::from_result(&result) else {
+        anyhow::bail!("Task {} has no debug bundle; it either succeeded or its worker doesn't set sandbox.debug_bundles", args.task_id);
+    };
+    let bundle = serde_json::to_string_pretty(&bundle?)?;
+    match args.out {
+        Some(path) => {
+            std::fs::write(&path, bundle).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("🧾 Debug bundle of {} written to {}", args.task_id, path.display());
+        }
+        None => println!("{}", bundle),
+    }
+    Ok(())
+}
+
+async fn worker_selftest(config: Option<PathBuf>, verbosity: Verbosity) -> Result<()> {
+    let config = match config {
+        Some(path) => WorkerConfig::from_file(path)?,
+        None => WorkerConfig::default(),
+    };
+    let worker = Worker::from_config(config)?.with_verbosity(verbosity);
+    let report = worker.self_test().await;
+    for check in &report.checks {
+        let mark = if check.passed { "✅" } else { "❌" };
+        match &check.detail {
+            Some(detail) => println!("{} {} ({}ms): {}", mark, check.backend, check.duration_ms, detail),
+            None => println!("{} {} ({}ms)", mark, check.backend, check.duration_ms),
+        }
+    }
+    if !report.is_ready() {
+        anyhow::bail!("Worker {} is not ready: {} failed", report.worker_id, report.failed_backends().join(", "));
+    }
+    println!("🩺 Worker {} ready", report.worker_id);
+    Ok(())
+}
+
+async fn check_reproducibility(
+    task_file: &Path,
+    inputs: &str,
+    runs: usize,
+    seed: Option<u64>,
+    config: Option<PathBuf>,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let task_definition = read_task_file(task_file)?;
+    let inputs: serde_json::Value = serde_json::from_str(inputs).context("--inputs is not JSON")?;
+    let mut config = match config {
+        Some(path) => WorkerConfig::from_file(path)?,
+        None => WorkerConfig::default(),
+    };
+    config.verbosity = verbosity;
+    let worker = Worker::from_config(config)?;
+    let determinism = seed.map(|seed| Determinism { seed, ..task_definition.determinism().unwrap_or_default() });
+    let report = check_reproducible(worker.executor(), &task_definition, &inputs, runs, determinism).await?;
+
+    println!("🎲 Seed {}, clock frozen at {}", report.determinism.seed, report.determinism.clock);
+    for (run, outcome) in report.runs.iter().enumerate() {
+        let seconds = outcome.execution_time_seconds.unwrap_or_default();
+        match &outcome.error {
+            Some(error) => println!("❌ Run {} ({:.2}s): {}", run + 1, seconds, error),
+            None => println!("✅ Run {} ({:.2}s)", run + 1, seconds),
+        }
+    }
+    println!("{}", serde_json::to_string_pretty(&report.outputs)?);
+    if report.failed_runs() > 0 {
+        anyhow::bail!("{} of {} runs failed", report.failed_runs(), report.runs.len());
+    }
+    if !report.is_reproducible() {
+        anyhow::bail!("Outputs differ between runs: {}", report.differing_outputs.join(", "));
+    }
+    println!("🔒 {} runs produced byte-identical outputs", report.runs.len());
+    Ok(())
+}
+
+async fn submit_task(args: SubmitArgs, verbosity: Verbosity) -> Result<()> {
+    let task_definition = read_task_file(&args.task_file)?;
+    let inputs: serde_json::Value = serde_json::from_str(&args.inputs).context("--inputs is not JSON")?;
+    let mut client = TaskClient::new(create_zenoh_session().await?, &args.queue).with_verbosity(verbosity);
+    if let Some(path) = &args.queue_config {
+        client = client.with_queue_defaults(QueueSettings::from_file(path)?.for_queue(&args.queue));
+    }
+    let job = Job::new_user_task(args.queue.clone(), task_definition, inputs);
+
+    if args.dry_run {
+        let report = client.dry_run(job, WORKER_DISCOVERY_WINDOW).await;
+        client.close().await?;
+        return print_dry_run(&report?);
+    }
+    if args.estimate {
+        let estimate = client.estimate(&job).await;
+        client.close().await?;
+        print_estimate(&estimate?);
+        return Ok(());
+    }
+    let result = async {
+        let handle = client.submit_job(job).await?;
+        println!("📤 Submitted task {}", handle.task_id());
+        handle.await_result_timeout(Duration::from_secs(args.timeout_secs)).await
+    }
+    .await;
+    client.close().await?;
+    println!("{}", serde_json::to_string_pretty(&result?)?);
+    Ok(())
+}
+
+fn print_dry_run(report: &DryRunReport) -> Result<()> {
+    println!("🧪 Dry run of task {} on queue {}", report.task_id, report.queue);
+    if let Some(backend) = &report.backend {
+        println!("   backend: {}", backend);
+    }
+    println!("   inputs: {}{}", report.inputs, if report.inputs_offloaded { " (served by the submitter)" } else { "" });
+    if let Some(timeout) = report.timeout_seconds {
+        println!("   timeout: {}s", timeout);
+    }
+    if let Some(max_attempts) = report.max_attempts {
+        println!("   max attempts: {}", max_attempts);
+    }
+    for worker in &report.workers {
+        match &worker.unfit {
+            None => println!("✅ {} could take it", worker.worker_id),
+            Some(reason) => println!("⏭️  {}: {}", worker.worker_id, reason),
+        }
+    }
+    for problem in &report.problems {
+        println!("❌ {}", problem);
+    }
+    if !report.is_ok() {
+        anyhow::bail!("Dry run of task {} found {} problem(s)", report.task_id, report.problems.len());
+    }
+    println!("👍 {} worker(s) could take task {}; nothing was submitted", report.eligible_workers().count(), report.task_id);
+    Ok(())
+}
+
+fn print_estimate(estimate: &Estimate) {
+    println!("🔮 {} job(s) queued and {} running", estimate.queue_depth, estimate.running);
+    match estimate.expected_duration_seconds {
+        Some(seconds) => println!("   expected run time: {:.1}s (from {} run(s))", seconds, estimate.duration_samples),
+        None => println!("   expected run time: unknown, no run of this task has completed yet"),
+    }
+    match (estimate.estimated_wait_seconds, estimate.estimated_start_at) {
+        (Some(seconds), Some(start)) => println!("   likely start: in {:.1}s, around {}", seconds, start.format("%H:%M:%S")),
+        _ if estimate.candidates.is_empty() => println!("   likely start: unknown, no live worker could take it"),
+        _ => println!("   likely start: unknown, nothing has completed on the queue yet"),
+    }
+    for worker_id in &estimate.candidates {
+        println!("✅ {} could take it", worker_id);
+    }
+}
+
+async fn list_tasks(args: TasksArgs) -> Result<()> {
+    let client = TaskClient::new(create_zenoh_session().await?, &args.queue);
+    let request = TaskListRequest {
+        filter: TaskFilter {
+            statuses: args.status,
+            worker_id: args.worker,
+            task_id_prefix: args.prefix,
+        },
+        page_token: args.page_token,
+        page_size: args.page_size,
+    };
+    let page = client.list_tasks_with(&args.queue, &request).await;
+    client.close().await?;
+    let page = page?;
+
+    for task in &page.tasks {
+        println!(
+            "{}  {:?}  {}  {}{}",
+            task.task_id,
+            task.status,
+            task.worker_id,
+            task.updated_at.format("%Y-%m-%d %H:%M:%S"),
+            task.message.as_deref().map(|message| format!("  {}", message)).unwrap_or_default()
+        );
+    }
+    match page.next_page_token {
+        Some(token) => println!("📄 More tasks: --page-token {}", token),
+        None => println!("📄 {} task(s), end of listing", page.tasks.len()),
+    }
+    Ok(())
+}
+
+async fn print_task_tree(args: TreeArgs) -> Result<()> {
+    let client = TaskClient::new(create_zenoh_session().await?, &args.queue);
+    let tree = client.task_tree(&args.queue, &args.task_id).await;
+    client.close().await?;
+    let tree = tree?;
+
+    if args.dot {
+        print!("{}", tree.to_dot());
+    } else {
+        println!("{}", serde_json::to_string_pretty(&tree)?);
+    }
+    if tree.truncated {
+        eprintln!("⚠️  Tree cut off after {} tasks", MAX_TASK_TREE_NODES);
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    match cli.command {
+        DemoCommand::Simple => SimpleZenohDemo::new().with_verbosity(cli.verbosity).run_simple_zenoh_demo().await,
+        DemoCommand::User { embedded_router: true } => {
+            let router = EmbeddedRouter::start(EMBEDDED_ROUTER_ENDPOINT).await?;
+            println!("🛰️  Embedded Zenoh router listening on {}", router.endpoint());
+            let demo = UserDefinedDemo::with_zenoh_config(router.client_config()?).with_verbosity(cli.verbosity);
+            let result = demo.run_demo().await;
+            router.close().await?;
+            result
+        }
+        DemoCommand::User { embedded_router: false } => UserDefinedDemo::new().with_verbosity(cli.verbosity).run_demo().await,
+        DemoCommand::ExternalSources(args) => WorkingUserZenohDemo::new().with_verbosity(cli.verbosity).run_working_user_zenoh_demo(&args).await,
+        DemoCommand::Submit(args) => submit_task(args, cli.verbosity).await,
+        DemoCommand::Tasks(args) => list_tasks(args).await,
+        DemoCommand::Tree(args) => print_task_tree(args).await,
+        DemoCommand::Export(args) => export_tasks(args, cli.verbosity).await,
+        DemoCommand::Replay(args) => replay_archive(args, cli.verbosity).await,
+        DemoCommand::Compare(args) => compare_task_versions(args, cli.verbosity).await,
+        DemoCommand::DebugBundle(args) => download_debug_bundle(args).await,
+        DemoCommand::Worker(WorkerCommand::Selftest { config }) => worker_selftest(config, cli.verbosity).await,
+        DemoCommand::Worker(WorkerCommand::Reproduce { task_file, inputs, runs, seed, config }) => {
+            check_reproducibility(&task_file, &inputs, runs, seed, config, cli.verbosity).await
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use anyhow::Result;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+use crate::assigner::Assigner;
+use crate::client::TaskClient;
+use crate::config::{QueueSettings, WorkerConfig};
+use crate::events::EventBus;
+use crate::gc::TaskGc;
+use crate::keyspace::Keyspace;
+use crate::metrics::serve_prometheus;
+use crate::receipts::ReceiptSigner;
+use crate::status::StatusAggregator;
+use crate::scheduling::{Coordination, FleetRouting, SchedulingStrategy, DEFAULT_CLAIM_WINDOW};
+use crate::result_listener::ResultListener;
+use crate::verbosity::Verbosity;
+use crate::worker::Worker;
+use crate::summary;
+
+// Time given to components to declare their announcement subscribers before jobs
+// are submitted; claims and assignments are retained and don't depend on it
+const STARTUP_GRACE: Duration = Duration::from_secs(1);
+// Default time in-flight tasks get to finish once shutdown starts
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Hosts an assigner, a result listener and any number of workers for one queue,
+// each on its own Zenoh session as if they were separate processes
+pub struct DemoRuntime {
+    keyspace: Keyspace,
+    zenoh_config: zenoh::Config,
+    shutdown: CancellationToken,
+    shutdown_timeout: Duration,
+    claim_window: Duration,
+    strategy: SchedulingStrategy,
+    fleet_routing: FleetRouting,
+    coordination: Coordination,
+    metrics_addr: Option<SocketAddr>,
+    task_ttl: Option<Duration>,
+    verbosity: Verbosity,
+    events: EventBus,
+    receipt_signer: Option<ReceiptSigner>,
+    queue_settings: QueueSettings,
+    components: Vec<(String, JoinHandle<()>)>,
+}
+
+impl DemoRuntime {
+    pub fn new(queue: &str) -> Self {
+        Self::with_zenoh_config(queue, zenoh::Config::default())
+    }
+
+    pub fn with_zenoh_config(queue: &str, zenoh_config: zenoh::Config) -> Self {
+        Self {
+            keyspace: Keyspace::for_queue(queue),
+            zenoh_config,
+            shutdown: CancellationToken::new(),
+            shutdown_timeout: SHUTDOWN_TIMEOUT,
+            claim_window: DEFAULT_CLAIM_WINDOW,
+            strategy: SchedulingStrategy::default(),
+            fleet_routing: FleetRouting::default(),
+            coordination: Coordination::default(),
+            metrics_addr: None,
+            task_ttl: None,
+            verbosity: Verbosity::Summary,
+            events: EventBus::new(),
+            receipt_signer: None,
+            queue_settings: QueueSettings::default(),
+            components: Vec::new(),
+        }
+    }
+
+    // Requirement defaults for the jobs submitted through `client`
+    pub fn with_queue_settings(mut self, queue_settings: QueueSettings) -> Self {
+        self.queue_settings = queue_settings;
+        self
+    }
+
+    pub fn with_shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    // Serves the queue's depth gauges for Prometheus on `addr`
+    pub fn with_metrics_endpoint(mut self, addr: SocketAddr) -> Self {
+        self.metrics_addr = Some(addr);
+        self
+    }
+
+    // Runs a garbage collector deleting the keys of tasks finished more than `ttl` ago
+    pub fn with_task_gc(mut self, ttl: Duration) -> Self {
+        self.task_ttl = Some(ttl);
+        self
+    }
+
+    // Applied to every hosted component, workers included; demos print a summary
+    // unless told otherwise
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Has the assigner sign its receipts and clients verify them
+    pub fn with_receipt_signer(mut self, signer: ReceiptSigner) -> Self {
+        self.receipt_signer = Some(signer);
+        self
+    }
+
+    // With WorkStealing no assigner is started; workers take jobs from the client
+    pub fn with_coordination(mut self, coordination: Coordination) -> Self {
+        self.coordination = coordination;
+        self
+    }
+
+    pub fn with_scheduling(mut self, strategy: SchedulingStrategy, claim_window: Duration) -> Self {
+        self.strategy = strategy;
+        self.claim_window = claim_window;
+        self
+    }
+
+    pub fn with_fleet_routing(mut self, fleet_routing: FleetRouting) -> Self {
+        self.fleet_routing = fleet_routing;
+        self
+    }
+
+    // A worker with default settings that waits `claim_delay` before every claim
+    pub fn worker(worker_id: &str, claim_delay: Duration) -> Result<Worker> {
+        let config = WorkerConfig {
+            worker_id: worker_id.to_string(),
+            ..WorkerConfig::default()
+        };
+        Ok(Worker::from_config(config)?.with_claim_delay(claim_delay))
+    }
+
+    pub fn keyspace(&self) -> &Keyspace {
+        &self.keyspace
+    }
+
+    // Lifecycle events of the hosted assigner and workers
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub async fn open_session(&self) -> Result<zenoh::Session> {
+        zenoh::open(self.zenoh_config.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to open Zenoh session: {}", e))
+    }
+
+    // Starts the assigner (unless work stealing), the result listener and the given
+    // workers, then waits for their subscriptions to settle
+    pub async fn start(&mut self, workers: Vec<Worker>) {
+        let mut assigner = Assigner::new(self.keyspace.clone())
+            .with_claim_window(self.claim_window)
+            .with_strategy(self.strategy)
+            .with_fleet_routing(self.fleet_routing.clone())
+            .with_events(self.events.clone())
+            .with_verbosity(self.verbosity);
+        if let Some(signer) = &self.receipt_signer {
+            assigner = assigner.with_receipt_signer(signer.clone());
+        }
+        let gc = self.task_ttl.map(|ttl| {
+            TaskGc::new(self.keyspace.clone())
+                .with_ttl(ttl)
+                .with_verbosity(self.verbosity)
+        });
+        let verbosity = self.verbosity;
+        let with_assigner = self.coordination == Coordination::Assigner;
+        if let Some(addr) = self.metrics_addr {
+            let gauges = if with_assigner { vec![assigner.gauges()] } else { Vec::new() };
+            let versions = if with_assigner { vec![assigner.version_counters()] } else { Vec::new() };
+            let collectors = gc.iter().map(TaskGc::counters).collect();
+            let shutdown = self.shutdown.child_token();
+            let handle = tokio::spawn(async move {
+                if let Err(e) = serve_prometheus(addr, gauges, collectors, versions, shutdown, verbosity).await {
+                    summary!(verbosity, "❌ metrics endpoint error: {}", e);
+                }
+            });
+            self.components.push(("metrics endpoint".to_string(), handle));
+        }
+        if with_assigner {
+            self.spawn("assigner".to_string(), move |session, shutdown| async move {
+                assigner.run(&session, shutdown).await
+            });
+        }
+
+        if let Some(gc) = gc {
+            self.spawn("task gc".to_string(), move |session, shutdown| async move {
+                gc.run(&session, shutdown).await
+            });
+        }
+
+        let listener = ResultListener::new(self.keyspace.clone()).with_verbosity(self.verbosity);
+        self.spawn("result listener".to_string(), move |session, shutdown| async move {
+            listener.run(&session, shutdown).await
+        });
+
+        // Only speaks up when a status update breaks the task state machine
+        let keyspace = self.keyspace.clone();
+        self.spawn("status aggregator".to_string(), move |session, shutdown| async move {
+            StatusAggregator::new()
+                .with_verbosity(verbosity)
+                .run(&session, &keyspace, shutdown).await
+        });
+
+        for worker in workers {
+            let worker = worker
+                .with_events(self.events.clone())
+                .with_coordination(self.coordination)
+                .with_verbosity(self.verbosity);
+            let keyspace = self.keyspace.clone();
+            self.spawn(format!("worker {}", worker.worker_id()), move |session, shutdown| async move {
+                worker.run(&session, &keyspace, shutdown).await
+            });
+        }
+
+        tokio::time::sleep(STARTUP_GRACE).await;
+    }
+
+    pub async fn client(&self) -> Result<TaskClient> {
+        let client = TaskClient::new(self.open_session().await?, self.keyspace.queue())
+            .with_coordination(self.coordination)
+            .with_queue_defaults(self.queue_settings.for_queue(self.keyspace.queue()))
+            .with_verbosity(self.verbosity);
+        Ok(match &self.receipt_signer {
+            Some(signer) => client.with_receipt_signer(signer.clone()),
+            None => client,
+        })
+    }
+
+    fn spawn<F, Fut>(&mut self, name: String, component: F)
+    where
+        F: FnOnce(zenoh::Session, CancellationToken) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let zenoh_config = self.zenoh_config.clone();
+        let shutdown = self.shutdown.child_token();
+        let label = name.clone();
+        let verbosity = self.verbosity;
+        let handle = tokio::spawn(async move {
+            let session = match zenoh::open(zenoh_config).await {
+                Ok(session) => session,
+                Err(e) => {
+                    summary!(verbosity, "❌ {} failed to open Zenoh session: {}", label, e);
+                    return;
+                }
+            };
+            if let Err(e) = component(session.clone(), shutdown).await {
+                summary!(verbosity, "❌ {} error: {}", label, e);
+            }
+            if let Err(e) = session.close().await {
+                summary!(verbosity, "⚠️  {} failed to close its Zenoh session: {}", label, e);
+            }
+        });
+        self.components.push((name, handle));
+    }
+
+    // Runs the demo body until it completes or Ctrl-C is pressed; either way the
+    // caller should follow up with shutdown()
+    pub async fn run_until_interrupted<F>(&self, body: F) -> Result<()>
+    where
+        F: Future<Output = Result<()>>,
+    {
+        tokio::select! {
+            result = body => result,
+            signal = tokio::signal::ctrl_c() => {
+                signal?;
+                summary!(self.verbosity, "\n⚠️  Interrupted, shutting down...");
+                Ok(())
+            }
+        }
+    }
+
+    // Stops all components, letting workers finish their in-flight task until the
+    // shutdown deadline; whatever is still running after that is aborted
+    pub async fn shutdown(self) {
+        summary!(self.verbosity, "🛑 Stopping demo components...");
+        self.shutdown.cancel();
+        let deadline = Instant::now() + self.shutdown_timeout;
+        for (name, mut handle) in self.components {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(_) => summary!(self.verbosity, "✅ {} stopped", name),
+                Err(_) => {
+                    handle.abort();
+                    summary!(self.verbosity, "⏰ {} did not stop in time and was aborted", name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::dynamic_executor::{sha256_hex, DynamicTaskExecutor};
+use crate::schema::{Determinism, Result as TaskResult, TaskDefinition, TaskStatus};
+
+// One run of a reproducibility check
+#[derive(Debug, Clone, Serialize)]
+pub struct DeterministicRun {
+    pub status: TaskStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    // SHA-256 of each output as serialized JSON
+    pub output_sha256: BTreeMap<String, String>,
+    pub execution_time_seconds: Option<f64>,
+}
+
+impl DeterministicRun {
+    pub fn from_result(result: &TaskResult) -> Self {
+        Self {
+            status: result.status.clone(),
+            error: result.error.clone(),
+            output_sha256: output_digests(&result.outputs),
+            execution_time_seconds: result.execution_time_seconds,
+        }
+    }
+}
+
+// Repeated deterministic runs of a task on the same inputs, and the outputs that
+// didn't come out byte-identical
+#[derive(Debug, Clone, Serialize)]
+pub struct ReproducibilityReport {
+    pub determinism: Determinism,
+    pub runs: Vec<DeterministicRun>,
+    // Missing from some runs or serialized differently in some
+    pub differing_outputs: Vec<String>,
+    // Of the first run
+    pub outputs: HashMap<String, serde_json::Value>,
+}
+
+impl ReproducibilityReport {
+    pub fn from_results(determinism: Determinism, results: &[TaskResult]) -> Self {
+        let runs: Vec<DeterministicRun> = results.iter().map(DeterministicRun::from_result).collect();
+        let names: BTreeSet<&String> = runs.iter().flat_map(|run| run.output_sha256.keys()).collect();
+        let differing_outputs = names
+            .into_iter()
+            .filter(|name| {
+                let mut digests = runs.iter().map(|run| run.output_sha256.get(*name));
+                let first = digests.next().flatten();
+                digests.any(|digest| digest != first)
+            })
+            .cloned()
+            .collect();
+        Self {
+            determinism,
+            runs,
+            differing_outputs,
+            outputs: results.first().map(|result| result.outputs.clone()).unwrap_or_default(),
+        }
+    }
+
+    pub fn failed_runs(&self) -> usize {
+        self.runs.iter().filter(|run| run.status != TaskStatus::Completed).count()
+    }
+
+    // True when every run completed with byte-identical outputs
+    pub fn is_reproducible(&self) -> bool {
+        !self.runs.is_empty() && self.failed_runs() == 0 && self.differing_outputs.is_empty()
+    }
+}
+
+// serde_json keeps object keys sorted, so equal values serialize to equal bytes
+pub fn output_digests(outputs: &HashMap<String, serde_json::Value>) -> BTreeMap<String, String> {
+    outputs
+        .iter()
+        .map(|(name, value)| (name.clone(), sha256_hex(value.to_string().as_bytes())))
+        .collect()
+}
+
+// The definition with deterministic execution switched on: with `determinism` when
+// given, otherwise with the task's own settings or the defaults
+pub fn deterministic(task_definition: &TaskDefinition, determinism: Option<Determinism>) -> TaskDefinition {
+    let mut pinned = task_definition.clone();
+    let determinism = determinism.or(task_definition.determinism()).unwrap_or_default();
+    pinned.requirements.get_or_insert_with(Default::default).deterministic = Some(determinism);
+    pinned
+}
+
+// Runs a task `runs` times (at least twice) in deterministic mode on the same inputs,
+// one run after the other, and checks that their outputs are byte-identical
+pub async fn check_reproducible(
+    executor: &DynamicTaskExecutor,
+    task_definition: &TaskDefinition,
+    inputs: &serde_json::Value,
+    runs: usize,
+    determinism: Option<Determinism>,
+) -> anyhow::Result<ReproducibilityReport> {
+    let task_definition = deterministic(task_definition, determinism);
+    let mut results = Vec::with_capacity(runs);
+    for _ in 0..runs.max(2) {
+        results.push(executor.execute_task(&task_definition, inputs.clone()).await?);
+    }
+    let determinism = task_definition.determinism().unwrap_or_default();
+    Ok(ReproducibilityReport::from_results(determinism, &results))
+}
@@ -0,0 +1,255 @@
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderName, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use crate::config::{CacheSettings, SourceSettings};
+use crate::dynamic_executor::sha256_hex;
+use crate::verbosity::Verbosity;
+use crate::{detail, summary};
+
+static SHARED_DOWNLOADER: OnceLock<Arc<Downloader>> = OnceLock::new();
+
+// Doubled after every failed attempt
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const PEM_BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+const PEM_END: &str = "-----END CERTIFICATE-----";
+
+#[derive(Debug)]
+pub struct Download {
+    pub body: Vec<u8>,
+    // Where the body actually came from, after redirects
+    pub url: String,
+    pub content_type: Option<String>,
+    // Served from the cache after the server answered 304 Not Modified
+    pub cached: bool,
+}
+
+// Validators of a cached response, stored next to its body
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    content_type: Option<String>,
+}
+
+enum Fetched {
+    NotModified,
+    Body(CacheEntry, Vec<u8>),
+}
+
+// Fetches task sources over HTTP(S) with timeouts, a size cap and retries of
+// transient failures. Responses are cached on disk; those carrying an ETag or
+// Last-Modified are revalidated with a conditional request on the next fetch.
+// Offline, only the cache is consulted.
+pub struct Downloader {
+    client: reqwest::Client,
+    cache_dir: PathBuf,
+    max_bytes: u64,
+    retries: u32,
+    offline: bool,
+    verbosity: Verbosity,
+}
+
+impl Downloader {
+    pub fn new(settings: &SourceSettings, cache_dir: PathBuf) -> Result<Self> {
+        let timeout = Duration::from_secs(settings.download_timeout_seconds);
+        let mut builder = reqwest::Client::builder().connect_timeout(timeout).timeout(timeout);
+        if let Some(proxy) = settings.proxy() {
+            let proxy = reqwest::Proxy::all(&proxy)
+                .with_context(|| format!("Invalid proxy {}", proxy))?
+                .no_proxy(settings.no_proxy().and_then(|no_proxy| reqwest::NoProxy::from_string(&no_proxy)));
+            builder = builder.proxy(proxy);
+        }
+        if let Some(ca_bundle) = settings.ca_bundle() {
+            for certificate in load_ca_bundle(&ca_bundle)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        let client = builder.build().context("Failed to build HTTP client")?;
+        Ok(Self {
+            client,
+            cache_dir,
+            max_bytes: settings.max_download_bytes,
+            retries: settings.download_retries,
+            offline: settings.offline(),
+            verbosity: Verbosity::default(),
+        })
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Process-wide downloader with default settings, used by executors that weren't
+    // given one explicitly
+    pub fn shared() -> Arc<Downloader> {
+        SHARED_DOWNLOADER
+            .get_or_init(|| {
+                let cache_dir = CacheSettings::default().dir.join("http");
+                Arc::new(Downloader::new(&SourceSettings::default(), cache_dir).expect("Failed to build shared downloader"))
+            })
+            .clone()
+    }
+
+    // For requests that need their own handling, e.g. the GitHub API
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    pub async fn fetch(&self, url: &str) -> Result<Download> {
+        let entry_dir = self.cache_dir.join(&sha256_hex(url.as_bytes())[..16]);
+        let mut cached = read_cache_entry(&entry_dir);
+        if self.offline {
+            let (entry, body) = cached.ok_or_else(|| anyhow::anyhow!("Offline mode: {} is not in the download cache", url))?;
+            return Ok(Download {
+                body,
+                url: entry.url,
+                content_type: entry.content_type,
+                cached: true,
+            });
+        }
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            let outcome = self.try_fetch(url, cached.as_ref().map(|(entry, _)| entry)).await;
+            match outcome {
+                Ok(Fetched::NotModified) => {
+                    let (entry, body) = cached.take().context("Server answered 304 without a cached copy")?;
+                    return Ok(Download {
+                        body,
+                        url: entry.url,
+                        content_type: entry.content_type,
+                        cached: true,
+                    });
+                }
+                Ok(Fetched::Body(entry, body)) => {
+                    if let Err(e) = write_cache_entry(&entry_dir, &entry, &body) {
+                        summary!(self.verbosity, "⚠️  Failed to cache download of {}: {}", url, e);
+                    }
+                    return Ok(Download {
+                        body,
+                        url: entry.url,
+                        content_type: entry.content_type,
+                        cached: false,
+                    });
+                }
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    attempt += 1;
+                    detail!(self.verbosity, "🔁 Download of {} failed ({}), retrying in {}ms", url, e, backoff.as_millis());
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => return Err(e.context(format!("Failed to download {}", url))),
+            }
+        }
+    }
+
+    async fn try_fetch(&self, url: &str, cached: Option<&CacheEntry>) -> Result<Fetched> {
+        let mut request = self.client.get(url);
+        if let Some(entry) = cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(Fetched::NotModified);
+        }
+        let mut response = response.error_for_status()?;
+        if let Some(length) = response.content_length().filter(|&length| length > self.max_bytes) {
+            anyhow::bail!("Response is {} bytes, over the {} byte limit", length, self.max_bytes);
+        }
+
+        let header = |name: HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let entry = CacheEntry {
+            url: response.url().to_string(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+            content_type: header(CONTENT_TYPE),
+        };
+        // Content-Length may be missing or wrong, so the cap is enforced while reading
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (body.len() + chunk.len()) as u64 > self.max_bytes {
+                anyhow::bail!("Response exceeds the {} byte limit", self.max_bytes);
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(Fetched::Body(entry, body))
+    }
+}
+
+// Every certificate in a PEM bundle
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read_to_string(path).with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+    let mut certificates = Vec::new();
+    let mut rest = pem.as_str();
+    while let Some(start) = rest.find(PEM_BEGIN) {
+        let end = rest[start..]
+            .find(PEM_END)
+            .map(|end| start + end + PEM_END.len())
+            .with_context(|| format!("Unterminated certificate in {}", path.display()))?;
+        let certificate = reqwest::Certificate::from_pem(rest[start..end].as_bytes())
+            .with_context(|| format!("Invalid certificate in {}", path.display()))?;
+        certificates.push(certificate);
+        rest = &rest[end..];
+    }
+    if certificates.is_empty() {
+        anyhow::bail!("No certificates found in {}", path.display());
+    }
+    Ok(certificates)
+}
+
+// Timeouts, connection failures, 5xx and 429 are worth another try
+fn is_transient(error: &anyhow::Error) -> bool {
+    let Some(error) = error.downcast_ref::<reqwest::Error>() else {
+        return false;
+    };
+    if error.is_timeout() || error.is_connect() {
+        return true;
+    }
+    error
+        .status()
+        .is_some_and(|status| status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS)
+}
+
+fn read_cache_entry(dir: &Path) -> Option<(CacheEntry, Vec<u8>)> {
+    let entry = serde_json::from_slice(&std::fs::read(dir.join("entry.json")).ok()?).ok()?;
+    let body = std::fs::read(dir.join("body")).ok()?;
+    Some((entry, body))
+}
+
+// The body goes first, so an entry is never paired with the body of an older one
+// for longer than it takes to replace it
+fn write_cache_entry(dir: &Path, entry: &CacheEntry, body: &[u8]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let _ = std::fs::remove_file(dir.join("entry.json"));
+    write_replacing(&dir.join("body"), body)?;
+    write_replacing(&dir.join("entry.json"), &serde_json::to_vec(entry)?)?;
+    Ok(())
+}
+
+fn write_replacing(path: &Path, content: &[u8]) -> Result<()> {
+    let partial = path.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&partial, content)?;
+    std::fs::rename(&partial, path)?;
+    Ok(())
+}
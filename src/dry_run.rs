@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::language::is_auto_language;
+use crate::schema::{Isolation, TaskDefinition, WorkerInfo, WorkerStatus, DEFAULT_FLEET};
+
+// Workers heartbeat every second; listening this long hears each live one at least twice
+pub const WORKER_DISCOVERY_WINDOW: Duration = Duration::from_millis(2500);
+
+// What submitting a job would do, worked out without announcing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DryRunReport {
+    pub task_id: String,
+    pub queue: String,
+    // None for handler jobs
+    pub backend: Option<String>,
+    // As the worker would get them: queue defaults applied, coerced and with defaults
+    // filled in
+    pub inputs: serde_json::Value,
+    // Whether the inputs would be served by the submitter rather than announced
+    pub inputs_offloaded: bool,
+    pub timeout_seconds: Option<u64>,
+    pub max_attempts: Option<u32>,
+    // Live workers on the queue, as heard from their heartbeats
+    pub workers: Vec<WorkerMatch>,
+    // Why the job would be rejected, fail or never be claimed
+    pub problems: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerMatch {
+    pub worker_id: String,
+    pub status: Option<WorkerStatus>,
+    // Why the worker wouldn't take the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unfit: Option<String>,
+}
+
+impl DryRunReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn eligible_workers(&self) -> impl Iterator<Item = &WorkerMatch> {
+        self.workers.iter().filter(|worker| worker.unfit.is_none())
+    }
+}
+
+// Why the worker advertising `info` wouldn't take a task, judged by its heartbeat:
+// the backend (and microvm, for microVM isolation) and every label of the task must
+// be among its capabilities, the backend must have passed its self-test, and the
+// worker must be in the fleet the task requires, if any. Workers that heartbeat
+// without their info are given the benefit of the doubt.
+pub fn worker_unfit(info: Option<&WorkerInfo>, task_definition: Option<&TaskDefinition>) -> Option<String> {
+    let (info, task_definition) = (info?, task_definition?);
+    if matches!(info.status, WorkerStatus::Offline) {
+        return Some("offline".to_string());
+    }
+    let fleet = info.fleet.as_deref().unwrap_or(DEFAULT_FLEET);
+    if let Some(required) = task_definition.fleet().filter(|required| *required != fleet) {
+        return Some(format!("in the {} fleet, not {}", fleet, required));
+    }
+    if task_definition.isolation() == Isolation::Microvm && !info.capabilities.iter().any(|capability| capability == "microvm") {
+        return Some("no microVM isolation".to_string());
+    }
+    let backend = task_definition.backend();
+    // Resolved from the source once fetched, so any worker might run it
+    if !is_auto_language(backend) {
+        if !info.capabilities.iter().any(|capability| capability == backend) {
+            return Some(format!("backend '{}' not enabled", backend));
+        }
+        let failed_probe = info.failed_probes.iter().any(|probe| probe == backend);
+        if failed_probe && task_definition.isolation() == Isolation::Process {
+            return Some(format!("backend '{}' failed its self-test", backend));
+        }
+    }
+    let missing: Vec<&str> = task_definition
+        .labels()
+        .iter()
+        .filter(|label| !info.capabilities.contains(label))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Some(format!("missing label(s) {}", missing.join(", ")));
+    }
+    None
+}
@@ -1,55 +1,527 @@
-use anyhow::{Result, Context};
-use crate::schema::{TaskDefinition, TaskSource, TaskStatus, Result as TaskResult};
-use std::collections::HashMap;
-use std::process::Command;
+use anyhow::Result;
+use crate::schema::{Determinism, ExecutionMetadata, Isolation, JsRuntime, Permissions, Provenance, TaskDefinition, TaskRequirements, TaskSource, TaskStatus, Result as TaskResult};
+#[cfg(any(feature = "sql", feature = "dataframe"))]
+use crate::schema::DatasetFormat;
+#[cfg(feature = "dataframe")]
+use crate::schema::FrameOperation;
+#[cfg(any(feature = "plugin", feature = "onnx"))]
+use crate::schema::is_sha256_hex;
+#[cfg(feature = "image")]
+use crate::schema::{ImageOutput, ImageStep};
+use crate::process::{run_command, DiskQuota, ProcessLimits, ProcessOutput, MAX_PROCESS_OUTPUT_BYTES};
+use crate::workspace::{default_workspace_root, Workspace};
+#[cfg(any(feature = "git", feature = "http"))]
+use crate::workspace::{safe_file_name, safe_relative_path};
+use crate::config::DependencyPolicy;
+use crate::language::resolve_language;
+use crate::runtimes::c_compiler;
+#[cfg(feature = "http")]
+use crate::language::{is_auto_language, language_from_extension};
+use crate::runners::{
+    RunnerResult, DENO_RUNNER, DENO_RUNNER_FILE, JS_RUNNER, JS_RUNNER_FILE, JULIA_RUNNER, JULIA_RUNNER_FILE, PYTHON_RUNNER, PYTHON_RUNNER_FILE,
+    RUNNER_RESULT_FILE, R_RUNNER, R_RUNNER_FILE,
+};
+use crate::execution_pool::ExecutionPool;
+use crate::benchmark::{benchmark_outputs, benchmark_size, run_benchmark};
+use crate::builtins::{run_builtin, sleep_duration};
+use crate::debug_bundle::{self, DebugBundle, DEBUG_BUNDLE_ARTIFACT};
+use crate::verbosity::Verbosity;
+use crate::{detail, summary};
+#[cfg(feature = "wasm")]
+use crate::wasm_runtime::{interrupt_wasm, interruptible_engine, run_wasi_command};
+#[cfg(feature = "docker")]
+use crate::container_pool::{ContainerPool, ContainerResources};
+#[cfg(feature = "http")]
+use crate::archive::extract_archive;
+#[cfg(feature = "http")]
+use crate::download::{Download, Downloader};
+#[cfg(feature = "firecracker")]
+use crate::config::MicrovmSettings;
+#[cfg(feature = "git")]
+use crate::config::SourceSettings;
+#[cfg(feature = "firecracker")]
+use crate::microvm::run_in_microvm;
+#[cfg(feature = "plugin")]
+use crate::native_plugin::NativePlugin;
+#[cfg(feature = "onnx")]
+use crate::onnx::{OnnxModel, Tensor, TensorSpec};
+#[cfg(feature = "sql")]
+use crate::sql::{open_sandboxed, parquet_row_count, run_query, SqlTable};
+#[cfg(feature = "dataframe")]
+use crate::dataframe::transform_dataset;
+#[cfg(feature = "image")]
+use crate::image_pipeline::{preprocess_image, ImageData};
+#[cfg(feature = "expression")]
+use crate::expression::evaluate;
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "git")]
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(any(feature = "git", feature = "http"))]
+use std::sync::Mutex;
+use std::time::Duration;
 use std::fs;
-use tempfile::TempDir;
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
 use serde_json;
+use sha2::{Digest, Sha256};
+
+// Upper bound on how much of each stream is kept in the result artifacts
+const MAX_CAPTURED_OUTPUT_BYTES: usize = 64 * 1024;
+const TRUNCATION_MARKER: &str = "\n... [truncated]";
+// Retries of a GitHub request rejected by the rate limit, and the longest wait for
+// the limit to reset before giving up
+#[cfg(feature = "http")]
+const GITHUB_RATE_LIMIT_RETRIES: u32 = 3;
+#[cfg(feature = "http")]
+const GITHUB_MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+// Dependency manifests picked up next to git and archive entrypoints
+pub const PYTHON_MANIFEST: &str = "requirements.txt";
+pub const NODE_MANIFEST: &str = "package.json";
+#[cfg(any(feature = "git", feature = "http"))]
+const NODE_LOCKFILE: &str = "package-lock.json";
+// Written once an environment is completely installed
+#[cfg(any(feature = "git", feature = "http"))]
+const ENV_READY_MARKER: &str = ".corebrum-ready";
+
+// What deterministic runs keep of the worker's environment: where to find
+// interpreters, installed packages and certificates
+const DETERMINISTIC_PASSTHROUGH_ENV: &[&str] = &[
+    "PATH",
+    "HOME",
+    "USER",
+    "LD_LIBRARY_PATH",
+    "SSL_CERT_FILE",
+    "PYTHONPATH",
+    "VIRTUAL_ENV",
+    "NODE_PATH",
+    "R_LIBS",
+    "R_LIBS_USER",
+    "R_LIBS_SITE",
+    "JULIA_DEPOT_PATH",
+    "JULIA_LOAD_PATH",
+    "JAVA_HOME",
+];
+
+pub const STDOUT_ARTIFACT: &str = "stdout.log";
+pub const STDERR_ARTIFACT: &str = "stderr.log";
+
+#[derive(Debug, Default)]
+struct CapturedOutput {
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+// State scoped to a single execution; the workspace is deleted when this is dropped
+struct ExecutionContext {
+    workspace: Workspace,
+    limits: ProcessLimits,
+    isolation: Isolation,
+    captured: CapturedOutput,
+    metadata: ExecutionMetadata,
+    provenance: Provenance,
+    #[cfg_attr(not(any(feature = "git", feature = "http")), allow(dead_code))]
+    install_dependencies: bool,
+    // Set when the script runs in an installed dependency environment
+    interpreter: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    determinism: Option<Determinism>,
+    js_runtime: JsRuntime,
+    permissions: Permissions,
+    memory_mb: Option<u64>,
+    #[cfg_attr(not(feature = "sql"), allow(dead_code))]
+    cpu_cores: Option<u32>,
+    // Output declared with data_type "parquet", for tasks that can produce one
+    #[cfg_attr(not(feature = "sql"), allow(dead_code))]
+    parquet_output: Option<String>,
+    // Produced by the task itself, next to the captured streams
+    artifacts: HashMap<String, String>,
+    // Child of the executor's token, so cancelling one execution leaves the others be
+    cancel: CancellationToken,
+}
+
+impl ExecutionContext {
+    fn record_process(&mut self, output: &ProcessOutput) {
+        self.captured.stdout.extend_from_slice(&output.stdout);
+        self.captured.stderr.extend_from_slice(&output.stderr);
+        // Earlier steps (e.g. a git clone) count towards the task's usage too
+        let mut resources = self.metadata.resources;
+        if let Some(usage) = &output.usage {
+            resources.get_or_insert_with(Default::default).accumulate(usage);
+        }
+        self.metadata = ExecutionMetadata {
+            exit_code: output.exit_code(),
+            signal: output.signal(),
+            timed_out: output.timed_out,
+            resources,
+        };
+    }
+
+    // Always report both streams, even on success, so malformed output can be inspected
+    fn output_artifacts(&self) -> HashMap<String, String> {
+        let mut artifacts = self.artifacts.clone();
+        artifacts.insert(STDOUT_ARTIFACT.to_string(), truncate_output(&self.captured.stdout, MAX_CAPTURED_OUTPUT_BYTES));
+        artifacts.insert(STDERR_ARTIFACT.to_string(), truncate_output(&self.captured.stderr, MAX_CAPTURED_OUTPUT_BYTES));
+        artifacts
+    }
+
+    // The task's own environment on a script or program. Deterministic runs start
+    // from a clean one instead of the worker's, so its locale, timezone and variables
+    // can't leak into the outputs.
+    fn apply_env(&self, command: &mut Command) {
+        if let Some(determinism) = self.determinism {
+            command.env_clear();
+            for name in DETERMINISTIC_PASSTHROUGH_ENV {
+                if let Some(value) = std::env::var_os(name) {
+                    command.env(name, value);
+                }
+            }
+            command
+                .env("TMPDIR", self.workspace.path())
+                .env("LANG", "C.UTF-8")
+                .env("LC_ALL", "C.UTF-8")
+                .env("TZ", "UTC")
+                // Python only takes 32-bit hash seeds
+                .env("PYTHONHASHSEED", (determinism.seed & u64::from(u32::MAX)).to_string())
+                .env("COREBRUM_SEED", determinism.seed.to_string())
+                .env("SOURCE_DATE_EPOCH", determinism.clock.to_string());
+        }
+        command.envs(self.env.iter().map(|(key, value)| (key, value)));
+    }
+}
 
 pub struct DynamicTaskExecutor {
-    temp_dir: Option<TempDir>,
+    workspace_root: PathBuf,
+    cache_dir: PathBuf,
+    dependency_policy: DependencyPolicy,
+    // Fail instead of fetching remote sources that aren't cached
+    offline: bool,
+    // One lock per cached git repository or dependency environment, so concurrent
+    // tasks don't write to it at once
+    #[cfg(any(feature = "git", feature = "http"))]
+    cache_locks: Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>,
+    // `-c` options and environment for git's own HTTP transport (proxy, CA bundle)
+    #[cfg(feature = "git")]
+    git_config: Vec<String>,
+    #[cfg(feature = "git")]
+    git_env: Vec<(String, String)>,
+    cancel: CancellationToken,
+    pool: Arc<ExecutionPool>,
+    #[cfg(feature = "http")]
+    downloader: Arc<Downloader>,
+    #[cfg(feature = "http")]
+    github_token: Option<String>,
+    #[cfg(feature = "docker")]
+    containers: Option<Arc<ContainerPool>>,
+    #[cfg(feature = "firecracker")]
+    microvm: Option<MicrovmSettings>,
+    // Attach a DebugBundle to failed results
+    debug_bundles: bool,
+    verbosity: Verbosity,
+}
+
+#[cfg(not(all(feature = "http", feature = "git", feature = "wasm", feature = "docker", feature = "firecracker", feature = "plugin", feature = "onnx", feature = "sql", feature = "dataframe", feature = "image", feature = "expression")))]
+fn feature_disabled(feature: &str) -> anyhow::Error {
+    anyhow::anyhow!("This worker was built without the '{}' feature", feature)
+}
+
+// Read access to the workspace and write access to the runner's result file, plus
+// what the task declared. Remote modules are still fetched (into DENO_DIR) without
+// network permission.
+pub fn deno_permission_flags(permissions: &Permissions, workdir: &Path, result_path: &Path) -> Result<Vec<String>> {
+    // An empty entry would turn `--allow-net=` into unrestricted access
+    let list = |kind: &str, entries: &[String]| -> Result<String> {
+        if let Some(entry) = entries.iter().find(|entry| entry.is_empty() || entry.contains([',', ' ', '='])) {
+            anyhow::bail!("Invalid {} permission {:?}", kind, entry);
+        }
+        Ok(entries.join(","))
+    };
+    let mut flags = vec!["--no-prompt".to_string(), format!("--allow-read={}", workdir.display())];
+    if permissions.write {
+        flags.push(format!("--allow-write={}", workdir.display()));
+    } else {
+        flags.push(format!("--allow-write={}", result_path.display()));
+    }
+    if !permissions.net.is_empty() {
+        flags.push(format!("--allow-net={}", list("net", &permissions.net)?));
+    }
+    if !permissions.env.is_empty() {
+        flags.push(format!("--allow-env={}", list("env", &permissions.env)?));
+    }
+    Ok(flags)
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+pub fn truncate_output(bytes: &[u8], limit: usize) -> String {
+    if bytes.len() <= limit {
+        return String::from_utf8_lossy(bytes).into_owned();
+    }
+    let mut text = String::from_utf8_lossy(&bytes[..limit]).into_owned();
+    text.push_str(TRUNCATION_MARKER);
+    text
 }
 
 impl DynamicTaskExecutor {
     pub fn new() -> Self {
+        Self::with_workspace_root(default_workspace_root())
+    }
+
+    pub fn with_workspace_root(workspace_root: PathBuf) -> Self {
         Self {
-            temp_dir: None,
+            workspace_root,
+            cache_dir: std::env::temp_dir().join("corebrum-cache"),
+            offline: false,
+            dependency_policy: DependencyPolicy::default(),
+            #[cfg(any(feature = "git", feature = "http"))]
+            cache_locks: Mutex::new(HashMap::new()),
+            #[cfg(feature = "git")]
+            git_config: Vec::new(),
+            #[cfg(feature = "git")]
+            git_env: Vec::new(),
+            cancel: CancellationToken::new(),
+            pool: ExecutionPool::shared(),
+            #[cfg(feature = "http")]
+            downloader: Downloader::shared(),
+            #[cfg(feature = "http")]
+            github_token: None,
+            #[cfg(feature = "docker")]
+            containers: None,
+            #[cfg(feature = "firecracker")]
+            microvm: None,
+            debug_bundles: false,
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Where fetched sources (e.g. git repositories) are kept between tasks
+    pub fn with_cache_dir(mut self, cache_dir: PathBuf) -> Self {
+        self.cache_dir = cache_dir;
+        self
+    }
+
+    pub fn with_dependency_policy(mut self, policy: DependencyPolicy) -> Self {
+        self.dependency_policy = policy;
+        self
+    }
+
+    // Offline mode for git and gists; URL downloads take it from the downloader
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    // Proxy and CA settings for git; URL and gist fetches take them from the downloader
+    #[cfg(feature = "git")]
+    pub fn with_network_settings(mut self, settings: &SourceSettings) -> Self {
+        self.git_config.clear();
+        self.git_env.clear();
+        if let Some(proxy) = settings.proxy() {
+            self.git_config.push(format!("http.proxy={}", proxy));
+        }
+        if let Some(no_proxy) = settings.no_proxy() {
+            self.git_env.push(("NO_PROXY".to_string(), no_proxy.clone()));
+            self.git_env.push(("no_proxy".to_string(), no_proxy));
         }
+        if let Some(ca_bundle) = settings.ca_bundle() {
+            self.git_config.push(format!("http.sslCAInfo={}", ca_bundle.display()));
+        }
+        self
+    }
+
+    #[cfg(feature = "http")]
+    pub fn with_downloader(mut self, downloader: Arc<Downloader>) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
+    #[cfg(feature = "http")]
+    pub fn with_github_token(mut self, token: Option<String>) -> Self {
+        self.github_token = token;
+        self
+    }
+
+    pub fn with_debug_bundles(mut self, debug_bundles: bool) -> Self {
+        self.debug_bundles = debug_bundles;
+        self
+    }
+
+    pub fn with_execution_pool(mut self, pool: Arc<ExecutionPool>) -> Self {
+        self.pool = pool;
+        self
+    }
+
+    pub fn execution_pool(&self) -> &Arc<ExecutionPool> {
+        &self.pool
+    }
+
+    #[cfg(feature = "docker")]
+    pub fn with_container_pool(mut self, containers: Arc<ContainerPool>) -> Self {
+        self.containers = Some(containers);
+        self
+    }
+
+    #[cfg(feature = "docker")]
+    pub fn container_pool(&self) -> Option<&Arc<ContainerPool>> {
+        self.containers.as_ref()
+    }
+
+    #[cfg(feature = "firecracker")]
+    pub fn with_microvm(mut self, settings: MicrovmSettings) -> Self {
+        self.microvm = Some(settings);
+        self
+    }
+
+    // Cancelling this token kills the process groups of every task running here, and
+    // of any started afterwards
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.cancel.clone()
     }
 
     pub async fn execute_task(
-        &mut self,
+        &self,
         task_definition: &TaskDefinition,
         inputs: serde_json::Value,
     ) -> Result<TaskResult> {
         let start_time = std::time::Instant::now();
-        
-        // Create temporary directory for execution
-        let temp_dir = TempDir::new().context("Failed to create temporary directory")?;
-        self.temp_dir = Some(temp_dir);
-        
+        let requirements = task_definition.requirements.as_ref();
+        // Refused before anything is written, and reported like any other task failure.
+        // Sources come off the network, so their hashes and refs are checked here too.
+        let refusal = task_definition.source.check().and_then(|_| requirements.map_or(Ok(()), TaskRequirements::check));
+        if let Err(e) = refusal {
+            return Ok(TaskResult::failure(uuid::Uuid::new_v4().to_string(), "dynamic_executor".to_string(), e.to_string()));
+        }
+        // Fresh workspace per execution, removed when ctx goes out of scope
+        let workspace = Workspace::create_in(&self.workspace_root)?;
+        let requested = requirements.is_some_and(|r| r.install_dependencies);
+        if requested && self.dependency_policy == DependencyPolicy::Never {
+            anyhow::bail!("This worker does not install task dependencies");
+        }
+        let limits = ProcessLimits {
+            timeout: requirements.and_then(|r| r.timeout_seconds).map(Duration::from_secs),
+            disk_quota: requirements.and_then(|r| r.disk_mb).map(|disk_mb| DiskQuota {
+                path: workspace.path().to_path_buf(),
+                limit_bytes: disk_mb.saturating_mul(1024 * 1024),
+            }),
+        };
+        let mut ctx = ExecutionContext {
+            workspace,
+            limits,
+            isolation: task_definition.isolation(),
+            captured: CapturedOutput::default(),
+            metadata: ExecutionMetadata::default(),
+            provenance: Provenance {
+                source_kind: task_definition.source.kind().to_string(),
+                ..Default::default()
+            },
+            install_dependencies: match self.dependency_policy {
+                DependencyPolicy::Never => false,
+                DependencyPolicy::OnRequest => requested,
+                DependencyPolicy::Always => true,
+            },
+            interpreter: None,
+            env: Vec::new(),
+            determinism: task_definition.determinism(),
+            js_runtime: task_definition.js_runtime(),
+            permissions: task_definition.permissions(),
+            memory_mb: requirements.and_then(|r| r.memory_mb),
+            cpu_cores: requirements.and_then(|r| r.cpu_cores),
+            parquet_output: task_definition
+                .outputs
+                .iter()
+                .find(|output| output.data_type.eq_ignore_ascii_case("parquet"))
+                .map(|output| output.name.clone()),
+            artifacts: HashMap::new(),
+            cancel: self.cancel.child_token(),
+        };
+        let bundle_inputs = self.debug_bundles.then(|| debug_bundle::truncated_inputs(&inputs));
+
         let result = match &task_definition.source {
             TaskSource::Inline { code } => {
-                self.execute_inline_code(&task_definition.language, code, inputs).await
+                let language = resolve_language(&task_definition.language, None, None, code);
+                self.execute_inline_code(&mut ctx, &language, code, inputs).await
             }
+            #[cfg(feature = "http")]
             TaskSource::Url { url } => {
-                self.execute_from_url(url, inputs).await
+                self.execute_from_url(&mut ctx, &task_definition.language, url, inputs).await
             }
-            TaskSource::Git { repo, path, branch } => {
-                self.execute_from_git(repo, path, branch.as_deref(), inputs).await
+            #[cfg(feature = "http")]
+            TaskSource::Archive { url, sha256, entrypoint } => {
+                self.execute_from_archive(&mut ctx, &task_definition.language, url, sha256, entrypoint, inputs).await
             }
+            #[cfg(feature = "git")]
+            TaskSource::Git { repo, path, branch, rev, submodules } => {
+                let reference = rev.as_deref().or(branch.as_deref());
+                self.execute_from_git(&mut ctx, &task_definition.language, repo, path, reference, *submodules, inputs).await
+            }
+            #[cfg(feature = "http")]
             TaskSource::Gist { id, filename } => {
-                self.execute_from_gist(id, filename, inputs).await
+                self.execute_from_gist(&mut ctx, &task_definition.language, id, filename, inputs).await
             }
+            #[cfg(feature = "wasm")]
             TaskSource::Wasm { wasm_bytes } => {
-                self.execute_wasm(wasm_bytes, inputs).await
+                self.execute_wasm(&mut ctx, wasm_bytes, inputs).await
             }
+            #[cfg(feature = "docker")]
             TaskSource::Docker { image, command } => {
-                self.execute_docker(image, command, inputs).await
+                self.execute_docker(&mut ctx, image, command, inputs).await
+            }
+            #[cfg(feature = "plugin")]
+            TaskSource::Plugin { url, sha256 } => {
+                self.execute_plugin(&mut ctx, url, sha256, inputs).await
             }
+            #[cfg(feature = "onnx")]
+            TaskSource::Onnx { url, sha256, batch_size } => {
+                let threads = requirements.and_then(|r| r.cpu_cores).unwrap_or(1) as usize;
+                self.execute_onnx(&mut ctx, url, sha256, *batch_size, threads, inputs).await
+            }
+            #[cfg(feature = "dataframe")]
+            TaskSource::DataFrame { dataset, operations, output_format } => {
+                self.execute_dataframe(&mut ctx, dataset, operations, *output_format, inputs).await
+            }
+            #[cfg(feature = "image")]
+            TaskSource::Image { frame, steps, output } => {
+                self.execute_image(&mut ctx, frame, steps, *output, inputs).await
+            }
+            TaskSource::Builtin { name } => {
+                self.execute_builtin(&mut ctx, name, inputs).await
+            }
+            #[cfg(not(feature = "http"))]
+            TaskSource::Url { .. } | TaskSource::Gist { .. } | TaskSource::Archive { .. } => Err(feature_disabled("http")),
+            #[cfg(not(feature = "git"))]
+            TaskSource::Git { .. } => Err(feature_disabled("git")),
+            #[cfg(not(feature = "wasm"))]
+            TaskSource::Wasm { .. } => Err(feature_disabled("wasm")),
+            #[cfg(not(feature = "docker"))]
+            TaskSource::Docker { .. } => Err(feature_disabled("docker")),
+            #[cfg(not(feature = "plugin"))]
+            TaskSource::Plugin { .. } => Err(feature_disabled("plugin")),
+            #[cfg(not(feature = "onnx"))]
+            TaskSource::Onnx { .. } => Err(feature_disabled("onnx")),
+            #[cfg(not(feature = "dataframe"))]
+            TaskSource::DataFrame { .. } => Err(feature_disabled("dataframe")),
+            #[cfg(not(feature = "image"))]
+            TaskSource::Image { .. } => Err(feature_disabled("image")),
         };
 
         let execution_time = start_time.elapsed().as_secs_f64();
+        let mut artifacts = ctx.output_artifacts();
+        // Taken before ctx (and with it the workspace) goes away
+        if let (Err(e), Some(inputs)) = (&result, bundle_inputs) {
+            let bundle = Self::debug_bundle(&ctx, task_definition, e, inputs);
+            artifacts.insert(DEBUG_BUNDLE_ARTIFACT.to_string(), serde_json::to_string(&bundle)?);
+        }
+        let metadata = ctx.metadata;
+        let provenance = Some(ctx.provenance);
         
         match result {
             Ok(outputs) => Ok(TaskResult {
@@ -60,6 +532,9 @@ impl DynamicTaskExecutor {
                 error: None,
                 execution_time_seconds: Some(execution_time),
                 completed_at: chrono::Utc::now(),
+                artifacts,
+                metadata,
+                provenance,
             }),
             Err(e) => Ok(TaskResult {
                 task_id: uuid::Uuid::new_v4().to_string(),
@@ -69,134 +544,1271 @@ impl DynamicTaskExecutor {
                 error: Some(e.to_string()),
                 execution_time_seconds: Some(execution_time),
                 completed_at: chrono::Utc::now(),
+                artifacts,
+                metadata,
+                provenance,
             }),
         }
     }
 
+    fn debug_bundle(ctx: &ExecutionContext, task_definition: &TaskDefinition, error: &anyhow::Error, inputs: String) -> DebugBundle {
+        let (workspace_files, workspace_truncated) = debug_bundle::list_workspace(ctx.workspace.path());
+        DebugBundle {
+            captured_at: chrono::Utc::now(),
+            language: task_definition.language.clone(),
+            source_kind: ctx.provenance.source_kind.clone(),
+            error: Some(error.to_string()),
+            workspace_files,
+            workspace_truncated,
+            inputs,
+            interpreter: ctx.interpreter.as_ref().map(|path| path.display().to_string()),
+            runtimes: Vec::new(),
+            env: debug_bundle::task_env(&ctx.env),
+            stderr_tail: debug_bundle::stderr_tail(&ctx.captured.stderr),
+        }
+    }
+
     async fn execute_inline_code(
         &self,
+        ctx: &mut ExecutionContext,
         language: &str,
         code: &str,
         inputs: serde_json::Value,
     ) -> Result<HashMap<String, serde_json::Value>> {
-        let temp_dir = self.temp_dir.as_ref().unwrap();
+        ctx.provenance.code_sha256 = Some(sha256_hex(code.as_bytes()));
+        // Evaluated in-process with no access to anything, so it never needs a microVM
+        if language == "expression" {
+            return Self::execute_expression(code, inputs);
+        }
+        if ctx.isolation == Isolation::Microvm {
+            if ctx.js_runtime == JsRuntime::Deno {
+                anyhow::bail!("Deno tasks can't run in a microVM");
+            }
+            return self.execute_in_microvm(ctx, language, code, inputs).await;
+        }
         
+        let script_name = match language {
+            "python" => "script.py",
+            "javascript" | "js" => "script.js",
+            "typescript" | "ts" => "script.ts",
+            "r" => "script.R",
+            "julia" => "script.jl",
+            "java" => anyhow::bail!("Java tasks run a JAR from a url or archive source"),
+            "c" => "main.c",
+            "sql" => "query.sql",
+            "cpp" | "c++" => "main.cpp",
+            _ => anyhow::bail!("Unsupported language: {}", language),
+        };
+        let script_path = ctx.workspace.path().join(script_name);
+        fs::write(&script_path, code)?;
+        self.run_script(ctx, language, &script_path, inputs).await
+    }
+
+    // Runs a script file from its own directory under the language's runner wrapper,
+    // which calls the handler(inputs) the script defines or exports
+    async fn run_script(
+        &self,
+        ctx: &mut ExecutionContext,
+        language: &str,
+        script_path: &Path,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
         match language {
-            "python" => {
-                let script_path = temp_dir.path().join("script.py");
-                fs::write(&script_path, code)?;
-                
-                // Write inputs to a JSON file
-                let inputs_path = temp_dir.path().join("inputs.json");
-                fs::write(&inputs_path, serde_json::to_string(&inputs)?)?;
-                
-                let output = Command::new("python3")
-                    .arg(&script_path)
-                    .arg(&inputs_path)
-                    .current_dir(temp_dir.path())
-                    .output()?;
-                
-                if !output.status.success() {
-                    anyhow::bail!("Python execution failed: {}", String::from_utf8_lossy(&output.stderr));
-                }
-                
-                let result_str = String::from_utf8(output.stdout)?;
-                let result: HashMap<String, serde_json::Value> = serde_json::from_str(&result_str)?;
-                Ok(result)
-            }
-            "javascript" | "js" => {
-                let script_path = temp_dir.path().join("script.js");
-                fs::write(&script_path, code)?;
-                
-                let inputs_path = temp_dir.path().join("inputs.json");
-                fs::write(&inputs_path, serde_json::to_string(&inputs)?)?;
-                
-                let output = Command::new("node")
-                    .arg(&script_path)
-                    .arg(&inputs_path)
-                    .current_dir(temp_dir.path())
-                    .output()?;
-                
-                if !output.status.success() {
-                    anyhow::bail!("JavaScript execution failed: {}", String::from_utf8_lossy(&output.stderr));
-                }
-                
-                let result_str = String::from_utf8(output.stdout)?;
-                let result: HashMap<String, serde_json::Value> = serde_json::from_str(&result_str)?;
-                Ok(result)
+            "java" => return self.run_jar(ctx, script_path, inputs).await,
+            "c" | "cpp" | "c++" => return self.compile_and_run(ctx, language, script_path, inputs).await,
+            "sql" => return self.execute_sql(ctx, &fs::read_to_string(script_path)?, inputs).await,
+            _ => {}
+        }
+        // Deno runs TypeScript as is; Node runs a bundle of it
+        let bundle;
+        let (language, script_path) = match (language, ctx.js_runtime) {
+            ("typescript" | "ts", JsRuntime::Node) => {
+                bundle = self.bundle_typescript(ctx, script_path).await?;
+                ("javascript", bundle.as_path())
             }
+            _ => (language, script_path),
+        };
+        let (interpreter, label, runner_file, runner) = match (language, ctx.js_runtime) {
+            ("python", JsRuntime::Node) => ("python3", "Python", PYTHON_RUNNER_FILE, PYTHON_RUNNER),
+            ("javascript" | "js", JsRuntime::Node) => ("node", "JavaScript", JS_RUNNER_FILE, JS_RUNNER),
+            ("r", JsRuntime::Node) => ("Rscript", "R", R_RUNNER_FILE, R_RUNNER),
+            ("julia", JsRuntime::Node) => ("julia", "Julia", JULIA_RUNNER_FILE, JULIA_RUNNER),
+            ("javascript" | "js" | "typescript" | "ts", JsRuntime::Deno) => ("deno", "Deno", DENO_RUNNER_FILE, DENO_RUNNER),
+            (_, JsRuntime::Deno) => anyhow::bail!("Deno can't run {} tasks", language),
             _ => anyhow::bail!("Unsupported language: {}", language),
+        };
+        let workdir = ctx.workspace.path().to_path_buf();
+        let inputs_path = workdir.join("inputs.json");
+        let result_path = workdir.join(RUNNER_RESULT_FILE);
+        fs::write(&inputs_path, serde_json::to_string(&inputs)?)?;
+        
+        let mut command = match &ctx.interpreter {
+            Some(interpreter) => Command::new(interpreter),
+            None => Command::new(interpreter),
+        };
+        let runner_path = workdir.join(runner_file);
+        fs::write(&runner_path, runner)?;
+        ctx.apply_env(&mut command);
+        if ctx.js_runtime == JsRuntime::Deno {
+            // The runner reads the pinned seed and clock from the environment
+            let mut permissions = ctx.permissions.clone();
+            if ctx.determinism.is_some() {
+                permissions.env.extend(["COREBRUM_SEED".to_string(), "SOURCE_DATE_EPOCH".to_string()]);
+            }
+            command
+                .arg("run")
+                .args(deno_permission_flags(&permissions, &workdir, &result_path)?)
+                .env("DENO_DIR", self.cache_dir.join("deno"))
+                .env("DENO_NO_UPDATE_CHECK", "1");
+            if self.offline {
+                command.arg("--cached-only");
+            }
         }
+        if language == "julia" {
+            // Nothing from the worker user's own setup
+            command.args(["--startup-file=no", "--history-file=no"]);
+        }
+        command
+            .arg(&runner_path)
+            .arg(script_path)
+            .arg(&inputs_path)
+            .arg(&result_path)
+            .current_dir(script_path.parent().unwrap_or(&workdir));
+        let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output);
+        
+        Self::script_outputs(ctx, label, &output, &result_path)
     }
 
-    async fn execute_from_url(&self, url: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
-        // Download and execute code from URL
-        let response = reqwest::get(url).await?;
-        let code = response.text().await?;
-        
-        // Determine language from URL or content
-        let language = if url.ends_with(".py") {
-            "python"
-        } else if url.ends_with(".js") {
-            "javascript"
-        } else {
-            "python" // default
+    async fn run_jar(&self, ctx: &mut ExecutionContext, jar_path: &Path, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        let mut command = Command::new("java");
+        if let Some(memory_mb) = ctx.memory_mb {
+            command.arg(format!("-Xmx{}m", jvm_heap_mb(memory_mb)));
+        }
+        command.arg("-jar").arg(jar_path);
+        self.run_program(ctx, "Java", command, jar_path, inputs).await
+    }
+
+    // Compiles a C or C++ source with the worker's compiler (cached by source, the
+    // local headers it includes and the compiler flags) and runs the binary
+    async fn compile_and_run(&self, ctx: &mut ExecutionContext, language: &str, source_path: &Path, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        let (label, flags): (&str, &[&str]) = match language {
+            "c" => ("C", &["-O2", "-std=c17", "-lm"]),
+            _ => ("C++", &["-O2", "-std=c++17"]),
         };
-        
-        self.execute_inline_code(language, &code, inputs).await
+        let compiler = c_compiler(language).ok_or_else(|| anyhow::anyhow!("No {} compiler was found on this worker", label))?;
+        let workdir = ctx.workspace.path().to_path_buf();
+        let source_dir = source_path.parent().unwrap_or(&workdir).to_path_buf();
+        let binary = workdir.join("task");
+
+        let mut key = format!("{} {}\n", compiler, flags.join(" ")).into_bytes();
+        key.extend(fs::read(source_path)?);
+        let cache = self.cache_dir.join("native").join(&sha256_hex(&key)[..16]);
+        if let Some(cached) = cached_build(&cache, "binary", &source_dir) {
+            fs::copy(cached, &binary)?;
+        } else {
+            let depfile = workdir.join("task.d");
+            let mut command = Command::new(compiler);
+            command
+                .arg(source_path)
+                .arg("-o")
+                .arg(&binary)
+                .arg("-MMD")
+                .arg("-MF")
+                .arg(&depfile)
+                .args(flags)
+                .current_dir(&source_dir);
+            let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+            ctx.record_process(&output);
+            if !output.success() {
+                anyhow::bail!("{} compilation failed ({}): {}", label, output.failure_reason(), String::from_utf8_lossy(&output.stderr));
+            }
+            let inputs = fs::read_to_string(&depfile)
+                .map_err(anyhow::Error::from)
+                .and_then(|deps| hash_inputs(depfile_inputs(&deps), &source_dir));
+            if let Err(e) = inputs.and_then(|inputs| store_build(&cache, "binary", &binary, &inputs)) {
+                summary!(self.verbosity, "⚠️  Failed to cache {} build: {}", label, e);
+            }
+        }
+
+        self.run_program(ctx, label, Command::new(&binary), source_path, inputs).await
     }
 
-    async fn execute_from_git(&self, repo: &str, path: &str, branch: Option<&str>, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
-        let temp_dir = self.temp_dir.as_ref().unwrap();
-        
-        // Clone repository
-        let mut git_cmd = Command::new("git");
-        git_cmd.arg("clone");
-        if let Some(branch) = branch {
-            git_cmd.arg("-b").arg(branch);
+    // Runs a program (a JAR, a compiled binary) from the directory of `entrypoint`,
+    // with the path of the inputs file as its argument and in COREBRUM_INPUTS. It
+    // prints its outputs as a JSON object, or writes {"outputs": {...}} to the file
+    // named by COREBRUM_RESULT.
+    async fn run_program(
+        &self,
+        ctx: &mut ExecutionContext,
+        label: &str,
+        mut command: Command,
+        entrypoint: &Path,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        let workdir = ctx.workspace.path().to_path_buf();
+        let inputs_path = workdir.join("inputs.json");
+        let result_path = workdir.join(RUNNER_RESULT_FILE);
+        fs::write(&inputs_path, serde_json::to_string(&inputs)?)?;
+
+        ctx.apply_env(&mut command);
+        command
+            .arg(&inputs_path)
+            .env("COREBRUM_INPUTS", &inputs_path)
+            .env("COREBRUM_RESULT", &result_path)
+            .current_dir(entrypoint.parent().unwrap_or(&workdir));
+        let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output);
+
+        Self::script_outputs(ctx, label, &output, &result_path)
+    }
+
+    // Bundles a TypeScript entrypoint and the local modules it imports into one
+    // CommonJS file next to it. Packages stay external and resolve from node_modules
+    // at run time. Bundles are cached by entrypoint and reused while none of the
+    // files that went into them changed.
+    async fn bundle_typescript(&self, ctx: &mut ExecutionContext, script_path: &Path) -> Result<PathBuf> {
+        let workdir = ctx.workspace.path().to_path_buf();
+        let source_dir = script_path.parent().unwrap_or(&workdir).to_path_buf();
+        let bundle_path = script_path.with_extension("corebrum.cjs");
+        let cache = self.cache_dir.join("typescript").join(&sha256_hex(&fs::read(script_path)?)[..16]);
+        if let Some(cached) = cached_build(&cache, "bundle.cjs", &source_dir) {
+            fs::copy(cached, &bundle_path)?;
+            return Ok(bundle_path);
+        }
+
+        let metafile = workdir.join("esbuild-meta.json");
+        let mut command = Command::new("esbuild");
+        command
+            .arg(script_path)
+            .args(["--bundle", "--platform=node", "--format=cjs", "--packages=external", "--log-level=warning"])
+            .arg(format!("--outfile={}", bundle_path.display()))
+            .arg(format!("--metafile={}", metafile.display()))
+            .current_dir(&source_dir);
+        let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output);
+        if !output.success() {
+            anyhow::bail!("TypeScript bundling failed ({}): {}", output.failure_reason(), String::from_utf8_lossy(&output.stderr));
         }
-        git_cmd.arg(repo).arg(temp_dir.path().join("repo"));
+        let inputs = bundle_inputs(&metafile).and_then(|inputs| hash_inputs(inputs, &source_dir));
+        if let Err(e) = inputs.and_then(|inputs| store_build(&cache, "bundle.cjs", &bundle_path, &inputs)) {
+            summary!(self.verbosity, "⚠️  Failed to cache TypeScript bundle: {}", e);
+        }
+        Ok(bundle_path)
+    }
+
+    // Outputs from the runner's result file when the script defines a handler, else
+    // from the JSON object a legacy script printed
+    fn script_outputs(ctx: &mut ExecutionContext, label: &str, output: &ProcessOutput, result_path: &Path) -> Result<HashMap<String, serde_json::Value>> {
+        if output.timed_out || output.cancelled || output.quota_exceeded {
+            anyhow::bail!("{} execution failed ({})", label, output.failure_reason());
+        }
+        if let Ok(result) = fs::read(result_path) {
+            let result: RunnerResult = serde_json::from_slice(&result)
+                .map_err(|e| anyhow::anyhow!("{} runner wrote an unreadable result: {}", label, e))?;
+            return match result {
+                RunnerResult::Outputs(outputs) => Ok(outputs),
+                RunnerResult::Error(error) => {
+                    // The traceback goes with the task's stderr, the message into the error
+                    if let Some(details) = &error.details {
+                        ctx.captured.stderr.extend_from_slice(details.as_bytes());
+                    }
+                    match error.kind.as_str() {
+                        "contract" => anyhow::bail!("{} task broke the handler contract: {}", label, error.message),
+                        _ => anyhow::bail!("{} task failed: {}", label, error.message),
+                    }
+                }
+            };
+        }
+        if !output.success() {
+            anyhow::bail!("{} execution failed ({}): {}", label, output.failure_reason(), String::from_utf8_lossy(&output.stderr));
+        }
+        if output.stdout_truncated {
+            anyhow::bail!("{} script printed more than {} bytes of outputs", label, MAX_PROCESS_OUTPUT_BYTES);
+        }
+        serde_json::from_slice(&output.stdout).map_err(|e| {
+            anyhow::anyhow!(
+                "{} script defines no handler(inputs) and did not print a JSON object of outputs ({})",
+                label,
+                e
+            )
+        })
+    }
+
+    #[cfg(feature = "http")]
+    async fn execute_from_url(&self, ctx: &mut ExecutionContext, language: &str, url: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        // Download and execute code from URL
+        let download = self.downloader.fetch(url).await?;
+        ctx.provenance.source_url = Some(download.url.clone());
+        // A JAR is binary, so it is run as downloaded rather than as code
+        let jar = language == "java" || is_auto_language(language) && language_from_extension(&download.url) == Some("java");
+        if jar {
+            if ctx.isolation == Isolation::Microvm {
+                anyhow::bail!("Java tasks can't run in a microVM yet");
+            }
+            ctx.provenance.code_sha256 = Some(sha256_hex(&download.body));
+            let jar_path = ctx.workspace.path().join("task.jar");
+            fs::write(&jar_path, &download.body)?;
+            return self.run_jar(ctx, &jar_path, inputs).await;
+        }
+        let code = script_text(url, download)?;
         
-        let output = git_cmd.output()?;
-        if !output.status.success() {
-            anyhow::bail!("Git clone failed: {}", String::from_utf8_lossy(&output.stderr));
+        let language = resolve_language(language, Some(url), None, &code);
+        self.execute_inline_code(ctx, &language, &code, inputs).await
+    }
+
+    // Downloads and verifies the archive, unpacks it into the workspace and runs the
+    // entrypoint from there, so it can import its neighbours
+    #[cfg(feature = "http")]
+    async fn execute_from_archive(
+        &self,
+        ctx: &mut ExecutionContext,
+        language: &str,
+        url: &str,
+        sha256: &str,
+        entrypoint: &str,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        if ctx.isolation == Isolation::Microvm {
+            anyhow::bail!("Archive sources can't run in a microVM yet");
+        }
+        let entrypoint_path = safe_relative_path(entrypoint)?;
+        let download = self.downloader.fetch(url).await?;
+        let digest = sha256_hex(&download.body);
+        if !digest.eq_ignore_ascii_case(sha256) {
+            anyhow::bail!("Checksum mismatch for {}: expected {}, got {}", url, sha256, digest);
+        }
+        ctx.provenance.source_url = Some(download.url.clone());
+        ctx.provenance.code_sha256 = Some(digest);
+
+        let project = ctx.workspace.path().join("project");
+        fs::create_dir_all(&project)?;
+        let limit = ctx.limits.disk_quota.as_ref().map(|quota| quota.limit_bytes);
+        let (body, dest) = (download.body, project.clone());
+        self.pool.run(move || extract_archive(&body, &dest, limit)).await??;
+
+        let script_path = project.join(entrypoint_path);
+        if !script_path.is_file() {
+            anyhow::bail!("Entrypoint {} not found in {}", entrypoint, url);
+        }
+        let language = &resolve_language(language, Some(entrypoint), None, &fs::read_to_string(&script_path).unwrap_or_default());
+        if ctx.install_dependencies {
+            self.prepare_dependencies(ctx, language, &project, &script_path).await?;
         }
+        self.run_script(ctx, language, &script_path, inputs).await
+    }
+
+    // Installs the requirements.txt or package.json closest to `script_path` (up to
+    // `root`) into an environment cached by manifest contents, and points the script
+    // at it. Python gets a virtualenv, JavaScript a node_modules linked next to the
+    // manifest.
+    #[cfg(any(feature = "git", feature = "http"))]
+    async fn prepare_dependencies(&self, ctx: &mut ExecutionContext, language: &str, root: &Path, script_path: &Path) -> Result<()> {
+        let (kind, manifest_name) = match language {
+            "python" => ("python", PYTHON_MANIFEST),
+            "javascript" | "js" | "typescript" | "ts" => ("node", NODE_MANIFEST),
+            _ => return Ok(()),
+        };
+        let Some(manifest) = find_manifest(root, script_path, manifest_name) else {
+            return Ok(());
+        };
+        let manifest_dir = manifest.parent().unwrap_or(root).to_path_buf();
+        let mut fingerprint = fs::read(&manifest)?;
+        let lockfile = manifest_dir.join(NODE_LOCKFILE);
+        if kind == "node" {
+            if let Ok(lock) = fs::read(&lockfile) {
+                fingerprint.extend_from_slice(&lock);
+            }
+        }
+        let env_dir = self.cache_dir.join("envs").join(kind).join(&sha256_hex(&fingerprint)[..16]);
+
+        let lock = self.cache_lock(&env_dir);
+        let _guard = lock.lock().await;
+        if !env_dir.join(ENV_READY_MARKER).exists() {
+            if self.offline {
+                anyhow::bail!("Offline mode: dependencies from {} are not in the cache", manifest_name);
+            }
+            detail!(self.verbosity, "📦 Installing dependencies from {}", manifest_name);
+            // A leftover from an interrupted install
+            let _ = fs::remove_dir_all(&env_dir);
+            fs::create_dir_all(&env_dir)?;
+            if kind == "python" {
+                let mut venv = Command::new("python3");
+                venv.arg("-m").arg("venv").arg(&env_dir);
+                self.run_install_step(ctx, venv, "Creating virtualenv").await?;
+                let mut pip = Command::new(venv_python(&env_dir));
+                pip.args(["-m", "pip", "install", "--disable-pip-version-check", "-r"]).arg(&manifest);
+                self.run_install_step(ctx, pip, "pip install").await?;
+            } else {
+                fs::copy(&manifest, env_dir.join(NODE_MANIFEST))?;
+                let mut npm = Command::new("npm");
+                if lockfile.exists() {
+                    fs::copy(&lockfile, env_dir.join(NODE_LOCKFILE))?;
+                    npm.arg("ci");
+                } else {
+                    npm.arg("install");
+                }
+                npm.args(["--omit=dev", "--no-audit", "--no-fund"]).current_dir(&env_dir);
+                self.run_install_step(ctx, npm, "npm install").await?;
+            }
+            fs::write(env_dir.join(ENV_READY_MARKER), b"")?;
+        }
+
+        if kind == "python" {
+            ctx.interpreter = Some(venv_python(&env_dir));
+        } else {
+            let node_modules = env_dir.join("node_modules");
+            #[cfg(unix)]
+            if !manifest_dir.join("node_modules").exists() {
+                std::os::unix::fs::symlink(&node_modules, manifest_dir.join("node_modules"))?;
+            }
+            ctx.env.push(("NODE_PATH".to_string(), node_modules.to_string_lossy().into_owned()));
+        }
+        Ok(())
+    }
+
+    #[cfg(any(feature = "git", feature = "http"))]
+    async fn run_install_step(&self, ctx: &mut ExecutionContext, command: Command, step: &str) -> Result<()> {
+        let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output);
+        if !output.success() {
+            anyhow::bail!("{} failed ({}): {}", step, output.failure_reason(), String::from_utf8_lossy(&output.stderr));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "git")]
+    async fn execute_from_git(
+        &self,
+        ctx: &mut ExecutionContext,
+        language: &str,
+        repo: &str,
+        path: &str,
+        reference: Option<&str>,
+        submodules: bool,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        let repo_dir = ctx.workspace.path().join("repo");
+        let commit = self.checkout_git(ctx, repo, reference, submodules, &repo_dir).await?;
+        ctx.provenance.source_url = Some(repo.to_string());
+        ctx.provenance.git_commit = Some(commit);
         
-        // Execute the file
-        let file_path = temp_dir.path().join("repo").join(path);
+        // Run the file in place, so it can import the rest of the repository
+        let file_path = repo_dir.join(safe_relative_path(path)?);
         let code = fs::read_to_string(&file_path)?;
-        
-        let language = if path.ends_with(".py") {
-            "python"
-        } else if path.ends_with(".js") {
-            "javascript"
+        let language = &resolve_language(language, Some(path), None, &code);
+        if ctx.isolation == Isolation::Microvm {
+            return self.execute_inline_code(ctx, language, &code, inputs).await;
+        }
+        ctx.provenance.code_sha256 = Some(sha256_hex(code.as_bytes()));
+        if ctx.install_dependencies {
+            self.prepare_dependencies(ctx, language, &repo_dir, &file_path).await?;
+        }
+        self.run_script(ctx, language, &file_path, inputs).await
+    }
+
+    // Checks `reference` (commit, tag or branch; the remote HEAD if None) of `repo`
+    // out into `dest` and returns the commit. Every repository has a bare cache that
+    // only the wanted ref is shallow-fetched into, and each task gets a worktree of
+    // it, so repeated runs only transfer what changed.
+    #[cfg(feature = "git")]
+    async fn checkout_git(
+        &self,
+        ctx: &mut ExecutionContext,
+        repo: &str,
+        reference: Option<&str>,
+        submodules: bool,
+        dest: &Path,
+    ) -> Result<String> {
+        // Both end up in git's argv, where a leading `-` would make them options
+        for (field, value) in [("repo", Some(repo)), ("rev", reference)] {
+            if let Some(value) = value.filter(|value| value.starts_with('-')) {
+                anyhow::bail!("Git {} {:?} can't start with '-'", field, value);
+            }
+        }
+        let cache = self.cache_dir.join("git").join(format!("{}.git", &sha256_hex(repo.as_bytes())[..16]));
+        let lock = self.cache_lock(&cache);
+        let _guard = lock.lock().await;
+
+        if !cache.join("HEAD").exists() {
+            if self.offline {
+                anyhow::bail!("Offline mode: {} is not in the git cache", repo);
+            }
+            fs::create_dir_all(&cache)?;
+            self.git(ctx, [OsStr::new("init"), OsStr::new("--bare"), OsStr::new("--"), cache.as_os_str()]).await?;
+            self.git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("remote"), OsStr::new("add"), OsStr::new("--"), OsStr::new("origin"), OsStr::new(repo)]).await?;
+        }
+        // Worktrees of earlier tasks are gone along with their workspaces
+        self.git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("worktree"), OsStr::new("prune")]).await?;
+
+        let wanted = reference.unwrap_or("HEAD");
+        // Remembers what `wanted` resolved to, for offline runs and to keep it from
+        // being garbage collected
+        let pin = format!("refs/pinned/{}", &sha256_hex(wanted.as_bytes())[..16]);
+        if self.offline {
+            if submodules {
+                anyhow::bail!("Offline mode: submodules of {} can't be fetched", repo);
+            }
+            for candidate in [format!("{}^{{commit}}", pin), format!("{}^{{commit}}", wanted)] {
+                let resolved = self
+                    .try_git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("rev-parse"), OsStr::new("--verify"), OsStr::new("--end-of-options"), OsStr::new(&candidate)])
+                    .await?;
+                if resolved.success() {
+                    let commit = String::from_utf8_lossy(&resolved.stdout).trim().to_string();
+                    self.git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("worktree"), OsStr::new("add"), OsStr::new("--detach"), OsStr::new("--force"), OsStr::new("--"), dest.as_os_str(), OsStr::new(&commit)])
+                        .await?;
+                    return Ok(commit);
+                }
+            }
+            anyhow::bail!("Offline mode: {} of {} is not in the git cache", wanted, repo);
+        }
+
+        let shallow = self
+            .try_git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("fetch"), OsStr::new("--depth"), OsStr::new("1"), OsStr::new("--no-tags"), OsStr::new("--end-of-options"), OsStr::new("origin"), OsStr::new(wanted)])
+            .await?;
+        let resolve = if shallow.success() {
+            "FETCH_HEAD".to_string()
+        } else {
+            // Servers may refuse to fetch a commit that no ref points at; fall back to
+            // fetching all branches and tags and resolving it locally
+            let mut fetch = vec![OsStr::new("-C"), cache.as_os_str(), OsStr::new("fetch"), OsStr::new("--tags")];
+            if cache.join("shallow").exists() {
+                fetch.push(OsStr::new("--unshallow"));
+            }
+            fetch.extend([OsStr::new("--end-of-options"), OsStr::new("origin"), OsStr::new("+refs/heads/*:refs/remotes/origin/*")]);
+            self.git(ctx, fetch).await?;
+            format!("{}^{{commit}}", wanted)
+        };
+        let resolved = self
+            .git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("rev-parse"), OsStr::new("--end-of-options"), OsStr::new(&resolve)])
+            .await?;
+        let commit = String::from_utf8_lossy(&resolved.stdout).trim().to_string();
+        self.git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("update-ref"), OsStr::new(&pin), OsStr::new(&commit)])
+            .await?;
+
+        self.git(ctx, [OsStr::new("-C"), cache.as_os_str(), OsStr::new("worktree"), OsStr::new("add"), OsStr::new("--detach"), OsStr::new("--force"), OsStr::new("--"), dest.as_os_str(), OsStr::new(&commit)])
+            .await?;
+        if submodules {
+            self.git(ctx, [OsStr::new("-C"), dest.as_os_str(), OsStr::new("submodule"), OsStr::new("update"), OsStr::new("--init"), OsStr::new("--recursive"), OsStr::new("--depth"), OsStr::new("1")])
+                .await?;
+        }
+        Ok(commit)
+    }
+
+    #[cfg(any(feature = "git", feature = "http"))]
+    fn cache_lock(&self, cache: &Path) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.cache_locks.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        locks.entry(cache.to_path_buf()).or_default().clone()
+    }
+
+    // Runs git under the task's limits; a failing command is returned, not an error
+    #[cfg(feature = "git")]
+    async fn try_git<'a>(&self, ctx: &mut ExecutionContext, args: impl IntoIterator<Item = &'a OsStr>) -> Result<ProcessOutput> {
+        let mut command = Command::new("git");
+        for option in &self.git_config {
+            command.arg("-c").arg(option);
+        }
+        command.envs(self.git_env.iter().map(|(key, value)| (key, value)));
+        command.args(args);
+        let output = run_command(command, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output);
+        Ok(output)
+    }
+
+    #[cfg(feature = "git")]
+    async fn git<'a>(&self, ctx: &mut ExecutionContext, args: impl IntoIterator<Item = &'a OsStr>) -> Result<ProcessOutput> {
+        let args: Vec<&OsStr> = args.into_iter().collect();
+        let output = self.try_git(ctx, args.iter().copied()).await?;
+        if !output.success() {
+            anyhow::bail!(
+                "git {} failed ({}): {}",
+                args.iter().map(|arg| arg.to_string_lossy()).collect::<Vec<_>>().join(" "),
+                output.failure_reason(),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(output)
+    }
+
+    // Goes through the GitHub API, which names the current revision and (for small
+    // files) includes the content; larger files come from the revision's raw URL
+    #[cfg(feature = "http")]
+    async fn execute_from_gist(&self, ctx: &mut ExecutionContext, language: &str, id: &str, filename: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        let token = self.github_token.as_deref();
+        let api_url = format!("https://api.github.com/gists/{}", id);
+        // The last API response is kept, so offline runs know the revision too
+        let index = self.cache_dir.join("gists").join(format!("{}.json", &sha256_hex(id.as_bytes())[..16]));
+        let gist: serde_json::Value = if self.offline {
+            let cached = fs::read(&index).map_err(|_| anyhow::anyhow!("Offline mode: gist {} is not in the cache", id))?;
+            serde_json::from_slice(&cached)?
         } else {
-            "python" // default
+            let gist: serde_json::Value = github_get(self.downloader.client(), &api_url, token, self.verbosity).await?.json().await?;
+            let cached = fs::create_dir_all(self.cache_dir.join("gists")).and_then(|_| fs::write(&index, gist.to_string()));
+            if let Err(e) = cached {
+                summary!(self.verbosity, "⚠️  Failed to cache gist {}: {}", id, e);
+            }
+            gist
+        };
+        let revision = gist["history"][0]["version"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Gist {} has no revision history", id))?;
+        let file = &gist["files"][filename];
+        let raw_url = file["raw_url"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Gist {} has no file {}", id, filename))?;
+
+        let code = match file["content"].as_str() {
+            Some(content) if !file["truncated"].as_bool().unwrap_or(false) => {
+                check_script(raw_url, None, content)?;
+                content.to_string()
+            }
+            // Raw URLs are pinned to the revision, so these cache well
+            _ => script_text(raw_url, self.downloader.fetch(raw_url).await?)?,
+        };
+        ctx.provenance.source_url = Some(raw_url.to_string());
+        ctx.provenance.gist_revision = Some(revision.to_string());
+        let language = resolve_language(language, Some(filename), file["language"].as_str(), &code);
+        self.execute_inline_code(ctx, &language, &code, inputs).await
+    }
+
+    #[cfg(feature = "wasm")]
+    async fn execute_wasm(&self, ctx: &mut ExecutionContext, wasm_bytes: &[u8], inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        // Compilation and execution are CPU-bound, so both happen on the execution pool
+        ctx.provenance.code_sha256 = Some(sha256_hex(wasm_bytes));
+        let wasm_bytes = wasm_bytes.to_vec();
+        let stdin = serde_json::to_vec(&inputs)?;
+        let engine = interruptible_engine()?;
+        let memory_mb = ctx.memory_mb;
+        let run = self.pool.run({
+            let engine = engine.clone();
+            move || run_wasi_command(&engine, &wasm_bytes, stdin, memory_mb)
+        });
+        tokio::pin!(run);
+        let timeout = ctx.limits.timeout;
+        let deadline = async move {
+            match timeout {
+                Some(timeout) => tokio::time::sleep(timeout).await,
+                None => std::future::pending().await,
+            }
+        };
+        // The guest only stops at its next epoch check, so wait for it to free the pool thread
+        let output = tokio::select! {
+            output = &mut run => output??,
+            _ = deadline => {
+                interrupt_wasm(&engine);
+                let _ = run.await;
+                ctx.metadata.timed_out = true;
+                anyhow::bail!("WASM execution failed (timed out)");
+            }
+            _ = ctx.cancel.cancelled() => {
+                interrupt_wasm(&engine);
+                let _ = run.await;
+                anyhow::bail!("WASM execution failed (cancelled)");
+            }
         };
+        ctx.captured.stdout.extend_from_slice(&output.stdout);
+        ctx.captured.stderr.extend_from_slice(&output.stderr);
+        ctx.metadata.exit_code = Some(output.exit_code);
+        
+        if output.exit_code != 0 {
+            anyhow::bail!("WASM execution failed (exited with code {}): {}", output.exit_code, String::from_utf8_lossy(&output.stderr));
+        }
         
-        self.execute_inline_code(language, &code, inputs).await
+        let result: HashMap<String, serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+        Ok(result)
+    }
+
+    // Path of a binary artifact (plugin library, model) in the cache, downloaded and
+    // checked against its hash unless an intact copy is already there
+    #[cfg(any(feature = "plugin", feature = "onnx"))]
+    async fn cached_artifact(&self, ctx: &mut ExecutionContext, kind: &str, extension: &str, url: &str, sha256: &str) -> Result<PathBuf> {
+        // Part of the cache path, so never anything but hex
+        if !is_sha256_hex(sha256) {
+            anyhow::bail!("{:?} is not a hex SHA-256", sha256);
+        }
+        let sha256 = sha256.to_ascii_lowercase();
+        let dir = self.cache_dir.join(kind);
+        let path = dir.join(format!("{}.{}", &sha256[..16], extension));
+        let cached = fs::read(&path).is_ok_and(|artifact| sha256_hex(&artifact) == sha256);
+        if !cached {
+            let download = self.downloader.fetch(url).await?;
+            let digest = sha256_hex(&download.body);
+            if digest != sha256 {
+                anyhow::bail!("Checksum mismatch for {}: expected {}, got {}", url, sha256, digest);
+            }
+            fs::create_dir_all(&dir)?;
+            let partial = path.with_extension(format!("partial-{}", uuid::Uuid::new_v4()));
+            fs::write(&partial, &download.body)?;
+            fs::rename(&partial, &path)?;
+        }
+        ctx.provenance.source_url = Some(url.to_string());
+        ctx.provenance.code_sha256 = Some(sha256);
+        Ok(path)
+    }
+
+    // The library is downloaded once into the cache, checked against its hash, and
+    // called on the execution pool. Being in-process, a call that overruns the
+    // timeout can only be abandoned, not stopped.
+    #[cfg(feature = "plugin")]
+    async fn execute_plugin(&self, ctx: &mut ExecutionContext, url: &str, sha256: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        if ctx.isolation == Isolation::Microvm {
+            anyhow::bail!("Plugin tasks run in the worker process and can't be isolated in a microVM");
+        }
+        let path = self.cached_artifact(ctx, "plugins", std::env::consts::DLL_EXTENSION, url, sha256).await?;
+        let sha256 = sha256.to_ascii_lowercase();
+
+        let inputs = serde_json::to_string(&inputs)?;
+        let call = self.pool.run(move || NativePlugin::load(&path, &sha256)?.run(&inputs));
+        let outputs = match ctx.limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| anyhow::anyhow!("Plugin call timed out after {}s and was abandoned", timeout.as_secs()))???,
+            None => call.await??,
+        };
+        serde_json::from_str(&outputs).map_err(|e| anyhow::anyhow!("Plugin did not return a JSON object of outputs: {}", e))
+    }
+
+    // Every input is a tensor (inline or referenced by URL); every output the model
+    // declares comes back as one. Sessions are cached per model and run on the
+    // execution pool.
+    #[cfg(feature = "onnx")]
+    async fn execute_onnx(
+        &self,
+        ctx: &mut ExecutionContext,
+        url: &str,
+        sha256: &str,
+        batch_size: Option<usize>,
+        threads: usize,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        if ctx.isolation == Isolation::Microvm {
+            anyhow::bail!("ONNX tasks can't run in a microVM yet");
+        }
+        let path = self.cached_artifact(ctx, "models", "onnx", url, sha256).await?;
+        let serde_json::Value::Object(inputs) = inputs else {
+            anyhow::bail!("ONNX task inputs must be an object of named tensors");
+        };
+        let mut tensors = Vec::new();
+        for (name, value) in inputs {
+            let spec: TensorSpec = serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Input {} is not a tensor: {}", name, e))?;
+            let tensor = match &spec.url {
+                Some(tensor_url) => {
+                    let download = self.downloader.fetch(tensor_url).await?;
+                    if let Some(expected) = &spec.sha256 {
+                        let digest = sha256_hex(&download.body);
+                        if !digest.eq_ignore_ascii_case(expected) {
+                            anyhow::bail!("Checksum mismatch for {}: expected {}, got {}", tensor_url, expected, digest);
+                        }
+                    }
+                    Tensor::from_bytes(&spec.dtype, spec.shape.clone(), &download.body)
+                }
+                None => Tensor::from_spec(&spec),
+            };
+            tensors.push((name.clone(), tensor.map_err(|e| e.context(format!("Invalid input tensor {}", name)))?));
+        }
+
+        let sha256 = sha256.to_ascii_lowercase();
+        let threads = threads.max(1);
+        let call = self.pool.run(move || OnnxModel::load(&path, &sha256, threads)?.infer(tensors, batch_size));
+        let outputs = match ctx.limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| anyhow::anyhow!("Inference timed out after {}s and was abandoned", timeout.as_secs()))???,
+            None => call.await??,
+        };
+        Ok(outputs.into_iter().map(|(name, tensor)| (name, tensor.to_json())).collect())
     }
 
-    async fn execute_from_gist(&self, id: &str, filename: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
-        let url = format!("https://gist.githubusercontent.com/{}/raw/{}", id, filename);
-        self.execute_from_url(&url, inputs).await
+    // Downloads a dataset input ({"url": ..., "format"?: ..., "sha256"?: ...}) to
+    // `<dir>/<name>.<ext>`; None if the input isn't one
+    #[cfg(any(feature = "sql", feature = "dataframe"))]
+    async fn stage_dataset(&self, dir: &Path, name: &str, value: &serde_json::Value) -> Result<Option<(PathBuf, DatasetFormat)>> {
+        let Some(url) = value.get("url").and_then(|url| url.as_str()) else {
+            return Ok(None);
+        };
+        // The input name becomes the file name, so it can't be allowed to leave `dir`
+        let file_name = safe_file_name(name)?;
+        let format = value
+            .get("format")
+            .and_then(|format| format.as_str())
+            .or(Some(url))
+            .and_then(DatasetFormat::parse)
+            .ok_or_else(|| anyhow::anyhow!("Can't tell the format of input {}; set `format` to csv, parquet or json", name))?;
+        let download = self.downloader.fetch(url).await?;
+        if let Some(expected) = value.get("sha256").and_then(|sha256| sha256.as_str()) {
+            let digest = sha256_hex(&download.body);
+            if !digest.eq_ignore_ascii_case(expected) {
+                anyhow::bail!("Checksum mismatch for {}: expected {}, got {}", url, expected, digest);
+            }
+        }
+        let path = dir.join(format!("{}.{}", file_name, format.extension()));
+        fs::write(&path, &download.body)?;
+        Ok(Some((path, format)))
     }
 
-    async fn execute_wasm(&self, wasm_bytes: &[u8], inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
-        // WASM execution would require wasmtime runtime
-        // This is a simplified implementation
-        anyhow::bail!("WASM execution not yet implemented")
+    // Object inputs with a `url` are datasets, staged into the workspace and queried
+    // as views by input name; other inputs are variables. The result set comes back
+    // as rows, or as a Parquet artifact if the task declares a parquet output.
+    #[cfg(feature = "sql")]
+    async fn execute_sql(&self, ctx: &mut ExecutionContext, query: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        use base64::Engine;
+
+        let workdir = ctx.workspace.path().to_path_buf();
+        let data_dir = workdir.join("data");
+        fs::create_dir_all(&data_dir)?;
+        let serde_json::Value::Object(inputs) = inputs else {
+            anyhow::bail!("SQL task inputs must be an object");
+        };
+        let mut tables = Vec::new();
+        let mut variables = Vec::new();
+        for (name, value) in inputs {
+            match self.stage_dataset(&data_dir, &name, &value).await? {
+                Some((path, format)) => tables.push(SqlTable { name, path, format }),
+                None => variables.push((name, value)),
+            }
+        }
+
+        let parquet_output = ctx.parquet_output.clone();
+        let format = if parquet_output.is_some() { DatasetFormat::Parquet } else { DatasetFormat::Json };
+        let output_path = workdir.join(format!("result.{}", format.extension()));
+        let conn = open_sandboxed(&workdir, ctx.memory_mb, ctx.cpu_cores)?;
+        let interrupt = conn.interrupt_handle();
+        let (query, output) = (query.to_string(), output_path.clone());
+        let call = self.pool.run(move || -> Result<Option<u64>> {
+            run_query(&conn, &tables, &variables, &query, &output, format)?;
+            match format {
+                DatasetFormat::Parquet => Ok(Some(parquet_row_count(&conn, &output)?)),
+                _ => Ok(None),
+            }
+        });
+        let row_count = match ctx.limits.timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, call).await {
+                Ok(result) => result??,
+                Err(_) => {
+                    interrupt.interrupt();
+                    anyhow::bail!("SQL query timed out after {}s", timeout.as_secs());
+                }
+            },
+            None => call.await??,
+        };
+
+        let mut outputs = HashMap::new();
+        match parquet_output {
+            Some(name) => {
+                let bytes = fs::read(&output_path)?;
+                let artifact = format!("{}.parquet", name);
+                outputs.insert(name, serde_json::json!({ "artifact": artifact, "encoding": "base64", "bytes": bytes.len() }));
+                outputs.insert("row_count".to_string(), serde_json::json!(row_count));
+                ctx.artifacts.insert(artifact, base64::engine::general_purpose::STANDARD.encode(bytes));
+            }
+            None => {
+                let rows: serde_json::Value = serde_json::from_slice(&fs::read(&output_path)?)?;
+                let count = rows.as_array().map_or(0, Vec::len);
+                outputs.insert("rows".to_string(), rows);
+                outputs.insert("row_count".to_string(), serde_json::json!(count));
+            }
+        }
+        Ok(outputs)
     }
 
-    async fn execute_docker(&self, image: &str, command: &[String], inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
-        // Docker execution would require docker daemon
-        // This is a simplified implementation
-        anyhow::bail!("Docker execution not yet implemented")
+    // Built-ins run in the worker process: sleep waits on the runtime, the rest run on
+    // the execution pool, the benchmark writing its disk test's file to the workspace
+    async fn execute_builtin(&self, ctx: &mut ExecutionContext, name: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        if name == "sleep" {
+            let duration = sleep_duration(&inputs)?;
+            let waited = ctx.limits.timeout.map_or(duration, |timeout| timeout.min(duration));
+            tokio::select! {
+                _ = tokio::time::sleep(waited) => {}
+                _ = ctx.cancel.cancelled() => anyhow::bail!("Built-in sleep cancelled"),
+            }
+            if waited < duration {
+                ctx.metadata.timed_out = true;
+                anyhow::bail!("Built-in sleep timed out after {}s", waited.as_secs_f64());
+            }
+            return Ok(HashMap::from([("slept_seconds".to_string(), serde_json::json!(duration.as_secs_f64()))]));
+        }
+        let name = name.to_string();
+        let dir = ctx.workspace.path().to_path_buf();
+        let run = self.pool.run(move || match name.as_str() {
+            "benchmark" => run_benchmark(&dir, benchmark_size(&inputs)?).and_then(|scores| benchmark_outputs(&scores)),
+            _ => run_builtin(&name, &inputs),
+        });
+        match ctx.limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, run)
+                .await
+                .map_err(|_| anyhow::anyhow!("Built-in task timed out after {}s and was abandoned", timeout.as_secs()))??,
+            None => run.await?,
+        }
     }
+
+    #[cfg(feature = "expression")]
+    fn execute_expression(code: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        evaluate(code.trim(), &inputs)
+    }
+
+    #[cfg(not(feature = "expression"))]
+    fn execute_expression(_code: &str, _inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        Err(feature_disabled("expression"))
+    }
+
+    #[cfg(not(feature = "sql"))]
+    async fn execute_sql(&self, _ctx: &mut ExecutionContext, _query: &str, _inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        Err(feature_disabled("sql"))
+    }
+
+    // The `dataset` input is staged like a SQL dataset and transformed by Polars on the
+    // execution pool; the result comes back as a `result.<ext>` artifact. Being
+    // in-process, a transform that overruns the timeout can only be abandoned.
+    #[cfg(feature = "dataframe")]
+    async fn execute_dataframe(
+        &self,
+        ctx: &mut ExecutionContext,
+        dataset: &str,
+        operations: &[FrameOperation],
+        output_format: DatasetFormat,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        use base64::Engine;
+
+        if ctx.isolation == Isolation::Microvm {
+            anyhow::bail!("DataFrame tasks run in the worker process and can't be isolated in a microVM");
+        }
+        let workdir = ctx.workspace.path().to_path_buf();
+        let data_dir = workdir.join("data");
+        fs::create_dir_all(&data_dir)?;
+        let value = inputs.get(dataset).ok_or_else(|| anyhow::anyhow!("Missing dataset input {}", dataset))?;
+        let (input_path, input_format) = self
+            .stage_dataset(&data_dir, dataset, value)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Input {} must be a dataset with a `url`", dataset))?;
+
+        let artifact = format!("result.{}", output_format.extension());
+        let output_path = workdir.join(&artifact);
+        let (operations, output) = (operations.to_vec(), output_path.clone());
+        let call = self.pool.run(move || transform_dataset(&input_path, input_format, &operations, &output, output_format));
+        let summary = match ctx.limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| anyhow::anyhow!("DataFrame transform timed out after {}s and was abandoned", timeout.as_secs()))???,
+            None => call.await??,
+        };
+
+        let bytes = fs::read(&output_path)?;
+        let (encoding, content) = match output_format {
+            DatasetFormat::Parquet => ("base64", base64::engine::general_purpose::STANDARD.encode(&bytes)),
+            DatasetFormat::Csv | DatasetFormat::Json => ("utf-8", String::from_utf8(bytes.clone())?),
+        };
+        let mut outputs = HashMap::new();
+        outputs.insert("result".to_string(), serde_json::json!({ "artifact": artifact, "encoding": encoding, "bytes": bytes.len() }));
+        outputs.insert("row_count".to_string(), serde_json::json!(summary.rows));
+        outputs.insert("columns".to_string(), serde_json::json!(summary.columns));
+        ctx.artifacts.insert(artifact, content);
+        Ok(outputs)
+    }
+
+    // The `frame` input is a base64 JPEG or PNG (a data URL is fine) or a reference to
+    // one; it's preprocessed on the execution pool into a `tensor` output or a
+    // `frame.<ext>` artifact
+    #[cfg(feature = "image")]
+    async fn execute_image(
+        &self,
+        ctx: &mut ExecutionContext,
+        frame: &str,
+        steps: &[ImageStep],
+        output: ImageOutput,
+        inputs: serde_json::Value,
+    ) -> Result<HashMap<String, serde_json::Value>> {
+        use base64::Engine;
+
+        if ctx.isolation == Isolation::Microvm {
+            anyhow::bail!("Image tasks run in the worker process and can't be isolated in a microVM");
+        }
+        let bytes = match inputs.get(frame) {
+            Some(serde_json::Value::String(encoded)) => {
+                let encoded = encoded.split_once(";base64,").map_or(encoded.as_str(), |(_, data)| data);
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .map_err(|e| anyhow::anyhow!("Input {} is not valid base64: {}", frame, e))?
+            }
+            Some(value) => {
+                let url = value
+                    .get("url")
+                    .and_then(|url| url.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("Input {} must be a base64 image or have a `url`", frame))?;
+                let download = self.downloader.fetch(url).await?;
+                if let Some(expected) = value.get("sha256").and_then(|sha256| sha256.as_str()) {
+                    let digest = sha256_hex(&download.body);
+                    if !digest.eq_ignore_ascii_case(expected) {
+                        anyhow::bail!("Checksum mismatch for {}: expected {}, got {}", url, expected, digest);
+                    }
+                }
+                download.body
+            }
+            None => anyhow::bail!("Missing frame input {}", frame),
+        };
+
+        let steps = steps.to_vec();
+        let call = self.pool.run(move || preprocess_image(&bytes, &steps, output));
+        let image = match ctx.limits.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, call)
+                .await
+                .map_err(|_| anyhow::anyhow!("Image preprocessing timed out after {}s and was abandoned", timeout.as_secs()))???,
+            None => call.await??,
+        };
+
+        let mut outputs = HashMap::new();
+        outputs.insert("width".to_string(), serde_json::json!(image.width));
+        outputs.insert("height".to_string(), serde_json::json!(image.height));
+        if let Some(tensor) = image.tensor_json() {
+            outputs.insert("tensor".to_string(), tensor);
+        }
+        if let ImageData::Encoded(encoded) = &image.data {
+            let artifact = match output {
+                ImageOutput::Jpeg { .. } => "frame.jpg",
+                _ => "frame.png",
+            };
+            outputs.insert("frame".to_string(), serde_json::json!({ "artifact": artifact, "encoding": "base64", "bytes": encoded.len() }));
+            ctx.artifacts.insert(artifact.to_string(), base64::engine::general_purpose::STANDARD.encode(encoded));
+        }
+        Ok(outputs)
+    }
+
+    #[cfg(feature = "firecracker")]
+    async fn execute_in_microvm(&self, ctx: &mut ExecutionContext, language: &str, code: &str, inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        let settings = self.microvm.as_ref().ok_or_else(|| anyhow::anyhow!("This worker has no microVM backend configured"))?;
+        let workdir = ctx.workspace.path().to_path_buf();
+        let output = run_in_microvm(settings, &workdir, language, code, &inputs, &ctx.limits, &ctx.cancel).await?;
+        ctx.record_process(&output.process);
+        ctx.metadata.exit_code = output.exit_code;
+
+        if !output.process.success() {
+            anyhow::bail!("MicroVM execution failed ({})", output.process.failure_reason());
+        }
+        if output.exit_code != Some(0) {
+            anyhow::bail!("MicroVM task failed (exited with code {})", output.exit_code.unwrap_or(-1));
+        }
+        let result: HashMap<String, serde_json::Value> = serde_json::from_slice(&output.stdout)?;
+        Ok(result)
+    }
+
+    #[cfg(not(feature = "firecracker"))]
+    async fn execute_in_microvm(&self, _ctx: &mut ExecutionContext, _language: &str, _code: &str, _inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        Err(feature_disabled("firecracker"))
+    }
+
+    // Runs in a warm or parked container from the pool when one is ready for the
+    // image, otherwise in a fresh container with the workspace mounted
+    #[cfg(feature = "docker")]
+    async fn execute_docker(&self, ctx: &mut ExecutionContext, image: &str, command: &[String], inputs: serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+        let Some(containers) = &self.containers else {
+            anyhow::bail!("Docker tasks need a container pool, see DynamicTaskExecutor::with_container_pool");
+        };
+        let resources = ContainerResources {
+            memory_mb: ctx.memory_mb,
+            cpu_cores: ctx.cpu_cores,
+        };
+        let warm = containers.acquire(image, command, resources).await?;
+        let run = async {
+            match warm {
+                Some(container) => containers.run(container, command, &inputs, ctx.limits.timeout).await,
+                None => {
+                    containers
+                        .run_once(image, command, ctx.workspace.path(), &inputs, resources, ctx.limits.timeout)
+                        .await
+                }
+            }
+        };
+        let output = tokio::select! {
+            output = run => output?,
+            _ = ctx.cancel.cancelled() => anyhow::bail!("Docker task cancelled"),
+        };
+        // Pulled by now, if it had to be
+        if let Ok(details) = containers.docker().inspect_image(image).await {
+            ctx.provenance.image_digest = details.repo_digests.and_then(|digests| digests.into_iter().next()).or(details.id);
+        }
+        ctx.captured.stdout.extend_from_slice(&output.stdout);
+        ctx.captured.stderr.extend_from_slice(&output.stderr);
+        ctx.metadata.exit_code = output.exit_code.map(|code| code as i32);
+        ctx.metadata.timed_out = output.timed_out;
+
+        if output.timed_out {
+            anyhow::bail!("Docker execution failed (timed out)");
+        }
+        if output.exit_code != Some(0) {
+            anyhow::bail!(
+                "Docker execution failed (exited with code {}): {}",
+                output.exit_code.unwrap_or(-1),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        if output.stdout_truncated {
+            anyhow::bail!("Docker task printed more than {} bytes of outputs", MAX_PROCESS_OUTPUT_BYTES);
+        }
+
+        serde_json::from_slice(&output.stdout)
+            .map_err(|e| anyhow::anyhow!("Docker task did not print a JSON object of outputs ({})", e))
+    }
+}
+
+// Most of the task's memory goes to the heap; the rest is left for the JVM's own
+// metaspace, thread stacks and code cache
+pub fn jvm_heap_mb(memory_mb: u64) -> u64 {
+    (memory_mb.saturating_mul(3) / 4).max(32)
+}
+
+// Source files that went into a bundle, relative to the directory esbuild ran in,
+// from esbuild's metafile
+pub fn bundle_inputs(metafile: &Path) -> Result<Vec<String>> {
+    let meta: serde_json::Value = serde_json::from_slice(&fs::read(metafile)?)?;
+    let inputs = meta
+        .get("inputs")
+        .and_then(|inputs| inputs.as_object())
+        .ok_or_else(|| anyhow::anyhow!("esbuild metafile lists no inputs"))?;
+    Ok(inputs.keys().cloned().collect())
+}
+
+// Source and local headers from a make-style dependency file written by `-MMD`
+pub fn depfile_inputs(deps: &str) -> Vec<String> {
+    let Some((_, prerequisites)) = deps.split_once(": ") else {
+        return Vec::new();
+    };
+    prerequisites
+        .split_whitespace()
+        .filter(|prerequisite| *prerequisite != "\\")
+        .map(str::to_string)
+        .collect()
+}
+
+pub fn hash_inputs(inputs: Vec<String>, source_dir: &Path) -> Result<BTreeMap<String, String>> {
+    inputs
+        .into_iter()
+        .map(|input| {
+            let hash = sha256_hex(&fs::read(source_dir.join(&input))?);
+            Ok((input, hash))
+        })
+        .collect()
+}
+
+// A cached build output, as long as none of the files it was built from changed
+pub fn cached_build(cache: &Path, name: &str, source_dir: &Path) -> Option<PathBuf> {
+    let inputs: BTreeMap<String, String> = serde_json::from_slice(&fs::read(cache.join("inputs.json")).ok()?).ok()?;
+    let unchanged = inputs
+        .iter()
+        .all(|(input, hash)| fs::read(source_dir.join(input)).is_ok_and(|content| sha256_hex(&content) == *hash));
+    let output = cache.join(name);
+    (unchanged && output.is_file()).then_some(output)
+}
+
+// The output goes first, so the inputs listed are never those of an older build
+// for longer than it takes to replace it
+pub fn store_build(cache: &Path, name: &str, output: &Path, inputs: &BTreeMap<String, String>) -> Result<()> {
+    fs::create_dir_all(cache)?;
+    let _ = fs::remove_file(cache.join("inputs.json"));
+    let partial = cache.join(format!("{}.partial-{}", name, uuid::Uuid::new_v4()));
+    fs::copy(output, &partial)?;
+    fs::rename(&partial, cache.join(name))?;
+    let partial = cache.join(format!("inputs.partial-{}", uuid::Uuid::new_v4()));
+    fs::write(&partial, serde_json::to_vec(inputs)?)?;
+    fs::rename(&partial, cache.join("inputs.json"))?;
+    Ok(())
 }
 
-impl Drop for DynamicTaskExecutor {
-    fn drop(&mut self) {
-        // Cleanup is handled automatically by TempDir
+// Nearest `name` from the script's directory up to `root`
+#[cfg(any(feature = "git", feature = "http"))]
+fn find_manifest(root: &Path, script_path: &Path, name: &str) -> Option<PathBuf> {
+    script_path
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .map(|dir| dir.join(name))
+        .find(|manifest| manifest.is_file())
+}
+
+#[cfg(any(feature = "git", feature = "http"))]
+fn venv_python(env_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        env_dir.join("Scripts").join("python.exe")
+    } else {
+        env_dir.join("bin").join("python")
+    }
+}
+
+// Downloaded code, unless it obviously isn't a script
+#[cfg(feature = "http")]
+fn script_text(source: &str, download: Download) -> Result<String> {
+    let body = String::from_utf8(download.body).map_err(|_| anyhow::anyhow!("{} did not return UTF-8 text", source))?;
+    check_script(source, download.content_type.as_deref(), &body)?;
+    Ok(body)
+}
+
+// Rejects HTML (error and login pages) and empty bodies before they get executed
+#[cfg(feature = "http")]
+fn check_script(source: &str, content_type: Option<&str>, body: &str) -> Result<()> {
+    if content_type.is_some_and(|content_type| content_type.starts_with("text/html")) {
+        anyhow::bail!("{} returned an HTML page instead of code", source);
+    }
+    let start: String = body.trim_start().chars().take(16).collect::<String>().to_ascii_lowercase();
+    if start.is_empty() {
+        anyhow::bail!("{} returned no code", source);
+    }
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        anyhow::bail!("{} returned an HTML page instead of code", source);
+    }
+    Ok(())
+}
+
+// GET against GitHub, authenticated if a token is configured. Requests rejected by
+// the rate limit are retried once it resets, if that's soon enough.
+#[cfg(feature = "http")]
+async fn github_get(client: &reqwest::Client, url: &str, token: Option<&str>, verbosity: Verbosity) -> Result<reqwest::Response> {
+    let mut retries = 0;
+    loop {
+        let mut request = client.get(url).header(reqwest::header::USER_AGENT, "corebrum-examples");
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        if response.status().is_success() {
+            return Ok(response);
+        }
+        let Some(wait) = rate_limit_wait(&response) else {
+            anyhow::bail!("GitHub request to {} failed: {}", url, response.status());
+        };
+        if retries == GITHUB_RATE_LIMIT_RETRIES || wait > GITHUB_MAX_RATE_LIMIT_WAIT {
+            anyhow::bail!(
+                "GitHub rate limit exceeded for {} (resets in {}s){}",
+                url,
+                wait.as_secs(),
+                if token.is_none() { "; configure a GitHub token for a higher limit" } else { "" }
+            );
+        }
+        retries += 1;
+        detail!(verbosity, "⏳ GitHub rate limit hit, retrying {} in {}s", url, wait.as_secs());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// How long to back off if `response` is a rate-limit rejection
+#[cfg(feature = "http")]
+fn rate_limit_wait(response: &reqwest::Response) -> Option<Duration> {
+    let status = response.status();
+    if status != reqwest::StatusCode::FORBIDDEN && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    if let Some(seconds) = header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header("x-ratelimit-remaining") == Some(0) {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        return Some(Duration::from_secs(header("x-ratelimit-reset")?.saturating_sub(now).max(1)));
     }
+    // Secondary rate limits may come without headers; GitHub asks to wait a minute
+    (status == reqwest::StatusCode::TOO_MANY_REQUESTS).then_some(Duration::from_secs(60))
 }
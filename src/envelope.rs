@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::schema::{
+    Assign, AssignAck, Claim, Estimate, EstimateRequest, Heartbeat, Job, QueueMetrics, Receipt, Result as TaskResult, Status, TaskListRequest,
+    TaskPage, TaskTree, TaskTreeRequest, SCHEMA_VERSION,
+};
+
+// A message body that can travel in an Envelope, named by its type discriminator
+pub trait Message: Serialize + DeserializeOwned {
+    const MSG_TYPE: &'static str;
+}
+
+impl Message for Job {
+    const MSG_TYPE: &'static str = "job";
+}
+
+impl Message for Claim {
+    const MSG_TYPE: &'static str = "claim";
+}
+
+impl Message for Assign {
+    const MSG_TYPE: &'static str = "assign";
+}
+
+impl Message for AssignAck {
+    const MSG_TYPE: &'static str = "ack";
+}
+
+impl Message for Receipt {
+    const MSG_TYPE: &'static str = "receipt";
+}
+
+impl Message for Status {
+    const MSG_TYPE: &'static str = "status";
+}
+
+impl Message for TaskResult {
+    const MSG_TYPE: &'static str = "result";
+}
+
+impl Message for Heartbeat {
+    const MSG_TYPE: &'static str = "heartbeat";
+}
+
+impl Message for QueueMetrics {
+    const MSG_TYPE: &'static str = "queue_metrics";
+}
+
+impl Message for TaskListRequest {
+    const MSG_TYPE: &'static str = "task_list_request";
+}
+
+impl Message for TaskPage {
+    const MSG_TYPE: &'static str = "task_page";
+}
+
+impl Message for TaskTreeRequest {
+    const MSG_TYPE: &'static str = "task_tree_request";
+}
+
+impl Message for TaskTree {
+    const MSG_TYPE: &'static str = "task_tree";
+}
+
+impl Message for EstimateRequest {
+    const MSG_TYPE: &'static str = "estimate_request";
+}
+
+impl Message for Estimate {
+    const MSG_TYPE: &'static str = "estimate";
+}
+
+// Wraps every published payload, so consumers can tell what a message is without
+// relying on the key it arrived on, and each body type can evolve on its own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    // Empty, like schema_version 0, for bare bodies from peers that predate envelopes
+    pub msg_type: String,
+    pub schema_version: u32,
+    // Worker ID, or the Zenoh session ID of other publishers; empty for bare bodies
+    pub sender: String,
+    pub ts: chrono::DateTime<chrono::Utc>,
+    pub body: T,
+}
+
+// An envelope whose body hasn't been decoded, for consumers that dispatch on msg_type
+pub type RawEnvelope = Envelope<serde_json::Value>;
+
+impl<T: Message> Envelope<T> {
+    pub fn new(sender: impl Into<String>, body: T) -> Self {
+        Self {
+            msg_type: T::MSG_TYPE.to_string(),
+            schema_version: SCHEMA_VERSION,
+            sender: sender.into(),
+            ts: chrono::Utc::now(),
+            body,
+        }
+    }
+}
+
+impl RawEnvelope {
+    // Decodes the body as a `T`; fails on any other message type
+    pub fn open<T: Message>(self) -> Result<Envelope<T>> {
+        if !self.msg_type.is_empty() && self.msg_type != T::MSG_TYPE {
+            anyhow::bail!("Expected a {} message, got {}", T::MSG_TYPE, self.msg_type);
+        }
+        let body = serde_json::from_value(self.body).with_context(|| format!("Invalid {} message body", T::MSG_TYPE))?;
+        Ok(Envelope {
+            msg_type: T::MSG_TYPE.to_string(),
+            schema_version: self.schema_version,
+            sender: self.sender,
+            ts: self.ts,
+            body,
+        })
+    }
+}
+
+pub fn seal<T: Message>(sender: &str, body: &T) -> Result<String> {
+    let envelope = Envelope {
+        msg_type: T::MSG_TYPE.to_string(),
+        schema_version: SCHEMA_VERSION,
+        sender: sender.to_string(),
+        ts: chrono::Utc::now(),
+        body,
+    };
+    Ok(serde_json::to_string(&envelope)?)
+}
+
+// Reads an enveloped JSON payload, or a bare body as sent by peers that predate envelopes
+pub fn unseal(payload: &str) -> Result<RawEnvelope> {
+    let value: serde_json::Value = serde_json::from_str(payload)?;
+    if value.get("msg_type").is_some() && value.get("body").is_some() {
+        return Ok(serde_json::from_value(value)?);
+    }
+    Ok(Envelope {
+        msg_type: String::new(),
+        schema_version: 0,
+        sender: String::new(),
+        ts: chrono::Utc::now(),
+        body: value,
+    })
+}
@@ -0,0 +1,97 @@
+use std::collections::{HashMap, VecDeque};
+use crate::dry_run::worker_unfit;
+use crate::schema::{Estimate, Job, WorkerInfo, WorkerStatus};
+
+// Completed runs remembered per task name
+const SAMPLES_PER_TASK: usize = 50;
+// Task names remembered; the least recently completed is forgotten beyond this
+const MAX_TASK_NAMES: usize = 1024;
+
+// Execution times of recently completed runs, by task name, as seen by the assigner
+#[derive(Debug, Default)]
+pub struct DurationHistory {
+    samples: HashMap<String, VecDeque<f64>>,
+    // Task names, least recently completed first
+    recency: VecDeque<String>,
+}
+
+impl DurationHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, name: &str, seconds: f64) {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return;
+        }
+        self.recency.retain(|known| known != name);
+        self.recency.push_back(name.to_string());
+        if self.recency.len() > MAX_TASK_NAMES {
+            if let Some(oldest) = self.recency.pop_front() {
+                self.samples.remove(&oldest);
+            }
+        }
+        let samples = self.samples.entry(name.to_string()).or_default();
+        if samples.len() == SAMPLES_PER_TASK {
+            samples.pop_front();
+        }
+        samples.push_back(seconds);
+    }
+
+    // Mean duration and sample count of the named task's latest runs
+    pub fn expected(&self, name: &str) -> Option<(f64, usize)> {
+        mean(self.samples.get(name)?.iter().copied())
+    }
+
+    // Mean duration and sample count over every remembered run
+    pub fn overall(&self) -> Option<(f64, usize)> {
+        mean(self.samples.values().flatten().copied())
+    }
+}
+
+fn mean(samples: impl Iterator<Item = f64>) -> Option<(f64, usize)> {
+    let (sum, count) = samples.fold((0.0, 0), |(sum, count), seconds| (sum + seconds, count + 1));
+    (count > 0).then(|| (sum / count as f64, count))
+}
+
+// How `job` would fare if submitted now, given `pending` jobs ahead of it, `running`
+// leased ones, and the live workers' latest heartbeats. Jobs ahead are assumed to
+// spread evenly over the candidates and take as long as the typical run on the queue.
+pub fn estimate(
+    job: &Job,
+    history: &DurationHistory,
+    pending: usize,
+    running: usize,
+    workers: &[WorkerInfo],
+    now: chrono::DateTime<chrono::Utc>,
+) -> Estimate {
+    let candidates: Vec<&WorkerInfo> = workers
+        .iter()
+        .filter(|info| worker_unfit(Some(info), job.task_definition.as_ref()).is_none())
+        .collect();
+    let idle = candidates.iter().filter(|info| matches!(info.status, WorkerStatus::Available)).count();
+    let expected = history.expected(job.name());
+    let estimated_wait_seconds = if candidates.is_empty() {
+        None
+    } else if pending < idle {
+        Some(0.0)
+    } else {
+        // The job starts once the jobs ahead of it have been worked through, plus the
+        // runs the candidates are busy with
+        let rounds = (pending - idle) / candidates.len() + 1;
+        history.overall().map(|(typical, _)| rounds as f64 * typical)
+    };
+    let mut candidates: Vec<String> = candidates.into_iter().map(|info| info.worker_id.clone()).collect();
+    candidates.sort();
+    Estimate {
+        queue_depth: pending,
+        running,
+        candidates,
+        expected_duration_seconds: expected.map(|(seconds, _)| seconds),
+        duration_samples: expected.map_or(0, |(_, count)| count),
+        estimated_wait_seconds,
+        estimated_start_at: estimated_wait_seconds
+            .map(|seconds| now + chrono::Duration::milliseconds((seconds * 1000.0) as i64)),
+        estimated_at: now,
+    }
+}
@@ -0,0 +1,66 @@
+use tokio::sync::broadcast;
+use crate::schema::TaskStatus;
+
+pub const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+// Task lifecycle as seen by the assigner and workers running in this process.
+// Claimed, Started, Completed and Failed come from workers; JobAnnounced and
+// Assigned from the assigner, which also reports Failed when it gives up on a job.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LifecycleEvent {
+    JobAnnounced { queue: String, task_id: String, name: String, attempt: u32 },
+    Claimed { task_id: String, worker_id: String },
+    Assigned { task_id: String, worker_id: String },
+    Started { task_id: String, worker_id: String },
+    Completed { task_id: String, worker_id: String, execution_time_seconds: Option<f64> },
+    // Also covers timeouts and cancellations, told apart by `status`
+    Failed { task_id: String, worker_id: String, status: TaskStatus, error: Option<String> },
+}
+
+impl LifecycleEvent {
+    pub fn task_id(&self) -> &str {
+        match self {
+            LifecycleEvent::JobAnnounced { task_id, .. }
+            | LifecycleEvent::Claimed { task_id, .. }
+            | LifecycleEvent::Assigned { task_id, .. }
+            | LifecycleEvent::Started { task_id, .. }
+            | LifecycleEvent::Completed { task_id, .. }
+            | LifecycleEvent::Failed { task_id, .. } => task_id,
+        }
+    }
+}
+
+// In-process broadcast of lifecycle events, for embedders driving their own UIs or
+// metrics. Cloning shares the channel; subscribers that fall more than the capacity
+// behind get RecvError::Lagged and miss the oldest events.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<LifecycleEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_EVENT_CAPACITY)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    // Only sees events emitted after subscribing
+    pub fn subscribe(&self) -> broadcast::Receiver<LifecycleEvent> {
+        self.sender.subscribe()
+    }
+
+    // Events nobody is subscribed to are dropped
+    pub fn emit(&self, event: LifecycleEvent) {
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+
+static SHARED_POOL: OnceLock<Arc<ExecutionPool>> = OnceLock::new();
+
+// States of a job on the pool, as seen by the job and by the caller awaiting it
+const RUNNING: u8 = 0;
+const FINISHED: u8 = 1;
+const ABANDONED: u8 = 2;
+
+pub fn default_execution_threads() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(2)
+}
+
+// CPU-heavy in-process work (WASM, interpreters, compilers) runs here instead of on
+// the Tokio workers, so a long task can't starve Zenoh message handling
+pub struct ExecutionPool {
+    pool: rayon::ThreadPool,
+    // Jobs whose caller stopped waiting (a timeout) but which still hold, or are
+    // queued for, a pool thread: in-process code can't be interrupted
+    abandoned: Arc<AtomicUsize>,
+}
+
+impl ExecutionPool {
+    pub fn new(threads: usize) -> Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|i| format!("corebrum-exec-{}", i))
+            .build()
+            .context("Failed to build execution thread pool")?;
+        Ok(Self { pool, abandoned: Arc::new(AtomicUsize::new(0)) })
+    }
+
+    // Process-wide pool used by executors that weren't given one explicitly
+    pub fn shared() -> Arc<ExecutionPool> {
+        SHARED_POOL
+            .get_or_init(|| {
+                Arc::new(ExecutionPool::new(default_execution_threads()).expect("Failed to build shared execution pool"))
+            })
+            .clone()
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    pub fn abandoned(&self) -> usize {
+        self.abandoned.load(Ordering::Relaxed)
+    }
+
+    // Every thread is taken by abandoned jobs, so new work would only queue behind them
+    pub fn saturated(&self) -> bool {
+        self.abandoned() >= self.threads()
+    }
+
+    pub async fn run<F, T>(&self, job: F) -> Result<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let state = Arc::new(AtomicU8::new(RUNNING));
+        let abandoned = self.abandoned.clone();
+        let job_state = state.clone();
+        self.pool.spawn(move || {
+            // Abandoned before it started: nobody wants the result
+            if job_state.load(Ordering::Acquire) != ABANDONED {
+                // A panicking task must not take down the pool thread (rayon aborts by default)
+                let _ = tx.send(std::panic::catch_unwind(AssertUnwindSafe(job)));
+            }
+            if job_state.swap(FINISHED, Ordering::AcqRel) == ABANDONED {
+                abandoned.fetch_sub(1, Ordering::Relaxed);
+            }
+        });
+        let _waiting = Waiting { state, abandoned: &self.abandoned };
+        match rx.await.context("Execution pool dropped the task")? {
+            Ok(value) => Ok(value),
+            Err(_) => anyhow::bail!("Task panicked on the execution pool"),
+        }
+    }
+}
+
+// Held while a caller awaits a job; dropping it before the job finishes (the caller
+// timed out or was cancelled) counts the job as abandoned until it returns
+struct Waiting<'a> {
+    state: Arc<AtomicU8>,
+    abandoned: &'a AtomicUsize,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        // Counted first so the job can't uncount it before it is counted
+        self.abandoned.fetch_add(1, Ordering::Relaxed);
+        if self.state.compare_exchange(RUNNING, ABANDONED, Ordering::AcqRel, Ordering::Acquire).is_err() {
+            self.abandoned.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
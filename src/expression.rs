@@ -0,0 +1,67 @@
+use anyhow::{Context, Result};
+use evalexpr::{eval_with_context_mut, ContextWithMutableVariables, HashMapContext, IterateVariablesContext, Value};
+use std::collections::HashMap;
+
+// Longer than any one-liner; anything bigger belongs in a script
+pub const MAX_EXPRESSION_BYTES: usize = 4096;
+
+// Evaluates an evalexpr expression in-process, with each top-level input bound to a
+// variable of the same name. Numbers stay integers when the JSON had no fraction, so
+// `n / 2` divides like integers do; arrays become tuples, and objects aren't bound.
+// Variables the expression assigns (`area = w * h; perimeter = 2 * (w + h)`) are the
+// outputs, and a final value that isn't assigned is returned as `result`.
+pub fn evaluate(expression: &str, inputs: &serde_json::Value) -> Result<HashMap<String, serde_json::Value>> {
+    if expression.len() > MAX_EXPRESSION_BYTES {
+        anyhow::bail!("Expression is {} bytes, over the {} byte limit", expression.len(), MAX_EXPRESSION_BYTES);
+    }
+    let mut context = HashMapContext::new();
+    let mut bound = HashMap::new();
+    if let Some(inputs) = inputs.as_object() {
+        for (name, value) in inputs {
+            let Some(value) = to_value(value) else { continue };
+            context
+                .set_value(name.clone(), value.clone())
+                .with_context(|| format!("Failed to bind input {}", name))?;
+            bound.insert(name.clone(), value);
+        }
+    }
+
+    let value = eval_with_context_mut(expression, &mut context).context("Expression failed")?;
+    let mut outputs = HashMap::new();
+    for (name, value) in context.iter_variables() {
+        if bound.get(&name) != Some(&value) {
+            outputs.insert(name.clone(), to_json(&value).with_context(|| format!("Output {} is not JSON", name))?);
+        }
+    }
+    if value != Value::Empty {
+        outputs.insert("result".to_string(), to_json(&value).context("The expression's value is not JSON")?);
+    }
+    Ok(outputs)
+}
+
+fn to_value(value: &serde_json::Value) -> Option<Value> {
+    Some(match value {
+        serde_json::Value::Null => Value::Empty,
+        serde_json::Value::Bool(boolean) => Value::Boolean(*boolean),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => Value::Int(integer),
+            None => Value::Float(number.as_f64()?),
+        },
+        serde_json::Value::String(text) => Value::String(text.clone()),
+        serde_json::Value::Array(values) => Value::Tuple(values.iter().map(to_value).collect::<Option<_>>()?),
+        serde_json::Value::Object(_) => return None,
+    })
+}
+
+fn to_json(value: &Value) -> Result<serde_json::Value> {
+    Ok(match value {
+        Value::Empty => serde_json::Value::Null,
+        Value::Boolean(boolean) => serde_json::Value::Bool(*boolean),
+        Value::Int(integer) => serde_json::Value::from(*integer),
+        Value::Float(float) => serde_json::Number::from_f64(*float)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a finite number", float))?,
+        Value::String(text) => serde_json::Value::String(text.clone()),
+        Value::Tuple(values) => serde_json::Value::Array(values.iter().map(to_json).collect::<Result<_>>()?),
+    })
+}
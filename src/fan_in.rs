@@ -0,0 +1,180 @@
+use anyhow::Result;
+use base64::Engine;
+use futures::future::select_all;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::Duration;
+use zenoh::key_expr::KeyExpr;
+use zenoh::sample::Sample;
+use zenoh::Session;
+use crate::schema::{StreamInputs, SyncPolicy};
+use crate::zenoh_utils::declare_history_subscriber;
+
+// How long a worker waits for a matching set when the job doesn't say
+pub const DEFAULT_FAN_IN_TIMEOUT: Duration = Duration::from_secs(10);
+// Longer waits are refused at submission and clamped to this on the worker
+pub const MAX_FAN_IN_TIMEOUT_MS: u64 = 3_600_000;
+// Samples kept per key while looking for an aligned set
+const ALIGNMENT_WINDOW: usize = 32;
+// Wider tolerances are refused at submission and clamped to this on the worker
+pub const MAX_ALIGNMENT_TOLERANCE_MS: u64 = 3_600_000;
+
+// A sample as bound to a job's input: JSON payloads parsed, other text as a string
+// and anything else as base64
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamSample {
+    pub value: serde_json::Value,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl StreamSample {
+    // Stamped with the arrival time when the publisher's session doesn't timestamp
+    pub fn from_sample(sample: &Sample) -> Self {
+        let bytes = sample.payload().to_bytes();
+        let value = serde_json::from_slice(&bytes).unwrap_or_else(|_| match std::str::from_utf8(&bytes) {
+            Ok(text) => serde_json::Value::String(text.to_string()),
+            Err(_) => serde_json::Value::String(base64::engine::general_purpose::STANDARD.encode(&bytes)),
+        });
+        let timestamp = sample
+            .timestamp()
+            .map_or_else(chrono::Utc::now, |timestamp| timestamp.get_time().to_system_time().into());
+        Self { value, timestamp }
+    }
+}
+
+// Collects samples per input until a set satisfying the sync policy comes together
+#[derive(Debug)]
+pub struct FanIn {
+    sync: SyncPolicy,
+    samples: BTreeMap<String, VecDeque<StreamSample>>,
+}
+
+impl FanIn {
+    pub fn new(stream_inputs: &StreamInputs) -> Self {
+        Self {
+            sync: stream_inputs.sync,
+            samples: stream_inputs.keys.keys().map(|name| (name.clone(), VecDeque::new())).collect(),
+        }
+    }
+
+    // Adds a sample for input `name`, returning the set once there is one; samples
+    // for inputs the job didn't declare are ignored
+    pub fn push(&mut self, name: &str, sample: StreamSample) -> Option<BTreeMap<String, StreamSample>> {
+        let kept = match self.sync {
+            SyncPolicy::LatestOfEach => 1,
+            SyncPolicy::Aligned { .. } => ALIGNMENT_WINDOW,
+        };
+        let samples = self.samples.get_mut(name)?;
+        samples.push_back(sample);
+        if samples.len() > kept {
+            samples.pop_front();
+        }
+        match self.sync {
+            SyncPolicy::LatestOfEach => self
+                .samples
+                .iter()
+                .map(|(name, samples)| Some((name.clone(), samples.back()?.clone())))
+                .collect(),
+            SyncPolicy::Aligned { tolerance_ms } => self.aligned(name, alignment_tolerance(tolerance_ms)),
+        }
+    }
+
+    // The sample just added with the closest of every other input's, if they are all
+    // within `tolerance` of each other
+    fn aligned(&self, anchor: &str, tolerance: chrono::Duration) -> Option<BTreeMap<String, StreamSample>> {
+        let anchor = self.samples.get(anchor)?.back()?.timestamp;
+        let set: BTreeMap<String, StreamSample> = self
+            .samples
+            .iter()
+            .map(|(name, samples)| {
+                let closest = samples.iter().min_by_key(|sample| apart(sample.timestamp, anchor))?;
+                Some((name.clone(), closest.clone()))
+            })
+            .collect::<Option<_>>()?;
+        let earliest = set.values().map(|sample| sample.timestamp).min()?;
+        let latest = set.values().map(|sample| sample.timestamp).max()?;
+        (latest - earliest <= tolerance).then_some(set)
+    }
+}
+
+// `tolerance_ms` comes off the network, so it must not be trusted to fit a TimeDelta
+fn alignment_tolerance(tolerance_ms: u64) -> chrono::Duration {
+    i64::try_from(tolerance_ms.min(MAX_ALIGNMENT_TOLERANCE_MS))
+        .ok()
+        .and_then(chrono::Duration::try_milliseconds)
+        .unwrap_or(chrono::Duration::zero())
+}
+
+// Refuses stream inputs no worker could satisfy; clients check this before submitting
+pub fn validate_stream_inputs(stream_inputs: &StreamInputs) -> Result<()> {
+    for (name, key) in &stream_inputs.keys {
+        KeyExpr::try_from(key.as_str()).map_err(|e| anyhow::anyhow!("Stream input {} has an invalid key expression {:?}: {}", name, key, e))?;
+    }
+    if let SyncPolicy::Aligned { tolerance_ms } = stream_inputs.sync {
+        if tolerance_ms > MAX_ALIGNMENT_TOLERANCE_MS {
+            anyhow::bail!("Alignment tolerance of {}ms is over the {}ms limit", tolerance_ms, MAX_ALIGNMENT_TOLERANCE_MS);
+        }
+    }
+    if let Some(timeout_ms) = stream_inputs.timeout_ms.filter(|timeout_ms| *timeout_ms > MAX_FAN_IN_TIMEOUT_MS) {
+        anyhow::bail!("Stream input timeout of {}ms is over the {}ms limit", timeout_ms, MAX_FAN_IN_TIMEOUT_MS);
+    }
+    Ok(())
+}
+
+// How long to wait for a matching set; like the tolerance, `timeout_ms` comes off
+// the network
+pub fn fan_in_timeout(stream_inputs: &StreamInputs) -> Duration {
+    stream_inputs
+        .timeout_ms
+        .map_or(DEFAULT_FAN_IN_TIMEOUT, |timeout_ms| Duration::from_millis(timeout_ms.min(MAX_FAN_IN_TIMEOUT_MS)))
+}
+
+fn apart(a: chrono::DateTime<chrono::Utc>, b: chrono::DateTime<chrono::Utc>) -> chrono::Duration {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+// Subscribes to every key of `stream_inputs` and waits for a set of samples that
+// satisfies its sync policy. Samples published before the job started count only
+// where the publisher keeps a history cache.
+pub async fn await_streams(session: &Session, stream_inputs: &StreamInputs) -> Result<BTreeMap<String, StreamSample>> {
+    if stream_inputs.keys.is_empty() {
+        return Ok(BTreeMap::new());
+    }
+    let mut subscribers = Vec::new();
+    for (name, key) in &stream_inputs.keys {
+        subscribers.push((name, key, declare_history_subscriber(session, key.clone()).await?));
+    }
+    let mut fan_in = FanIn::new(stream_inputs);
+    let collect = async {
+        loop {
+            let receives = subscribers.iter().map(|(_, _, subscriber)| Box::pin(subscriber.recv_async()));
+            let (sample, index, _) = select_all(receives).await;
+            let (name, key, _) = &subscribers[index];
+            let sample = sample.map_err(|e| anyhow::anyhow!("Subscriber on {} closed: {}", key, e))?;
+            if let Some(set) = fan_in.push(name, StreamSample::from_sample(&sample)) {
+                return anyhow::Ok(set);
+            }
+        }
+    };
+    let timeout = fan_in_timeout(stream_inputs);
+    tokio::time::timeout(timeout, collect).await.map_err(|_| {
+        let keys: Vec<&str> = stream_inputs.keys.values().map(String::as_str).collect();
+        anyhow::anyhow!("No matching samples on {} within {}ms ({:?})", keys.join(", "), timeout.as_millis(), stream_inputs.sync)
+    })?
+}
+
+// `inputs` with each sample bound to the input of the same name; inputs that are
+// not an object are replaced
+pub fn bind_streams(inputs: serde_json::Value, samples: BTreeMap<String, StreamSample>) -> serde_json::Value {
+    let mut inputs = match inputs {
+        serde_json::Value::Object(inputs) => inputs,
+        _ => serde_json::Map::new(),
+    };
+    for (name, sample) in samples {
+        inputs.insert(name, sample.value);
+    }
+    serde_json::Value::Object(inputs)
+}
@@ -0,0 +1,224 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use zenoh::Session;
+use crate::batching::unbatch_messages;
+use crate::keyspace::Keyspace;
+use crate::metrics::GcCounters;
+use crate::schema::Status;
+use crate::task_archive::TaskArchiver;
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::deserialize_from_sample_with_context;
+use crate::summary;
+
+pub const DEFAULT_TASK_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+// Deletes the keys of finished tasks (status, result, claim, ...) once they have
+// been finished for longer than the TTL, so storage-backed deployments don't grow
+// forever. Finished tasks are learnt from terminal status updates, and on startup
+// from whatever the queue's storages still hold. With an archiver, nothing is
+// deleted before it is safely in the archive.
+pub struct TaskGc {
+    keyspace: Keyspace,
+    ttl: Duration,
+    sweep_interval: Duration,
+    counters: Arc<GcCounters>,
+    archiver: Option<Arc<TaskArchiver>>,
+    verbosity: Verbosity,
+}
+
+struct FinishedTask {
+    finished_at: chrono::DateTime<chrono::Utc>,
+    // Only known from storage, so a live archiver never saw it finish
+    stored_only: bool,
+}
+
+impl TaskGc {
+    pub fn new(keyspace: Keyspace) -> Self {
+        Self {
+            counters: GcCounters::new(keyspace.queue()),
+            keyspace,
+            ttl: DEFAULT_TASK_TTL,
+            sweep_interval: DEFAULT_SWEEP_INTERVAL,
+            archiver: None,
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub fn with_sweep_interval(mut self, sweep_interval: Duration) -> Self {
+        self.sweep_interval = sweep_interval;
+        self
+    }
+
+    // `archiver` should be running on the same queue, so tasks finishing from now on
+    // are archived as they finish; older ones are archived from storage before deletion
+    pub fn with_archiver(mut self, archiver: Arc<TaskArchiver>) -> Self {
+        self.archiver = Some(archiver);
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn counters(&self) -> Arc<GcCounters> {
+        self.counters.clone()
+    }
+
+    pub async fn run(&self, session: &Session, shutdown: CancellationToken) -> Result<()> {
+        let statuses = session
+            .declare_subscriber(self.keyspace.all_statuses())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare status subscriber: {}", e))?;
+        let batches = session
+            .declare_subscriber(self.keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+
+        // Finish time of every task waiting to be collected
+        let mut finished = HashMap::new();
+        for status in self.stored_statuses(session).await {
+            self.track(&mut finished, &status, true);
+        }
+        summary!(self.verbosity, "🧹 Task GC started on queue {} (TTL {}s, {} finished tasks known)", self.keyspace.queue(), self.ttl.as_secs(), finished.len());
+
+        let mut sweep = tokio::time::interval(self.sweep_interval);
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = sweep.tick() => {
+                    self.sweep(session, &mut finished).await;
+                    continue;
+                }
+                sample = statuses.recv_async() => match sample {
+                    Ok(sample) => vec![deserialize_from_sample_with_context::<Status>(&sample, "status")],
+                    Err(_) => break,
+                },
+                sample = batches.recv_async() => match sample {
+                    Ok(sample) => unbatch_messages::<Status>(&sample, &self.keyspace.all_statuses(), "status"),
+                    Err(_) => break,
+                },
+            };
+            for status in received.into_iter().flatten() {
+                self.track(&mut finished, &status, false);
+            }
+            self.counters.set_tracked(finished.len());
+        }
+
+        summary!(self.verbosity, "🧹 Task GC stopped");
+        Ok(())
+    }
+
+    // A task that reports progress again (e.g. a retry) is no longer finished
+    fn track(&self, finished: &mut HashMap<String, FinishedTask>, status: &Status, stored: bool) {
+        if status.status.is_terminal() {
+            let task = finished.entry(status.task_id.clone()).or_insert(FinishedTask {
+                finished_at: status.timestamp,
+                stored_only: stored,
+            });
+            task.finished_at = task.finished_at.max(status.timestamp);
+            task.stored_only &= stored;
+        } else {
+            finished.remove(&status.task_id);
+        }
+    }
+
+    // Latest statuses held by storages on the queue; none without storages
+    async fn stored_statuses(&self, session: &Session) -> Vec<Status> {
+        let replies = match session.get(self.keyspace.all_statuses()).await {
+            Ok(replies) => replies,
+            Err(e) => {
+                summary!(self.verbosity, "⚠️  Task GC could not query stored statuses: {}", e);
+                return Vec::new();
+            }
+        };
+        let mut statuses = Vec::new();
+        while let Ok(reply) = replies.recv_async().await {
+            if let Ok(sample) = reply.result() {
+                if let Ok(status) = deserialize_from_sample_with_context::<Status>(sample, "status") {
+                    statuses.push(status);
+                }
+            }
+        }
+        statuses
+    }
+
+    async fn sweep(&self, session: &Session, finished: &mut HashMap<String, FinishedTask>) {
+        // A TTL too large to represent never expires anything
+        let Some(cutoff) = chrono::Duration::from_std(self.ttl)
+            .ok()
+            .and_then(|ttl| chrono::Utc::now().checked_sub_signed(ttl))
+        else {
+            return;
+        };
+        let expired: Vec<String> = finished
+            .iter()
+            .filter(|(_, task)| task.finished_at <= cutoff)
+            .map(|(task_id, _)| task_id.clone())
+            .collect();
+        let expired = match &self.archiver {
+            Some(archiver) => self.archive(session, archiver, finished, expired).await,
+            None => expired,
+        };
+        for task_id in expired {
+            // Failed deletions stay tracked and are retried on the next sweep
+            match self.delete_task(session, &task_id).await {
+                Ok(()) => {
+                    finished.remove(&task_id);
+                    self.counters.record_reclaimed();
+                }
+                Err(e) => {
+                    summary!(self.verbosity, "⚠️  Task GC failed to delete keys of {}: {}", task_id, e);
+                    self.counters.record_failed_delete();
+                }
+            }
+        }
+        self.counters.set_tracked(finished.len());
+    }
+
+    // The expired tasks that are safely archived and may be deleted
+    async fn archive(
+        &self,
+        session: &Session,
+        archiver: &TaskArchiver,
+        finished: &mut HashMap<String, FinishedTask>,
+        expired: Vec<String>,
+    ) -> Vec<String> {
+        let mut archived = Vec::with_capacity(expired.len());
+        for task_id in expired {
+            if let Some(task) = finished.get_mut(&task_id).filter(|task| task.stored_only) {
+                if let Err(e) = archiver.archive_from_storage(session, &task_id).await {
+                    summary!(self.verbosity, "⚠️  Task GC keeps {}, archiving it failed: {}", task_id, e);
+                    continue;
+                }
+                // Archived once, even if deleting it fails below
+                task.stored_only = false;
+            }
+            archived.push(task_id);
+        }
+        if let Err(e) = archiver.flush() {
+            summary!(self.verbosity, "⚠️  Task GC skips this sweep, flushing the archive failed: {}", e);
+            return Vec::new();
+        }
+        archived
+    }
+
+    async fn delete_task(&self, session: &Session, task_id: &str) -> Result<()> {
+        for key in [format!("{}/**", self.keyspace.task(task_id)), self.keyspace.pending(task_id)] {
+            session
+                .delete(key.clone())
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to delete {}: {}", key, e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, Rgb32FImage};
+use std::io::Cursor;
+use crate::schema::{ImageOutput, ImageStep, ResizeFilter, TensorLayout};
+
+// Frames are held as 12-byte Rgb32F pixels; this keeps a resized one within the
+// 512MiB the decoder allows itself by default
+pub const MAX_FRAME_PIXELS: u64 = 512 * 1024 * 1024 / 12;
+
+// A preprocessed frame: float32 values in the task's tensor layout, or the re-encoded image
+#[derive(Debug, Clone)]
+pub enum ImageData {
+    Tensor { shape: Vec<usize>, data: Vec<f32> },
+    Encoded(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+pub struct PreprocessedImage {
+    pub width: u32,
+    pub height: u32,
+    pub data: ImageData,
+}
+
+impl PreprocessedImage {
+    // In the tensor form Onnx tasks take as input
+    pub fn tensor_json(&self) -> Option<serde_json::Value> {
+        match &self.data {
+            ImageData::Tensor { shape, data } => Some(serde_json::json!({ "dtype": "float32", "shape": shape, "data": data })),
+            ImageData::Encoded(_) => None,
+        }
+    }
+}
+
+// Decodes a JPEG or PNG, applies the steps in order and produces `output`. Blocking, so
+// call this from the execution pool.
+pub fn preprocess_image(bytes: &[u8], steps: &[ImageStep], output: ImageOutput) -> Result<PreprocessedImage> {
+    let mut frame = image::load_from_memory(bytes).context("Failed to decode frame")?.to_rgb32f();
+    for (index, step) in steps.iter().enumerate() {
+        frame = apply(frame, step, output).with_context(|| format!("Invalid step {} ({:?})", index + 1, step))?;
+    }
+
+    let (width, height) = frame.dimensions();
+    let data = match output {
+        ImageOutput::Tensor { layout } => {
+            let (w, h) = (width as usize, height as usize);
+            match layout {
+                TensorLayout::Nhwc => ImageData::Tensor { shape: vec![1, h, w, 3], data: frame.into_raw() },
+                TensorLayout::Nchw => {
+                    let mut data = vec![0.0; 3 * h * w];
+                    for (x, y, pixel) in frame.enumerate_pixels() {
+                        for channel in 0..3 {
+                            data[channel * h * w + y as usize * w + x as usize] = pixel[channel];
+                        }
+                    }
+                    ImageData::Tensor { shape: vec![1, 3, h, w], data }
+                }
+            }
+        }
+        ImageOutput::Png => {
+            let mut encoded = Cursor::new(Vec::new());
+            DynamicImage::ImageRgb32F(frame).to_rgb8().write_to(&mut encoded, ImageFormat::Png)?;
+            ImageData::Encoded(encoded.into_inner())
+        }
+        ImageOutput::Jpeg { quality } => {
+            let mut encoded = Vec::new();
+            let rgb = DynamicImage::ImageRgb32F(frame).to_rgb8();
+            JpegEncoder::new_with_quality(&mut encoded, quality.clamp(1, 100)).encode_image(&rgb)?;
+            ImageData::Encoded(encoded)
+        }
+    };
+    Ok(PreprocessedImage { width, height, data })
+}
+
+fn apply(frame: Rgb32FImage, step: &ImageStep, output: ImageOutput) -> Result<Rgb32FImage> {
+    let frame = match step {
+        ImageStep::Resize { width, height, filter } => {
+            if *width == 0 || *height == 0 {
+                anyhow::bail!("Can't resize to {}x{}", width, height);
+            }
+            if u64::from(*width) * u64::from(*height) > MAX_FRAME_PIXELS {
+                anyhow::bail!("Can't resize to {}x{}, over the {} pixel limit", width, height, MAX_FRAME_PIXELS);
+            }
+            let filter = match filter {
+                ResizeFilter::Nearest => FilterType::Nearest,
+                ResizeFilter::Bilinear => FilterType::Triangle,
+                ResizeFilter::Bicubic => FilterType::CatmullRom,
+                ResizeFilter::Lanczos => FilterType::Lanczos3,
+            };
+            imageops::resize(&frame, *width, *height, filter)
+        }
+        ImageStep::Crop { x, y, width, height } => crop(&frame, *x, *y, *width, *height)?,
+        ImageStep::CenterCrop { width, height } => {
+            let x = frame.width().saturating_sub(*width) / 2;
+            let y = frame.height().saturating_sub(*height) / 2;
+            crop(&frame, x, y, *width, *height)?
+        }
+        ImageStep::Normalize { mean, std } => {
+            if !matches!(output, ImageOutput::Tensor { .. }) {
+                anyhow::bail!("normalize only applies to tensor output");
+            }
+            if std.iter().any(|s| *s == 0.0) {
+                anyhow::bail!("std can't be zero");
+            }
+            let mut frame = frame;
+            for pixel in frame.pixels_mut() {
+                for channel in 0..3 {
+                    pixel[channel] = (pixel[channel] - mean[channel]) / std[channel];
+                }
+            }
+            frame
+        }
+    };
+    Ok(frame)
+}
+
+fn crop(frame: &Rgb32FImage, x: u32, y: u32, width: u32, height: u32) -> Result<Rgb32FImage> {
+    let fits = |start: u32, len: u32, bound: u32| len > 0 && start.checked_add(len).is_some_and(|end| end <= bound);
+    if !fits(x, width, frame.width()) || !fits(y, height, frame.height()) {
+        anyhow::bail!("{}x{} at ({}, {}) doesn't fit in a {}x{} frame", width, height, x, y, frame.width(), frame.height());
+    }
+    Ok(imageops::crop_imm(frame, x, y, width, height).to_image())
+}
@@ -0,0 +1,316 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt;
+use crate::schema::{InputType, TaskDefinition};
+
+// Everything wrong with a submission's inputs, not just the first problem. Submitters
+// get it wrapped in an anyhow::Error and can downcast to it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputValidationError {
+    pub task: String,
+    pub errors: Vec<InputError>,
+    // Only evaluated once every input is valid on its own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_checks: Vec<FailedCheck>,
+}
+
+// A check of the definition that didn't hold, with its message or, if it couldn't be
+// evaluated, why not
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailedCheck {
+    pub expression: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputError {
+    pub input: String,
+    #[serde(flatten)]
+    pub problem: InputProblem,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum InputProblem {
+    Missing,
+    // Not coercible to the input's type
+    Invalid { message: String },
+    NotAllowed { value: Value, choices: Vec<Value> },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            InputProblem::Missing => write!(f, "missing required input '{}'", self.input),
+            InputProblem::Invalid { message } => write!(f, "input '{}': {}", self.input, message),
+            InputProblem::NotAllowed { value, choices } => {
+                let choices: Vec<String> = choices.iter().map(Value::to_string).collect();
+                write!(f, "input '{}' must be one of {}, got {}", self.input, choices.join(", "), value)
+            }
+        }
+    }
+}
+
+impl fmt::Display for FailedCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "check `{}` failed: {}", self.expression, message),
+            None => write!(f, "check `{}` failed", self.expression),
+        }
+    }
+}
+
+impl fmt::Display for InputValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<String> = self
+            .errors
+            .iter()
+            .map(InputError::to_string)
+            .chain(self.failed_checks.iter().map(FailedCheck::to_string))
+            .collect();
+        write!(f, "Invalid inputs for {}: {}", self.task, errors.join("; "))
+    }
+}
+
+impl std::error::Error for InputValidationError {}
+
+impl InputType {
+    // `value` as this type, or why it can't be one
+    pub fn coerce(&self, value: Value) -> Result<Value> {
+        match (self, value) {
+            (InputType::String, Value::String(s)) => Ok(Value::String(s)),
+            (InputType::String, value @ (Value::Number(_) | Value::Bool(_))) => Ok(Value::String(value.to_string())),
+            (InputType::Integer, Value::Number(n)) if n.is_i64() || n.is_u64() => Ok(Value::Number(n)),
+            (InputType::Integer, Value::Number(n)) => match n.as_f64() {
+                Some(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Ok(Value::from(f as i64)),
+                _ => anyhow::bail!("expected an integer, got {}", n),
+            },
+            (InputType::Integer, Value::String(s)) => {
+                let n: i64 = s.trim().parse().with_context(|| format!("expected an integer, got {:?}", s))?;
+                Ok(Value::from(n))
+            }
+            (InputType::Number, Value::Number(n)) => Ok(Value::Number(n)),
+            (InputType::Number, Value::String(s)) => number(s.trim().parse().ok(), &s),
+            (InputType::Boolean, Value::Bool(b)) => Ok(Value::Bool(b)),
+            (InputType::Boolean, Value::String(s)) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "off" | "0" => Ok(Value::Bool(false)),
+                _ => anyhow::bail!("expected a boolean, got {:?}", s),
+            },
+            (InputType::Object, value @ Value::Object(_)) => Ok(value),
+            (InputType::Array, value @ Value::Array(_)) => Ok(value),
+            (InputType::Timestamp, Value::String(s)) => {
+                let timestamp = DateTime::parse_from_rfc3339(s.trim())
+                    .with_context(|| format!("expected an RFC 3339 timestamp, got {:?}", s))?;
+                Ok(Value::String(rfc3339(timestamp.with_timezone(&Utc))))
+            }
+            (InputType::Timestamp, Value::Number(n)) => {
+                let timestamp = n
+                    .as_i64()
+                    .and_then(|seconds| DateTime::<Utc>::from_timestamp(seconds, 0))
+                    .ok_or_else(|| anyhow::anyhow!("expected Unix seconds, got {}", n))?;
+                Ok(Value::String(rfc3339(timestamp)))
+            }
+            (InputType::Duration, Value::Number(n)) => match n.as_f64() {
+                Some(seconds) if seconds >= 0.0 => duration(seconds),
+                _ => anyhow::bail!("expected a non-negative number of seconds, got {}", n),
+            },
+            (InputType::Duration, Value::String(s)) => duration(parse_duration(&s)?),
+            (InputType::Bytesize, Value::Number(n)) => match n.as_u64() {
+                Some(bytes) => Ok(Value::from(bytes)),
+                None => anyhow::bail!("expected a whole number of bytes, got {}", n),
+            },
+            (InputType::Bytesize, Value::String(s)) => Ok(Value::from(parse_bytesize(&s)?)),
+            (InputType::Enum, Value::String(s)) => Ok(Value::String(s)),
+            (InputType::Json | InputType::Other(_), value) => Ok(value),
+            (data_type, value) => anyhow::bail!("expected {}, got {}", String::from(data_type.clone()), kind(&value)),
+        }
+    }
+}
+
+impl TaskDefinition {
+    // Submitted inputs coerced to the declared input types and checked against their
+    // choices, with defaults filled in. Inputs the definition doesn't declare pass
+    // through untouched. Fails with an InputValidationError listing every bad input.
+    pub fn coerce_inputs(&self, inputs: Value) -> Result<Value> {
+        self.coerce_matching(inputs, |_| true, true)
+    }
+
+    // coerce_inputs for a job whose `deferred` inputs only arrive on the worker, such
+    // as stream inputs: those are left alone, and so are the checks, which may read them
+    pub fn coerce_inputs_except(&self, inputs: Value, deferred: &[&str]) -> Result<Value> {
+        self.coerce_matching(inputs, |name| !deferred.contains(&name), false)
+    }
+
+    // The other half of coerce_inputs_except, once the deferred inputs are in
+    pub fn coerce_deferred_inputs(&self, inputs: Value, deferred: &[&str]) -> Result<Value> {
+        self.coerce_matching(inputs, |name| deferred.contains(&name), true)
+    }
+
+    fn coerce_matching(&self, inputs: Value, included: impl Fn(&str) -> bool, run_checks: bool) -> Result<Value> {
+        if self.inputs.is_empty() && self.checks.is_empty() {
+            return Ok(inputs);
+        }
+        let mut values = match inputs {
+            Value::Object(values) => values,
+            Value::Null => Map::new(),
+            other => anyhow::bail!("Inputs of {} must be an object, got {}", self.name, kind(&other)),
+        };
+        let mut errors = Vec::new();
+        for input in self.inputs.iter().filter(|input| included(&input.name)) {
+            let value = match values.remove(&input.name).filter(|value| !value.is_null()) {
+                Some(value) => value,
+                None => match &input.default_value {
+                    Some(default_value) => default_value.clone(),
+                    None if input.required => {
+                        errors.push(InputError { input: input.name.clone(), problem: InputProblem::Missing });
+                        continue;
+                    }
+                    None => continue,
+                },
+            };
+            let coerced = match &input.data_type {
+                Some(data_type) => data_type.coerce(value),
+                None => Ok(value),
+            };
+            let value = match coerced {
+                Ok(value) => value,
+                Err(e) => {
+                    let problem = InputProblem::Invalid { message: format!("{:#}", e) };
+                    errors.push(InputError { input: input.name.clone(), problem });
+                    continue;
+                }
+            };
+            if !input.choices.is_empty() && !input.choices.contains(&value) {
+                let problem = InputProblem::NotAllowed { value, choices: input.choices.clone() };
+                errors.push(InputError { input: input.name.clone(), problem });
+                continue;
+            }
+            values.insert(input.name.clone(), value);
+        }
+        let inputs = Value::Object(values);
+        let failed_checks = if errors.is_empty() && run_checks { self.failed_checks(&inputs) } else { Vec::new() };
+        if !errors.is_empty() || !failed_checks.is_empty() {
+            return Err(InputValidationError { task: self.name.clone(), errors, failed_checks }.into());
+        }
+        Ok(inputs)
+    }
+
+    // Evaluates the definition's checks on inputs that were already coerced; workers
+    // re-run them on the jobs they take
+    pub fn check_inputs(&self, inputs: &Value) -> Result<()> {
+        let failed_checks = self.failed_checks(inputs);
+        if failed_checks.is_empty() {
+            return Ok(());
+        }
+        Err(InputValidationError { task: self.name.clone(), errors: Vec::new(), failed_checks }.into())
+    }
+
+    fn failed_checks(&self, inputs: &Value) -> Vec<FailedCheck> {
+        self.checks
+            .iter()
+            .filter_map(|check| {
+                let message = match check.passes(inputs) {
+                    Ok(true) => return None,
+                    Ok(false) => check.message.clone(),
+                    Err(e) => Some(format!("{:#}", e)),
+                };
+                Some(FailedCheck { expression: check.expression.clone(), message })
+            })
+            .collect()
+    }
+}
+
+// Seconds in "1h30m", "250ms", "1.5d" or plain "90"
+pub fn parse_duration(text: &str) -> Result<f64> {
+    let invalid = || anyhow::anyhow!("expected a duration like 30s or 1h30m, got {:?}", text);
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(seconds) = rest.parse::<f64>() {
+        return Some(seconds).filter(|s| s.is_finite() && *s >= 0.0).ok_or_else(invalid);
+    }
+    let mut seconds = 0.0;
+    while !rest.is_empty() {
+        let (amount, unit_and_rest) = split_number(rest).ok_or_else(invalid)?;
+        let unit_len = unit_and_rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(unit_and_rest.len());
+        let unit = match &unit_and_rest[..unit_len] {
+            "ms" => 0.001,
+            "s" | "sec" | "secs" => 1.0,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" => 3600.0,
+            "d" => 86400.0,
+            "w" => 604800.0,
+            _ => return Err(invalid()),
+        };
+        seconds += amount * unit;
+        rest = unit_and_rest[unit_len..].trim_start();
+    }
+    Ok(seconds)
+}
+
+// Bytes in "512MB", "1.5GiB", "64k" or plain "4096". KB/MB/GB/TB are decimal,
+// KiB/MiB/GiB/TiB and the bare K/M/G/T binary.
+pub fn parse_bytesize(text: &str) -> Result<u64> {
+    let invalid = || anyhow::anyhow!("expected a size like 512MB or 1GiB, got {:?}", text);
+    let (amount, unit) = split_number(text.trim()).ok_or_else(invalid)?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        _ => return Err(invalid()),
+    };
+    let bytes = (amount * multiplier as f64).round();
+    if bytes > u64::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes as u64)
+}
+
+// Leading non-negative decimal number and what follows it
+fn split_number(text: &str) -> Option<(f64, &str)> {
+    let end = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let amount: f64 = text[..end].parse().ok()?;
+    Some((amount, &text[end..]))
+}
+
+fn number(value: Option<f64>, text: &str) -> Result<Value> {
+    value
+        .and_then(serde_json::Number::from_f64)
+        .map(Value::Number)
+        .ok_or_else(|| anyhow::anyhow!("expected a number, got {:?}", text))
+}
+
+// Whole seconds stay integers, so "30s" reaches the task as 30 rather than 30.0
+fn duration(seconds: f64) -> Result<Value> {
+    if seconds.fract() == 0.0 && seconds < u64::MAX as f64 {
+        Ok(Value::from(seconds as u64))
+    } else {
+        number(Some(seconds), &seconds.to_string())
+    }
+}
+
+fn rfc3339(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
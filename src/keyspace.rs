@@ -0,0 +1,204 @@
+use anyhow::Result;
+use std::borrow::Cow;
+use std::fmt::Write;
+
+pub const DEFAULT_NAMESPACE: &str = "comp";
+
+// Characters with a meaning in key expressions (chunk separator, wildcards,
+// verbatim and selector markers), plus '%' so escaping stays reversible
+const RESERVED_KEY_CHARS: &[char] = &['/', '*', '$', '?', '#', '%'];
+
+fn is_reserved(c: char) -> bool {
+    RESERVED_KEY_CHARS.contains(&c) || c.is_control() || c.is_whitespace()
+}
+
+// A chunk starting with '@' is a Zenoh admin or verbatim chunk
+fn is_reserved_at(index: usize, c: char) -> bool {
+    is_reserved(c) || (index == 0 && c == '@')
+}
+
+// Rejects identifiers (task IDs, queue names, worker IDs) that can't be used as a
+// single key expression chunk as-is
+pub fn validate_key_segment(kind: &str, value: &str) -> Result<()> {
+    if value.is_empty() {
+        anyhow::bail!("{} must not be empty", kind);
+    }
+    if let Some((_, c)) = value.char_indices().find(|&(index, c)| is_reserved_at(index, c)) {
+        anyhow::bail!("{} {:?} contains {:?}, which is not allowed in key expressions", kind, value, c);
+    }
+    Ok(())
+}
+
+// Percent-encodes reserved characters so `value` always stays within one chunk. The
+// empty string becomes a lone '%', which no other value escapes to.
+pub fn escape_key_segment(value: &str) -> Cow<'_, str> {
+    if value.is_empty() {
+        return Cow::Borrowed("%");
+    }
+    if !value.char_indices().any(|(index, c)| is_reserved_at(index, c)) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for (index, c) in value.char_indices() {
+        if is_reserved_at(index, c) {
+            let mut buf = [0u8; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                let _ = write!(escaped, "%{:02X}", byte);
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    Cow::Owned(escaped)
+}
+
+// Key expressions used by submitters, assigners, workers and listeners of one queue.
+// Everything lives under `<namespace>/queues/<queue>/`, so wildcard subscriptions
+// only see their own queue's traffic. Identifiers are escaped when interpolated, so
+// a hostile task ID or queue name can't reach into another part of the keyspace.
+#[derive(Debug, Clone)]
+pub struct Keyspace {
+    namespace: String,
+    queue: String,
+}
+
+impl Keyspace {
+    // The namespace is used verbatim and may span several chunks
+    pub fn new(namespace: &str, queue: &str) -> Self {
+        Self {
+            namespace: namespace.to_string(),
+            queue: queue.to_string(),
+        }
+    }
+
+    pub fn for_queue(queue: &str) -> Self {
+        Self::new(DEFAULT_NAMESPACE, queue)
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    pub fn queue(&self) -> &str {
+        &self.queue
+    }
+
+    fn queue_prefix(&self) -> String {
+        format!("{}/queues/{}", self.namespace, escape_key_segment(&self.queue))
+    }
+
+    pub fn announce(&self) -> String {
+        format!("{}/announce", self.queue_prefix())
+    }
+
+    // Jobs still waiting for an assignee, served by the assigner to late joiners
+    pub fn pending(&self, task_id: &str) -> String {
+        format!("{}/pending/{}", self.queue_prefix(), escape_key_segment(task_id))
+    }
+
+    // Queue depth gauges for autoscalers
+    pub fn metrics(&self) -> String {
+        format!("{}/metrics", self.queue_prefix())
+    }
+
+    // Paginated task listings, served by status stores
+    pub fn task_list(&self) -> String {
+        format!("{}/task-list", self.queue_prefix())
+    }
+
+    // Parent/child task trees, served by status stores
+    pub fn task_tree(&self) -> String {
+        format!("{}/task-tree", self.queue_prefix())
+    }
+
+    // Estimates for jobs not yet submitted, served by the assigner
+    pub fn estimate(&self) -> String {
+        format!("{}/estimate", self.queue_prefix())
+    }
+
+    pub fn task(&self, task_id: &str) -> String {
+        format!("{}/tasks/{}", self.queue_prefix(), escape_key_segment(task_id))
+    }
+
+    pub fn claim(&self, task_id: &str) -> String {
+        format!("{}/claim", self.task(task_id))
+    }
+
+    pub fn assign(&self, task_id: &str) -> String {
+        format!("{}/assign", self.task(task_id))
+    }
+
+    // Queried by workers taking the job when the queue has no assigner
+    pub fn lock(&self, task_id: &str) -> String {
+        format!("{}/lock", self.task(task_id))
+    }
+
+    // Inputs too large to announce, served by the job's submitter
+    pub fn inputs(&self, task_id: &str) -> String {
+        format!("{}/inputs", self.task(task_id))
+    }
+
+    pub fn ack(&self, task_id: &str) -> String {
+        format!("{}/ack", self.task(task_id))
+    }
+
+    // Submission receipts from the assigner
+    pub fn receipt(&self, task_id: &str) -> String {
+        format!("{}/receipt", self.task(task_id))
+    }
+
+    pub fn status(&self, task_id: &str) -> String {
+        format!("{}/status", self.task(task_id))
+    }
+
+    pub fn result(&self, task_id: &str) -> String {
+        format!("{}/result", self.task(task_id))
+    }
+
+    pub fn heartbeat(&self, worker_id: &str) -> String {
+        format!("{}/workers/{}/heartbeat", self.queue_prefix(), escape_key_segment(worker_id))
+    }
+
+    // Statuses and results a batching worker publishes together
+    pub fn batch(&self, worker_id: &str) -> String {
+        format!("{}/workers/{}/batch", self.queue_prefix(), escape_key_segment(worker_id))
+    }
+
+    // Wildcards are spelled out here since the helpers above would escape them
+
+    pub fn all_pending(&self) -> String {
+        format!("{}/pending/**", self.queue_prefix())
+    }
+
+    pub fn all_heartbeats(&self) -> String {
+        format!("{}/workers/*/heartbeat", self.queue_prefix())
+    }
+
+    pub fn all_tasks(&self) -> String {
+        format!("{}/tasks/**", self.queue_prefix())
+    }
+
+    pub fn all_batches(&self) -> String {
+        format!("{}/workers/*/batch", self.queue_prefix())
+    }
+
+    pub fn all_statuses(&self) -> String {
+        format!("{}/tasks/*/status", self.queue_prefix())
+    }
+
+    pub fn all_claims(&self) -> String {
+        format!("{}/tasks/*/claim", self.queue_prefix())
+    }
+
+    pub fn all_assigns(&self) -> String {
+        format!("{}/tasks/*/assign", self.queue_prefix())
+    }
+
+    pub fn all_acks(&self) -> String {
+        format!("{}/tasks/*/ack", self.queue_prefix())
+    }
+
+    pub fn all_results(&self) -> String {
+        format!("{}/tasks/*/result", self.queue_prefix())
+    }
+}
@@ -0,0 +1,101 @@
+// Task definitions may leave the language to the worker by declaring it as "auto"
+// (or leaving it empty); any other value is used as-is
+pub const AUTO_LANGUAGE: &str = "auto";
+
+// Used when nothing points either way, as the original URL/git sources did
+const FALLBACK_LANGUAGE: &str = "python";
+
+pub fn is_auto_language(language: &str) -> bool {
+    language.is_empty() || language.eq_ignore_ascii_case(AUTO_LANGUAGE)
+}
+
+// The declared language, or the one detected from `code`, the path or URL it came
+// from and any language the source itself reports (e.g. gist metadata)
+pub fn resolve_language(declared: &str, path: Option<&str>, reported: Option<&str>, code: &str) -> String {
+    if !is_auto_language(declared) {
+        return declared.to_string();
+    }
+    detect_language(path, reported, code)
+        .unwrap_or(FALLBACK_LANGUAGE)
+        .to_string()
+}
+
+// Shebang first, then what the source reports, then the file extension, and finally
+// a guess from the code itself
+pub fn detect_language(path: Option<&str>, reported: Option<&str>, code: &str) -> Option<&'static str> {
+    language_from_shebang(code)
+        .or_else(|| reported.and_then(language_from_name))
+        .or_else(|| path.and_then(language_from_extension))
+        .or_else(|| language_from_content(code))
+}
+
+fn language_from_name(name: &str) -> Option<&'static str> {
+    match name.to_ascii_lowercase().as_str() {
+        "python" | "python3" | "py" => Some("python"),
+        "javascript" | "js" | "node" | "nodejs" => Some("javascript"),
+        "typescript" | "ts" | "ts-node" | "tsx" => Some("typescript"),
+        "r" | "rscript" => Some("r"),
+        "julia" | "jl" => Some("julia"),
+        "java" | "jvm" => Some("java"),
+        "c" => Some("c"),
+        "cpp" | "c++" | "cxx" => Some("cpp"),
+        "sql" | "duckdb" => Some("sql"),
+        "expression" => Some("expression"),
+        _ => None,
+    }
+}
+
+fn language_from_shebang(code: &str) -> Option<&'static str> {
+    let interpreter_line = code.lines().next()?.strip_prefix("#!")?;
+    // "#!/usr/bin/env python3" or "#!/usr/local/bin/node --harmony"
+    interpreter_line
+        .split_whitespace()
+        .map(|word| word.rsplit('/').next().unwrap_or(word))
+        .find_map(|program| language_from_name(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')))
+}
+
+pub fn language_from_extension(path: &str) -> Option<&'static str> {
+    // URLs may carry a query or fragment after the file name
+    let path = path.split(['?', '#']).next().unwrap_or(path);
+    let extension = path.rsplit_once('.').map(|(_, extension)| extension)?;
+    match extension.to_ascii_lowercase().as_str() {
+        "py" | "pyw" => Some("python"),
+        "js" | "mjs" | "cjs" => Some("javascript"),
+        "ts" | "mts" | "cts" => Some("typescript"),
+        "r" => Some("r"),
+        "jl" => Some("julia"),
+        "jar" => Some("java"),
+        "c" => Some("c"),
+        "cc" | "cpp" | "cxx" | "c++" => Some("cpp"),
+        "sql" => Some("sql"),
+        _ => None,
+    }
+}
+
+// Counts tell-tale constructs of each language; no verdict on a tie
+fn language_from_content(code: &str) -> Option<&'static str> {
+    let mut python = 0;
+    let mut javascript = 0;
+    for line in code.lines().map(str::trim) {
+        if line.starts_with("def ") || line.starts_with("elif ") || line.starts_with("from ") && line.contains(" import ") {
+            python += 2;
+        }
+        if line.starts_with("import ") && !line.contains(" from ") && !line.ends_with(';') {
+            python += 1;
+        }
+        if line.starts_with("if __name__") || line.starts_with("print(") || line.ends_with(':') && !line.ends_with("::") {
+            python += 1;
+        }
+        if line.starts_with("function ") || line.starts_with("const ") || line.starts_with("let ") || line.contains("require(") {
+            javascript += 2;
+        }
+        if line.contains("console.log") || line.contains("=>") || line.ends_with(';') || line.ends_with('{') {
+            javascript += 1;
+        }
+    }
+    match python.cmp(&javascript) {
+        std::cmp::Ordering::Greater => Some("python"),
+        std::cmp::Ordering::Less => Some("javascript"),
+        std::cmp::Ordering::Equal => None,
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use zenoh::Session;
+use crate::dynamic_executor::sha256_hex;
+use crate::keyspace::Keyspace;
+use crate::schema::{InputsRef, Job};
+use crate::zenoh_utils::Codec;
+
+pub const DEFAULT_INLINE_INPUTS_BYTES: usize = 64 * 1024;
+// How long a worker waits for the submitter to serve offloaded inputs
+pub const INPUTS_FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+// When a client serves a job's inputs apart from its announcement, so one large
+// job doesn't hold up the announce channel for everyone else
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputsOffload {
+    // Inputs whose JSON is larger are offloaded; None always announces them inline
+    pub threshold_bytes: Option<usize>,
+    // Applied to the offloaded JSON; None serves it as is
+    pub codec: Option<Codec>,
+}
+
+impl Default for InputsOffload {
+    fn default() -> Self {
+        Self {
+            threshold_bytes: Some(DEFAULT_INLINE_INPUTS_BYTES),
+            codec: Some(Codec::Gzip),
+        }
+    }
+}
+
+impl InputsOffload {
+    pub fn disabled() -> Self {
+        Self { threshold_bytes: None, codec: None }
+    }
+
+    // Swaps inputs over the threshold for a reference to a key under the job's task,
+    // returning the payload to serve there
+    pub fn offload(&self, job: &mut Job, keyspace: &Keyspace) -> Result<Option<Vec<u8>>> {
+        let Some(threshold) = self.threshold_bytes else {
+            return Ok(None);
+        };
+        // Already served by whoever offloaded them first
+        if job.inputs_ref.is_some() {
+            return Ok(None);
+        }
+        let json = serde_json::to_vec(&job.inputs)?;
+        if json.len() <= threshold {
+            return Ok(None);
+        }
+        let payload = match self.codec {
+            Some(codec) => codec.compress(&json)?,
+            None => json.clone(),
+        };
+        job.inputs_ref = Some(InputsRef {
+            key: keyspace.inputs(&job.task_id),
+            size_bytes: json.len() as u64,
+            sha256: sha256_hex(&json),
+            codec: self.codec,
+        });
+        job.inputs = serde_json::Value::Null;
+        Ok(Some(payload))
+    }
+}
+
+// Checks the served payload against the reference before trusting it
+pub fn decode_inputs(inputs_ref: &InputsRef, payload: &[u8]) -> Result<serde_json::Value> {
+    let json = match inputs_ref.codec {
+        Some(codec) => codec.decompress(payload)?,
+        None => payload.to_vec(),
+    };
+    if json.len() as u64 != inputs_ref.size_bytes || sha256_hex(&json) != inputs_ref.sha256 {
+        anyhow::bail!("Inputs served at {} don't match the job's reference", inputs_ref.key);
+    }
+    serde_json::from_slice(&json).with_context(|| format!("Malformed inputs at {}", inputs_ref.key))
+}
+
+pub async fn fetch_inputs(session: &Session, inputs_ref: &InputsRef) -> Result<serde_json::Value> {
+    let replies = session
+        .get(inputs_ref.key.as_str())
+        .timeout(INPUTS_FETCH_TIMEOUT)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to query {}: {}", inputs_ref.key, e))?;
+    match replies.recv_async().await {
+        Ok(reply) => match reply.result() {
+            Ok(sample) => decode_inputs(inputs_ref, &sample.payload().to_bytes()),
+            Err(e) => anyhow::bail!("Fetching inputs from {} failed: {}", inputs_ref.key, String::from_utf8_lossy(&e.payload().to_bytes())),
+        },
+        Err(_) => anyhow::bail!("Nobody served the inputs at {}", inputs_ref.key),
+    }
+}
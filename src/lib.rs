@@ -1,7 +1,130 @@
+pub mod verbosity;
 pub mod schema;
+pub mod input_types;
+pub mod envelope;
 pub mod dynamic_executor;
+pub mod debug_bundle;
 pub mod zenoh_utils;
+pub mod batching;
+pub mod qos;
+pub mod process;
+pub mod usage;
+pub mod telemetry;
+pub mod metrics;
+pub mod events;
+pub mod workspace;
+pub mod execution_pool;
+pub mod runtimes;
+pub mod selftest;
+pub mod language;
+pub mod runners;
+#[cfg(feature = "http")]
+pub mod download;
+#[cfg(feature = "http")]
+pub mod archive;
+#[cfg(feature = "http")]
+pub mod registry;
+#[cfg(feature = "wasm")]
+pub mod wasm_runtime;
+#[cfg(feature = "docker")]
+pub mod container_pool;
+#[cfg(feature = "firecracker")]
+pub mod microvm;
+#[cfg(feature = "plugin")]
+pub mod native_plugin;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "sql")]
+pub mod sql;
+#[cfg(feature = "dataframe")]
+pub mod dataframe;
+#[cfg(feature = "image")]
+pub mod image_pipeline;
+#[cfg(feature = "expression")]
+pub mod expression;
+pub mod benchmark;
+pub mod builtins;
+pub mod config;
+pub mod worker;
+pub mod keyspace;
+pub mod receipts;
+pub mod client;
+pub mod local_runtime;
+pub mod status;
+pub mod scheduling;
+pub mod subtasks;
+pub mod large_inputs;
+pub mod fan_in;
+pub mod dry_run;
+pub mod estimation;
+pub mod assigner;
+pub mod gc;
+pub mod task_archive;
+pub mod replay;
+pub mod ab_test;
+pub mod determinism;
+pub mod result_listener;
+pub mod demo_runtime;
 
+pub use verbosity::*;
 pub use schema::*;
+pub use input_types::*;
+pub use envelope::*;
 pub use dynamic_executor::*;
+pub use debug_bundle::*;
 pub use zenoh_utils::*;
+pub use batching::*;
+pub use qos::*;
+pub use process::*;
+pub use usage::*;
+pub use telemetry::*;
+pub use metrics::*;
+pub use events::*;
+pub use workspace::*;
+pub use execution_pool::*;
+pub use runtimes::*;
+pub use selftest::*;
+pub use language::*;
+pub use runners::*;
+#[cfg(feature = "http")]
+pub use download::*;
+#[cfg(feature = "http")]
+pub use archive::*;
+#[cfg(feature = "http")]
+pub use registry::*;
+#[cfg(feature = "wasm")]
+pub use wasm_runtime::*;
+#[cfg(feature = "docker")]
+pub use container_pool::*;
+#[cfg(feature = "firecracker")]
+pub use microvm::*;
+#[cfg(feature = "plugin")]
+pub use native_plugin::*;
+#[cfg(feature = "onnx")]
+pub use onnx::*;
+#[cfg(feature = "sql")]
+pub use sql::*;
+#[cfg(feature = "dataframe")]
+pub use dataframe::*;
+#[cfg(feature = "image")]
+pub use image_pipeline::*;
+pub use config::*;
+pub use worker::*;
+pub use keyspace::*;
+pub use receipts::*;
+pub use client::*;
+pub use local_runtime::*;
+pub use status::*;
+pub use scheduling::*;
+pub use subtasks::*;
+pub use large_inputs::*;
+pub use fan_in::*;
+pub use dry_run::*;
+pub use estimation::*;
+pub use assigner::*;
+pub use gc::*;
+pub use task_archive::*;
+pub use replay::*;
+pub use ab_test::*;
+pub use result_listener::*;
+pub use demo_runtime::*;
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use crate::client::{TaskHandle, TaskSubmitter};
+use crate::config::WorkerConfig;
+use crate::schema::{Job, Result as TaskResult, TaskDefinition};
+use crate::worker::Worker;
+
+const LOCAL_QUEUE: &str = "local";
+
+type LocalJob = (Job, oneshot::Sender<TaskResult>);
+
+// Runs assignment and execution in-process without a Zenoh session, for tests,
+// laptops without a router, and single-node deployments
+pub struct LocalRuntime {
+    jobs_tx: mpsc::UnboundedSender<LocalJob>,
+}
+
+impl LocalRuntime {
+    // Must be called from within a Tokio runtime
+    pub fn new(config: WorkerConfig) -> Result<Self> {
+        let slots = config.concurrency.max_concurrent_tasks.max(1);
+        let worker = Arc::new(Worker::from_config(config)?);
+        let (jobs_tx, jobs_rx) = mpsc::unbounded_channel();
+        tokio::spawn(Self::assign_loop(worker, jobs_rx, slots));
+        Ok(Self { jobs_tx })
+    }
+
+    async fn assign_loop(worker: Arc<Worker>, mut jobs_rx: mpsc::UnboundedReceiver<LocalJob>, slots: usize) {
+        let slots = Arc::new(Semaphore::new(slots));
+        while let Some((job, result_tx)) = jobs_rx.recv().await {
+            let Ok(permit) = slots.clone().acquire_owned().await else {
+                break;
+            };
+            let worker = worker.clone();
+            tokio::spawn(async move {
+                let result = worker.run_job(&job).await;
+                let _ = result_tx.send(result);
+                drop(permit);
+            });
+        }
+    }
+}
+
+impl TaskSubmitter for LocalRuntime {
+    async fn submit(&self, task_definition: TaskDefinition, inputs: serde_json::Value) -> Result<TaskHandle> {
+        let inputs = task_definition.coerce_inputs(inputs)?;
+        let job = Job::new_user_task(LOCAL_QUEUE.to_string(), task_definition, inputs);
+        let task_id = job.task_id.clone();
+        let (result_tx, result_rx) = oneshot::channel();
+        self.jobs_tx
+            .send((job, result_tx))
+            .map_err(|_| anyhow::anyhow!("Local runtime has shut down"))?;
+        Ok(TaskHandle::new(task_id, result_rx))
+    }
+}
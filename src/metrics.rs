@@ -0,0 +1,280 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::sync::CancellationToken;
+use crate::schema::QueueMetrics;
+use crate::verbosity::Verbosity;
+use crate::summary;
+
+// A scraper gets this long to send its request and take the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+// Pause after a failed accept, which tends to repeat (e.g. out of file descriptors)
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// Backlog of one queue as last seen by its assigner: jobs waiting for (or in the
+// middle of) assignment, and jobs an acknowledged worker is executing
+#[derive(Debug)]
+pub struct QueueGauges {
+    queue: String,
+    pending: AtomicU64,
+    running: AtomicU64,
+}
+
+impl QueueGauges {
+    pub fn new(queue: &str) -> Arc<Self> {
+        Arc::new(Self {
+            queue: queue.to_string(),
+            pending: AtomicU64::new(0),
+            running: AtomicU64::new(0),
+        })
+    }
+
+    pub fn set(&self, pending: usize, running: usize) {
+        self.pending.store(pending as u64, Ordering::Relaxed);
+        self.running.store(running as u64, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> QueueMetrics {
+        QueueMetrics {
+            queue: self.queue.clone(),
+            pending: self.pending.load(Ordering::Relaxed),
+            running: self.running.load(Ordering::Relaxed),
+            timestamp: chrono::Utc::now(),
+        }
+    }
+}
+
+// Work done by a queue's task key garbage collector since it started
+#[derive(Debug)]
+pub struct GcCounters {
+    queue: String,
+    reclaimed_tasks: AtomicU64,
+    failed_deletes: AtomicU64,
+    tracked_tasks: AtomicU64,
+}
+
+impl GcCounters {
+    pub fn new(queue: &str) -> Arc<Self> {
+        Arc::new(Self {
+            queue: queue.to_string(),
+            reclaimed_tasks: AtomicU64::new(0),
+            failed_deletes: AtomicU64::new(0),
+            tracked_tasks: AtomicU64::new(0),
+        })
+    }
+
+    pub fn record_reclaimed(&self) {
+        self.reclaimed_tasks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failed_delete(&self) {
+        self.failed_deletes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Finished tasks waiting for their TTL to run out
+    pub fn set_tracked(&self, tracked: usize) {
+        self.tracked_tasks.store(tracked as u64, Ordering::Relaxed);
+    }
+
+    pub fn reclaimed_tasks(&self) -> u64 {
+        self.reclaimed_tasks.load(Ordering::Relaxed)
+    }
+
+    pub fn failed_deletes(&self) -> u64 {
+        self.failed_deletes.load(Ordering::Relaxed)
+    }
+
+    pub fn tracked_tasks(&self) -> u64 {
+        self.tracked_tasks.load(Ordering::Relaxed)
+    }
+}
+
+// Results of jobs submitted by registry reference, per `name@version`, as seen by a
+// queue's assigner: how a canary version fares against the stable one
+#[derive(Debug)]
+pub struct VersionCounters {
+    queue: String,
+    // Reference -> (completed, failed or timed out)
+    results: Mutex<BTreeMap<String, (u64, u64)>>,
+}
+
+impl VersionCounters {
+    pub fn new(queue: &str) -> Arc<Self> {
+        Arc::new(Self {
+            queue: queue.to_string(),
+            results: Mutex::new(BTreeMap::new()),
+        })
+    }
+
+    pub fn record(&self, reference: &str, succeeded: bool) {
+        let mut results = self.results.lock().unwrap();
+        let (completed, failed) = results.entry(reference.to_string()).or_default();
+        if succeeded {
+            *completed += 1;
+        } else {
+            *failed += 1;
+        }
+    }
+
+    // Completed and failed results of `reference` so far
+    pub fn results(&self, reference: &str) -> (u64, u64) {
+        self.results.lock().unwrap().get(reference).copied().unwrap_or_default()
+    }
+
+    // Share of the results of `reference` that completed; None before the first
+    pub fn success_rate(&self, reference: &str) -> Option<f64> {
+        let (completed, failed) = self.results(reference);
+        let total = completed + failed;
+        (total > 0).then(|| completed as f64 / total as f64)
+    }
+}
+
+// Prometheus text exposition of the given queues, garbage collectors and task versions
+pub fn render_prometheus(queues: &[Arc<QueueGauges>], collectors: &[Arc<GcCounters>], versions: &[Arc<VersionCounters>]) -> String {
+    let mut body = String::new();
+    body.push_str("# HELP corebrum_queue_pending_jobs Jobs waiting for a worker to be assigned.\n");
+    body.push_str("# TYPE corebrum_queue_pending_jobs gauge\n");
+    for gauges in queues {
+        body.push_str(&format!(
+            "corebrum_queue_pending_jobs{{queue=\"{}\"}} {}\n",
+            escape_label(&gauges.queue),
+            gauges.pending.load(Ordering::Relaxed)
+        ));
+    }
+    body.push_str("# HELP corebrum_queue_running_jobs Jobs being executed by an acknowledged worker.\n");
+    body.push_str("# TYPE corebrum_queue_running_jobs gauge\n");
+    for gauges in queues {
+        body.push_str(&format!(
+            "corebrum_queue_running_jobs{{queue=\"{}\"}} {}\n",
+            escape_label(&gauges.queue),
+            gauges.running.load(Ordering::Relaxed)
+        ));
+    }
+    render_versions(&mut body, versions);
+    if collectors.is_empty() {
+        return body;
+    }
+    body.push_str("# HELP corebrum_gc_reclaimed_tasks_total Finished tasks whose keys were deleted after their TTL.\n");
+    body.push_str("# TYPE corebrum_gc_reclaimed_tasks_total counter\n");
+    for counters in collectors {
+        body.push_str(&format!(
+            "corebrum_gc_reclaimed_tasks_total{{queue=\"{}\"}} {}\n",
+            escape_label(&counters.queue),
+            counters.reclaimed_tasks()
+        ));
+    }
+    body.push_str("# HELP corebrum_gc_failed_deletes_total Key deletions that failed and will be retried.\n");
+    body.push_str("# TYPE corebrum_gc_failed_deletes_total counter\n");
+    for counters in collectors {
+        body.push_str(&format!(
+            "corebrum_gc_failed_deletes_total{{queue=\"{}\"}} {}\n",
+            escape_label(&counters.queue),
+            counters.failed_deletes()
+        ));
+    }
+    body.push_str("# HELP corebrum_gc_tracked_tasks Finished tasks waiting for their TTL to expire.\n");
+    body.push_str("# TYPE corebrum_gc_tracked_tasks gauge\n");
+    for counters in collectors {
+        body.push_str(&format!(
+            "corebrum_gc_tracked_tasks{{queue=\"{}\"}} {}\n",
+            escape_label(&counters.queue),
+            counters.tracked_tasks()
+        ));
+    }
+    body
+}
+
+fn render_versions(body: &mut String, versions: &[Arc<VersionCounters>]) {
+    let results: Vec<(&str, BTreeMap<String, (u64, u64)>)> = versions
+        .iter()
+        .map(|counters| (counters.queue.as_str(), counters.results.lock().unwrap().clone()))
+        .filter(|(_, results)| !results.is_empty())
+        .collect();
+    if results.is_empty() {
+        return;
+    }
+    body.push_str("# HELP corebrum_task_version_results_total Results of registry-pinned jobs by task version and outcome.\n");
+    body.push_str("# TYPE corebrum_task_version_results_total counter\n");
+    for (queue, results) in &results {
+        for (reference, (completed, failed)) in results {
+            let (task, version) = reference.rsplit_once('@').unwrap_or((reference.as_str(), ""));
+            for (outcome, count) in [("completed", completed), ("failed", failed)] {
+                body.push_str(&format!(
+                    "corebrum_task_version_results_total{{queue=\"{}\",task=\"{}\",version=\"{}\",outcome=\"{}\"}} {}\n",
+                    escape_label(queue),
+                    escape_label(task),
+                    escape_label(version),
+                    outcome,
+                    count
+                ));
+            }
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+// Minimal HTTP endpoint for Prometheus scrapes: GET /metrics, anything else is a 404
+pub async fn serve_prometheus(
+    addr: SocketAddr,
+    queues: Vec<Arc<QueueGauges>>,
+    collectors: Vec<Arc<GcCounters>>,
+    versions: Vec<Arc<VersionCounters>>,
+    shutdown: CancellationToken,
+    verbosity: Verbosity,
+) -> Result<()> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind metrics endpoint on {}: {}", addr, e))?;
+    summary!(verbosity, "📈 Serving Prometheus metrics on http://{}/metrics", listener.local_addr()?);
+
+    loop {
+        let accepted = tokio::select! {
+            _ = shutdown.cancelled() => break,
+            accepted = listener.accept() => accepted,
+        };
+        let stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                summary!(verbosity, "⚠️  Metrics endpoint failed to accept a connection: {}", e);
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let body = render_prometheus(&queues, &collectors, &versions);
+        tokio::spawn(async move {
+            match tokio::time::timeout(REQUEST_TIMEOUT, respond(stream, body)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => summary!(verbosity, "⚠️  Metrics request failed: {}", e),
+                Err(_) => summary!(verbosity, "⚠️  Metrics request timed out after {}s", REQUEST_TIMEOUT.as_secs()),
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn respond(mut stream: TcpStream, body: String) -> Result<()> {
+    // Only the request line matters; scrapes are small enough to arrive in one read
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request).await?;
+    let request = String::from_utf8_lossy(&request[..read]);
+    let response = if request.starts_with("GET /metrics ") {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+use crate::config::MicrovmSettings;
+use crate::process::{run_command, ProcessLimits, ProcessOutput};
+
+// Experimental Firecracker backend. The guest boots the worker's kernel with the
+// rootfs template attached read-only, and the task as a tar archive on a second
+// drive (/dev/vdb). The template's init (see MICROVM_INIT) is expected to:
+//   1. extract /dev/vdb into a tmpfs mounted at /task
+//   2. run /task/run.sh
+//   3. print STDOUT_BEGIN, the command's stdout, STDOUT_END and `EXIT_MARKER <code>`
+//      on the serial console, then power off
+// Everything else on the console (kernel messages, stderr) is kept as the log.
+
+pub const MICROVM_INIT: &str = "/sbin/corebrum-init";
+const STDOUT_BEGIN: &str = "===COREBRUM-STDOUT-BEGIN===";
+const STDOUT_END: &str = "===COREBRUM-STDOUT-END===";
+const EXIT_MARKER: &str = "===COREBRUM-EXIT===";
+
+#[derive(Debug)]
+pub struct MicrovmOutput {
+    pub process: ProcessOutput,
+    // The task's stdout, cut out of the console
+    pub stdout: Vec<u8>,
+    pub exit_code: Option<i32>,
+}
+
+// Guest command line for the languages the template is expected to provide
+pub fn guest_command(language: &str) -> Result<&'static str> {
+    match language {
+        "python" => Ok("python3 /task/script /task/inputs.json"),
+        "javascript" | "js" => Ok("node /task/script /task/inputs.json"),
+        _ => anyhow::bail!("Unsupported language for microVM execution: {}", language),
+    }
+}
+
+// Boots a microVM in `workdir` that runs `code` with `inputs`, and waits for it to
+// power off; the process limits apply to the whole VM
+pub async fn run_in_microvm(
+    settings: &MicrovmSettings,
+    workdir: &Path,
+    language: &str,
+    code: &str,
+    inputs: &serde_json::Value,
+    limits: &ProcessLimits,
+    cancel: &CancellationToken,
+) -> Result<MicrovmOutput> {
+    let kernel = settings
+        .kernel_image
+        .as_ref()
+        .context("No microVM kernel image configured")?;
+    let rootfs = settings
+        .rootfs_template
+        .as_ref()
+        .context("No microVM rootfs template configured")?;
+
+    let task_drive = workdir.join("task.tar");
+    write_task_archive(&task_drive, &[
+        ("script", code.as_bytes()),
+        ("inputs.json", &serde_json::to_vec(inputs)?),
+        ("run.sh", format!("#!/bin/sh\nexec {}\n", guest_command(language)?).as_bytes()),
+    ])?;
+
+    let vm_config = serde_json::json!({
+        "boot-source": {
+            "kernel_image_path": kernel,
+            "boot_args": format!("console=ttyS0 reboot=k panic=1 pci=off quiet init={}", MICROVM_INIT),
+        },
+        "drives": [
+            {"drive_id": "rootfs", "path_on_host": rootfs, "is_root_device": true, "is_read_only": true},
+            {"drive_id": "task", "path_on_host": task_drive, "is_root_device": false, "is_read_only": true},
+        ],
+        "machine-config": {
+            "vcpu_count": settings.vcpus,
+            "mem_size_mib": settings.memory_mb,
+        },
+    });
+    let config_path = workdir.join("vm.json");
+    std::fs::write(&config_path, serde_json::to_vec_pretty(&vm_config)?)?;
+
+    let mut command = Command::new(&settings.firecracker_binary);
+    command
+        .arg("--no-api")
+        .arg("--config-file")
+        .arg(&config_path)
+        .arg("--id")
+        .arg(uuid::Uuid::new_v4().to_string())
+        .current_dir(workdir);
+    let process = run_command(command, limits, cancel)
+        .await
+        .context("Failed to launch Firecracker")?;
+
+    let console = String::from_utf8_lossy(&process.stdout).into_owned();
+    Ok(MicrovmOutput {
+        stdout: extract_stdout(&console).into_bytes(),
+        exit_code: extract_exit_code(&console),
+        process,
+    })
+}
+
+fn write_task_archive(path: &Path, files: &[(&str, &[u8])]) -> Result<()> {
+    let mut archive = tar::Builder::new(std::fs::File::create(path)?);
+    for (name, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        archive.append_data(&mut header, name, *content)?;
+    }
+    archive.finish()?;
+    Ok(())
+}
+
+fn extract_stdout(console: &str) -> String {
+    let Some(start) = console.find(STDOUT_BEGIN) else {
+        return String::new();
+    };
+    let rest = &console[start + STDOUT_BEGIN.len()..];
+    let end = rest.find(STDOUT_END).unwrap_or(rest.len());
+    // Serial consoles translate newlines to CRLF
+    rest[..end].replace("\r\n", "\n").trim().to_string()
+}
+
+fn extract_exit_code(console: &str) -> Option<i32> {
+    let start = console.rfind(EXIT_MARKER)? + EXIT_MARKER.len();
+    console[start..].split_whitespace().next()?.parse().ok()
+}
@@ -0,0 +1,65 @@
+use anyhow::{Context, Result};
+use libloading::Library;
+use std::collections::HashMap;
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// The C ABI a plugin library exports:
+//
+//   char *corebrum_run(const char *inputs_json);
+//   void corebrum_free(char *outputs_json);
+//
+// corebrum_run gets the task inputs as a NUL-terminated JSON object and returns the
+// outputs the same way, or NULL on failure; the worker hands the returned string
+// back to corebrum_free once it has copied it.
+const RUN_SYMBOL: &[u8] = b"corebrum_run\0";
+const FREE_SYMBOL: &[u8] = b"corebrum_free\0";
+
+type RunFn = unsafe extern "C" fn(*const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+// Libraries stay loaded for the life of the worker, by SHA-256: unloading native
+// code that may have left threads or callbacks behind isn't safe
+static LOADED: OnceLock<Mutex<HashMap<String, Arc<NativePlugin>>>> = OnceLock::new();
+
+pub struct NativePlugin {
+    library: Library,
+}
+
+impl NativePlugin {
+    pub fn load(path: &Path, sha256: &str) -> Result<Arc<NativePlugin>> {
+        let mut loaded = LOADED.get_or_init(Default::default).lock().unwrap_or_else(|p| p.into_inner());
+        if let Some(plugin) = loaded.get(sha256) {
+            return Ok(plugin.clone());
+        }
+        // SAFETY: loading runs the library's initializers in the worker process; plugin
+        // tasks are trusted code by definition (see the `plugin` feature)
+        let library = unsafe { Library::new(path) }.with_context(|| format!("Failed to load plugin {}", path.display()))?;
+        unsafe {
+            library.get::<RunFn>(RUN_SYMBOL).context("Plugin does not export corebrum_run")?;
+            library.get::<FreeFn>(FREE_SYMBOL).context("Plugin does not export corebrum_free")?;
+        }
+        let plugin = Arc::new(NativePlugin { library });
+        loaded.insert(sha256.to_string(), plugin.clone());
+        Ok(plugin)
+    }
+
+    // Calls corebrum_run in-process. Blocking, so call this from the execution pool.
+    pub fn run(&self, inputs_json: &str) -> Result<String> {
+        let inputs = CString::new(inputs_json).context("Plugin inputs contain a NUL byte")?;
+        // SAFETY: both symbols were checked at load time and have the documented
+        // signatures; the returned string is only read before it is freed
+        unsafe {
+            let run = self.library.get::<RunFn>(RUN_SYMBOL)?;
+            let free = self.library.get::<FreeFn>(FREE_SYMBOL)?;
+            let outputs = run(inputs.as_ptr());
+            if outputs.is_null() {
+                anyhow::bail!("Plugin returned no outputs");
+            }
+            let text = CStr::from_ptr(outputs).to_str().map(str::to_string);
+            free(outputs);
+            text.context("Plugin returned outputs that are not UTF-8")
+        }
+    }
+}
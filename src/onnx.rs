@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use ort::session::{Session, SessionInputs};
+use ort::tensor::TensorElementType;
+use ort::value::{DynValue, ValueType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+// Sessions stay loaded for the life of the worker, by model SHA-256 and thread count
+static LOADED: OnceLock<Mutex<HashMap<(String, usize), Arc<OnnxModel>>>> = OnceLock::new();
+
+// How a tensor appears in task inputs and outputs:
+//   {"dtype": "float32", "shape": [1, 3], "data": [0.1, 0.2, 0.3]}
+// Large inputs can reference their raw little-endian values instead of inlining them:
+//   {"dtype": "uint8", "shape": [1, 480, 640, 3], "url": "https://...", "sha256": "..."}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TensorSpec {
+    #[serde(default = "default_dtype")]
+    pub dtype: String,
+    pub shape: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Vec<serde_json::Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+fn default_dtype() -> String {
+    "float32".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TensorData {
+    Float32(Vec<f32>),
+    Int64(Vec<i64>),
+    Int32(Vec<i32>),
+    Uint8(Vec<u8>),
+}
+
+// Applies `$body` to the values of a TensorData whatever their element type
+macro_rules! each_dtype {
+    ($data:expr, $values:ident => $body:expr) => {
+        match $data {
+            TensorData::Float32($values) => $body,
+            TensorData::Int64($values) => $body,
+            TensorData::Int32($values) => $body,
+            TensorData::Uint8($values) => $body,
+        }
+    };
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tensor {
+    pub shape: Vec<usize>,
+    pub data: TensorData,
+}
+
+impl Tensor {
+    // From a spec carrying its values inline
+    pub fn from_spec(spec: &TensorSpec) -> Result<Self> {
+        let values = spec.data.as_ref().context("Tensor has neither `data` nor `url`")?;
+        let number = |value: &serde_json::Value| value.as_f64().with_context(|| format!("Tensor value {} is not a number", value));
+        let data = match spec.dtype.as_str() {
+            "float32" => TensorData::Float32(values.iter().map(|v| number(v).map(|n| n as f32)).collect::<Result<_>>()?),
+            "int64" => TensorData::Int64(values.iter().map(|v| v.as_i64().with_context(|| format!("Tensor value {} is not an int64", v))).collect::<Result<_>>()?),
+            "int32" => TensorData::Int32(values.iter().map(integer).collect::<Result<_>>()?),
+            "uint8" => TensorData::Uint8(values.iter().map(integer).collect::<Result<_>>()?),
+            other => anyhow::bail!("Unsupported tensor dtype {}", other),
+        };
+        Self::new(spec.shape.clone(), data)
+    }
+
+    // From raw little-endian values, e.g. a referenced tensor
+    pub fn from_bytes(dtype: &str, shape: Vec<usize>, bytes: &[u8]) -> Result<Self> {
+        let data = match dtype {
+            "float32" => TensorData::Float32(bytes.chunks_exact(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect()),
+            "int64" => TensorData::Int64(bytes.chunks_exact(8).map(|b| i64::from_le_bytes(b.try_into().unwrap())).collect()),
+            "int32" => TensorData::Int32(bytes.chunks_exact(4).map(|b| i32::from_le_bytes(b.try_into().unwrap())).collect()),
+            "uint8" => TensorData::Uint8(bytes.to_vec()),
+            other => anyhow::bail!("Unsupported tensor dtype {}", other),
+        };
+        Self::new(shape, data)
+    }
+
+    pub fn new(shape: Vec<usize>, data: TensorData) -> Result<Self> {
+        let expected: usize = shape.iter().product();
+        let len = each_dtype!(&data, values => values.len());
+        if len != expected {
+            anyhow::bail!("Tensor of shape {:?} needs {} values, got {}", shape, expected, len);
+        }
+        Ok(Self { shape, data })
+    }
+
+    pub fn dtype(&self) -> &'static str {
+        match self.data {
+            TensorData::Float32(_) => "float32",
+            TensorData::Int64(_) => "int64",
+            TensorData::Int32(_) => "int32",
+            TensorData::Uint8(_) => "uint8",
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let data = each_dtype!(&self.data, values => serde_json::json!(values));
+        serde_json::json!({ "dtype": self.dtype(), "shape": self.shape, "data": data })
+    }
+
+    // Splits along the first dimension into tensors of at most `batch_size` rows
+    pub fn split(&self, batch_size: usize) -> Vec<Tensor> {
+        let rows = self.shape.first().copied().unwrap_or(1);
+        let row_len: usize = self.shape.iter().skip(1).product();
+        (0..rows)
+            .step_by(batch_size)
+            .map(|start| {
+                let end = (start + batch_size).min(rows);
+                let mut shape = self.shape.clone();
+                shape[0] = end - start;
+                let range = start * row_len..end * row_len;
+                let data = match &self.data {
+                    TensorData::Float32(values) => TensorData::Float32(values[range].to_vec()),
+                    TensorData::Int64(values) => TensorData::Int64(values[range].to_vec()),
+                    TensorData::Int32(values) => TensorData::Int32(values[range].to_vec()),
+                    TensorData::Uint8(values) => TensorData::Uint8(values[range].to_vec()),
+                };
+                Tensor { shape, data }
+            })
+            .collect()
+    }
+
+    // Joins batches back together along the first dimension
+    pub fn concat(mut parts: Vec<Tensor>) -> Result<Tensor> {
+        let mut joined = parts.remove(0);
+        for part in parts {
+            if part.shape.get(1..) != joined.shape.get(1..) {
+                anyhow::bail!("Batches produced outputs of shapes {:?} and {:?}", joined.shape, part.shape);
+            }
+            joined.shape[0] += part.shape[0];
+            match (&mut joined.data, part.data) {
+                (TensorData::Float32(all), TensorData::Float32(more)) => all.extend(more),
+                (TensorData::Int64(all), TensorData::Int64(more)) => all.extend(more),
+                (TensorData::Int32(all), TensorData::Int32(more)) => all.extend(more),
+                (TensorData::Uint8(all), TensorData::Uint8(more)) => all.extend(more),
+                _ => anyhow::bail!("Batches produced outputs of different dtypes"),
+            }
+        }
+        Ok(joined)
+    }
+}
+
+fn integer<T: TryFrom<i64>>(value: &serde_json::Value) -> Result<T> {
+    value
+        .as_i64()
+        .and_then(|n| T::try_from(n).ok())
+        .with_context(|| format!("Tensor value {} is out of range for its dtype", value))
+}
+
+pub struct OnnxModel {
+    session: Session,
+}
+
+impl OnnxModel {
+    pub fn load(path: &Path, sha256: &str, threads: usize) -> Result<Arc<OnnxModel>> {
+        let key = (sha256.to_string(), threads);
+        let mut loaded = LOADED.get_or_init(Default::default).lock().unwrap_or_else(|p| p.into_inner());
+        if let Some(model) = loaded.get(&key) {
+            return Ok(model.clone());
+        }
+        let session = Session::builder()?
+            .with_intra_threads(threads)?
+            .commit_from_file(path)
+            .with_context(|| format!("Failed to load ONNX model {}", path.display()))?;
+        let model = Arc::new(OnnxModel { session });
+        loaded.insert(key, model.clone());
+        Ok(model)
+    }
+
+    // Runs the model, `batch_size` rows of the inputs at a time if set, and returns
+    // every output it declares. Blocking, so call this from the execution pool.
+    pub fn infer(&self, inputs: Vec<(String, Tensor)>, batch_size: Option<usize>) -> Result<Vec<(String, Tensor)>> {
+        let Some(batch_size) = batch_size.filter(|&size| size > 0) else {
+            return self.run(inputs);
+        };
+        let rows: Vec<usize> = inputs.iter().map(|(_, tensor)| tensor.shape.first().copied().unwrap_or(1)).collect();
+        if rows.windows(2).any(|pair| pair[0] != pair[1]) {
+            anyhow::bail!("Batched inputs must share their first dimension, got {:?}", rows);
+        }
+        let split: Vec<(String, Vec<Tensor>)> = inputs.iter().map(|(name, tensor)| (name.clone(), tensor.split(batch_size))).collect();
+        let batches = split.first().map_or(0, |(_, parts)| parts.len());
+
+        let mut outputs: Vec<(String, Vec<Tensor>)> = Vec::new();
+        for batch in 0..batches {
+            let batch_inputs = split.iter().map(|(name, parts)| (name.clone(), parts[batch].clone())).collect();
+            for (index, (name, tensor)) in self.run(batch_inputs)?.into_iter().enumerate() {
+                match outputs.get_mut(index) {
+                    Some((_, parts)) => parts.push(tensor),
+                    None => outputs.push((name, vec![tensor])),
+                }
+            }
+        }
+        outputs
+            .into_iter()
+            .map(|(name, parts)| Ok((name, Tensor::concat(parts)?)))
+            .collect()
+    }
+
+    fn run(&self, inputs: Vec<(String, Tensor)>) -> Result<Vec<(String, Tensor)>> {
+        let values = inputs
+            .into_iter()
+            .map(|(name, tensor)| Ok((name, to_value(tensor)?)))
+            .collect::<Result<Vec<(String, DynValue)>>>()?;
+        let outputs = self.session.run(SessionInputs::from(values)).context("ONNX inference failed")?;
+        self.session
+            .outputs
+            .iter()
+            .map(|output| Ok((output.name.clone(), from_value(&outputs[output.name.as_str()])?)))
+            .collect()
+    }
+}
+
+fn to_value(tensor: Tensor) -> Result<DynValue> {
+    let shape: Vec<i64> = tensor.shape.iter().map(|&dim| dim as i64).collect();
+    let value = match tensor.data {
+        TensorData::Float32(values) => ort::value::Tensor::from_array((shape, values))?.into_dyn(),
+        TensorData::Int64(values) => ort::value::Tensor::from_array((shape, values))?.into_dyn(),
+        TensorData::Int32(values) => ort::value::Tensor::from_array((shape, values))?.into_dyn(),
+        TensorData::Uint8(values) => ort::value::Tensor::from_array((shape, values))?.into_dyn(),
+    };
+    Ok(value)
+}
+
+fn from_value(value: &DynValue) -> Result<Tensor> {
+    let ValueType::Tensor { ty, .. } = value.dtype() else {
+        anyhow::bail!("Only tensor outputs are supported");
+    };
+    let dims = |shape: Vec<i64>| shape.into_iter().map(|dim| dim.max(0) as usize).collect::<Vec<_>>();
+    let (shape, data) = match ty {
+        TensorElementType::Float32 => {
+            let (shape, values) = value.try_extract_raw_tensor::<f32>()?;
+            (dims(shape), TensorData::Float32(values.to_vec()))
+        }
+        TensorElementType::Int64 => {
+            let (shape, values) = value.try_extract_raw_tensor::<i64>()?;
+            (dims(shape), TensorData::Int64(values.to_vec()))
+        }
+        TensorElementType::Int32 => {
+            let (shape, values) = value.try_extract_raw_tensor::<i32>()?;
+            (dims(shape), TensorData::Int32(values.to_vec()))
+        }
+        TensorElementType::Uint8 => {
+            let (shape, values) = value.try_extract_raw_tensor::<u8>()?;
+            (dims(shape), TensorData::Uint8(values.to_vec()))
+        }
+        other => anyhow::bail!("Unsupported output tensor type {:?}", other),
+    };
+    Tensor::new(shape, data)
+}
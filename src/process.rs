@@ -0,0 +1,253 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::{Child, Command};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use crate::schema::ResourceUsage;
+use crate::usage::UsageTracker;
+use crate::workspace::disk_usage;
+
+// Walking the workspace is comparatively expensive, so usage is only sampled periodically
+const QUOTA_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+// Short-lived tasks need fine sampling to be measured at all
+const USAGE_SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+// Most of each output stream kept in memory, enough for a script's JSON outputs;
+// anything past it is read and dropped so the child never blocks on a full pipe
+pub const MAX_PROCESS_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub struct DiskQuota {
+    pub path: PathBuf,
+    pub limit_bytes: u64,
+}
+
+impl DiskQuota {
+    pub fn is_exceeded(&self) -> bool {
+        disk_usage(&self.path) > self.limit_bytes
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcessLimits {
+    pub timeout: Option<Duration>,
+    pub disk_quota: Option<DiskQuota>,
+}
+
+// Process groups of every child currently running, so worker shutdown can reap them all
+static RUNNING_GROUPS: OnceLock<Mutex<HashSet<u32>>> = OnceLock::new();
+
+fn running_groups() -> &'static Mutex<HashSet<u32>> {
+    RUNNING_GROUPS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+pub fn kill_all_process_groups() {
+    let groups: Vec<u32> = running_groups().lock().unwrap().drain().collect();
+    for pgid in groups {
+        kill_process_group(pgid);
+    }
+}
+
+#[cfg(unix)]
+fn kill_process_group(pgid: u32) {
+    unsafe {
+        libc::killpg(pgid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_process_group(_pgid: u32) {}
+
+// Kills whatever is left of the group and unregisters it, including when the
+// run_command future is dropped before the child exits
+struct GroupGuard(u32);
+
+impl Drop for GroupGuard {
+    fn drop(&mut self) {
+        kill_process_group(self.0);
+        running_groups().lock().unwrap().remove(&self.0);
+    }
+}
+
+fn kill_child(child: &mut Child, pgid: u32) {
+    // The child leads its own group, so this also takes out anything it forked
+    kill_process_group(pgid);
+    let _ = child.start_kill();
+}
+
+enum KillReason {
+    Timeout,
+    Cancelled,
+    QuotaExceeded,
+}
+
+#[derive(Debug)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    // More than MAX_PROCESS_OUTPUT_BYTES was written; only the start was kept
+    pub stdout_truncated: bool,
+    pub stderr_truncated: bool,
+    pub timed_out: bool,
+    pub cancelled: bool,
+    pub quota_exceeded: bool,
+    pub usage: Option<ResourceUsage>,
+}
+
+impl ProcessOutput {
+    pub fn exit_code(&self) -> Option<i32> {
+        self.status.code()
+    }
+
+    pub fn signal(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            self.status.signal()
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        !self.timed_out && !self.cancelled && !self.quota_exceeded && self.status.success()
+    }
+
+    // Human readable reason for a failed run, used in task error messages
+    pub fn failure_reason(&self) -> String {
+        if self.timed_out {
+            "timed out".to_string()
+        } else if self.cancelled {
+            "cancelled".to_string()
+        } else if self.quota_exceeded {
+            "workspace disk quota exceeded".to_string()
+        } else if let Some(signal) = self.signal() {
+            format!("terminated by signal {}", signal)
+        } else {
+            format!("exited with code {}", self.exit_code().unwrap_or(-1))
+        }
+    }
+}
+
+pub async fn run_command(
+    mut command: Command,
+    limits: &ProcessLimits,
+    cancel: &CancellationToken,
+) -> Result<ProcessOutput> {
+    command.stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    #[cfg(unix)]
+    command.process_group(0);
+    let mut child = command.spawn().context("Failed to spawn process")?;
+    let pgid = child.id().context("Process exited before it could be tracked")?;
+    running_groups().lock().unwrap().insert(pgid);
+    let _group_guard = GroupGuard(pgid);
+
+    // Drain both pipes concurrently so a chatty child can't block on a full pipe
+    let stdout_reader = spawn_reader(child.stdout.take());
+    let stderr_reader = spawn_reader(child.stderr.take());
+
+    let deadline = async {
+        match limits.timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(deadline);
+    let mut quota_check = tokio::time::interval(QUOTA_CHECK_INTERVAL);
+    let mut usage_sample = tokio::time::interval(USAGE_SAMPLE_INTERVAL);
+    let mut usage = UsageTracker::new();
+
+    let mut kill_reason = None;
+    let status = loop {
+        let reason = tokio::select! {
+            status = child.wait() => break status?,
+            _ = usage_sample.tick() => {
+                usage.sample(pgid);
+                continue;
+            }
+            _ = &mut deadline, if kill_reason.is_none() => KillReason::Timeout,
+            _ = cancel.cancelled(), if kill_reason.is_none() => KillReason::Cancelled,
+            _ = quota_check.tick(), if kill_reason.is_none() && limits.disk_quota.is_some() => {
+                if !limits.disk_quota.as_ref().is_some_and(|quota| quota.is_exceeded()) {
+                    continue;
+                }
+                KillReason::QuotaExceeded
+            }
+        };
+        usage.sample(pgid);
+        kill_child(&mut child, pgid);
+        kill_reason = Some(reason);
+    };
+
+    let mut quota_exceeded = matches!(kill_reason, Some(KillReason::QuotaExceeded));
+    // Catch anything written between the last sample and exit
+    if !quota_exceeded {
+        quota_exceeded = limits.disk_quota.as_ref().is_some_and(|quota| quota.is_exceeded());
+    }
+
+    let (stdout, stdout_truncated) = join_reader(stdout_reader).await;
+    let (stderr, stderr_truncated) = join_reader(stderr_reader).await;
+    Ok(ProcessOutput {
+        status,
+        stdout,
+        stderr,
+        stdout_truncated,
+        stderr_truncated,
+        timed_out: matches!(kill_reason, Some(KillReason::Timeout)),
+        cancelled: matches!(kill_reason, Some(KillReason::Cancelled)),
+        quota_exceeded,
+        usage: usage.usage(),
+    })
+}
+
+// Output kept up to a limit; whatever comes after is dropped and only noted
+#[derive(Debug)]
+pub struct CappedOutput {
+    bytes: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl CappedOutput {
+    pub fn new(limit: usize) -> Self {
+        Self { bytes: Vec::new(), limit, truncated: false }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        let room = self.limit.saturating_sub(self.bytes.len());
+        self.bytes.extend_from_slice(&bytes[..bytes.len().min(room)]);
+        self.truncated |= bytes.len() > room;
+    }
+
+    // The kept bytes, and whether any were dropped
+    pub fn finish(self) -> (Vec<u8>, bool) {
+        (self.bytes, self.truncated)
+    }
+}
+
+fn spawn_reader<R: AsyncRead + Unpin + Send + 'static>(stream: Option<R>) -> JoinHandle<(Vec<u8>, bool)> {
+    tokio::spawn(async move {
+        let mut output = CappedOutput::new(MAX_PROCESS_OUTPUT_BYTES);
+        if let Some(mut stream) = stream {
+            let mut chunk = vec![0; 64 * 1024];
+            while let Ok(read) = stream.read(&mut chunk).await {
+                if read == 0 {
+                    break;
+                }
+                output.push(&chunk[..read]);
+            }
+        }
+        output.finish()
+    })
+}
+
+async fn join_reader(handle: JoinHandle<(Vec<u8>, bool)>) -> (Vec<u8>, bool) {
+    handle.await.unwrap_or_default()
+}
@@ -0,0 +1,70 @@
+use zenoh::qos::{CongestionControl, Priority};
+
+// Coarse categories of mesh traffic, each published with its own QoS so the
+// scheduling handshake keeps moving when bulky results saturate a link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageClass {
+    // Announcements, claims, assignments, acks and heartbeats
+    Control,
+    // Task status updates
+    Status,
+    // Results and their artifacts
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Qos {
+    pub priority: Priority,
+    pub congestion_control: CongestionControl,
+    // Sends immediately instead of waiting to be batched with other messages
+    pub express: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QosPolicy {
+    control: Qos,
+    status: Qos,
+    data: Qos,
+}
+
+impl QosPolicy {
+    pub fn with(mut self, class: MessageClass, qos: Qos) -> Self {
+        match class {
+            MessageClass::Control => self.control = qos,
+            MessageClass::Status => self.status = qos,
+            MessageClass::Data => self.data = qos,
+        }
+        self
+    }
+
+    pub fn get(&self, class: MessageClass) -> Qos {
+        match class {
+            MessageClass::Control => self.control,
+            MessageClass::Status => self.status,
+            MessageClass::Data => self.data,
+        }
+    }
+}
+
+impl Default for QosPolicy {
+    fn default() -> Self {
+        Self {
+            control: Qos {
+                priority: Priority::InteractiveHigh,
+                congestion_control: CongestionControl::Block,
+                express: true,
+            },
+            // Under congestion a dropped update is superseded by the next one or the result
+            status: Qos {
+                priority: Priority::DataHigh,
+                congestion_control: CongestionControl::Drop,
+                express: false,
+            },
+            data: Qos {
+                priority: Priority::Data,
+                congestion_control: CongestionControl::Block,
+                express: false,
+            },
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use crate::builtins::{builtin_task, BUILTIN_NAMESPACE, BUILTIN_VERSION};
+use crate::config::CacheSettings;
+use crate::download::Downloader;
+use crate::schema::{DefinitionPin, TaskDefinition};
+use crate::verbosity::Verbosity;
+use crate::detail;
+
+// `name@version` of a task definition in a registry. Names may have `/`-separated
+// namespaces, e.g. `vision/resize@1.2.0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DefinitionRef {
+    pub name: String,
+    pub version: String,
+}
+
+impl FromStr for DefinitionRef {
+    type Err = anyhow::Error;
+
+    fn from_str(reference: &str) -> Result<Self> {
+        let (name, version) = reference
+            .rsplit_once('@')
+            .ok_or_else(|| anyhow::anyhow!("Registry reference {:?} is not name@version", reference))?;
+        // Both end up in URLs and in the pin cache's paths
+        let valid_segment = |segment: &str| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+'))
+        };
+        if !name.split('/').all(valid_segment) || !valid_segment(version) {
+            anyhow::bail!("Invalid registry reference {:?}", reference);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            version: version.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for DefinitionRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+// Weighted routing between two published versions of a task: `canary_percent` of
+// the submissions by bare name get the canary version, the rest the stable one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rollout {
+    pub stable: String,
+    pub canary: String,
+    pub canary_percent: f64,
+}
+
+impl Rollout {
+    pub fn new(stable: &str, canary: &str, canary_percent: f64) -> Result<Self> {
+        let rollout = Self {
+            stable: stable.to_string(),
+            canary: canary.to_string(),
+            canary_percent,
+        };
+        rollout.validate()?;
+        Ok(rollout)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.canary_percent) {
+            anyhow::bail!("Canary percentage {} is not between 0 and 100", self.canary_percent);
+        }
+        Ok(())
+    }
+
+    // The version one submission gets; `roll` is uniform in [0, 1)
+    pub fn pick(&self, roll: f64) -> &str {
+        if roll * 100.0 < self.canary_percent {
+            &self.canary
+        } else {
+            &self.stable
+        }
+    }
+}
+
+// Task definitions published under `name@version`, served over HTTP as task files.
+// The first definition a reference resolves to is pinned on disk, so a version the
+// submitter reviewed keeps meaning the same definition even if the registry changes.
+// Tasks being rolled out are submitted by bare name and routed by their rollout.
+// The worker's built-ins are always published, as `builtin/<name>@1`.
+pub struct TaskRegistry {
+    url_template: String,
+    pins_dir: PathBuf,
+    downloader: Arc<Downloader>,
+    rollouts: Mutex<HashMap<String, Rollout>>,
+    verbosity: Verbosity,
+}
+
+impl TaskRegistry {
+    // `url_template` has `{name}` and `{version}` placeholders, e.g.
+    // `https://tasks.example.com/{name}/{version}.yaml`; files are read as JSON when
+    // the URL ends in `.json`, as YAML otherwise
+    pub fn new(url_template: &str) -> Self {
+        Self {
+            url_template: url_template.to_string(),
+            pins_dir: CacheSettings::default().dir.join("registry"),
+            downloader: Downloader::shared(),
+            rollouts: Mutex::new(HashMap::new()),
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    pub fn with_pins_dir(mut self, pins_dir: PathBuf) -> Self {
+        self.pins_dir = pins_dir;
+        self
+    }
+
+    pub fn with_downloader(mut self, downloader: Arc<Downloader>) -> Self {
+        self.downloader = downloader;
+        self
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn with_rollout(self, name: &str, rollout: Rollout) -> Result<Self> {
+        self.set_rollout(name, rollout)?;
+        Ok(self)
+    }
+
+    // Starts or reweights the rollout of `name`; takes effect with the next submission,
+    // so the canary share can be raised step by step while its success rate holds up
+    pub fn set_rollout(&self, name: &str, rollout: Rollout) -> Result<()> {
+        rollout.validate()?;
+        // Both versions must make valid references
+        for version in [&rollout.stable, &rollout.canary] {
+            format!("{}@{}", name, version).parse::<DefinitionRef>()?;
+        }
+        detail!(self.verbosity, "🐤 Rolling out {}@{} to {}% over {}", name, rollout.canary, rollout.canary_percent, rollout.stable);
+        self.rollouts.lock().unwrap().insert(name.to_string(), rollout);
+        Ok(())
+    }
+
+    // Ends the rollout of `name`; bare-name submissions fail until a new one starts
+    pub fn end_rollout(&self, name: &str) -> Option<Rollout> {
+        self.rollouts.lock().unwrap().remove(name)
+    }
+
+    pub fn rollout(&self, name: &str) -> Option<Rollout> {
+        self.rollouts.lock().unwrap().get(name).cloned()
+    }
+
+    // `name@version` as given, or a bare `name` routed to a version by its rollout;
+    // `roll` is uniform in [0, 1)
+    pub fn route(&self, reference: &str, roll: f64) -> Result<DefinitionRef> {
+        if reference.contains('@') {
+            return reference.parse();
+        }
+        let rollout = self
+            .rollout(reference)
+            .ok_or_else(|| anyhow::anyhow!("Registry reference {:?} has no version and no rollout", reference))?;
+        format!("{}@{}", reference, rollout.pick(roll)).parse()
+    }
+
+    pub fn url(&self, reference: &DefinitionRef) -> String {
+        self.url_template
+            .replace("{name}", &reference.name)
+            .replace("{version}", &reference.version)
+    }
+
+    fn pin_path(&self, reference: &DefinitionRef) -> PathBuf {
+        self.pins_dir.join(&reference.name).join(format!("{}.json", reference.version))
+    }
+
+    // The pinned definition of `reference`, fetched and pinned on first use
+    pub async fn resolve(&self, reference: &DefinitionRef) -> Result<(TaskDefinition, DefinitionPin)> {
+        let path = self.pin_path(reference);
+        let builtin = reference.name.strip_prefix(BUILTIN_NAMESPACE).and_then(|name| name.strip_prefix('/'));
+        let task_definition = match (builtin, fs::read_to_string(&path)) {
+            // Nothing to fetch, and nothing that could change under a pin
+            (Some(name), _) if reference.version == BUILTIN_VERSION => {
+                builtin_task(name).ok_or_else(|| anyhow::anyhow!("{} is not a built-in task", reference))?
+            }
+            (Some(_), _) => anyhow::bail!("Built-in tasks are only published as version {}", BUILTIN_VERSION),
+            (None, Ok(content)) => serde_json::from_str(&content)
+                .with_context(|| format!("Malformed pin of {} at {}", reference, path.display()))?,
+            (None, Err(_)) => {
+                let url = self.url(reference);
+                let download = self.downloader.fetch(&url).await?;
+                let content = String::from_utf8(download.body).with_context(|| format!("{} is not UTF-8", url))?;
+                let task_definition = TaskDefinition::from_task_file(&content, url.ends_with(".json"))
+                    .with_context(|| format!("Malformed task file for {} at {}", reference, url))?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&path, serde_json::to_string_pretty(&task_definition)?)
+                    .with_context(|| format!("Failed to pin {} at {}", reference, path.display()))?;
+                detail!(self.verbosity, "📌 Pinned {} to {}", reference, &task_definition.sha256()[..12]);
+                task_definition
+            }
+        };
+        let pin = DefinitionPin {
+            reference: reference.to_string(),
+            sha256: task_definition.sha256(),
+        };
+        Ok((task_definition, pin))
+    }
+
+    // Drops the pin, so the next resolve takes whatever the registry serves then
+    pub fn forget(&self, reference: &DefinitionRef) -> Result<()> {
+        match fs::remove_file(self.pin_path(reference)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
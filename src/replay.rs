@@ -0,0 +1,268 @@
+use futures::stream::{self, StreamExt};
+use serde::Serialize;
+use std::time::Duration;
+use crate::client::TaskSubmitter;
+use crate::dynamic_executor::DynamicTaskExecutor;
+use crate::schema::{Provenance, Result as TaskResult, TaskDefinition, TaskSource, TaskStatus};
+use crate::task_archive::ArchivedTask;
+
+// How archived tasks are re-run; `ignore_outputs` names outputs that legitimately
+// differ between runs (timestamps, hostnames, ...)
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    pub concurrency: usize,
+    pub timeout: Duration,
+    pub ignore_outputs: Vec<String>,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            timeout: Duration::from_secs(300),
+            ignore_outputs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum OutputChange {
+    Missing { name: String, archived: serde_json::Value },
+    Added { name: String, replayed: serde_json::Value },
+    Changed { name: String, archived: serde_json::Value, replayed: serde_json::Value },
+}
+
+// How a replayed result differs from the archived one
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct ResultDiff {
+    pub status: Option<(TaskStatus, TaskStatus)>,
+    pub error: Option<(Option<String>, Option<String>)>,
+    pub outputs: Vec<OutputChange>,
+}
+
+impl ResultDiff {
+    pub fn between(archived: &TaskResult, replayed: &TaskResult, ignore_outputs: &[String]) -> Self {
+        Self::between_within(archived, replayed, ignore_outputs, None)
+    }
+
+    // Like `between`, but with a tolerance numbers anywhere in the outputs are compared
+    // by value and match when no further apart than it, so 3 and 3.0 are the same
+    pub fn between_within(archived: &TaskResult, replayed: &TaskResult, ignore_outputs: &[String], tolerance: Option<f64>) -> Self {
+        let mut diff = ResultDiff::default();
+        if archived.status != replayed.status {
+            diff.status = Some((archived.status.clone(), replayed.status.clone()));
+        }
+        if archived.error != replayed.error {
+            diff.error = Some((archived.error.clone(), replayed.error.clone()));
+        }
+
+        let mut names: Vec<&String> = archived.outputs.keys().chain(replayed.outputs.keys()).collect();
+        names.sort();
+        names.dedup();
+        for name in names.into_iter().filter(|name| !ignore_outputs.contains(name)) {
+            let change = match (archived.outputs.get(name), replayed.outputs.get(name)) {
+                (Some(archived), Some(replayed)) if values_match(archived, replayed, tolerance) => continue,
+                (Some(archived), Some(replayed)) => OutputChange::Changed {
+                    name: name.clone(),
+                    archived: archived.clone(),
+                    replayed: replayed.clone(),
+                },
+                (Some(archived), None) => OutputChange::Missing { name: name.clone(), archived: archived.clone() },
+                (None, Some(replayed)) => OutputChange::Added { name: name.clone(), replayed: replayed.clone() },
+                (None, None) => continue,
+            };
+            diff.outputs.push(change);
+        }
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.status.is_none() && self.error.is_none() && self.outputs.is_empty()
+    }
+}
+
+fn values_match(a: &serde_json::Value, b: &serde_json::Value, tolerance: Option<f64>) -> bool {
+    use serde_json::Value;
+    let Some(tolerance) = tolerance else { return a == b };
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => (a - b).abs() <= tolerance,
+            _ => a == b,
+        },
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_match(a, b, Some(tolerance))),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| values_match(a, b, Some(tolerance))))
+        }
+        _ => a == b,
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayOutcome {
+    Matched,
+    Differs(ResultDiff),
+    // The replay itself failed, e.g. no worker answered in time
+    Failed(String),
+    // Nothing to replay or compare against: no definition or no archived result
+    Skipped(&'static str),
+}
+
+#[derive(Debug)]
+pub struct ReplayedTask {
+    pub archived_task_id: String,
+    pub replay_task_id: Option<String>,
+    pub name: String,
+    pub outcome: ReplayOutcome,
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayReport {
+    pub tasks: Vec<ReplayedTask>,
+}
+
+impl ReplayReport {
+    fn count(&self, matches: impl Fn(&ReplayOutcome) -> bool) -> usize {
+        self.tasks.iter().filter(|task| matches(&task.outcome)).count()
+    }
+
+    pub fn matched(&self) -> usize {
+        self.count(|outcome| matches!(outcome, ReplayOutcome::Matched))
+    }
+
+    pub fn differing(&self) -> usize {
+        self.count(|outcome| matches!(outcome, ReplayOutcome::Differs(_)))
+    }
+
+    pub fn failed(&self) -> usize {
+        self.count(|outcome| matches!(outcome, ReplayOutcome::Failed(_)))
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.count(|outcome| matches!(outcome, ReplayOutcome::Skipped(_)))
+    }
+
+    // True when every replayed task reproduced its archived result
+    pub fn is_clean(&self) -> bool {
+        self.differing() == 0 && self.failed() == 0
+    }
+}
+
+// Re-submits archived tasks through `submitter` (a TaskClient on any queue or fleet,
+// or a LocalRuntime) and compares each new result with the archived one
+pub async fn replay<S: TaskSubmitter + Sync>(submitter: &S, archived: &[ArchivedTask], options: &ReplayOptions) -> ReplayReport {
+    let tasks = stream::iter(archived)
+        .map(|task| replay_one(submitter, task, options))
+        .buffer_unordered(options.concurrency.max(1))
+        .collect()
+        .await;
+    ReplayReport { tasks }
+}
+
+async fn replay_one<S: TaskSubmitter>(submitter: &S, task: &ArchivedTask, options: &ReplayOptions) -> ReplayedTask {
+    let replayed = |replay_task_id, outcome| ReplayedTask {
+        archived_task_id: task.task_id.clone(),
+        replay_task_id,
+        name: task.name().to_string(),
+        outcome,
+    };
+    let Some(definition) = &task.task_definition else {
+        return replayed(None, ReplayOutcome::Skipped("no task definition archived"));
+    };
+    let Some(archived_result) = &task.result else {
+        return replayed(None, ReplayOutcome::Skipped("no result archived"));
+    };
+
+    let handle = match submitter.submit(definition.clone(), task.inputs.clone()).await {
+        Ok(handle) => handle,
+        Err(e) => return replayed(None, ReplayOutcome::Failed(format!("{:#}", e))),
+    };
+    let replay_task_id = Some(handle.task_id().to_string());
+    let outcome = match handle.await_result_timeout(options.timeout).await {
+        Ok(result) => {
+            let diff = ResultDiff::between(archived_result, &result, &options.ignore_outputs);
+            if diff.is_empty() {
+                ReplayOutcome::Matched
+            } else {
+                ReplayOutcome::Differs(diff)
+            }
+        }
+        Err(e) => ReplayOutcome::Failed(format!("{:#}", e)),
+    };
+    replayed(replay_task_id, outcome)
+}
+
+// An archived task re-run on this machine, e.g. to reproduce a production failure
+#[derive(Debug)]
+pub struct LocalReplay {
+    pub task_id: String,
+    // The archived definition with its source pinned to what the worker resolved
+    pub task_definition: TaskDefinition,
+    pub result: TaskResult,
+    // Against the archived result; None if none was archived
+    pub diff: Option<ResultDiff>,
+    // Hashes of the code the worker ran and of the code run here, when they differ:
+    // the source changed since, so the replay may not reproduce anything
+    pub source_drift: Option<(String, String)>,
+}
+
+impl LocalReplay {
+    // True when the archived result came out again from the same code
+    pub fn reproduced(&self) -> bool {
+        self.source_drift.is_none() && self.diff.as_ref().is_some_and(ResultDiff::is_empty)
+    }
+}
+
+// `task_definition` with its source narrowed down to what `provenance` says the
+// worker actually ran: git sources at the commit it checked out, gists at the
+// revision it fetched and URLs at where their redirects led
+pub fn pinned_source(task_definition: &TaskDefinition, provenance: Option<&Provenance>) -> TaskDefinition {
+    let mut pinned = task_definition.clone();
+    let Some(provenance) = provenance else { return pinned };
+    match &mut pinned.source {
+        TaskSource::Git { rev, .. } => {
+            if let Some(commit) = &provenance.git_commit {
+                *rev = Some(commit.clone());
+            }
+        }
+        // Raw gist URLs name the revision
+        TaskSource::Gist { .. } | TaskSource::Url { .. } => {
+            if let Some(url) = &provenance.source_url {
+                pinned.source = TaskSource::Url { url: url.clone() };
+            }
+        }
+        _ => {}
+    }
+    pinned
+}
+
+// Re-runs an archived task through `executor` with its archived inputs and pinned
+// source, and compares the outcome with the archived result
+pub async fn replay_locally(executor: &DynamicTaskExecutor, task: &ArchivedTask, ignore_outputs: &[String]) -> anyhow::Result<LocalReplay> {
+    let task_definition = task
+        .task_definition
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("Task {} was archived without its definition", task.task_id))?;
+    let archived_result = task.result.as_ref();
+    let task_definition = pinned_source(task_definition, archived_result.and_then(|result| result.provenance.as_ref()));
+    let worker_id = "local-replay".to_string();
+    let mut result = match executor.execute_task(&task_definition, task.inputs.clone()).await {
+        Ok(result) => result,
+        Err(e) => TaskResult::failure(task.task_id.clone(), worker_id.clone(), e.to_string()),
+    };
+    result.task_id = task.task_id.clone();
+    result.worker_id = worker_id;
+
+    let code_sha256 = |result: &TaskResult| result.provenance.as_ref().and_then(|provenance| provenance.code_sha256.clone());
+    let source_drift = match (archived_result.and_then(code_sha256), code_sha256(&result)) {
+        (Some(archived), Some(local)) if !archived.eq_ignore_ascii_case(&local) => Some((archived, local)),
+        _ => None,
+    };
+    Ok(LocalReplay {
+        task_id: task.task_id.clone(),
+        diff: archived_result.map(|archived| ResultDiff::between(archived, &result, ignore_outputs)),
+        task_definition,
+        result,
+        source_drift,
+    })
+}
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use tokio_util::sync::CancellationToken;
+use zenoh::Session;
+use crate::batching::unbatch_messages;
+use crate::keyspace::Keyspace;
+use crate::schema::{PerceptionResult, Result as TaskResult, TaskStatus};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::deserialize_from_sample_with_context;
+use crate::{detail, summary};
+
+// Prints every result published on a queue's namespace
+pub struct ResultListener {
+    keyspace: Keyspace,
+    verbosity: Verbosity,
+}
+
+impl ResultListener {
+    pub fn new(keyspace: Keyspace) -> Self {
+        Self {
+            keyspace,
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    // Summary prints each result's outcome, outputs and error; debug adds detections,
+    // artifacts, resource usage and provenance
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub async fn run(&self, session: &Session, shutdown: CancellationToken) -> Result<()> {
+        let results = session
+            .declare_subscriber(self.keyspace.all_results())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        let batches = session
+            .declare_subscriber(self.keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+        summary!(self.verbosity, "👂 Result listener started");
+
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                sample = results.recv_async() => match sample {
+                    Ok(sample) => vec![deserialize_from_sample_with_context::<TaskResult>(&sample, "result")],
+                    Err(e) => {
+                        summary!(self.verbosity, "❌ Result subscriber closed: {}", e);
+                        break;
+                    }
+                },
+                sample = batches.recv_async() => match sample {
+                    Ok(sample) => unbatch_messages::<TaskResult>(&sample, &self.keyspace.all_results(), "result"),
+                    Err(e) => {
+                        summary!(self.verbosity, "❌ Batch subscriber closed: {}", e);
+                        break;
+                    }
+                },
+            };
+            for result in received {
+                match result {
+                    Ok(result) => print_result(&result, self.verbosity),
+                    Err(e) => summary!(self.verbosity, "⚠️  Ignoring malformed result: {}", e),
+                }
+            }
+        }
+
+        summary!(self.verbosity, "👂 Result listener stopped");
+        Ok(())
+    }
+}
+
+fn print_result(result: &TaskResult, verbosity: Verbosity) {
+    if !verbosity.shows_summary() {
+        return;
+    }
+    let outcome = match result.status {
+        TaskStatus::Completed => "✅ SUCCESS",
+        _ => "❌ FAILED",
+    };
+    summary!(verbosity, "📊 RESULT: {} from {} - {}", result.task_id, result.worker_id, outcome);
+    for (name, value) in &result.outputs {
+        summary!(verbosity, "   {}: {}", name, value);
+    }
+    match PerceptionResult::from_result(result) {
+        Some(Ok(perception)) => {
+            for detection in &perception.objects {
+                let (x, y) = detection.bbox.center();
+                detail!(
+                    verbosity,
+                    "   detected {} ({:.2}) at ({:.0}, {:.0}){}",
+                    detection.label,
+                    detection.score,
+                    x,
+                    y,
+                    detection.depth_m.map(|depth| format!(", {:.2} m away", depth)).unwrap_or_default()
+                );
+            }
+        }
+        Some(Err(e)) => detail!(verbosity, "   ⚠️  Malformed {}: {}", PerceptionResult::ARTIFACT, e),
+        None => {}
+    }
+    for (name, content) in &result.artifacts {
+        if name == PerceptionResult::ARTIFACT {
+            continue;
+        }
+        // Structured artifacts are shown inline; logs are too long for the console
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(content) {
+            detail!(verbosity, "   {}: {}", name, value);
+        }
+    }
+    if let Some(usage) = &result.metadata.resources {
+        detail!(
+            verbosity,
+            "   resources: {:.2}s CPU, {:.1} MiB peak RSS, {} B read, {} B written",
+            usage.cpu_time_seconds,
+            usage.peak_rss_bytes as f64 / (1024.0 * 1024.0),
+            usage.read_bytes,
+            usage.write_bytes
+        );
+    }
+    if let Some(provenance) = result.provenance.as_ref().filter(|provenance| !provenance.source_kind.is_empty()) {
+        let revision = provenance.git_commit.as_deref()
+            .or(provenance.gist_revision.as_deref())
+            .or(provenance.image_digest.as_deref());
+        detail!(
+            verbosity,
+            "   provenance: {} source{}{} on {}",
+            provenance.source_kind,
+            revision.map(|revision| format!(" @ {}", revision)).unwrap_or_default(),
+            provenance.code_sha256.as_deref().map(|hash| format!(", sha256 {}", &hash[..12.min(hash.len())])).unwrap_or_default(),
+            provenance.worker_id.as_deref().unwrap_or(&result.worker_id)
+        );
+    }
+    if let Some(error) = &result.error {
+        summary!(verbosity, "   error: {}", error);
+    }
+}
@@ -0,0 +1,438 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Wrappers that run user scripts under the handler protocol: the script defines (or
+// exports) `handler(inputs)` returning an object of outputs, and the runner calls it
+// and writes `{"outputs": {...}}` or `{"error": {...}}` to the result file. Scripts
+// without a handler still run the old way, reading the injected `inputs` (or the
+// inputs path in argv) and printing a JSON object, in which case no result file is
+// written.
+//
+// argv: <runner> <script> <inputs.json> <result.json>
+//
+// Deterministic runs set COREBRUM_SEED and SOURCE_DATE_EPOCH; the runners then seed
+// the language's random generators and, where the language allows it, freeze the
+// clock at SOURCE_DATE_EPOCH before loading the script.
+
+pub const RUNNER_RESULT_FILE: &str = "corebrum-result.json";
+
+pub const PYTHON_RUNNER_FILE: &str = "corebrum_runner.py";
+pub const PYTHON_RUNNER: &str = r#"import json
+import os
+import runpy
+import sys
+import traceback
+
+def fail(result_path, kind, message, details=None):
+    with open(result_path, "w") as f:
+        json.dump({"error": {"kind": kind, "message": message, "details": details}}, f)
+    sys.exit(1)
+
+def freeze(seed, clock):
+    import datetime, random, time
+    random.seed(seed)
+    try:
+        import numpy
+        numpy.random.seed(seed % 2**32)
+    except Exception:
+        pass
+    time.time = lambda: float(clock)
+    time.time_ns = lambda: clock * 10**9
+
+    class FrozenDateTime(datetime.datetime):
+        @classmethod
+        def now(cls, tz=None):
+            return cls.fromtimestamp(clock, tz)
+
+        @classmethod
+        def utcnow(cls):
+            return cls.fromtimestamp(clock, datetime.timezone.utc).replace(tzinfo=None)
+
+        @classmethod
+        def today(cls):
+            return cls.fromtimestamp(clock)
+
+    class FrozenDate(datetime.date):
+        @classmethod
+        def today(cls):
+            return cls.fromtimestamp(clock)
+
+    datetime.datetime = FrozenDateTime
+    datetime.date = FrozenDate
+
+def main():
+    script_path, inputs_path, result_path = sys.argv[1:4]
+    with open(inputs_path) as f:
+        inputs = json.load(f)
+    if "COREBRUM_SEED" in os.environ:
+        freeze(int(os.environ["COREBRUM_SEED"]), int(os.environ.get("SOURCE_DATE_EPOCH", "0")))
+
+    # Look like a directly executed script to the user code
+    sys.argv = [script_path, inputs_path]
+    sys.path.insert(0, os.path.dirname(os.path.abspath(script_path)))
+    try:
+        namespace = runpy.run_path(script_path, init_globals={"inputs": inputs}, run_name="__main__")
+    except SystemExit:
+        raise
+    except BaseException as e:
+        fail(result_path, "load", "loading the script raised %s: %s" % (type(e).__name__, e), traceback.format_exc())
+
+    handler = namespace.get("handler")
+    if handler is None:
+        return
+    if not callable(handler):
+        fail(result_path, "contract", "`handler` must be a function, got %s" % type(handler).__name__)
+    try:
+        outputs = handler(inputs)
+    except Exception as e:
+        fail(result_path, "handler", "handler raised %s: %s" % (type(e).__name__, e), traceback.format_exc())
+    if not isinstance(outputs, dict):
+        fail(result_path, "contract", "handler must return a dict, got %s" % type(outputs).__name__)
+    try:
+        text = json.dumps({"outputs": outputs})
+    except (TypeError, ValueError) as e:
+        fail(result_path, "contract", "handler returned outputs that are not JSON serializable: %s" % e)
+    with open(result_path, "w") as f:
+        f.write(text)
+
+main()
+"#;
+
+// CommonJS, so a `"type": "module"` package.json next to the task doesn't apply to it
+pub const JS_RUNNER_FILE: &str = "corebrum_runner.cjs";
+pub const JS_RUNNER: &str = r#"'use strict';
+const fs = require('fs');
+const path = require('path');
+const { pathToFileURL } = require('url');
+
+const [scriptPath, inputsPath, resultPath] = process.argv.slice(2);
+
+function fail(kind, message, details) {
+  fs.writeFileSync(resultPath, JSON.stringify({ error: { kind, message, details: details || null } }));
+  process.exit(1);
+}
+
+function describe(error) {
+  return error instanceof Error ? `${error.name}: ${error.message}` : String(error);
+}
+
+// mulberry32 in place of Math.random, and a Date that defaults to the frozen clock
+function freeze(seed, clock) {
+  let state = Number(BigInt.asUintN(32, BigInt(seed)));
+  Math.random = () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+  const RealDate = Date;
+  class FrozenDate extends RealDate {
+    constructor(...args) {
+      super(...(args.length === 0 ? [clock] : args));
+    }
+
+    static now() {
+      return clock;
+    }
+  }
+  globalThis.Date = FrozenDate;
+}
+
+// require() for CommonJS, import() for ES modules
+async function load(file) {
+  const absolute = path.resolve(file);
+  if (absolute.endsWith('.mjs')) {
+    return import(pathToFileURL(absolute).href);
+  }
+  try {
+    return require(absolute);
+  } catch (error) {
+    if (error && error.code === 'ERR_REQUIRE_ESM') {
+      return import(pathToFileURL(absolute).href);
+    }
+    throw error;
+  }
+}
+
+async function main() {
+  const inputs = JSON.parse(fs.readFileSync(inputsPath, 'utf8'));
+  if (process.env.COREBRUM_SEED !== undefined) {
+    freeze(process.env.COREBRUM_SEED, Number(process.env.SOURCE_DATE_EPOCH || 0) * 1000);
+  }
+
+  // Look like a directly executed script to the user code
+  globalThis.inputs = inputs;
+  process.argv = [process.argv[0], scriptPath, inputsPath];
+  let exported;
+  try {
+    exported = await load(scriptPath);
+  } catch (error) {
+    fail('load', `loading the script threw ${describe(error)}`, error && error.stack);
+  }
+
+  const handler = exported && (exported.handler || (exported.default && exported.default.handler));
+  if (handler === undefined) {
+    return;
+  }
+  if (typeof handler !== 'function') {
+    fail('contract', `handler must be a function, got ${typeof handler}`);
+  }
+  let outputs;
+  try {
+    outputs = await handler(inputs);
+  } catch (error) {
+    fail('handler', `handler threw ${describe(error)}`, error && error.stack);
+  }
+  if (outputs === null || typeof outputs !== 'object' || Array.isArray(outputs)) {
+    fail('contract', `handler must return an object, got ${Array.isArray(outputs) ? 'array' : outputs === null ? 'null' : typeof outputs}`);
+  }
+  let text;
+  try {
+    text = JSON.stringify({ outputs });
+  } catch (error) {
+    fail('contract', `handler returned outputs that are not JSON serializable: ${describe(error)}`);
+  }
+  fs.writeFileSync(resultPath, text);
+  // Timers or sockets the handler left open would otherwise keep the task running
+  process.exit(0);
+}
+
+main().catch((error) => fail('load', describe(error), error && error.stack));
+"#;
+
+// ES module for Deno, which only gets read access to the workspace and write access
+// to the result file unless the task declares more
+pub const DENO_RUNNER_FILE: &str = "corebrum_runner.mjs";
+pub const DENO_RUNNER: &str = r#"const [scriptPath, inputsPath, resultPath] = Deno.args;
+
+function fail(kind, message, details) {
+  Deno.writeTextFileSync(resultPath, JSON.stringify({ error: { kind, message, details: details || null } }));
+  Deno.exit(1);
+}
+
+function describe(error) {
+  return error instanceof Error ? `${error.name}: ${error.message}` : String(error);
+}
+
+// Undefined unless the task was granted access to the variable
+function env(name) {
+  try {
+    return Deno.env.get(name);
+  } catch {
+    return undefined;
+  }
+}
+
+// mulberry32 in place of Math.random, and a Date that defaults to the frozen clock
+function freeze(seed, clock) {
+  let state = Number(BigInt.asUintN(32, BigInt(seed)));
+  Math.random = () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+  const RealDate = Date;
+  class FrozenDate extends RealDate {
+    constructor(...args) {
+      super(...(args.length === 0 ? [clock] : args));
+    }
+
+    static now() {
+      return clock;
+    }
+  }
+  globalThis.Date = FrozenDate;
+}
+
+async function main() {
+  const inputs = JSON.parse(Deno.readTextFileSync(inputsPath));
+  const seed = env('COREBRUM_SEED');
+  if (seed !== undefined) {
+    freeze(seed, Number(env('SOURCE_DATE_EPOCH') || 0) * 1000);
+  }
+  globalThis.inputs = inputs;
+  let exported;
+  try {
+    exported = await import('file://' + scriptPath.split('/').map(encodeURIComponent).join('/'));
+  } catch (error) {
+    fail('load', `loading the script threw ${describe(error)}`, error && error.stack);
+  }
+
+  const handler = exported.handler || (exported.default && exported.default.handler);
+  if (handler === undefined) {
+    return;
+  }
+  if (typeof handler !== 'function') {
+    fail('contract', `handler must be a function, got ${typeof handler}`);
+  }
+  let outputs;
+  try {
+    outputs = await handler(inputs);
+  } catch (error) {
+    fail('handler', `handler threw ${describe(error)}`, error && error.stack);
+  }
+  if (outputs === null || typeof outputs !== 'object' || Array.isArray(outputs)) {
+    fail('contract', `handler must return an object, got ${Array.isArray(outputs) ? 'array' : outputs === null ? 'null' : typeof outputs}`);
+  }
+  let text;
+  try {
+    text = JSON.stringify({ outputs });
+  } catch (error) {
+    fail('contract', `handler returned outputs that are not JSON serializable: ${describe(error)}`);
+  }
+  Deno.writeTextFileSync(resultPath, text);
+  Deno.exit(0);
+}
+
+main().catch((error) => fail('load', describe(error), error && error.stack));
+"#;
+
+// Needs the jsonlite package. Inputs are simplified the jsonlite way (arrays of
+// scalars become vectors, arrays of objects data frames), and `handler` must return
+// a named list.
+pub const R_RUNNER_FILE: &str = "corebrum_runner.R";
+pub const R_RUNNER: &str = r#"args <- commandArgs(trailingOnly = TRUE)
+script_path <- args[1]
+inputs_path <- args[2]
+result_path <- args[3]
+
+# Usable even when jsonlite is what's missing
+json_string <- function(text) {
+  if (requireNamespace("jsonlite", quietly = TRUE)) {
+    return(as.character(jsonlite::toJSON(text, auto_unbox = TRUE)))
+  }
+  encodeString(gsub("[[:cntrl:]]", " ", text), quote = "\"")
+}
+
+fail <- function(kind, message, details = NULL) {
+  details <- if (is.null(details)) "null" else json_string(details)
+  writeLines(sprintf("{\"error\": {\"kind\": \"%s\", \"message\": %s, \"details\": %s}}", kind, json_string(message), details), result_path)
+  quit(save = "no", status = 1)
+}
+
+if (!requireNamespace("jsonlite", quietly = TRUE)) {
+  fail("load", "the R runner needs the jsonlite package")
+}
+inputs <- jsonlite::fromJSON(inputs_path)
+seed <- Sys.getenv("COREBRUM_SEED")
+if (nzchar(seed)) {
+  set.seed(as.numeric(seed) %% .Machine$integer.max)
+}
+
+task <- new.env(parent = globalenv())
+assign("inputs", inputs, envir = task)
+tryCatch(
+  sys.source(script_path, envir = task, chdir = TRUE),
+  error = function(e) fail("load", paste("loading the script failed:", conditionMessage(e)), paste(deparse(conditionCall(e)), collapse = "\n"))
+)
+
+if (!exists("handler", envir = task, inherits = FALSE)) {
+  quit(save = "no", status = 0)
+}
+handler <- get("handler", envir = task)
+if (!is.function(handler)) {
+  fail("contract", paste("handler must be a function, got", class(handler)[1]))
+}
+outputs <- tryCatch(
+  handler(inputs),
+  error = function(e) fail("handler", paste("handler failed:", conditionMessage(e)), paste(deparse(conditionCall(e)), collapse = "\n"))
+)
+if (!is.list(outputs) || is.data.frame(outputs) || (length(outputs) > 0 && (is.null(names(outputs)) || any(names(outputs) == "")))) {
+  fail("contract", paste("handler must return a named list, got", class(outputs)[1]))
+}
+if (length(outputs) == 0) {
+  writeLines("{\"outputs\": {}}", result_path)
+} else {
+  text <- tryCatch(
+    jsonlite::toJSON(list(outputs = outputs), auto_unbox = TRUE, null = "null", na = "null", digits = NA),
+    error = function(e) fail("contract", paste("handler returned outputs that can't be serialized to JSON:", conditionMessage(e)))
+  )
+  writeLines(text, result_path)
+}
+"#;
+
+// Needs the JSON package. The script is included into a fresh module with `inputs`
+// (a Dict parsed from inputs.json) defined, and `handler` must return a Dict or a
+// NamedTuple.
+pub const JULIA_RUNNER_FILE: &str = "corebrum_runner.jl";
+pub const JULIA_RUNNER: &str = r#"script_path, inputs_path, result_path = ARGS[1:3]
+
+# Usable even when JSON is what's missing
+function json_string(text)
+    escaped = replace(text, "\\" => "\\\\", "\"" => "\\\"", "\n" => "\\n", "\t" => "\\t")
+    return "\"" * replace(escaped, r"[\x00-\x1f]" => " ") * "\""
+end
+
+function fail(kind, message, details = nothing)
+    open(result_path, "w") do io
+        print(io, "{\"error\": {\"kind\": \"", kind, "\", \"message\": ", json_string(message),
+              ", \"details\": ", details === nothing ? "null" : json_string(details), "}}")
+    end
+    exit(1)
+end
+
+import Random
+
+try
+    import JSON
+catch
+    fail("load", "the Julia runner needs the JSON package")
+end
+
+inputs = JSON.parsefile(inputs_path)
+if haskey(ENV, "COREBRUM_SEED")
+    Random.seed!(parse(UInt64, ENV["COREBRUM_SEED"]))
+end
+
+task = Module(:CorebrumTask)
+Core.eval(task, :(const inputs = $inputs))
+Core.eval(task, :(include(path::AbstractString) = Base.include($task, path)))
+try
+    cd(dirname(abspath(script_path))) do
+        Base.include(task, abspath(script_path))
+    end
+catch e
+    fail("load", "loading the script raised " * sprint(showerror, e), sprint(Base.show_backtrace, catch_backtrace()))
+end
+
+if !isdefined(task, :handler)
+    exit(0)
+end
+handler = getfield(task, :handler)
+if !(handler isa Function)
+    fail("contract", "handler must be a function, got $(typeof(handler))")
+end
+outputs = try
+    cd(() -> Base.invokelatest(handler, inputs), dirname(abspath(script_path)))
+catch e
+    fail("handler", "handler raised " * sprint(showerror, e), sprint(Base.show_backtrace, catch_backtrace()))
+end
+if !(outputs isa AbstractDict || outputs isa NamedTuple)
+    fail("contract", "handler must return a Dict or NamedTuple, got $(typeof(outputs))")
+end
+text = try
+    JSON.json(Dict("outputs" => outputs))
+catch e
+    fail("contract", "handler returned outputs that can't be serialized to JSON: " * sprint(showerror, e))
+end
+write(result_path, text)
+"#;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunnerResult {
+    Outputs(HashMap<String, serde_json::Value>),
+    Error(RunnerError),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunnerError {
+    // load, handler or contract
+    pub kind: String,
+    pub message: String,
+    // Traceback / stack of the failure, if any
+    pub details: Option<String>,
+}
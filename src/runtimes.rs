@@ -0,0 +1,226 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::process::Command;
+use crate::config::WorkerConfig;
+use crate::schema::{Isolation, TaskDefinition};
+
+// A runtime that doesn't answer its version query within this is treated as missing
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Interpreters and execution backends found on this host when the worker started,
+// by backend name ("python", "javascript", "typescript", "deno", "r", "julia",
+// "java", "c", "cpp", "sql", "dataframe", "image", "expression", "builtin", "docker",
+// "wasm", "plugin", "onnx", "microvm") with the version each reported
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeInventory {
+    versions: BTreeMap<String, String>,
+}
+
+impl RuntimeInventory {
+    // Probes every backend enabled in `config`; backends that aren't enabled are
+    // never run, so they aren't probed either
+    pub async fn probe(config: &WorkerConfig) -> Self {
+        let enabled = |backend: &str| config.backend_enabled(backend);
+        let (python, node, esbuild, deno, r, julia, java, c, cpp, docker, microvm) = tokio::join!(
+            async { if enabled("python") { command_version("python3", &["--version"]).await } else { None } },
+            async { if enabled("javascript") || enabled("typescript") { command_version("node", &["--version"]).await } else { None } },
+            async { if enabled("typescript") { command_version("esbuild", &["--version"]).await } else { None } },
+            async { if enabled("deno") { command_version("deno", &["--version"]).await } else { None } },
+            async { if enabled("r") { command_version("Rscript", &["--version"]).await } else { None } },
+            async { if enabled("julia") { command_version("julia", &["--version"]).await } else { None } },
+            async { if enabled("java") { command_version("java", &["-version"]).await } else { None } },
+            async { if enabled("c") { compiler_version("c").await } else { None } },
+            async { if enabled("cpp") { compiler_version("cpp").await } else { None } },
+            async { if enabled("docker") { docker_version().await } else { None } },
+            async { if enabled("microvm") { microvm_version(config).await } else { None } },
+        );
+
+        let mut versions = BTreeMap::new();
+        let mut found = |backend: &str, version: Option<String>| {
+            if let Some(version) = version {
+                versions.insert(backend.to_string(), version);
+            }
+        };
+        found("python", python);
+        // TypeScript is bundled with esbuild and then run by Node
+        if enabled("typescript") && node.is_some() {
+            found("typescript", esbuild.map(|version| format!("esbuild-{}", version)));
+        }
+        if enabled("javascript") {
+            found("javascript", node);
+        }
+        found("deno", deno);
+        found("r", r);
+        found("julia", julia);
+        found("java", java);
+        found("c", c);
+        found("cpp", cpp);
+        found("docker", docker);
+        found("microvm", microvm);
+        // Compiled in, nothing to look for on the host
+        if cfg!(feature = "wasm") && enabled("wasm") {
+            found("wasm", Some("wasmtime".to_string()));
+        }
+        if cfg!(feature = "plugin") && enabled("plugin") {
+            found("plugin", Some("c-abi".to_string()));
+        }
+        if cfg!(feature = "sql") && enabled("sql") {
+            found("sql", Some("duckdb".to_string()));
+        }
+        if cfg!(feature = "dataframe") && enabled("dataframe") {
+            found("dataframe", Some("polars".to_string()));
+        }
+        if cfg!(feature = "image") && enabled("image") {
+            found("image", Some("image-rs".to_string()));
+        }
+        if enabled("builtin") {
+            found("builtin", Some(env!("CARGO_PKG_VERSION").to_string()));
+        }
+        if cfg!(feature = "expression") && enabled("expression") {
+            found("expression", Some("evalexpr".to_string()));
+        }
+        if cfg!(feature = "onnx") && enabled("onnx") {
+            found("onnx", Some("onnxruntime".to_string()));
+        }
+        Self { versions }
+    }
+
+    pub fn version(&self, backend: &str) -> Option<&str> {
+        self.versions.get(canonical_backend(backend)).map(String::as_str)
+    }
+
+    pub fn supports(&self, backend: &str) -> bool {
+        self.version(backend).is_some()
+    }
+
+    // Advertised in heartbeats as `<backend>=<version>`
+    pub fn capabilities(&self) -> Vec<String> {
+        self.versions
+            .iter()
+            .map(|(backend, version)| format!("{}={}", backend, version))
+            .collect()
+    }
+
+    // Fails with the runtime that is missing for `task_definition`. Inside a microVM
+    // the guest brings its own interpreters, so only Firecracker itself is needed.
+    pub fn check(&self, task_definition: &TaskDefinition) -> Result<()> {
+        let backend = match task_definition.isolation() {
+            Isolation::Microvm => "microvm",
+            Isolation::Process => task_definition.backend(),
+        };
+        if !self.supports(backend) {
+            anyhow::bail!("No {} runtime was found on this worker ({})", backend, missing_hint(backend));
+        }
+        Ok(())
+    }
+}
+
+// Languages accepted under more than one name
+fn canonical_backend(backend: &str) -> &str {
+    match backend {
+        "js" => "javascript",
+        "ts" => "typescript",
+        "c++" => "cpp",
+        other => other,
+    }
+}
+
+fn missing_hint(backend: &str) -> &'static str {
+    match canonical_backend(backend) {
+        "python" => "`python3 --version` failed",
+        "javascript" => "`node --version` failed",
+        "typescript" => "`esbuild --version` or `node --version` failed",
+        "deno" => "`deno --version` failed",
+        "r" => "`Rscript --version` failed",
+        "julia" => "`julia --version` failed",
+        "java" => "`java -version` failed",
+        "c" => "none of cc, gcc or clang is on the PATH",
+        "cpp" => "none of c++, g++ or clang++ is on the PATH",
+        "docker" => "the Docker daemon is not reachable",
+        "microvm" => "the Firecracker binary did not run",
+        "wasm" => "built without the wasm feature",
+        "plugin" => "built without the plugin feature",
+        "onnx" => "built without the onnx feature",
+        "sql" => "built without the sql feature",
+        "dataframe" => "built without the dataframe feature",
+        "image" => "built without the image feature",
+        "expression" => "built without the expression feature",
+        "builtin" => "\"builtin\" is not among the enabled backends",
+        _ => "unknown backend",
+    }
+}
+
+// First version number on the first line the program prints for its version flag,
+// e.g. "3.11.2" from "Python 3.11.2" or "17.0.2" from `openjdk version "17.0.2"`;
+// old interpreters (and java) print it on stderr
+async fn command_version(program: &str, args: &[&str]) -> Option<String> {
+    let line = command_version_line(program, args).await?;
+    line.split_whitespace()
+        .map(|word| word.trim_matches('"').trim_start_matches('v'))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+async fn command_version_line(program: &str, args: &[&str]) -> Option<String> {
+    let output = tokio::time::timeout(PROBE_TIMEOUT, Command::new(program).args(args).kill_on_drop(true).output())
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let printed = if output.stdout.is_empty() { output.stderr } else { output.stdout };
+    Some(String::from_utf8_lossy(&printed).lines().next()?.to_string())
+}
+
+// The first C ("c") or C++ ("cpp") compiler found on the PATH
+pub fn c_compiler(language: &str) -> Option<&'static str> {
+    let candidates: &[&'static str] = match canonical_backend(language) {
+        "c" => &["cc", "gcc", "clang"],
+        "cpp" => &["c++", "g++", "clang++"],
+        _ => return None,
+    };
+    let path = std::env::var_os("PATH")?;
+    candidates
+        .iter()
+        .copied()
+        .find(|compiler| std::env::split_paths(&path).any(|dir| dir.join(compiler).is_file()))
+}
+
+// "gcc-12.2.0" from "gcc (Debian 12.2.0-14) 12.2.0", or "clang-16.0.6"; `cc` and
+// `c++` are usually links to one of the two
+async fn compiler_version(language: &str) -> Option<String> {
+    let line = command_version_line(c_compiler(language)?, &["--version"]).await?;
+    let version = line
+        .split_whitespace()
+        .rev()
+        .find(|word| word.contains('.') && word.chars().all(|c| c.is_ascii_digit() || c == '.'))?;
+    let family = if line.contains("clang") { "clang" } else { "gcc" };
+    Some(format!("{}-{}", family, version))
+}
+
+#[cfg(feature = "docker")]
+async fn docker_version() -> Option<String> {
+    let docker = bollard::Docker::connect_with_local_defaults().ok()?;
+    let version = tokio::time::timeout(PROBE_TIMEOUT, docker.version()).await.ok()?.ok()?;
+    version.version
+}
+
+#[cfg(not(feature = "docker"))]
+async fn docker_version() -> Option<String> {
+    None
+}
+
+#[cfg(feature = "firecracker")]
+async fn microvm_version(config: &WorkerConfig) -> Option<String> {
+    if !config.microvm.is_configured() {
+        return None;
+    }
+    command_version(&config.microvm.firecracker_binary.to_string_lossy(), &["--version"]).await
+}
+
+#[cfg(not(feature = "firecracker"))]
+async fn microvm_version(_config: &WorkerConfig) -> Option<String> {
+    None
+}
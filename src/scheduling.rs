@@ -0,0 +1,229 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use crate::schema::{Claim, Job};
+
+// How long the assigner keeps collecting claims after the first one arrives
+pub const DEFAULT_CLAIM_WINDOW: Duration = Duration::from_millis(150);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SchedulingStrategy {
+    // Smallest estimated duration, scaled by the work already queued on the
+    // claimant, wins; claims without an estimate rank last
+    #[default]
+    LowestEta,
+    // Fewest running and queued tasks wins
+    LeastLoaded,
+    // Whoever claimed first wins, regardless of load
+    FirstClaim,
+    // Highest benchmarked CPU score, divided among the work already queued on the
+    // claimant, wins; claimants that weren't benchmarked rank last
+    Fastest,
+}
+
+impl SchedulingStrategy {
+    // Claims are in arrival order, so ties go to the earliest claimant
+    pub fn select<'a>(&self, claims: &'a [Claim]) -> Option<&'a Claim> {
+        match self {
+            SchedulingStrategy::LowestEta => claims.iter().min_by_key(|claim| {
+                claim
+                    .estimated_duration_seconds
+                    .map_or(u64::MAX, |eta| eta.saturating_mul(u64::from(claim.load()) + 1))
+            }),
+            SchedulingStrategy::LeastLoaded => claims
+                .iter()
+                .min_by_key(|claim| (claim.load(), claim.estimated_duration_seconds.unwrap_or(u64::MAX))),
+            SchedulingStrategy::FirstClaim => claims.first(),
+            SchedulingStrategy::Fastest => {
+                let speed = |claim: &Claim| {
+                    claim
+                        .cpu_score
+                        .map_or(f64::NEG_INFINITY, |score| score / (f64::from(claim.load()) + 1.0))
+                };
+                claims.iter().min_by(|a, b| speed(b).total_cmp(&speed(a)))
+            }
+        }
+    }
+}
+
+// Share of a queue's jobs the assigner steers to each worker fleet, e.g. 95 to
+// "stable" and 5 to "canary", for rolling out a new worker build. Tasks that name a
+// fleet in their requirements always go to it. Fleets left out get no routed jobs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FleetRouting {
+    weights: BTreeMap<String, f64>,
+}
+
+// Which claimants may win a job
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum FleetChoice {
+    #[default]
+    Any,
+    // Claimants from the fleet win over the rest; if none of them claimed within the
+    // claim window anyone may, so a shrinking fleet never strands a job
+    Preferred(String),
+    // From the task's requirements: only the fleet's claimants may win
+    Required(String),
+}
+
+impl FleetRouting {
+    pub fn new<'a>(weights: impl IntoIterator<Item = (&'a str, f64)>) -> Result<Self> {
+        let routing = Self {
+            weights: weights.into_iter().map(|(fleet, weight)| (fleet.to_string(), weight)).collect(),
+        };
+        routing.validate()?;
+        Ok(routing)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if let Some((fleet, weight)) = self.weights.iter().find(|(_, weight)| !weight.is_finite() || **weight < 0.0) {
+            anyhow::bail!("Fleet {} has an invalid weight {}", fleet, weight);
+        }
+        if !self.weights.is_empty() && self.weights.values().sum::<f64>() <= 0.0 {
+            anyhow::bail!("Fleet weights add up to zero");
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty()
+    }
+
+    // Who may win `job`; `roll` is uniform in [0, 1)
+    pub fn choose(&self, job: &Job, roll: f64) -> FleetChoice {
+        if let Some(fleet) = job.task_definition.as_ref().and_then(|td| td.fleet()) {
+            return FleetChoice::Required(fleet.to_string());
+        }
+        let total: f64 = self.weights.values().sum();
+        let mut threshold = roll * total;
+        for (fleet, weight) in &self.weights {
+            if threshold < *weight {
+                return FleetChoice::Preferred(fleet.clone());
+            }
+            threshold -= weight;
+        }
+        // Rounding left the roll past the last fleet
+        self.weights
+            .iter()
+            .rev()
+            .find(|(_, weight)| **weight > 0.0)
+            .map_or(FleetChoice::Any, |(fleet, _)| FleetChoice::Preferred(fleet.clone()))
+    }
+}
+
+impl FleetChoice {
+    // Whether a claim from `claim`'s fleet is worth keeping at all
+    pub fn admits(&self, claim: &Claim) -> bool {
+        match self {
+            FleetChoice::Required(fleet) => claim.fleet() == fleet,
+            _ => true,
+        }
+    }
+
+    // The claims a winner is picked from
+    pub fn narrow(&self, claims: &[Claim]) -> Vec<Claim> {
+        let in_fleet = |fleet: &str| claims.iter().filter(|claim| claim.fleet() == fleet).cloned().collect::<Vec<_>>();
+        match self {
+            FleetChoice::Any => claims.to_vec(),
+            FleetChoice::Required(fleet) => in_fleet(fleet),
+            FleetChoice::Preferred(fleet) => match in_fleet(fleet) {
+                preferred if preferred.is_empty() => claims.to_vec(),
+                preferred => preferred,
+            },
+        }
+    }
+}
+
+// How the workers on a queue settle who runs a job
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Coordination {
+    // An Assigner collects claims, picks the winner and re-announces jobs whose
+    // worker goes silent
+    #[default]
+    Assigner,
+    // No assigner: the submitting client hands each job to the first worker asking
+    // for it on the job's lock key. Nothing re-runs a job whose worker dies, and
+    // claims.suppress_after has no published claims to count.
+    WorkStealing,
+}
+
+// Which announcements a worker claims; by default every job it can execute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClaimPolicy {
+    // Skip jobs while every execution slot is taken or spoken for by a claim; skipped
+    // jobs are fetched from the assigner again once a slot frees up
+    pub only_when_idle: bool,
+    // Skip jobs whose `requirements.labels` aren't all among the worker's capabilities
+    pub match_labels: bool,
+    // Chance of claiming a job right away, from 0 to 1. Below 1, a burst of workers
+    // seeing the same announcement doesn't all claim at once; the rest claim after
+    // `backoff_ms`, so no job is left without claimants.
+    pub probability: f64,
+    pub backoff_ms: u64,
+    // Random extra delay of up to this long before every claim, so a fleet seeing
+    // the same announcement doesn't claim in lockstep
+    pub jitter_ms: u64,
+    // While waiting to claim, watch the job's claim key and give up once this many
+    // other workers have claimed it. Only takes effect with a delay to wait out
+    // (jitter, backoff or the worker's claim delay), and should stay above the number
+    // of claimants expected to drop out before acknowledging.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppress_after: Option<usize>,
+}
+
+// What a worker does with an announcement it could execute
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimDecision {
+    Claim,
+    // Claim after this long
+    Defer(Duration),
+    Skip(String),
+}
+
+impl Default for ClaimPolicy {
+    fn default() -> Self {
+        Self {
+            only_when_idle: false,
+            match_labels: true,
+            probability: 1.0,
+            backoff_ms: 500,
+            jitter_ms: 0,
+            suppress_after: None,
+        }
+    }
+}
+
+impl ClaimPolicy {
+    // `roll` is uniform in [0, 1)
+    pub fn decide(&self, job: &Job, capabilities: &[String], idle: bool, roll: f64) -> ClaimDecision {
+        if self.only_when_idle && !idle {
+            return ClaimDecision::Skip("no idle execution slot".to_string());
+        }
+        if self.match_labels {
+            let labels = job.task_definition.as_ref().map_or(&[][..], |td| td.labels());
+            let missing: Vec<&str> = labels
+                .iter()
+                .filter(|label| !capabilities.contains(label))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                return ClaimDecision::Skip(format!("missing label(s) {}", missing.join(", ")));
+            }
+        }
+        if roll >= self.probability {
+            return ClaimDecision::Defer(Duration::from_millis(self.backoff_ms));
+        }
+        ClaimDecision::Claim
+    }
+
+    // `roll` is uniform in [0, 1)
+    pub fn jitter(&self, roll: f64) -> Duration {
+        Duration::from_millis((self.jitter_ms as f64 * roll) as u64)
+    }
+}
@@ -1,7 +1,18 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use crate::dynamic_executor::sha256_hex;
+use crate::zenoh_utils::Codec;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Version of the message formats below, carried in every message's attachment
+pub const SCHEMA_VERSION: u32 = 1;
+
+// Timeout of tasks that neither set one nor get one from their queue's defaults
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 300;
+
+// A petabyte, far beyond any worker, and small enough that its byte count fits an i64
+pub const MAX_REQUIREMENT_MB: u64 = 1 << 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskDefinition {
     pub name: String,
     pub description: Option<String>,
@@ -10,42 +21,996 @@ pub struct TaskDefinition {
     pub inputs: Vec<TaskInput>,
     pub outputs: Vec<TaskOutput>,
     pub requirements: Option<TaskRequirements>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<InputCheck>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TaskDefinition {
+    // A task with no description, declared inputs or outputs, requirements or checks;
+    // set whichever it needs with struct update syntax
+    pub fn new(name: &str, language: &str, source: TaskSource) -> Self {
+        Self {
+            name: name.to_string(),
+            description: None,
+            language: language.to_string(),
+            source,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            requirements: None,
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn inline(name: &str, language: &str, code: &str) -> Self {
+        Self::new(name, language, TaskSource::Inline { code: code.to_string() })
+    }
+
+    // Execution backend a worker needs in order to run this task
+    pub fn isolation(&self) -> Isolation {
+        self.requirements
+            .as_ref()
+            .and_then(|requirements| requirements.isolation)
+            .unwrap_or_default()
+    }
+
+    pub fn backend(&self) -> &str {
+        match &self.source {
+            TaskSource::Wasm { .. } => "wasm",
+            TaskSource::Docker { .. } => "docker",
+            TaskSource::Plugin { .. } => "plugin",
+            TaskSource::Onnx { .. } => "onnx",
+            TaskSource::DataFrame { .. } => "dataframe",
+            TaskSource::Image { .. } => "image",
+            TaskSource::Builtin { .. } => "builtin",
+            _ if self.js_runtime() == JsRuntime::Deno => "deno",
+            _ => self.language.as_str(),
+        }
+    }
+
+    // Runs on the worker's execution pool rather than in a child process or container
+    pub fn runs_in_process(&self) -> bool {
+        matches!(self.backend(), "wasm" | "plugin" | "onnx" | "sql" | "dataframe" | "image" | "builtin")
+    }
+
+    pub fn js_runtime(&self) -> JsRuntime {
+        self.requirements
+            .as_ref()
+            .and_then(|requirements| requirements.runtime)
+            .unwrap_or_default()
+    }
+
+    // Only enforced under Deno; Node tasks run with the worker's full permissions
+    pub fn permissions(&self) -> Permissions {
+        self.requirements
+            .as_ref()
+            .and_then(|requirements| requirements.permissions.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn labels(&self) -> &[String] {
+        self.requirements.as_ref().map_or(&[], |requirements| requirements.labels.as_slice())
+    }
+
+    // The worker fleet the task insists on, if any
+    pub fn fleet(&self) -> Option<&str> {
+        self.requirements.as_ref()?.fleet.as_deref()
+    }
+
+    pub fn determinism(&self) -> Option<Determinism> {
+        self.requirements.as_ref()?.deterministic
+    }
+
+    // Hex SHA-256 of the definition as JSON with sorted keys, so it doesn't depend on
+    // map iteration order or on who serialized it
+    pub fn sha256(&self) -> String {
+        let value = serde_json::to_value(self).map(canonical_json).unwrap_or_default();
+        sha256_hex(value.to_string().as_bytes())
+    }
+
+    // Task files wrap the definition in a top-level `task_definition` key; YAML unless
+    // `json`. Files can come from anywhere, so malformed ones must fail cleanly.
+    pub fn from_task_file(content: &str, json: bool) -> anyhow::Result<Self> {
+        if json {
+            let value: serde_json::Value = serde_json::from_str(content)?;
+            let inner = value.get("task_definition").ok_or_else(|| anyhow::anyhow!("No 'task_definition' found in JSON file"))?;
+            Ok(serde_json::from_value(inner.clone())?)
+        } else {
+            let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+            let inner = value.get("task_definition").ok_or_else(|| anyhow::anyhow!("No 'task_definition' found in YAML file"))?;
+            Ok(serde_yaml::from_value(inner.clone())?)
+        }
+    }
+}
+
+fn canonical_json(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(entries.into_iter().map(|(key, value)| (key, canonical_json(value))).collect())
+        }
+        serde_json::Value::Array(values) => serde_json::Value::Array(values.into_iter().map(canonical_json).collect()),
+        value => value,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TaskSource {
     Inline { code: String },
     Url { url: String },
-    Git { repo: String, path: String, branch: Option<String> },
+    Git {
+        repo: String,
+        path: String,
+        branch: Option<String>,
+        // Commit SHA or tag to pin to; takes precedence over `branch`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rev: Option<String>,
+        #[serde(default)]
+        submodules: bool,
+    },
     Gist { id: String, filename: String },
+    // A tar, tar.gz or zip of a multi-file project; `entrypoint` is the script to run,
+    // relative to the archive root
+    Archive { url: String, sha256: String, entrypoint: String },
     Wasm { wasm_bytes: Vec<u8> },
     Docker { image: String, command: Vec<String> },
+    // A prebuilt shared library (.so/.dylib/.dll for the worker's platform) exporting
+    // the corebrum_run C ABI, called in-process
+    Plugin { url: String, sha256: String },
+    // Inference with an ONNX model; inputs and outputs are tensors by name. With
+    // `batch_size`, inputs are fed through the model that many rows at a time.
+    Onnx {
+        url: String,
+        sha256: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        batch_size: Option<usize>,
+    },
+    // Polars operations applied in order to the dataset in input `dataset`
+    // ({"url": ..., "format"?: ..., "sha256"?: ...}); the result comes back as an
+    // artifact in `output_format`
+    DataFrame {
+        dataset: String,
+        operations: Vec<FrameOperation>,
+        #[serde(default)]
+        output_format: DatasetFormat,
+    },
+    // Decodes the JPEG or PNG in input `frame` (base64, or {"url": ..., "sha256"?: ...})
+    // and applies `steps` in order; the result is a tensor or a re-encoded image
+    Image {
+        frame: String,
+        steps: Vec<ImageStep>,
+        #[serde(default)]
+        output: ImageOutput,
+    },
+    // A task compiled into the worker, see builtins::BUILTIN_TASKS
+    Builtin { name: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TaskSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TaskSource::Inline { .. } => "inline",
+            TaskSource::Url { .. } => "url",
+            TaskSource::Git { .. } => "git",
+            TaskSource::Gist { .. } => "gist",
+            TaskSource::Archive { .. } => "archive",
+            TaskSource::Wasm { .. } => "wasm",
+            TaskSource::Docker { .. } => "docker",
+            TaskSource::Plugin { .. } => "plugin",
+            TaskSource::Onnx { .. } => "onnx",
+            TaskSource::DataFrame { .. } => "dataframe",
+            TaskSource::Image { .. } => "image",
+            TaskSource::Builtin { .. } => "builtin",
+        }
+    }
+
+    // Problems that would make any worker fail to fetch the source, found without
+    // fetching it
+    pub fn check(&self) -> anyhow::Result<()> {
+        let kind = self.kind();
+        let present = |field: &str, value: &str| {
+            if value.trim().is_empty() {
+                anyhow::bail!("The {} source's {} is empty", kind, field);
+            }
+            Ok(())
+        };
+        let url = |url: &str| {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                anyhow::bail!("The {} source's URL {:?} is not http(s)", kind, url);
+            }
+            Ok(())
+        };
+        let sha256 = |sha256: &str| {
+            if !is_sha256_hex(sha256) {
+                anyhow::bail!("The {} source's sha256 {:?} is not a hex SHA-256", kind, sha256);
+            }
+            Ok(())
+        };
+        match self {
+            TaskSource::Inline { code } => present("code", code),
+            TaskSource::Url { url: address } => url(address),
+            TaskSource::Git { repo, path, branch, rev, .. } => {
+                present("repo", repo).and(present("path", path))?;
+                // Passed to git as arguments, where a leading `-` would make them options
+                let option = [("repo", Some(repo)), ("branch", branch.as_ref()), ("rev", rev.as_ref())]
+                    .into_iter()
+                    .find_map(|(field, value)| value.filter(|value| value.starts_with('-')).map(|value| (field, value)));
+                if let Some((field, value)) = option {
+                    anyhow::bail!("The git source's {} {:?} can't start with '-'", field, value);
+                }
+                Ok(())
+            }
+            TaskSource::Gist { id, filename } => present("id", id).and(present("filename", filename)),
+            TaskSource::Archive { url: address, sha256: digest, entrypoint } => {
+                url(address).and(sha256(digest)).and(present("entrypoint", entrypoint))
+            }
+            TaskSource::Wasm { wasm_bytes } if wasm_bytes.is_empty() => anyhow::bail!("The wasm source has no module"),
+            TaskSource::Wasm { .. } => Ok(()),
+            TaskSource::Docker { image, .. } => present("image", image),
+            TaskSource::Plugin { url: address, sha256: digest } | TaskSource::Onnx { url: address, sha256: digest, .. } => {
+                url(address).and(sha256(digest))
+            }
+            TaskSource::DataFrame { dataset, .. } => present("dataset", dataset),
+            TaskSource::Image { frame, .. } => present("frame", frame),
+            TaskSource::Builtin { name } => present("name", name),
+        }
+    }
+}
+
+// A SHA-256 digest as 64 hex digits, either case
+pub fn is_sha256_hex(digest: &str) -> bool {
+    digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// File formats of datasets staged for SQL and DataFrame tasks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Csv,
+    #[default]
+    Parquet,
+    Json,
+}
+
+impl DatasetFormat {
+    // From an explicit format name or the extension of the file's URL
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.split(['?', '#']).next().unwrap_or(name);
+        let extension = name.rsplit_once('.').map_or(name, |(_, extension)| extension);
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" | "tsv" => Some(DatasetFormat::Csv),
+            "parquet" | "pq" => Some(DatasetFormat::Parquet),
+            "json" | "ndjson" | "jsonl" => Some(DatasetFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            DatasetFormat::Csv => "csv",
+            DatasetFormat::Parquet => "parquet",
+            DatasetFormat::Json => "json",
+        }
+    }
+}
+
+// One step of a DataFrame task, e.g. {"op": "filter", "column": "speed", "cmp": "gt", "value": 2.5}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FrameOperation {
+    Select { columns: Vec<String> },
+    Drop { columns: Vec<String> },
+    Rename { columns: std::collections::BTreeMap<String, String> },
+    Filter { column: String, cmp: Comparison, value: serde_json::Value },
+    // Rows with a null in any of `columns` (any column if empty)
+    DropNulls {
+        #[serde(default)]
+        columns: Vec<String>,
+    },
+    GroupBy { by: Vec<String>, aggs: Vec<Aggregation> },
+    Sort {
+        by: Vec<String>,
+        #[serde(default)]
+        descending: bool,
+    },
+    Limit { n: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aggregation {
+    pub column: String,
+    pub func: AggFunc,
+    // Defaults to <column>_<func>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggFunc {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Count,
+    First,
+    Last,
+    NUnique,
+}
+
+impl AggFunc {
+    pub fn name(self) -> &'static str {
+        match self {
+            AggFunc::Sum => "sum",
+            AggFunc::Mean => "mean",
+            AggFunc::Min => "min",
+            AggFunc::Max => "max",
+            AggFunc::Count => "count",
+            AggFunc::First => "first",
+            AggFunc::Last => "last",
+            AggFunc::NUnique => "n_unique",
+        }
+    }
+}
+
+// One step of an Image task, e.g. {"op": "resize", "width": 224, "height": 224}.
+// Pixel values are scaled to 0..1 on decode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ImageStep {
+    Resize {
+        width: u32,
+        height: u32,
+        #[serde(default)]
+        filter: ResizeFilter,
+    },
+    Crop { x: u32, y: u32, width: u32, height: u32 },
+    CenterCrop { width: u32, height: u32 },
+    // (value - mean) / std per RGB channel; tensor output only
+    Normalize { mean: [f32; 3], std: [f32; 3] },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResizeFilter {
+    Nearest,
+    #[default]
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+// What an Image task hands back, e.g. {"format": "tensor", "layout": "nchw"}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum ImageOutput {
+    // A float32 tensor of shape [1, 3, H, W] or [1, H, W, 3], ready for an Onnx task
+    Tensor {
+        #[serde(default)]
+        layout: TensorLayout,
+    },
+    Png,
+    Jpeg {
+        #[serde(default = "default_jpeg_quality")]
+        quality: u8,
+    },
+}
+
+impl Default for ImageOutput {
+    fn default() -> Self {
+        ImageOutput::Tensor { layout: TensorLayout::default() }
+    }
+}
+
+fn default_jpeg_quality() -> u8 {
+    90
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TensorLayout {
+    #[default]
+    Nchw,
+    Nhwc,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskInput {
     pub name: String,
     pub description: Option<String>,
     pub required: bool,
     pub default_value: Option<serde_json::Value>,
+    // Submitted values are coerced to it, see TaskDefinition::coerce_inputs; task
+    // files spell it `type`
+    #[serde(default, alias = "type", skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<InputType>,
+    // Allowed values, compared after coercion; required for `enum` inputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<serde_json::Value>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Type names are matched case-insensitively, with a few common aliases. Names this
+// worker doesn't know, e.g. "zenoh_topic", are kept as they are and not checked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum InputType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Object,
+    Array,
+    // Anything goes
+    Json,
+    // RFC 3339, or Unix seconds; passed on as RFC 3339 in UTC
+    Timestamp,
+    // "30s", "1h30m", "250ms" or seconds; passed on as seconds
+    Duration,
+    // "512MB", "1.5GiB", "64k" or bytes; passed on as bytes
+    Bytesize,
+    // A string among the input's `choices`
+    Enum,
+    Other(String),
+}
+
+impl From<String> for InputType {
+    fn from(name: String) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "string" | "str" | "text" => InputType::String,
+            "integer" | "int" => InputType::Integer,
+            "number" | "float" | "double" => InputType::Number,
+            "boolean" | "bool" => InputType::Boolean,
+            "object" | "map" | "dict" => InputType::Object,
+            "array" | "list" => InputType::Array,
+            "json" | "any" => InputType::Json,
+            "timestamp" | "datetime" => InputType::Timestamp,
+            "duration" => InputType::Duration,
+            "bytesize" | "bytes" => InputType::Bytesize,
+            "enum" | "choice" => InputType::Enum,
+            _ => InputType::Other(name),
+        }
+    }
+}
+
+impl From<InputType> for String {
+    fn from(data_type: InputType) -> Self {
+        match data_type {
+            InputType::String => "string",
+            InputType::Integer => "integer",
+            InputType::Number => "number",
+            InputType::Boolean => "boolean",
+            InputType::Object => "object",
+            InputType::Array => "array",
+            InputType::Json => "json",
+            InputType::Timestamp => "timestamp",
+            InputType::Duration => "duration",
+            InputType::Bytesize => "bytesize",
+            InputType::Enum => "enum",
+            InputType::Other(name) => return name,
+        }
+        .to_string()
+    }
+}
+
+// A condition on a task's inputs, checked after coercion when the task is submitted
+// and again by the worker that takes it, e.g. `end > start` or
+// `max_objs <= 128 when model == "vlm-x"`. See Expression for the syntax.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputCheck {
+    pub expression: String,
+    // Reported instead of the bare expression when the check fails
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl InputCheck {
+    pub fn passes(&self, inputs: &serde_json::Value) -> anyhow::Result<bool> {
+        Expression::parse(&self.expression)?.evaluate(inputs)
+    }
+}
+
+// Expressions come from task files, so their size is bounded; evaluation is linear in
+// it, with no loops or calls
+const MAX_EXPRESSION_LEN: usize = 1024;
+const MAX_EXPRESSION_DEPTH: usize = 32;
+
+// Boolean expression over a task's inputs. Inputs are referred to by name, with `.`
+// reaching into objects; missing ones are null. Literals are numbers, "strings",
+// true, false, null and [lists]. Operators, loosest first: `when` (`a when b` holds
+// whenever b doesn't), `or`/`||`, `and`/`&&`, `not`/`!`, comparisons (== != < <= >
+// >= and `in` for lists and substrings), + -, * / %. Strings that are both RFC 3339
+// timestamps compare as times.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(serde_json::Value),
+    Input(Vec<String>),
+    List(Vec<Expression>),
+    Not(Box<Expression>),
+    Negate(Box<Expression>),
+    Binary(Box<Expression>, BinaryOp, Box<Expression>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    When,
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        if text.len() > MAX_EXPRESSION_LEN {
+            anyhow::bail!("Expression is longer than {} bytes", MAX_EXPRESSION_LEN);
+        }
+        let mut parser = ExpressionParser { tokens: tokenize(text)?, pos: 0, depth: 0 };
+        let expression = parser.expression()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("Unexpected {} in {:?}", token, text);
+        }
+        Ok(expression)
+    }
+
+    pub fn evaluate(&self, inputs: &serde_json::Value) -> anyhow::Result<bool> {
+        truth(&self.value(inputs)?)
+    }
+
+    fn value(&self, inputs: &serde_json::Value) -> anyhow::Result<serde_json::Value> {
+        use serde_json::Value;
+        Ok(match self {
+            Expression::Literal(value) => value.clone(),
+            Expression::Input(path) => path
+                .iter()
+                .try_fold(inputs, |value, key| value.get(key.as_str()))
+                .cloned()
+                .unwrap_or(Value::Null),
+            Expression::List(items) => Value::Array(items.iter().map(|item| item.value(inputs)).collect::<anyhow::Result<_>>()?),
+            Expression::Not(operand) => Value::Bool(!truth(&operand.value(inputs)?)?),
+            Expression::Negate(operand) => arithmetic_result(-number(&operand.value(inputs)?)?)?,
+            Expression::Binary(left, op, right) => {
+                // The logical operators only evaluate the right side when they need to
+                match op {
+                    BinaryOp::When => return Ok(Value::Bool(!truth(&right.value(inputs)?)? || truth(&left.value(inputs)?)?)),
+                    BinaryOp::Or => return Ok(Value::Bool(truth(&left.value(inputs)?)? || truth(&right.value(inputs)?)?)),
+                    BinaryOp::And => return Ok(Value::Bool(truth(&left.value(inputs)?)? && truth(&right.value(inputs)?)?)),
+                    _ => {}
+                }
+                let (left, right) = (left.value(inputs)?, right.value(inputs)?);
+                match op {
+                    BinaryOp::Eq => Value::Bool(json_eq(&left, &right)),
+                    BinaryOp::Ne => Value::Bool(!json_eq(&left, &right)),
+                    BinaryOp::Lt => Value::Bool(compare(&left, &right)?.is_lt()),
+                    BinaryOp::Le => Value::Bool(compare(&left, &right)?.is_le()),
+                    BinaryOp::Gt => Value::Bool(compare(&left, &right)?.is_gt()),
+                    BinaryOp::Ge => Value::Bool(compare(&left, &right)?.is_ge()),
+                    BinaryOp::In => match (&left, &right) {
+                        (_, Value::Array(items)) => Value::Bool(items.iter().any(|item| json_eq(&left, item))),
+                        (Value::String(needle), Value::String(haystack)) => Value::Bool(haystack.contains(needle.as_str())),
+                        _ => anyhow::bail!("Cannot look for {} in {}", left, right),
+                    },
+                    BinaryOp::Add => arithmetic_result(number(&left)? + number(&right)?)?,
+                    BinaryOp::Sub => arithmetic_result(number(&left)? - number(&right)?)?,
+                    BinaryOp::Mul => arithmetic_result(number(&left)? * number(&right)?)?,
+                    BinaryOp::Div => arithmetic_result(number(&left)? / number(&right)?)?,
+                    BinaryOp::Rem => arithmetic_result(number(&left)? % number(&right)?)?,
+                    BinaryOp::When | BinaryOp::Or | BinaryOp::And => unreachable!(),
+                }
+            }
+        })
+    }
+}
+
+fn truth(value: &serde_json::Value) -> anyhow::Result<bool> {
+    value.as_bool().ok_or_else(|| anyhow::anyhow!("Expected true or false, got {}", value))
+}
+
+fn number(value: &serde_json::Value) -> anyhow::Result<f64> {
+    value.as_f64().ok_or_else(|| anyhow::anyhow!("Expected a number, got {}", value))
+}
+
+// Division by zero and overflow end up here as NaN or infinity
+fn arithmetic_result(n: f64) -> anyhow::Result<serde_json::Value> {
+    serde_json::Number::from_f64(n)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| anyhow::anyhow!("Arithmetic has no result"))
+}
+
+// Like ==, except that 30 equals 30.0
+fn json_eq(left: &serde_json::Value, right: &serde_json::Value) -> bool {
+    match (left.as_f64(), right.as_f64()) {
+        (Some(left), Some(right)) => left == right,
+        _ => left == right,
+    }
+}
+
+fn compare(left: &serde_json::Value, right: &serde_json::Value) -> anyhow::Result<std::cmp::Ordering> {
+    use serde_json::Value;
+    let ordering = match (left, right) {
+        (Value::Number(_), Value::Number(_)) => number(left)?.partial_cmp(&number(right)?),
+        (Value::String(left), Value::String(right)) => {
+            match (chrono::DateTime::parse_from_rfc3339(left), chrono::DateTime::parse_from_rfc3339(right)) {
+                (Ok(left), Ok(right)) => Some(left.cmp(&right)),
+                _ => Some(left.cmp(right)),
+            }
+        }
+        _ => None,
+    };
+    ordering.ok_or_else(|| anyhow::anyhow!("Cannot compare {} with {}", left, right))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Str(String),
+    Word(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Str(s) => write!(f, "{:?}", s),
+            Token::Word(word) => write!(f, "'{}'", word),
+            Token::Op(op) => write!(f, "'{}'", op),
+        }
+    }
+}
+
+const OPERATORS: [&str; 20] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "(", ")", "[", "]", ",", ".",
+];
+
+fn tokenize(text: &str) -> anyhow::Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_digit() {
+            let end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(rest.len());
+            let n = rest[..end].parse().map_err(|_| anyhow::anyhow!("Invalid number {:?} in {:?}", &rest[..end], text))?;
+            tokens.push(Token::Number(n));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if c == '"' || c == '\'' {
+            let mut value = String::new();
+            let mut chars = rest[1..].char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, quote)) if quote == c => break i + 2,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, escaped)) => value.push(escaped),
+                        None => anyhow::bail!("Unterminated string in {:?}", text),
+                    },
+                    Some((_, other)) => value.push(other),
+                    None => anyhow::bail!("Unterminated string in {:?}", text),
+                }
+            };
+            tokens.push(Token::Str(value));
+            rest = &rest[end..];
+        } else {
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| anyhow::anyhow!("Unexpected {:?} in {:?}", c, text))?;
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        }
+    }
+    Ok(tokens)
+}
+
+struct ExpressionParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl ExpressionParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, alternatives: &[&str]) -> Option<&'static str> {
+        let matched = match self.tokens.get(self.pos)? {
+            Token::Op(op) => alternatives.iter().find(|alternative| **alternative == *op).map(|_| *op),
+            Token::Word(word) => ["when", "or", "and", "not", "in"]
+                .into_iter()
+                .find(|keyword| *keyword == word.as_str() && alternatives.contains(keyword)),
+            _ => None,
+        };
+        if matched.is_some() {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect(&mut self, op: &str) -> anyhow::Result<()> {
+        match self.next() {
+            Some(Token::Op(found)) if found == op => Ok(()),
+            Some(token) => anyhow::bail!("Expected '{}', got {}", op, token),
+            None => anyhow::bail!("Expected '{}', got the end of the expression", op),
+        }
+    }
+
+    fn expression(&mut self) -> anyhow::Result<Expression> {
+        self.depth += 1;
+        if self.depth > MAX_EXPRESSION_DEPTH {
+            anyhow::bail!("Expression is nested deeper than {} levels", MAX_EXPRESSION_DEPTH);
+        }
+        let body = self.or()?;
+        let expression = if self.eat(&["when"]).is_some() {
+            Expression::Binary(Box::new(body), BinaryOp::When, Box::new(self.or()?))
+        } else {
+            body
+        };
+        self.depth -= 1;
+        Ok(expression)
+    }
+
+    fn or(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.and()?;
+        while self.eat(&["or", "||"]).is_some() {
+            left = Expression::Binary(Box::new(left), BinaryOp::Or, Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.not()?;
+        while self.eat(&["and", "&&"]).is_some() {
+            left = Expression::Binary(Box::new(left), BinaryOp::And, Box::new(self.not()?));
+        }
+        Ok(left)
+    }
+
+    // Prefix operators are counted rather than recursed into, so long runs of them
+    // can't exhaust the stack
+    fn not(&mut self) -> anyhow::Result<Expression> {
+        let mut negations = 0;
+        while self.eat(&["not", "!"]).is_some() {
+            negations += 1;
+        }
+        let mut expression = self.comparison()?;
+        for _ in 0..negations {
+            expression = Expression::Not(Box::new(expression));
+        }
+        Ok(expression)
+    }
+
+    fn comparison(&mut self) -> anyhow::Result<Expression> {
+        let left = self.sum()?;
+        let op = match self.eat(&["==", "!=", "<=", ">=", "<", ">", "in"]) {
+            Some("==") => BinaryOp::Eq,
+            Some("!=") => BinaryOp::Ne,
+            Some("<=") => BinaryOp::Le,
+            Some(">=") => BinaryOp::Ge,
+            Some("<") => BinaryOp::Lt,
+            Some(">") => BinaryOp::Gt,
+            Some(_) => BinaryOp::In,
+            None => return Ok(left),
+        };
+        Ok(Expression::Binary(Box::new(left), op, Box::new(self.sum()?)))
+    }
+
+    fn sum(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.product()?;
+        while let Some(op) = self.eat(&["+", "-"]) {
+            let op = if op == "+" { BinaryOp::Add } else { BinaryOp::Sub };
+            left = Expression::Binary(Box::new(left), op, Box::new(self.product()?));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> anyhow::Result<Expression> {
+        let mut left = self.negation()?;
+        while let Some(op) = self.eat(&["*", "/", "%"]) {
+            let op = match op {
+                "*" => BinaryOp::Mul,
+                "/" => BinaryOp::Div,
+                _ => BinaryOp::Rem,
+            };
+            left = Expression::Binary(Box::new(left), op, Box::new(self.negation()?));
+        }
+        Ok(left)
+    }
+
+    fn negation(&mut self) -> anyhow::Result<Expression> {
+        let mut negations = 0;
+        while self.eat(&["-"]).is_some() {
+            negations += 1;
+        }
+        let mut expression = self.primary()?;
+        for _ in 0..negations {
+            expression = Expression::Negate(Box::new(expression));
+        }
+        Ok(expression)
+    }
+
+    fn primary(&mut self) -> anyhow::Result<Expression> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expression::Literal(arithmetic_result(n)?)),
+            Some(Token::Str(s)) => Ok(Expression::Literal(serde_json::Value::String(s))),
+            Some(Token::Word(word)) => match word.as_str() {
+                "true" => Ok(Expression::Literal(serde_json::Value::Bool(true))),
+                "false" => Ok(Expression::Literal(serde_json::Value::Bool(false))),
+                "null" => Ok(Expression::Literal(serde_json::Value::Null)),
+                "when" | "or" | "and" | "not" | "in" => anyhow::bail!("Unexpected '{}'", word),
+                _ => {
+                    let mut path = vec![word];
+                    while self.eat(&["."]).is_some() {
+                        match self.next() {
+                            Some(Token::Word(key)) => path.push(key),
+                            _ => anyhow::bail!("Expected a field name after '.' in {}", path.join(".")),
+                        }
+                    }
+                    Ok(Expression::Input(path))
+                }
+            },
+            Some(Token::Op("(")) => {
+                let expression = self.expression()?;
+                self.expect(")")?;
+                Ok(expression)
+            }
+            Some(Token::Op("[")) => {
+                let mut items = Vec::new();
+                if self.eat(&["]"]).is_none() {
+                    loop {
+                        items.push(self.expression()?);
+                        if self.eat(&["]"]).is_some() {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Expression::List(items))
+            }
+            Some(token) => anyhow::bail!("Unexpected {}", token),
+            None => anyhow::bail!("Expression ended early"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TaskOutput {
     pub name: String,
     pub description: Option<String>,
     pub data_type: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TaskRequirements {
     pub memory_mb: Option<u64>,
     pub cpu_cores: Option<u32>,
     pub timeout_seconds: Option<u64>,
     pub dependencies: Option<Vec<String>>,
+    pub disk_mb: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolation: Option<Isolation>,
+    // Install requirements.txt / package.json found next to a git or archive
+    // entrypoint before running it, if the worker's policy allows
+    #[serde(default)]
+    pub install_dependencies: bool,
+    // Runtime for JavaScript tasks; Deno sandboxes them to `permissions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<JsRuntime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<Permissions>,
+    // Worker capabilities the task needs, e.g. "gpu"; workers matching labels only
+    // claim it if they advertise all of them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    // How many times the assigner announces the task, retries included, before
+    // giving up on it; the assigner's own limit when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    // Worker fleet the task must run on, e.g. "canary"; overrides the assigner's
+    // fleet routing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fleet: Option<String>,
+    // Run with pinned random seeds, a frozen clock and a normalized environment, so
+    // re-runs on the same inputs can be expected to produce identical outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deterministic: Option<Determinism>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl TaskRequirements {
+    // Sizes no host has, refused before they are turned into byte counts
+    pub fn check(&self) -> anyhow::Result<()> {
+        for (name, mb) in [("memory_mb", self.memory_mb), ("disk_mb", self.disk_mb)] {
+            if mb.is_some_and(|mb| mb > MAX_REQUIREMENT_MB) {
+                anyhow::bail!("Requirement {} of {}MB is over the {}MB limit", name, mb.unwrap_or_default(), MAX_REQUIREMENT_MB);
+            }
+        }
+        Ok(())
+    }
+}
+
+// What a deterministic run pins. Runners seed the language's random generators with
+// `seed` and, for Python and JavaScript, make the clock read `clock`; every process
+// also gets them as COREBRUM_SEED and SOURCE_DATE_EPOCH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Determinism {
+    #[serde(default)]
+    pub seed: u64,
+    // Wall clock the task sees, as Unix seconds
+    #[serde(default = "default_frozen_clock")]
+    pub clock: i64,
+}
+
+// 2000-01-01T00:00:00Z
+fn default_frozen_clock() -> i64 {
+    946_684_800
+}
+
+impl Default for Determinism {
+    fn default() -> Self {
+        Self { seed: 0, clock: default_frozen_clock() }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JsRuntime {
+    #[default]
+    Node,
+    Deno,
+}
+
+// What a Deno task may do besides reading its workspace; everything else is denied
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Permissions {
+    // Hosts it may connect to, optionally as host:port
+    #[serde(default)]
+    pub net: Vec<String>,
+    // Environment variables it may read
+    #[serde(default)]
+    pub env: Vec<String>,
+    // Write access to its workspace
+    #[serde(default)]
+    pub write: bool,
+}
+
+// How strongly a task's payload is separated from the worker host
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Isolation {
+    // A child process in a throwaway workspace
+    #[default]
+    Process,
+    // Experimental: a Firecracker microVM booted from the worker's rootfs template
+    Microvm,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Job {
     pub task_id: String,
     pub queue: String,
@@ -54,10 +1019,107 @@ pub struct Job {
     pub priority: Option<i32>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub timeout_seconds: Option<u64>,
+    // 1 for the original announcement, bumped every time the job is re-announced
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
+    #[serde(default)]
+    pub audit_trail: Vec<AuditEntry>,
+    // Set on jobs a running task spawned; the parent's result waits for them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_task_id: Option<String>,
+    // Where inputs too large to announce are fetched from; `inputs` is null then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inputs_ref: Option<InputsRef>,
+    // Set on jobs submitted by registry reference
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub definition_pin: Option<DefinitionPin>,
+    // Zenoh keys the worker reads further inputs from before running the job
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream_inputs: Option<StreamInputs>,
+}
+
+// The registry reference (`name@version`) a job was submitted by, and the hash of the
+// definition the submitter resolved it to. Workers run nothing else under it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DefinitionPin {
+    pub reference: String,
+    // TaskDefinition::sha256 of the resolved definition
+    pub sha256: String,
+}
+
+impl DefinitionPin {
+    pub fn verify(&self, task_definition: &TaskDefinition) -> anyhow::Result<()> {
+        let sha256 = task_definition.sha256();
+        if sha256 != self.sha256 {
+            anyhow::bail!("Task definition doesn't match {} pinned at {}, got {}", self.reference, self.sha256, sha256);
+        }
+        Ok(())
+    }
+}
+
+// Inputs served apart from the job by its submitter, as JSON encoded with `codec`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputsRef {
+    pub key: String,
+    // Of the JSON, before encoding
+    pub size_bytes: u64,
+    // Hex SHA-256 of the JSON
+    pub sha256: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<Codec>,
+}
+
+// Inputs a job takes from live Zenoh keys, e.g. a camera's rgb and depth topics. The
+// worker waits for one sample per key that together satisfy `sync`, then binds each
+// to the input of the same name before the job runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamInputs {
+    // Input name -> key expression
+    pub keys: BTreeMap<String, String>,
+    #[serde(default)]
+    pub sync: SyncPolicy,
+    // How long to wait for a matching set; fan_in::DEFAULT_FAN_IN_TIMEOUT when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+}
+
+impl StreamInputs {
+    // The inputs the samples are bound to
+    pub fn names(&self) -> Vec<&str> {
+        self.keys.keys().map(String::as_str).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPolicy {
+    // The newest sample of each key, however far apart they are
+    #[default]
+    LatestOfEach,
+    // Samples no more than `tolerance_ms` apart by their Zenoh timestamps, or by
+    // arrival time for samples published without one
+    Aligned { tolerance_ms: u64 },
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub attempt: u32,
+    pub worker_id: Option<String>,
+    pub event: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
 impl Job {
     pub fn new_user_task(queue: String, task_definition: TaskDefinition, inputs: serde_json::Value) -> Self {
+        let timeout_seconds = task_definition
+            .requirements
+            .as_ref()
+            .and_then(|requirements| requirements.timeout_seconds)
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS);
         Self {
             task_id: uuid::Uuid::new_v4().to_string(),
             queue,
@@ -65,29 +1127,196 @@ impl Job {
             inputs,
             priority: Some(0),
             created_at: chrono::Utc::now(),
-            timeout_seconds: Some(300), // 5 minutes default
+            timeout_seconds: Some(timeout_seconds),
+            attempt: first_attempt(),
+            audit_trail: Vec::new(),
+            parent_task_id: None,
+            inputs_ref: None,
+            definition_pin: None,
+            stream_inputs: None,
+        }
+    }
+
+    // A job without a task definition, for workers that install their own job handler
+    pub fn new(queue: String, inputs: serde_json::Value) -> Self {
+        Self {
+            task_id: uuid::Uuid::new_v4().to_string(),
+            queue,
+            task_definition: None,
+            inputs,
+            priority: Some(0),
+            created_at: chrono::Utc::now(),
+            timeout_seconds: Some(DEFAULT_TIMEOUT_SECONDS),
+            attempt: first_attempt(),
+            audit_trail: Vec::new(),
+            parent_task_id: None,
+            inputs_ref: None,
+            definition_pin: None,
+            stream_inputs: None,
         }
     }
+
+    // The same job, to be re-announced after `worker_id` failed to finish it
+    pub fn retry(&self, worker_id: &str, reason: &str) -> Self {
+        let mut job = self.clone();
+        job.audit_trail.push(AuditEntry {
+            attempt: self.attempt,
+            worker_id: Some(worker_id.to_string()),
+            event: reason.to_string(),
+            timestamp: chrono::Utc::now(),
+        });
+        job.attempt += 1;
+        job
+    }
+
+    // When the current attempt was announced: at the last retry, or on creation
+    pub fn announced_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.audit_trail.last().map_or(self.created_at, |entry| entry.timestamp)
+    }
+
+    pub fn failed_on(&self, worker_id: &str) -> bool {
+        self.audit_trail
+            .iter()
+            .any(|entry| entry.worker_id.as_deref() == Some(worker_id))
+    }
+
+    pub fn name(&self) -> &str {
+        self.task_definition.as_ref().map(|td| td.name.as_str()).unwrap_or("unknown")
+    }
+
+    pub fn max_attempts(&self) -> Option<u32> {
+        self.task_definition.as_ref()?.requirements.as_ref()?.max_attempts
+    }
+
+    pub fn with_stream_inputs(mut self, stream_inputs: StreamInputs) -> Self {
+        self.stream_inputs = Some(stream_inputs);
+        self
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Also accepts the legacy {task_id, peer, eta_ms, lease_until_ms} shape, see LegacyClaim
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Claim {
     pub task_id: String,
     pub worker_id: String,
     pub claimed_at: chrono::DateTime<chrono::Utc>,
     pub estimated_duration_seconds: Option<u64>,
+    // Worker load when the claim was made
+    #[serde(default)]
+    pub running_tasks: u32,
+    #[serde(default)]
+    pub queue_depth: u32,
+    // The claimant's fleet; DEFAULT_FLEET when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fleet: Option<String>,
+    // The claimant's benchmarked cpu_mb_per_second; None if it wasn't benchmarked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_score: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Claims as sent by workers that predate the current schema
+#[derive(Deserialize)]
+struct LegacyClaim {
+    task_id: String,
+    peer: String,
+    eta_ms: u64,
+}
+
+impl From<LegacyClaim> for Claim {
+    // The legacy lease was only ever 200ms past the claim, so arrival time stands in for
+    // the claim time; legacy workers don't report their load
+    fn from(legacy: LegacyClaim) -> Self {
+        Self {
+            task_id: legacy.task_id,
+            worker_id: legacy.peer,
+            claimed_at: chrono::Utc::now(),
+            estimated_duration_seconds: Some(legacy.eta_ms.div_ceil(1000)),
+            running_tasks: 0,
+            queue_depth: 0,
+            fleet: None,
+            cpu_score: None,
+        }
+    }
+}
+
+impl Serialize for Claim {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Claim::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Claim {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let claim = if is_legacy(&value, "peer", "worker_id") {
+            serde_json::from_value::<LegacyClaim>(value).map(Claim::from)
+        } else {
+            Claim::deserialize(value)
+        };
+        claim.map_err(serde::de::Error::custom)
+    }
+}
+
+// Legacy messages are told apart by a field the current shape doesn't have
+fn is_legacy(value: &serde_json::Value, legacy_field: &str, current_field: &str) -> bool {
+    value.get(legacy_field).is_some() && value.get(current_field).is_none()
+}
+
+impl Claim {
+    // Tasks the claimant has to get through before it could start this one
+    pub fn load(&self) -> u32 {
+        self.running_tasks + self.queue_depth
+    }
+
+    pub fn fleet(&self) -> &str {
+        self.fleet.as_deref().unwrap_or(DEFAULT_FLEET)
+    }
+}
+
+// Fleet of the workers that don't name one
+pub const DEFAULT_FLEET: &str = "stable";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Assign {
     pub task_id: String,
     pub worker_id: String,
     pub assigned_at: chrono::DateTime<chrono::Utc>,
-    pub task_definition: TaskDefinition,
+    pub task_definition: Option<TaskDefinition>,
     pub inputs: serde_json::Value,
+    // Assignments are retained per task, so a worker claiming a re-announced job
+    // must be able to tell a previous attempt's assignment apart
+    #[serde(default = "first_attempt")]
+    pub attempt: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Sent by the assignee once it has seen its Assign, so the assigner knows the
+// handoff succeeded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssignAck {
+    pub task_id: String,
+    pub worker_id: String,
+    pub acked_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Published by the assigner when it first accepts a job. `accepted_at` comes from
+// the assigner's clock and `queue_position` counts the jobs still waiting ahead, so
+// clients can order submissions without trusting their own clocks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Receipt {
+    pub task_id: String,
+    pub queue: String,
+    pub accepted_at: chrono::DateTime<chrono::Utc>,
+    pub queue_position: u64,
+    pub assigner_id: String,
+    // Hex HMAC-SHA256 over the other fields, see ReceiptSigner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+// Also accepts the legacy {task_id, state, progress} shape, see LegacyStatus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct Status {
     pub task_id: String,
     pub worker_id: String,
@@ -95,9 +1324,98 @@ pub struct Status {
     pub message: Option<String>,
     pub progress: Option<f64>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    // Identifies one incarnation of the publisher; sequence increases within it
+    #[serde(default)]
+    pub epoch: u64,
+    #[serde(default)]
+    pub sequence: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Status {
+    pub fn new(task_id: &str, worker_id: &str, status: TaskStatus, message: &str, progress: f64) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            worker_id: worker_id.to_string(),
+            status,
+            message: Some(message.to_string()),
+            progress: Some(progress),
+            timestamp: chrono::Utc::now(),
+            epoch: 0,
+            sequence: 0,
+        }
+    }
+
+    // Last-writer-wins: within one publisher incarnation the sequence number decides,
+    // across publishers or restarts the timestamp does
+    pub fn supersedes(&self, other: &Status) -> bool {
+        if self.epoch != 0 && self.epoch == other.epoch {
+            self.sequence > other.sequence
+        } else {
+            self.timestamp >= other.timestamp
+        }
+    }
+}
+
+// Status updates as sent by peers that predate the current schema; `state` is the
+// lowercase status name
+#[derive(Deserialize)]
+struct LegacyStatus {
+    task_id: String,
+    state: String,
+    #[serde(default)]
+    progress: Option<f64>,
+    #[serde(default)]
+    peer: Option<String>,
+}
+
+impl TryFrom<LegacyStatus> for Status {
+    type Error = String;
+
+    // Stamped with the arrival time, so it orders by timestamp against current updates
+    fn try_from(legacy: LegacyStatus) -> std::result::Result<Self, Self::Error> {
+        let status = match legacy.state.to_ascii_lowercase().as_str() {
+            "pending" => TaskStatus::Pending,
+            "claimed" => TaskStatus::Claimed,
+            "assigned" => TaskStatus::Assigned,
+            "running" => TaskStatus::Running,
+            "completed" | "done" => TaskStatus::Completed,
+            "failed" | "error" => TaskStatus::Failed,
+            "timeout" => TaskStatus::Timeout,
+            "cancelled" | "canceled" => TaskStatus::Cancelled,
+            other => return Err(format!("unknown legacy task state {}", other)),
+        };
+        Ok(Self {
+            task_id: legacy.task_id,
+            worker_id: legacy.peer.unwrap_or_default(),
+            status,
+            message: None,
+            progress: legacy.progress,
+            timestamp: chrono::Utc::now(),
+            epoch: 0,
+            sequence: 0,
+        })
+    }
+}
+
+impl Serialize for Status {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        Status::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        if is_legacy(&value, "state", "status") {
+            let legacy = serde_json::from_value::<LegacyStatus>(value).map_err(serde::de::Error::custom)?;
+            Status::try_from(legacy).map_err(serde::de::Error::custom)
+        } else {
+            Status::deserialize(value).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
     Pending,
     Claimed,
@@ -109,7 +1427,7 @@ pub enum TaskStatus {
     Cancelled,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Result {
     pub task_id: String,
     pub worker_id: String,
@@ -118,6 +1436,314 @@ pub struct Result {
     pub error: Option<String>,
     pub execution_time_seconds: Option<f64>,
     pub completed_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default)]
+    pub artifacts: HashMap<String, String>,
+    #[serde(default)]
+    pub metadata: ExecutionMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+impl Result {
+    pub fn failure(task_id: String, worker_id: String, error: String) -> Self {
+        Self {
+            task_id,
+            worker_id,
+            status: TaskStatus::Failed,
+            outputs: HashMap::new(),
+            error: Some(error),
+            execution_time_seconds: None,
+            completed_at: chrono::Utc::now(),
+            artifacts: HashMap::new(),
+            metadata: ExecutionMetadata::default(),
+            provenance: None,
+        }
+    }
+}
+
+// What a perception worker reports, as the `detections.json` artifact of its result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PerceptionResult {
+    pub objects: Vec<Detection>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub worker_id: String,
+}
+
+impl PerceptionResult {
+    pub const ARTIFACT: &'static str = "detections.json";
+
+    // None if the result carries no detections
+    pub fn from_result(result: &Result) -> Option<serde_json::Result<Self>> {
+        result.artifacts.get(Self::ARTIFACT).map(|content| serde_json::from_str(content))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Detection {
+    pub label: String,
+    // Confidence in 0..1
+    pub score: f32,
+    pub bbox: BoundingBox,
+    // Distance to the object from the depth image, where there is one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depth_m: Option<f32>,
+}
+
+// In pixels of the source frame, from its top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl BoundingBox {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+// What exactly produced a result, so it can be reproduced and audited: the source
+// as resolved at execution time, a hash of the code that ran, and who ran it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    // TaskSource kind: inline, url, git, gist, archive, wasm or docker
+    pub source_kind: String,
+    // Final URL after redirects, or the repository that was cloned
+    pub source_url: Option<String>,
+    pub git_commit: Option<String>,
+    pub gist_revision: Option<String>,
+    pub image_digest: Option<String>,
+    // SHA-256 of the script or module, hex encoded
+    pub code_sha256: Option<String>,
+    // `<backend>=<version>` for every runtime the worker found
+    #[serde(default)]
+    pub runtimes: Vec<String>,
+    pub worker_id: Option<String>,
+    pub hostname: Option<String>,
+    pub worker_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionMetadata {
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub timed_out: bool,
+    // Measured over every process the task ran; absent where the platform can't tell
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resources: Option<ResourceUsage>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub peak_rss_bytes: u64,
+    // User plus system time
+    pub cpu_time_seconds: f64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+}
+
+impl ResourceUsage {
+    // Combines the usage of processes that ran one after another
+    pub fn accumulate(&mut self, other: &ResourceUsage) {
+        self.peak_rss_bytes = self.peak_rss_bytes.max(other.peak_rss_bytes);
+        self.cpu_time_seconds += other.cpu_time_seconds;
+        self.read_bytes += other.read_bytes;
+        self.write_bytes += other.write_bytes;
+    }
+}
+
+// Backlog of a queue, published periodically by its assigner for autoscalers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueMetrics {
+    pub queue: String,
+    pub pending: u64,
+    pub running: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+pub const DEFAULT_TASK_PAGE_SIZE: usize = 100;
+pub const MAX_TASK_PAGE_SIZE: usize = 1000;
+
+// Which tasks a listing returns; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskFilter {
+    // Any of these; empty for all
+    pub statuses: Vec<TaskStatus>,
+    pub worker_id: Option<String>,
+    pub task_id_prefix: Option<String>,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, status: &Status) -> bool {
+        (self.statuses.is_empty() || self.statuses.contains(&status.status))
+            && self.worker_id.as_ref().is_none_or(|worker_id| *worker_id == status.worker_id)
+            && self.task_id_prefix.as_ref().is_none_or(|prefix| status.task_id.starts_with(prefix.as_str()))
+    }
+}
+
+// Query sent to a queue's status store by `TaskClient::list_tasks`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskListRequest {
+    pub filter: TaskFilter,
+    // From the previous page; None for the first one
+    pub page_token: Option<String>,
+    // Capped at MAX_TASK_PAGE_SIZE by the store
+    pub page_size: usize,
+}
+
+impl Default for TaskListRequest {
+    fn default() -> Self {
+        Self {
+            filter: TaskFilter::default(),
+            page_token: None,
+            page_size: DEFAULT_TASK_PAGE_SIZE,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskSummary {
+    pub task_id: String,
+    pub status: TaskStatus,
+    pub worker_id: String,
+    pub message: Option<String>,
+    pub progress: Option<f64>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<&Status> for TaskSummary {
+    fn from(status: &Status) -> Self {
+        Self {
+            task_id: status.task_id.clone(),
+            status: status.status.clone(),
+            worker_id: status.worker_id.clone(),
+            message: status.message.clone(),
+            progress: status.progress,
+            updated_at: status.timestamp,
+        }
+    }
+}
+
+// Tasks ordered by ID; pass `next_page_token` back for the following page, which
+// is None once the listing is exhausted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskSummary>,
+    pub next_page_token: Option<String>,
+}
+
+// Trees larger than this are cut off, with `truncated` set
+pub const MAX_TASK_TREE_NODES: usize = 10_000;
+
+// Query sent to a queue's status store by `TaskClient::task_tree`; any task of the
+// tree will do, the whole tree is returned from its root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskTreeRequest {
+    pub task_id: String,
+}
+
+// A task and the tasks it spawned, through delegation or as the steps of a
+// workflow. Tasks the store only knows as someone's parent have no status.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskTreeNode {
+    pub task_id: String,
+    pub name: Option<String>,
+    pub status: Option<TaskStatus>,
+    pub worker_id: Option<String>,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    // First seen Running
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    // Seen in a terminal status
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub children: Vec<TaskTreeNode>,
+}
+
+impl TaskTreeNode {
+    pub fn duration_seconds(&self) -> Option<f64> {
+        let (started_at, finished_at) = (self.started_at?, self.finished_at?);
+        Some((finished_at - started_at).num_milliseconds() as f64 / 1000.0)
+    }
+
+    // Depth-first, parents before their children
+    pub fn nodes(&self) -> Vec<&TaskTreeNode> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.nodes());
+        }
+        nodes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskTree {
+    pub root: TaskTreeNode,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+impl TaskTree {
+    // Graphviz digraph with one box per task, labelled with its name, status and duration
+    pub fn to_dot(&self) -> String {
+        let escape = |text: &str| text.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut dot = String::from("digraph tasks {\n    node [shape=box];\n");
+        for node in self.root.nodes() {
+            let mut lines = vec![node.name.clone().unwrap_or_else(|| node.task_id.clone()), node.task_id.clone()];
+            if let Some(status) = &node.status {
+                lines.push(match node.duration_seconds() {
+                    Some(duration) => format!("{:?} in {:.1}s", status, duration),
+                    None => format!("{:?}", status),
+                });
+            }
+            let label: Vec<String> = lines.iter().map(|line| escape(line)).collect();
+            // Graphviz breaks lines on a literal \n in the label
+            dot.push_str(&format!("    \"{}\" [label=\"{}\"];\n", escape(&node.task_id), label.join("\\n")));
+            for child in &node.children {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", escape(&node.task_id), escape(&child.task_id)));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// A job the submitter is considering, sent to the assigner by `TaskClient::estimate`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EstimateRequest {
+    pub job: Job,
+}
+
+// The assigner's guess at how a job would fare if it were submitted now
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Estimate {
+    // Jobs announced but not yet acknowledged by a worker
+    pub queue_depth: usize,
+    pub running: usize,
+    // Live workers that could take the job, as advertised in their heartbeats
+    pub candidates: Vec<String>,
+    // Mean of the latest completed runs of tasks with the same name; None before the
+    // first one
+    pub expected_duration_seconds: Option<f64>,
+    pub duration_samples: usize,
+    // None when no candidate is alive, or when the job would queue behind others and
+    // no run of anything on the queue has completed yet
+    pub estimated_wait_seconds: Option<f64>,
+    pub estimated_start_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub estimated_at: chrono::DateTime<chrono::Utc>,
+}
+
+// Published periodically by workers; renews the leases on the listed tasks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub worker_id: String,
+    pub task_ids: Vec<String>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub info: Option<WorkerInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,6 +1752,47 @@ pub struct WorkerInfo {
     pub capabilities: Vec<String>,
     pub status: WorkerStatus,
     pub last_heartbeat: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<HostMetrics>,
+    // Outcome of the startup self-test; None if the worker didn't run one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready: Option<bool>,
+    // Backends whose self-test failed; the assigner keeps their jobs away from this worker
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed_probes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fleet: Option<String>,
+    // From the benchmark built-in at startup; None if the worker didn't run it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub benchmark: Option<BenchmarkScores>,
+}
+
+// Snapshot of the machine a worker runs on, so schedulers can steer work away
+// from devices that are already loaded, full or hot
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HostMetrics {
+    pub cpu_usage_percent: f32,
+    pub load_average_1m: f64,
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    // Of the disk holding the worker's workspaces
+    pub disk_total_bytes: u64,
+    pub disk_available_bytes: u64,
+    // Hottest sensor reading; None where no sensors are exposed
+    pub max_temperature_celsius: Option<f32>,
+}
+
+// Single-threaded throughput a worker measured with the benchmark built-in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkScores {
+    // SHA-256 over a buffer in memory
+    pub cpu_mb_per_second: f64,
+    // Copying that buffer
+    pub memory_gb_per_second: f64,
+    // Of the disk holding the worker's workspaces; writes count until synced
+    pub disk_write_mb_per_second: f64,
+    pub disk_read_mb_per_second: f64,
+    pub measured_at: chrono::DateTime<chrono::Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
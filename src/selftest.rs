@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use crate::schema::{TaskDefinition, TaskRequirements, TaskSource};
+
+// Backends a worker exercises with a built-in task before taking work
+pub const SELF_TEST_BACKENDS: &[&str] = &["python", "javascript", "wasm", "expression", "docker"];
+// A probe that hasn't finished within this counts as failed
+pub const SELF_TEST_TIMEOUT_SECONDS: u64 = 30;
+
+const PYTHON_PROBE: &str = "def handler(inputs):\n    return {\"ok\": True}\n";
+const JAVASCRIPT_PROBE: &str = "exports.handler = () => ({ ok: true });\n";
+// WASI command writing {"ok":true} to stdout
+const WASM_PROBE: &str = r#"(module
+  (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+  (memory (export "memory") 1)
+  (data (i32.const 16) "{\"ok\":true}")
+  (func (export "_start")
+    (i32.store (i32.const 0) (i32.const 16))
+    (i32.store (i32.const 4) (i32.const 11))
+    (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+"#;
+
+// Outcome of one backend's probe
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendCheck {
+    pub backend: String,
+    pub passed: bool,
+    pub duration_ms: u64,
+    // What went wrong, or what was checked when there is no task to run
+    pub detail: Option<String>,
+}
+
+// Results of a worker's startup self-test; backends that aren't enabled aren't listed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    pub worker_id: String,
+    pub checks: Vec<BackendCheck>,
+}
+
+impl SelfTestReport {
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn failed_backends(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .filter(|check| !check.passed)
+            .map(|check| check.backend.clone())
+            .collect()
+    }
+
+    pub fn passed(&self, backend: &str) -> bool {
+        self.checks.iter().all(|check| check.backend != backend || check.passed)
+    }
+}
+
+// The built-in task run for `backend`, whose outputs are `{"ok": true}`. Docker has
+// none: it can only run in pooled containers, whose images are up to the operator.
+pub fn probe_task(backend: &str) -> Option<TaskDefinition> {
+    let (language, source) = match backend {
+        "python" => ("python", TaskSource::Inline { code: PYTHON_PROBE.to_string() }),
+        "javascript" => ("javascript", TaskSource::Inline { code: JAVASCRIPT_PROBE.to_string() }),
+        "wasm" => ("wasm", TaskSource::Wasm { wasm_bytes: WASM_PROBE.as_bytes().to_vec() }),
+        "expression" => ("expression", TaskSource::Inline { code: "ok = true".to_string() }),
+        _ => return None,
+    };
+    Some(TaskDefinition {
+        name: format!("selftest-{}", backend),
+        description: Some(format!("Worker self-test for the {} backend", backend)),
+        language: language.to_string(),
+        source,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: Some(TaskRequirements {
+            timeout_seconds: Some(SELF_TEST_TIMEOUT_SECONDS),
+            ..Default::default()
+        }),
+        checks: Vec::new(),
+    })
+}
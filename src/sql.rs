@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use duckdb::Connection;
+use std::path::{Path, PathBuf};
+use crate::schema::DatasetFormat;
+
+// A CSV, Parquet or JSON file staged into the workspace, queried as a view by name
+#[derive(Debug, Clone)]
+pub struct SqlTable {
+    pub name: String,
+    pub path: PathBuf,
+    pub format: DatasetFormat,
+}
+
+// An in-memory database that can only touch files under `workdir`: no other paths,
+// no network, no extension downloads, and settings locked before task SQL runs
+pub fn open_sandboxed(workdir: &Path, memory_mb: Option<u64>, threads: Option<u32>) -> Result<Connection> {
+    let conn = Connection::open_in_memory().context("Failed to open DuckDB")?;
+    let mut settings = vec![
+        "SET autoinstall_known_extensions = false".to_string(),
+        "SET autoload_known_extensions = false".to_string(),
+        format!("SET allowed_directories = [{}]", quote(&workdir.to_string_lossy())),
+        "SET enable_external_access = false".to_string(),
+    ];
+    if let Some(memory_mb) = memory_mb {
+        settings.push(format!("SET memory_limit = '{}MB'", memory_mb));
+    }
+    if let Some(threads) = threads {
+        settings.push(format!("SET threads = {}", threads.max(1)));
+    }
+    settings.push("SET lock_configuration = true".to_string());
+    conn.execute_batch(&settings.join(";\n")).context("Failed to configure DuckDB")?;
+    Ok(conn)
+}
+
+// Runs a single query over the tables, with scalar inputs available through
+// getvariable('<name>'), and writes the result set to `output`: a JSON array of rows,
+// or a Parquet file
+pub fn run_query(
+    conn: &Connection,
+    tables: &[SqlTable],
+    variables: &[(String, serde_json::Value)],
+    query: &str,
+    output: &Path,
+    format: DatasetFormat,
+) -> Result<()> {
+    for table in tables {
+        check_identifier(&table.name)?;
+        let sql = format!(
+            "CREATE VIEW \"{}\" AS SELECT * FROM {}({})",
+            table.name,
+            reader(table.format),
+            quote(&table.path.to_string_lossy())
+        );
+        conn.execute_batch(&sql).with_context(|| format!("Failed to load input {}", table.name))?;
+    }
+    for (name, value) in variables {
+        check_identifier(name)?;
+        conn.execute_batch(&format!("SET VARIABLE \"{}\" = {}", name, literal(value)))
+            .with_context(|| format!("Failed to set input {}", name))?;
+    }
+
+    let query = query.trim().trim_end_matches(';');
+    let options = match format {
+        DatasetFormat::Parquet => "FORMAT PARQUET",
+        DatasetFormat::Json => "FORMAT JSON, ARRAY true",
+        DatasetFormat::Csv => "FORMAT CSV, HEADER true",
+    };
+    conn.execute_batch(&format!("COPY ({}) TO {} ({})", query, quote(&output.to_string_lossy()), options))
+        .context("SQL query failed")?;
+    Ok(())
+}
+
+pub fn parquet_row_count(conn: &Connection, path: &Path) -> Result<u64> {
+    let count: i64 = conn.query_row(&format!("SELECT count(*) FROM read_parquet({})", quote(&path.to_string_lossy())), [], |row| row.get(0))?;
+    Ok(count as u64)
+}
+
+fn reader(format: DatasetFormat) -> &'static str {
+    match format {
+        DatasetFormat::Csv => "read_csv_auto",
+        DatasetFormat::Parquet => "read_parquet",
+        DatasetFormat::Json => "read_json_auto",
+    }
+}
+
+// Table and variable names come from input names, so they are kept to plain
+// identifiers rather than escaped
+pub fn check_identifier(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        anyhow::bail!("Input name {:?} can't be used in SQL; use letters, digits and underscores", name);
+    }
+    Ok(())
+}
+
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn literal(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "NULL".to_string(),
+        serde_json::Value::Bool(flag) => flag.to_string(),
+        serde_json::Value::Number(number) => number.to_string(),
+        serde_json::Value::String(text) => quote(text),
+        // Arrays and objects stay JSON, for DuckDB's JSON functions
+        other => format!("{}::JSON", quote(&other.to_string())),
+    }
+}
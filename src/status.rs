@@ -0,0 +1,380 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio_util::sync::CancellationToken;
+use zenoh::Session;
+use crate::batching::unbatch_messages;
+use crate::keyspace::Keyspace;
+use crate::envelope::seal;
+use crate::schema::{Job, Status, TaskListRequest, TaskPage, TaskStatus, TaskSummary, TaskTree, TaskTreeNode, TaskTreeRequest, MAX_TASK_PAGE_SIZE, MAX_TASK_TREE_NODES};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::{deserialize_from_sample_with_context, deserialize_payload_with_context};
+use crate::summary;
+
+impl TaskStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            TaskStatus::Completed | TaskStatus::Failed | TaskStatus::Timeout | TaskStatus::Cancelled
+        )
+    }
+}
+
+// Lifecycle of a single task:
+//   Pending -> Claimed -> Assigned -> Running -> Completed | Failed | Timeout
+// Reassignment (ack fallback, lost worker) may move a task back to Assigned, and
+// any non-terminal state may be cancelled or failed. Terminal states are final.
+#[derive(Debug, Clone)]
+pub struct StateMachine {
+    state: TaskStatus,
+}
+
+impl StateMachine {
+    pub fn new(initial: TaskStatus) -> Self {
+        Self { state: initial }
+    }
+
+    pub fn state(&self) -> &TaskStatus {
+        &self.state
+    }
+
+    pub fn is_allowed(from: &TaskStatus, to: &TaskStatus) -> bool {
+        use TaskStatus::*;
+        match (from, to) {
+            (from, _) if from.is_terminal() => false,
+            (_, Failed | Cancelled) => true,
+            (Pending, Claimed | Assigned) => true,
+            (Claimed, Claimed | Assigned) => true,
+            (Assigned, Assigned | Running | Timeout) => true,
+            // Progress updates repeat Running
+            (Running, Running | Assigned | Completed | Timeout) => true,
+            _ => false,
+        }
+    }
+
+    // Leaves the state untouched when the transition is illegal
+    pub fn transition(&mut self, to: TaskStatus) -> Result<()> {
+        if !Self::is_allowed(&self.state, &to) {
+            anyhow::bail!("Illegal status transition {:?} -> {:?}", self.state, to);
+        }
+        self.state = to;
+        Ok(())
+    }
+}
+
+// Stamps outgoing status updates with this publisher's epoch and a monotonically
+// increasing sequence number; create one per worker/assigner incarnation
+pub struct StatusSequencer {
+    epoch: u64,
+    next_sequence: AtomicU64,
+}
+
+impl StatusSequencer {
+    pub fn new() -> Self {
+        Self {
+            // Random rather than time-based so two publishers never share an epoch;
+            // zero is reserved for unsequenced updates
+            epoch: uuid::Uuid::new_v4().as_u64_pair().0.max(1),
+            next_sequence: AtomicU64::new(1),
+        }
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    pub fn stamp(&self, mut status: Status) -> Status {
+        status.epoch = self.epoch;
+        status.sequence = self.next_sequence.fetch_add(1, Ordering::Relaxed);
+        status
+    }
+}
+
+impl Default for StatusSequencer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Latest accepted status per task, built from every status update on a queue.
+// Stale updates (older than the latest by sequence or timestamp) are discarded;
+// updates that would make an illegal transition are flagged and dropped.
+// While running it also serves paginated task listings for its queue, and task
+// trees built from the parents of announced jobs.
+#[derive(Default)]
+pub struct StatusAggregator {
+    tasks: BTreeMap<String, (StateMachine, Status)>,
+    origins: HashMap<String, TaskOrigin>,
+    timings: HashMap<String, TaskTimings>,
+    verbosity: Verbosity,
+}
+
+// What the job announcement said about a task
+struct TaskOrigin {
+    name: String,
+    parent_task_id: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Default)]
+struct TaskTimings {
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+    finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl StatusAggregator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    // Ok(false) when the update was stale and ignored
+    pub fn apply(&mut self, status: Status) -> Result<bool> {
+        match self.tasks.get_mut(&status.task_id) {
+            Some((machine, latest)) => {
+                if !status.supersedes(latest) {
+                    return Ok(false);
+                }
+                machine
+                    .transition(status.status.clone())
+                    .map_err(|e| anyhow::anyhow!("Task {} from {}: {}", status.task_id, status.worker_id, e))?;
+                self.record_timing(&status);
+                *latest = status;
+            }
+            None => {
+                self.record_timing(&status);
+                let machine = StateMachine::new(status.status.clone());
+                self.tasks.insert(status.task_id.clone(), (machine, status));
+            }
+        }
+        Ok(true)
+    }
+
+    fn record_timing(&mut self, status: &Status) {
+        let timings = self.timings.entry(status.task_id.clone()).or_default();
+        if status.status == TaskStatus::Running {
+            timings.started_at.get_or_insert(status.timestamp);
+        }
+        if status.status.is_terminal() {
+            timings.finished_at = Some(status.timestamp);
+        }
+    }
+
+    // Remembers where an announced job came from; re-announcements keep the first creation
+    pub fn record_job(&mut self, job: &Job) {
+        self.origins.entry(job.task_id.clone()).or_insert_with(|| TaskOrigin {
+            name: job.name().to_string(),
+            parent_task_id: job.parent_task_id.clone(),
+            created_at: job.created_at,
+        });
+    }
+
+    fn parent_of(&self, task_id: &str) -> Option<&str> {
+        self.origins.get(task_id)?.parent_task_id.as_deref()
+    }
+
+    // The whole tree `task_id` belongs to, from its root down; None for unknown tasks
+    pub fn tree(&self, task_id: &str) -> Option<TaskTree> {
+        if !self.tasks.contains_key(task_id) && !self.origins.contains_key(task_id) {
+            return None;
+        }
+        let mut root = task_id;
+        let mut visited = HashSet::from([root]);
+        while let Some(parent) = self.parent_of(root) {
+            if !visited.insert(parent) {
+                break;
+            }
+            root = parent;
+        }
+
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        let known: HashSet<&str> = self.tasks.keys().chain(self.origins.keys()).map(String::as_str).collect();
+        for &task_id in &known {
+            if let Some(parent) = self.parent_of(task_id) {
+                children.entry(parent).or_default().push(task_id);
+            }
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|&task_id| (self.origins.get(task_id).map(|origin| origin.created_at), task_id));
+        }
+
+        let mut budget = MAX_TASK_TREE_NODES;
+        let mut seen = HashSet::new();
+        let root = self.tree_node(root, &children, &mut budget, &mut seen);
+        Some(TaskTree { root, truncated: budget == 0 })
+    }
+
+    fn tree_node<'a>(
+        &self,
+        task_id: &'a str,
+        children: &HashMap<&str, Vec<&'a str>>,
+        budget: &mut usize,
+        seen: &mut HashSet<&'a str>,
+    ) -> TaskTreeNode {
+        *budget = budget.saturating_sub(1);
+        seen.insert(task_id);
+        let origin = self.origins.get(task_id);
+        let status = self.get(task_id);
+        let timings = self.timings.get(task_id);
+        let mut node = TaskTreeNode {
+            task_id: task_id.to_string(),
+            name: origin.map(|origin| origin.name.clone()),
+            status: status.map(|status| status.status.clone()),
+            worker_id: status.map(|status| status.worker_id.clone()).filter(|worker_id| !worker_id.is_empty()),
+            created_at: origin.map(|origin| origin.created_at),
+            started_at: timings.and_then(|timings| timings.started_at),
+            finished_at: timings.and_then(|timings| timings.finished_at),
+            children: Vec::new(),
+        };
+        for &child in children.get(task_id).into_iter().flatten() {
+            if *budget == 0 {
+                break;
+            }
+            // A parent link loop can't recurse forever
+            if !seen.contains(child) {
+                node.children.push(self.tree_node(child, children, budget, seen));
+            }
+        }
+        node
+    }
+
+    pub fn get(&self, task_id: &str) -> Option<&Status> {
+        self.tasks.get(task_id).map(|(_, status)| status)
+    }
+
+    pub fn statuses(&self) -> impl Iterator<Item = &Status> {
+        self.tasks.values().map(|(_, status)| status)
+    }
+
+    // The page of matching tasks following `request.page_token`, ordered by task ID
+    pub fn list(&self, request: &TaskListRequest) -> TaskPage {
+        let page_size = request.page_size.clamp(1, MAX_TASK_PAGE_SIZE);
+        let start = match &request.page_token {
+            Some(token) => Bound::Excluded(token.as_str()),
+            None => Bound::Unbounded,
+        };
+        let mut matching = self
+            .tasks
+            .range::<str, _>((start, Bound::Unbounded))
+            .map(|(_, (_, status))| status)
+            .filter(|status| request.filter.matches(status));
+        let tasks: Vec<TaskSummary> = matching.by_ref().take(page_size).map(TaskSummary::from).collect();
+        let next_page_token = match matching.next() {
+            Some(_) => tasks.last().map(|task| task.task_id.clone()),
+            None => None,
+        };
+        TaskPage { tasks, next_page_token }
+    }
+
+    pub async fn run(&mut self, session: &Session, keyspace: &Keyspace, shutdown: CancellationToken) -> Result<()> {
+        let statuses = session
+            .declare_subscriber(keyspace.all_statuses())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare status subscriber: {}", e))?;
+        let batches = session
+            .declare_subscriber(keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+        let listings = session
+            .declare_queryable(keyspace.task_list())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare task list queryable: {}", e))?;
+        let trees = session
+            .declare_queryable(keyspace.task_tree())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare task tree queryable: {}", e))?;
+        let announcements = session
+            .declare_subscriber(keyspace.announce())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare job subscriber: {}", e))?;
+        let sender = session.zid().to_string();
+
+        loop {
+            let received = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                query = listings.recv_async() => {
+                    let Ok(query) = query else { break };
+                    let request = match query.payload() {
+                        Some(payload) => deserialize_payload_with_context::<TaskListRequest>(
+                            query.key_expr().as_str(),
+                            payload,
+                            query.attachment(),
+                            "task list request",
+                        ),
+                        None => Ok(TaskListRequest::default()),
+                    };
+                    let reply = match request {
+                        Ok(request) => match seal(&sender, &self.list(&request)) {
+                            Ok(payload) => query.reply(keyspace.task_list(), payload).await,
+                            Err(e) => query.reply_err(format!("Failed to encode the task list: {:#}", e)).await,
+                        },
+                        Err(e) => query.reply_err(format!("{:#}", e)).await,
+                    };
+                    if let Err(e) = reply {
+                        summary!(self.verbosity, "⚠️  Failed to reply to task list query: {}", e);
+                    }
+                    continue;
+                }
+                query = trees.recv_async() => {
+                    let Ok(query) = query else { break };
+                    let tree = match query.payload() {
+                        Some(payload) => deserialize_payload_with_context::<TaskTreeRequest>(
+                            query.key_expr().as_str(),
+                            payload,
+                            query.attachment(),
+                            "task tree request",
+                        )
+                        .and_then(|request| {
+                            self.tree(&request.task_id)
+                                .ok_or_else(|| anyhow::anyhow!("Unknown task {}", request.task_id))
+                        }),
+                        None => Err(anyhow::anyhow!("Task tree requests name a task")),
+                    };
+                    let reply = match tree {
+                        Ok(tree) => match seal(&sender, &tree) {
+                            Ok(payload) => query.reply(keyspace.task_tree(), payload).await,
+                            Err(e) => query.reply_err(format!("Failed to encode the task tree: {:#}", e)).await,
+                        },
+                        Err(e) => query.reply_err(format!("{:#}", e)).await,
+                    };
+                    if let Err(e) = reply {
+                        summary!(self.verbosity, "⚠️  Failed to reply to task tree query: {}", e);
+                    }
+                    continue;
+                }
+                sample = announcements.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    match deserialize_from_sample_with_context::<Job>(&sample, "job") {
+                        Ok(job) => self.record_job(&job),
+                        Err(e) => summary!(self.verbosity, "⚠️  Ignoring malformed job: {}", e),
+                    }
+                    continue;
+                }
+                sample = statuses.recv_async() => match sample {
+                    Ok(sample) => vec![deserialize_from_sample_with_context::<Status>(&sample, "status")],
+                    Err(_) => break,
+                },
+                sample = batches.recv_async() => match sample {
+                    Ok(sample) => unbatch_messages::<Status>(&sample, &keyspace.all_statuses(), "status"),
+                    Err(_) => break,
+                },
+            };
+            for status in received {
+                match status {
+                    Ok(status) => {
+                        if let Err(e) = self.apply(status) {
+                            summary!(self.verbosity, "⚠️  {}", e);
+                        }
+                    }
+                    Err(e) => summary!(self.verbosity, "⚠️  Ignoring malformed status: {}", e),
+                }
+            }
+        }
+        Ok(())
+    }
+}
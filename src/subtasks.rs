@@ -0,0 +1,108 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::schema::{AuditEntry, Job, Result as TaskResult, TaskDefinition, TaskStatus};
+
+// Output a task returns to have its worker submit child jobs, e.g. the map step of a
+// computation. It is replaced by CHILDREN_OUTPUT once every child has finished.
+pub const CHILD_JOBS_OUTPUT: &str = "__children__";
+// Output listing each child's task ID, status, outputs and error, in spawn order
+pub const CHILDREN_OUTPUT: &str = "children";
+// Caps how many children a single task may spawn
+pub const MAX_CHILD_JOBS: usize = 256;
+
+// One entry of CHILD_JOBS_OUTPUT; children run the parent's task definition unless
+// they bring their own
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildJobSpec {
+    #[serde(default)]
+    pub task_definition: Option<TaskDefinition>,
+    #[serde(default)]
+    pub inputs: serde_json::Value,
+}
+
+// What the parent's result records of a finished child
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChildOutcome {
+    pub task_id: String,
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub outputs: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ChildOutcome {
+    pub fn from_result(result: &TaskResult) -> Self {
+        Self {
+            task_id: result.task_id.clone(),
+            status: result.status.clone(),
+            outputs: result.outputs.clone().into_iter().collect(),
+            error: result.error.clone(),
+        }
+    }
+
+    // A child that never produced a result, e.g. because it timed out
+    pub fn lost(task_id: &str, error: String) -> Self {
+        Self {
+            task_id: task_id.to_string(),
+            status: TaskStatus::Failed,
+            outputs: serde_json::Map::new(),
+            error: Some(error),
+        }
+    }
+}
+
+// Takes CHILD_JOBS_OUTPUT out of `result` and builds the jobs it asks for, on the
+// parent's queue and tagged with its task ID. Empty when the task spawned nothing.
+pub fn child_jobs(parent: &Job, result: &mut TaskResult, worker_id: &str) -> Result<Vec<Job>> {
+    let Some(specs) = result.outputs.remove(CHILD_JOBS_OUTPUT) else {
+        return Ok(Vec::new());
+    };
+    let specs: Vec<ChildJobSpec> = serde_json::from_value(specs)
+        .with_context(|| format!("Task {} returned malformed {}", parent.task_id, CHILD_JOBS_OUTPUT))?;
+    if specs.len() > MAX_CHILD_JOBS {
+        anyhow::bail!("Task {} spawned {} child jobs, more than the {} allowed", parent.task_id, specs.len(), MAX_CHILD_JOBS);
+    }
+    Ok(specs
+        .into_iter()
+        .map(|spec| {
+            let mut child = parent.clone();
+            child.task_id = uuid::Uuid::new_v4().to_string();
+            if let Some(task_definition) = spec.task_definition {
+                // The parent's pin is for the parent's definition
+                child.task_definition = Some(task_definition);
+                child.definition_pin = None;
+            }
+            child.inputs = spec.inputs;
+            // The parent's offloaded inputs aren't the child's
+            child.inputs_ref = None;
+            child.created_at = chrono::Utc::now();
+            child.attempt = 1;
+            child.parent_task_id = Some(parent.task_id.clone());
+            child.audit_trail = vec![AuditEntry {
+                attempt: child.attempt,
+                worker_id: Some(worker_id.to_string()),
+                event: format!("spawned by task {}", parent.task_id),
+                timestamp: child.created_at,
+            }];
+            child
+        })
+        .collect())
+}
+
+// Records the children under CHILDREN_OUTPUT; the parent fails with the first child that did
+pub fn merge_children(result: &mut TaskResult, children: Vec<ChildOutcome>) {
+    if let Some(failed) = children.iter().find(|child| child.status != TaskStatus::Completed) {
+        result.status = TaskStatus::Failed;
+        result.error = Some(format!(
+            "Child task {} {:?}: {}",
+            failed.task_id,
+            failed.status,
+            failed.error.as_deref().unwrap_or("no error reported")
+        ));
+    }
+    result.outputs.insert(
+        CHILDREN_OUTPUT.to_string(),
+        serde_json::to_value(children).unwrap_or_default(),
+    );
+}
@@ -0,0 +1,400 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use zenoh::Session;
+use crate::batching::unbatch_messages;
+use crate::envelope::Message;
+use crate::keyspace::Keyspace;
+use crate::schema::{Job, Result as TaskResult, Status, TaskDefinition, TaskStatus};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::deserialize_from_sample_with_context;
+use crate::summary;
+
+// Parquet files can't be appended to, so records are written in files of this many rows
+const PARQUET_ROWS_PER_FILE: usize = 1000;
+
+// One finished task as kept for offline analysis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchivedTask {
+    pub task_id: String,
+    pub queue: String,
+    pub attempt: u32,
+    pub status: TaskStatus,
+    pub worker_id: Option<String>,
+    // Unknown for tasks only found in storage, whose announcement was never seen
+    pub task_definition: Option<TaskDefinition>,
+    #[serde(default)]
+    pub inputs: serde_json::Value,
+    pub result: Option<TaskResult>,
+    pub submitted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ArchivedTask {
+    pub fn name(&self) -> &str {
+        self.task_definition.as_ref().map(|td| td.name.as_str()).unwrap_or("unknown")
+    }
+
+    // From submission until the task finished
+    pub fn total_seconds(&self) -> Option<f64> {
+        self.submitted_at.map(|at| (self.finished_at - at).num_milliseconds() as f64 / 1000.0)
+    }
+
+    pub fn execution_seconds(&self) -> Option<f64> {
+        self.result.as_ref().and_then(|result| result.execution_time_seconds)
+    }
+
+    // Reads a JSONL archive back, e.g. for replaying it
+    pub fn read_jsonl(path: &Path) -> Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).with_context(|| format!("Invalid archived task on line {} of {}", index + 1, path.display()))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    Jsonl,
+    Parquet,
+}
+
+impl ArchiveFormat {
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl" | "ndjson") => Ok(ArchiveFormat::Jsonl),
+            Some("parquet") => Ok(ArchiveFormat::Parquet),
+            _ => anyhow::bail!("Unsupported archive file {} (expected .jsonl or .parquet)", path.display()),
+        }
+    }
+}
+
+// Destination of archived tasks. JSONL is appended to as tasks arrive; Parquet is
+// written next to the given path in files of up to 1000 rows, named after it plus
+// when the archive was opened and a counter (`tasks-20260101T120000-0001.parquet`).
+pub struct TaskArchive {
+    path: PathBuf,
+    format: ArchiveFormat,
+    jsonl: Option<BufWriter<File>>,
+    buffered: Vec<ArchivedTask>,
+    opened_at: chrono::DateTime<chrono::Utc>,
+    files_written: usize,
+    archived: u64,
+    verbosity: Verbosity,
+}
+
+impl TaskArchive {
+    // Format is picked from the extension
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let format = ArchiveFormat::from_path(&path)?;
+        if format == ArchiveFormat::Parquet && !cfg!(feature = "dataframe") {
+            anyhow::bail!("Parquet archives need the 'dataframe' feature; use .jsonl or rebuild with --features dataframe");
+        }
+        let jsonl = match format {
+            ArchiveFormat::Jsonl => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                Some(BufWriter::new(file))
+            }
+            ArchiveFormat::Parquet => None,
+        };
+        Ok(Self {
+            path,
+            format,
+            jsonl,
+            buffered: Vec::new(),
+            opened_at: chrono::Utc::now(),
+            files_written: 0,
+            archived: 0,
+            verbosity: Verbosity::default(),
+        })
+    }
+
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        self
+    }
+
+    pub fn format(&self) -> ArchiveFormat {
+        self.format
+    }
+
+    // Tasks handed to this archive so far
+    pub fn archived(&self) -> u64 {
+        self.archived
+    }
+
+    pub fn append(&mut self, task: ArchivedTask) -> Result<()> {
+        self.archived += 1;
+        match &mut self.jsonl {
+            Some(writer) => {
+                serde_json::to_writer(&mut *writer, &task)?;
+                writer.write_all(b"\n")?;
+            }
+            None => {
+                self.buffered.push(task);
+                if self.buffered.len() >= PARQUET_ROWS_PER_FILE {
+                    self.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Everything appended so far is on disk afterwards
+    pub fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = &mut self.jsonl {
+            return writer.flush().with_context(|| format!("Failed to write {}", self.path.display()));
+        }
+        if self.buffered.is_empty() {
+            return Ok(());
+        }
+        self.files_written += 1;
+        let path = self.path.with_file_name(format!(
+            "{}-{}-{:04}.parquet",
+            self.path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("tasks"),
+            self.opened_at.format("%Y%m%dT%H%M%S"),
+            self.files_written
+        ));
+        write_parquet(&path, &self.buffered)?;
+        self.buffered.clear();
+        Ok(())
+    }
+}
+
+impl Drop for TaskArchive {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            summary!(self.verbosity, "⚠️  Failed to flush task archive {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(feature = "dataframe")]
+fn write_parquet(path: &Path, tasks: &[ArchivedTask]) -> Result<()> {
+    crate::dataframe::write_task_archive(path, tasks)
+}
+
+#[cfg(not(feature = "dataframe"))]
+fn write_parquet(path: &Path, _tasks: &[ArchivedTask]) -> Result<()> {
+    anyhow::bail!("Cannot write {}: Parquet archives need the 'dataframe' feature", path.display())
+}
+
+// What has been seen of a task that hasn't finished yet
+#[derive(Default)]
+struct InFlight {
+    job: Option<Job>,
+    started_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Records every task finishing on a queue into a TaskArchive: the job as announced,
+// when it started running, and its result
+pub struct TaskArchiver {
+    keyspace: Keyspace,
+    archive: Mutex<TaskArchive>,
+    in_flight: Mutex<HashMap<String, InFlight>>,
+    verbosity: Verbosity,
+}
+
+impl TaskArchiver {
+    pub fn new(keyspace: Keyspace, archive: TaskArchive) -> Self {
+        Self {
+            keyspace,
+            archive: Mutex::new(archive),
+            in_flight: Mutex::new(HashMap::new()),
+            verbosity: Verbosity::default(),
+        }
+    }
+
+    // Also applies to the archive's own warnings
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.verbosity = verbosity;
+        let archive = self.archive.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+        archive.verbosity = verbosity;
+        self
+    }
+
+    pub fn archived(&self) -> u64 {
+        self.lock_archive().archived()
+    }
+
+    pub async fn run(&self, session: &Session, shutdown: CancellationToken) -> Result<()> {
+        let jobs = session
+            .declare_subscriber(self.keyspace.announce())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare announce subscriber: {}", e))?;
+        let statuses = session
+            .declare_subscriber(self.keyspace.all_statuses())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare status subscriber: {}", e))?;
+        let results = session
+            .declare_subscriber(self.keyspace.all_results())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare result subscriber: {}", e))?;
+        let batches = session
+            .declare_subscriber(self.keyspace.all_batches())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare batch subscriber: {}", e))?;
+        summary!(self.verbosity, "🗄️  Task archiver started on queue {}", self.keyspace.queue());
+
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                sample = jobs.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(job) = deserialize_from_sample_with_context::<Job>(&sample, "job") {
+                        self.on_job(job);
+                    }
+                }
+                sample = statuses.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(status) = deserialize_from_sample_with_context::<Status>(&sample, "status") {
+                        self.on_status(status)?;
+                    }
+                }
+                sample = results.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    if let Ok(result) = deserialize_from_sample_with_context::<TaskResult>(&sample, "result") {
+                        self.on_result(result)?;
+                    }
+                }
+                sample = batches.recv_async() => {
+                    let Ok(sample) = sample else { break };
+                    for status in unbatch_messages::<Status>(&sample, &self.keyspace.all_statuses(), "status").into_iter().flatten() {
+                        self.on_status(status)?;
+                    }
+                    for result in unbatch_messages::<TaskResult>(&sample, &self.keyspace.all_results(), "result").into_iter().flatten() {
+                        self.on_result(result)?;
+                    }
+                }
+            }
+        }
+
+        self.flush()?;
+        summary!(self.verbosity, "🗄️  Task archiver stopped after archiving {} task(s)", self.archived());
+        Ok(())
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.lock_archive().flush()
+    }
+
+    // Archives a task this archiver never saw finish (e.g. one that finished before
+    // it started) from what the queue's storages still hold
+    pub async fn archive_from_storage(&self, session: &Session, task_id: &str) -> Result<()> {
+        let status: Option<Status> = fetch_stored(session, &self.keyspace.status(task_id), "status").await;
+        let result: Option<TaskResult> = fetch_stored(session, &self.keyspace.result(task_id), "result").await;
+        let finished_at = match (&result, &status) {
+            (Some(result), _) => result.completed_at,
+            (None, Some(status)) => status.timestamp,
+            (None, None) => anyhow::bail!("Nothing stored for task {}", task_id),
+        };
+        self.lock_archive().append(ArchivedTask {
+            task_id: task_id.to_string(),
+            queue: self.keyspace.queue().to_string(),
+            attempt: 1,
+            status: result
+                .as_ref()
+                .map(|result| result.status.clone())
+                .or_else(|| status.as_ref().map(|status| status.status.clone()))
+                .unwrap_or(TaskStatus::Completed),
+            worker_id: result.as_ref().map(|result| result.worker_id.clone()).or(status.map(|status| status.worker_id)),
+            task_definition: None,
+            inputs: serde_json::Value::Null,
+            result,
+            submitted_at: None,
+            started_at: None,
+            finished_at,
+        })
+    }
+
+    fn on_job(&self, job: Job) {
+        // A retry replaces the previous attempt
+        self.lock_in_flight().entry(job.task_id.clone()).or_default().job = Some(job);
+    }
+
+    fn on_status(&self, status: Status) -> Result<()> {
+        if status.status == TaskStatus::Running {
+            let mut in_flight = self.lock_in_flight();
+            let entry = in_flight.entry(status.task_id.clone()).or_default();
+            entry.started_at.get_or_insert(status.timestamp);
+        } else if status.status == TaskStatus::Cancelled {
+            // Cancelled tasks may never produce a result
+            let entry = self.lock_in_flight().remove(&status.task_id).unwrap_or_default();
+            self.archive(entry, &status.task_id, status.status, Some(status.worker_id), None, status.timestamp)?;
+        }
+        Ok(())
+    }
+
+    fn on_result(&self, result: TaskResult) -> Result<()> {
+        let entry = self.lock_in_flight().remove(&result.task_id).unwrap_or_default();
+        let task_id = result.task_id.clone();
+        let status = result.status.clone();
+        let worker_id = Some(result.worker_id.clone());
+        let finished_at = result.completed_at;
+        self.archive(entry, &task_id, status, worker_id, Some(result), finished_at)
+    }
+
+    fn archive(
+        &self,
+        entry: InFlight,
+        task_id: &str,
+        status: TaskStatus,
+        worker_id: Option<String>,
+        result: Option<TaskResult>,
+        finished_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        let (attempt, task_definition, inputs, submitted_at) = match entry.job {
+            Some(job) => (job.attempt, job.task_definition, job.inputs, Some(job.created_at)),
+            None => (1, None, serde_json::Value::Null, None),
+        };
+        self.lock_archive().append(ArchivedTask {
+            task_id: task_id.to_string(),
+            queue: self.keyspace.queue().to_string(),
+            attempt,
+            status,
+            worker_id,
+            task_definition,
+            inputs,
+            result,
+            submitted_at,
+            started_at: entry.started_at,
+            finished_at,
+        })
+    }
+
+    fn lock_archive(&self) -> std::sync::MutexGuard<'_, TaskArchive> {
+        self.archive.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_in_flight(&self) -> std::sync::MutexGuard<'_, HashMap<String, InFlight>> {
+        self.in_flight.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+async fn fetch_stored<T: Message>(session: &Session, key: &str, context: &str) -> Option<T> {
+    let replies = session.get(key.to_string()).await.ok()?;
+    while let Ok(reply) = replies.recv_async().await {
+        if let Ok(sample) = reply.result() {
+            if let Ok(message) = deserialize_from_sample_with_context(sample, context) {
+                return Some(message);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+use sysinfo::{Components, CpuRefreshKind, Disks, MemoryRefreshKind, RefreshKind, System};
+use crate::schema::HostMetrics;
+
+// Keeps the sysinfo handles between samples: CPU usage is measured as the
+// difference between two refreshes, and listing disks and sensors is slow
+pub struct HostMonitor {
+    system: System,
+    disks: Disks,
+    components: Components,
+    workspace_root: PathBuf,
+}
+
+impl HostMonitor {
+    pub fn new(workspace_root: impl Into<PathBuf>) -> Self {
+        let refresh = RefreshKind::new()
+            .with_cpu(CpuRefreshKind::new().with_cpu_usage())
+            .with_memory(MemoryRefreshKind::new().with_ram());
+        Self {
+            system: System::new_with_specifics(refresh),
+            disks: Disks::new_with_refreshed_list(),
+            components: Components::new_with_refreshed_list(),
+            workspace_root: workspace_root.into(),
+        }
+    }
+
+    pub fn sample(&mut self) -> HostMetrics {
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        self.disks.refresh();
+        self.components.refresh();
+
+        let (disk_total_bytes, disk_available_bytes) = self
+            .workspace_disk()
+            .map_or((0, 0), |disk| (disk.total_space(), disk.available_space()));
+        HostMetrics {
+            cpu_usage_percent: self.system.global_cpu_info().cpu_usage(),
+            load_average_1m: System::load_average().one,
+            total_memory_bytes: self.system.total_memory(),
+            available_memory_bytes: self.system.available_memory(),
+            disk_total_bytes,
+            disk_available_bytes,
+            max_temperature_celsius: self
+                .components
+                .iter()
+                .map(|component| component.temperature())
+                .filter(|temperature| temperature.is_finite())
+                .reduce(f32::max),
+        }
+    }
+
+    // The disk with the longest mount point containing the workspace root
+    fn workspace_disk(&self) -> Option<&sysinfo::Disk> {
+        let root = self.workspace_root.canonicalize().unwrap_or_else(|_| self.workspace_root.clone());
+        self.disks
+            .iter()
+            .filter(|disk| root.starts_with(disk.mount_point()))
+            .max_by_key(|disk| disk.mount_point().components().count())
+            .or_else(|| self.disks.iter().find(|disk| disk.mount_point() == Path::new("/")))
+    }
+}
@@ -0,0 +1,115 @@
+use crate::schema::ResourceUsage;
+
+// Resource usage of a running process group, sampled from /proc while it runs.
+// Counters are cumulative per process, so the maximum over all samples is kept;
+// anything done between the last sample and exit is missed, which the sampling
+// interval bounds.
+#[derive(Debug, Default)]
+pub struct UsageTracker {
+    usage: Option<ResourceUsage>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sample(&mut self, pgid: u32) {
+        let Some(sample) = sample_process_group(pgid) else {
+            return;
+        };
+        let usage = self.usage.get_or_insert_with(ResourceUsage::default);
+        usage.peak_rss_bytes = usage.peak_rss_bytes.max(sample.peak_rss_bytes);
+        usage.cpu_time_seconds = usage.cpu_time_seconds.max(sample.cpu_time_seconds);
+        usage.read_bytes = usage.read_bytes.max(sample.read_bytes);
+        usage.write_bytes = usage.write_bytes.max(sample.write_bytes);
+    }
+
+    pub fn usage(&self) -> Option<ResourceUsage> {
+        self.usage
+    }
+}
+
+// Current totals across every process in the group; the peak RSS is the larger of
+// the group's current RSS and the leader's own high-water mark
+#[cfg(target_os = "linux")]
+pub fn sample_process_group(pgid: u32) -> Option<ResourceUsage> {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(1) as u64;
+    let clock_ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) }.max(1) as f64;
+
+    let mut usage = ResourceUsage::default();
+    let mut found = false;
+    let mut group_rss = 0;
+    for entry in std::fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Some(stat) = read_stat(pid) else { continue };
+        if stat.pgrp != pgid {
+            continue;
+        }
+        found = true;
+        group_rss += stat.rss_pages * page_size;
+        usage.cpu_time_seconds += stat.cpu_ticks as f64 / clock_ticks;
+        if let Some((read, written)) = read_io(pid) {
+            usage.read_bytes += read;
+            usage.write_bytes += written;
+        }
+    }
+    if !found {
+        return None;
+    }
+    usage.peak_rss_bytes = group_rss.max(read_peak_rss(pgid).unwrap_or(0));
+    Some(usage)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn sample_process_group(_pgid: u32) -> Option<ResourceUsage> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+struct ProcStat {
+    pgrp: u32,
+    // utime + stime, plus the same for children it has reaped
+    cpu_ticks: u64,
+    rss_pages: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn read_stat(pid: u32) -> Option<ProcStat> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name may contain spaces and parentheses; fields resume after the last ')'
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|value| value.parse::<u64>().ok());
+    Some(ProcStat {
+        pgrp: field(2)? as u32,
+        cpu_ticks: field(11)? + field(12)? + field(13)? + field(14)?,
+        rss_pages: field(21)?,
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_io(pid: u32) -> Option<(u64, u64)> {
+    let io = std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?;
+    let value = |key: &str| {
+        io.lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    Some((value("read_bytes:")?, value("write_bytes:")?))
+}
+
+#[cfg(target_os = "linux")]
+fn read_peak_rss(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kilobytes = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
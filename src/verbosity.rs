@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+// How much a component prints to stdout. Libraries embedding these components get
+// nothing by default; the demos opt into summary or debug output.
+//   off:     silent
+//   summary: start/stop, one line per finished task, warnings and errors
+//   debug:   everything, including the per-message scheduling narration
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    #[default]
+    Off,
+    Summary,
+    Debug,
+}
+
+impl Verbosity {
+    pub fn shows_summary(self) -> bool {
+        self >= Verbosity::Summary
+    }
+
+    pub fn shows_debug(self) -> bool {
+        self >= Verbosity::Debug
+    }
+}
+
+impl std::str::FromStr for Verbosity {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "off" | "quiet" => Ok(Verbosity::Off),
+            "summary" => Ok(Verbosity::Summary),
+            "debug" => Ok(Verbosity::Debug),
+            _ => anyhow::bail!("Unknown verbosity {:?} (expected off, summary or debug)", value),
+        }
+    }
+}
+
+// println! when `verbosity` includes summary output
+#[macro_export]
+macro_rules! summary {
+    ($verbosity:expr, $($arg:tt)*) => {
+        if $verbosity.shows_summary() {
+            println!($($arg)*);
+        }
+    };
+}
+
+// println! when `verbosity` includes debug output
+#[macro_export]
+macro_rules! detail {
+    ($verbosity:expr, $($arg:tt)*) => {
+        if $verbosity.shows_debug() {
+            println!($($arg)*);
+        }
+    };
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+// Capacity of the in-memory stdout/stderr pipes handed to the guest
+const MAX_WASM_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+struct WasmState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+pub struct WasmOutput {
+    pub exit_code: i32,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+// An engine whose guests can be stopped from another thread with `interrupt_wasm`
+pub fn interruptible_engine() -> Result<Engine> {
+    let mut config = Config::new();
+    config.epoch_interruption(true);
+    Engine::new(&config)
+}
+
+// Makes every guest running on `engine` trap at its next loop header or call
+pub fn interrupt_wasm(engine: &Engine) {
+    engine.increment_epoch();
+}
+
+// Runs a WASI command module synchronously: inputs arrive on stdin, outputs are read
+// from stdout. Linear memory is capped at `memory_mb`, and `engine` must come from
+// `interruptible_engine`. Call this from the execution pool, never from an async task.
+pub fn run_wasi_command(engine: &Engine, wasm_bytes: &[u8], stdin: Vec<u8>, memory_mb: Option<u64>) -> Result<WasmOutput> {
+    let module = Module::new(engine, wasm_bytes).context("Failed to compile WASM module")?;
+
+    let mut linker: Linker<WasmState> = Linker::new(engine);
+    preview1::add_to_linker_sync(&mut linker, |state| &mut state.wasi)?;
+
+    let stdout = MemoryOutputPipe::new(MAX_WASM_OUTPUT_BYTES);
+    let stderr = MemoryOutputPipe::new(MAX_WASM_OUTPUT_BYTES);
+    let wasi = WasiCtxBuilder::new()
+        .stdin(MemoryInputPipe::new(stdin))
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .build_p1();
+    let mut limits = StoreLimitsBuilder::new().trap_on_grow_failure(true);
+    if let Some(memory_mb) = memory_mb {
+        let bytes = memory_mb.saturating_mul(1024 * 1024);
+        limits = limits.memory_size(usize::try_from(bytes).unwrap_or(usize::MAX));
+    }
+    let mut store = Store::new(engine, WasmState { wasi, limits: limits.build() });
+    store.limiter(|state| &mut state.limits);
+    // Any epoch bump past the current one stops the guest
+    store.set_epoch_deadline(1);
+    store.epoch_deadline_trap();
+
+    let instance = linker.instantiate(&mut store, &module).context("Failed to instantiate WASM module")?;
+    let start = instance
+        .get_typed_func::<(), ()>(&mut store, "_start")
+        .context("WASM module has no _start export (expected a WASI command)")?;
+
+    // proc_exit surfaces as an I32Exit trap rather than a normal return
+    let exit_code = match start.call(&mut store, ()) {
+        Ok(()) => 0,
+        Err(e) => match e.downcast_ref::<I32Exit>() {
+            Some(exit) => exit.0,
+            None if e.downcast_ref::<Trap>() == Some(&Trap::Interrupt) => return Err(e.context("WASM execution interrupted")),
+            None => return Err(e.context("WASM execution trapped")),
+        },
+    };
+    drop(store);
+
+    Ok(WasmOutput {
+        exit_code,
+        stdout: stdout.contents().to_vec(),
+        stderr: stderr.contents().to_vec(),
+    })
+}
@@ -0,0 +1,1057 @@
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OnceCell, Semaphore, SemaphorePermit};
+use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
+use zenoh::Session;
+use crate::benchmark::scores_from_outputs;
+use crate::builtins::builtin_task;
+use crate::client::TaskClient;
+use crate::config::{DependencyPolicy, WorkerConfig};
+#[cfg(feature = "docker")]
+use crate::container_pool::ContainerPool;
+#[cfg(feature = "http")]
+use crate::download::Downloader;
+use crate::dynamic_executor::DynamicTaskExecutor;
+use crate::execution_pool::ExecutionPool;
+use crate::debug_bundle::DebugBundle;
+use crate::envelope::seal;
+use crate::events::{EventBus, LifecycleEvent};
+use crate::fan_in::{await_streams, bind_streams};
+use crate::keyspace::{validate_key_segment, Keyspace};
+use crate::process::kill_all_process_groups;
+use crate::language::is_auto_language;
+use crate::large_inputs::fetch_inputs;
+use crate::qos::{MessageClass, QosPolicy};
+use crate::runtimes::RuntimeInventory;
+use crate::scheduling::{ClaimDecision, Coordination};
+use crate::selftest::{probe_task, BackendCheck, SelfTestReport, SELF_TEST_BACKENDS};
+use crate::status::{StateMachine, StatusSequencer};
+use crate::subtasks::{child_jobs, merge_children, ChildOutcome};
+use crate::telemetry::HostMonitor;
+use crate::workspace::sweep_stale_workspaces;
+use crate::schema::{
+    Assign, AssignAck, BenchmarkScores, Claim, Heartbeat, Isolation, Job, Result as TaskResult, Status, TaskDefinition, TaskRequirements, TaskStatus, WorkerInfo,
+    WorkerStatus, DEFAULT_FLEET, DEFAULT_TIMEOUT_SECONDS,
+};
+use crate::verbosity::Verbosity;
+use crate::zenoh_utils::{declare_history_subscriber, deserialize_from_sample_with_context, MessageMetadata, PublisherCache};
+use crate::{detail, summary};
+
+// How long a worker waits for the assigner to answer its claim
+const ASSIGNMENT_TIMEOUT: Duration = Duration::from_secs(2);
+// Must stay well below the assigner's lease duration
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+// Replaces the dynamic executor for workers that compute results themselves,
+// e.g. the simulated perception workers
+pub type JobHandler = Arc<dyn Fn(Job) -> BoxFuture<'static, TaskResult> + Send + Sync>;
+
+pub struct Worker {
+    config: WorkerConfig,
+    executor: DynamicTaskExecutor,
+    handler: Option<JobHandler>,
+    claim_delay: Duration,
+    qos: QosPolicy,
+    // Assigned jobs currently executing / waiting for a free execution slot
+    running_tasks: AtomicU32,
+    queued_tasks: AtomicU32,
+    // Tasks assigned to this worker that have not finished, listed in heartbeats
+    assigned_tasks: Mutex<HashSet<String>>,
+    sequencer: StatusSequencer,
+    // Probed once, before the first job is looked at
+    runtimes: OnceCell<RuntimeInventory>,
+    // Run once, after the runtimes are probed
+    self_test: OnceCell<SelfTestReport>,
+    // Run once, at startup when config.benchmark is set; None if it failed
+    benchmark: OnceCell<Option<BenchmarkScores>>,
+    events: Option<EventBus>,
+}
+
+impl Worker {
+    pub fn from_config(config: WorkerConfig) -> Result<Self> {
+        validate_key_segment("Worker ID", &config.worker_id)?;
+        // Clean up after previous worker processes that died mid-task
+        match sweep_stale_workspaces(&config.sandbox.workspace_root, config.verbosity) {
+            Ok(0) => {}
+            Ok(removed) => summary!(config.verbosity, "🧹 Removed {} stale task workspaces", removed),
+            Err(e) => summary!(config.verbosity, "⚠️  Failed to sweep stale workspaces: {}", e),
+        }
+        let pool = Arc::new(ExecutionPool::new(config.concurrency.execution_threads)?);
+        let executor = DynamicTaskExecutor::with_workspace_root(config.sandbox.workspace_root.clone())
+            .with_cache_dir(config.cache.dir.clone())
+            .with_offline(config.sources.offline())
+            .with_dependency_policy(config.sandbox.dependency_policy)
+            .with_debug_bundles(config.sandbox.debug_bundles)
+            .with_execution_pool(pool)
+            .with_verbosity(config.verbosity);
+        #[cfg(feature = "git")]
+        let executor = executor.with_network_settings(&config.sources);
+        #[cfg(feature = "http")]
+        let executor = executor
+            .with_github_token(config.sources.github_token())
+            .with_downloader(Arc::new(
+                Downloader::new(&config.sources, config.cache.dir.join("http"))?.with_verbosity(config.verbosity),
+            ));
+        #[cfg(feature = "docker")]
+        // Without a warm pool or reuse, every Docker task gets a fresh container
+        let executor = if config.backend_enabled("docker") {
+            let mut containers = ContainerPool::connect(config.sandbox.workspace_root.clone(), config.docker.warm_pool.clone())?;
+            containers = containers.with_offline(config.sources.offline()).with_verbosity(config.verbosity);
+            if let Some(reuse_seconds) = config.docker.reuse_seconds {
+                containers = containers.with_reuse(Duration::from_secs(reuse_seconds));
+            }
+            executor.with_container_pool(Arc::new(containers))
+        } else {
+            executor
+        };
+        #[cfg(feature = "firecracker")]
+        let executor = if config.microvm.is_configured() {
+            executor.with_microvm(config.microvm.clone())
+        } else {
+            executor
+        };
+        Ok(Self {
+            config,
+            executor,
+            handler: None,
+            claim_delay: Duration::ZERO,
+            qos: QosPolicy::default(),
+            running_tasks: AtomicU32::new(0),
+            queued_tasks: AtomicU32::new(0),
+            assigned_tasks: Mutex::new(HashSet::new()),
+            sequencer: StatusSequencer::new(),
+            runtimes: OnceCell::new(),
+            self_test: OnceCell::new(),
+            benchmark: OnceCell::new(),
+            events: None,
+        })
+    }
+
+    pub fn with_job_handler(mut self, handler: JobHandler) -> Self {
+        self.handler = Some(handler);
+        self
+    }
+
+    // Artificial delay before claiming, used by the demos to simulate slower peers
+    pub fn with_claim_delay(mut self, claim_delay: Duration) -> Self {
+        self.claim_delay = claim_delay;
+        self
+    }
+
+    pub fn with_qos_policy(mut self, qos: QosPolicy) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    // Reports this worker's claims, starts and finished tasks on `events`
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn with_coordination(mut self, coordination: Coordination) -> Self {
+        self.config.coordination = coordination;
+        self
+    }
+
+    // Overrides `config.verbosity` for this worker's loop and executor
+    pub fn with_verbosity(mut self, verbosity: Verbosity) -> Self {
+        self.config.verbosity = verbosity;
+        self.executor = self.executor.with_verbosity(verbosity);
+        self
+    }
+
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_config(WorkerConfig::from_file(path)?)
+    }
+
+    pub fn worker_id(&self) -> &str {
+        &self.config.worker_id
+    }
+
+    pub fn config(&self) -> &WorkerConfig {
+        &self.config
+    }
+
+    pub fn executor(&self) -> &DynamicTaskExecutor {
+        &self.executor
+    }
+
+    pub async fn open_session(&self) -> Result<zenoh::Session> {
+        let config = self.config.zenoh.to_zenoh_config()?;
+        zenoh::open(config).await.map_err(|e| anyhow::anyhow!("Failed to open Zenoh session: {}", e))
+    }
+
+    // Runtimes found on this host, probed on first use
+    pub async fn runtimes(&self) -> &RuntimeInventory {
+        self.runtimes.get_or_init(|| RuntimeInventory::probe(&self.config)).await
+    }
+
+    pub fn info(&self, status: WorkerStatus) -> WorkerInfo {
+        let mut capabilities = self.config.capabilities.clone();
+        capabilities.extend(self.config.backends.enabled.iter().cloned());
+        if let Some(runtimes) = self.runtimes.get() {
+            capabilities.extend(runtimes.capabilities());
+        }
+        let self_test = self.self_test.get();
+        WorkerInfo {
+            worker_id: self.config.worker_id.clone(),
+            capabilities,
+            status,
+            last_heartbeat: chrono::Utc::now(),
+            host: None,
+            ready: self_test.map(SelfTestReport::is_ready),
+            failed_probes: self_test.map(SelfTestReport::failed_backends).unwrap_or_default(),
+            fleet: self.config.fleet.clone(),
+            benchmark: self.benchmark.get().cloned().flatten(),
+        }
+    }
+
+    // Runs the benchmark built-in, once
+    pub async fn benchmark(&self) -> Option<&BenchmarkScores> {
+        self.benchmark.get_or_init(|| self.run_benchmark()).await.as_ref()
+    }
+
+    async fn run_benchmark(&self) -> Option<BenchmarkScores> {
+        let task_definition = builtin_task("benchmark")?;
+        let outcome = async {
+            let result = self.executor.execute_task(&task_definition, serde_json::json!({})).await?;
+            if result.status != TaskStatus::Completed {
+                anyhow::bail!("{}", result.error.unwrap_or_else(|| format!("Benchmark ended {:?}", result.status)));
+            }
+            scores_from_outputs(&result.outputs)
+        };
+        match outcome.await {
+            Ok(scores) => {
+                summary!(
+                    self.config.verbosity,
+                    "📏 Worker {} benchmark: CPU {} MB/s, memory {} GB/s, disk write {} MB/s, read {} MB/s",
+                    self.config.worker_id,
+                    scores.cpu_mb_per_second,
+                    scores.memory_gb_per_second,
+                    scores.disk_write_mb_per_second,
+                    scores.disk_read_mb_per_second
+                );
+                Some(scores)
+            }
+            Err(e) => {
+                summary!(self.config.verbosity, "⚠️  Worker {} benchmark failed: {}", self.config.worker_id, e);
+                None
+            }
+        }
+    }
+
+    // Runs a built-in task through every enabled backend of SELF_TEST_BACKENDS, once
+    pub async fn self_test(&self) -> &SelfTestReport {
+        self.self_test.get_or_init(|| self.run_self_test()).await
+    }
+
+    async fn run_self_test(&self) -> SelfTestReport {
+        let runtimes = self.runtimes().await;
+        let mut checks = Vec::new();
+        for backend in SELF_TEST_BACKENDS.iter().copied().filter(|backend| self.config.backend_enabled(backend)) {
+            let started = std::time::Instant::now();
+            let outcome = if runtimes.supports(backend) {
+                self.probe(backend).await
+            } else {
+                Err(anyhow::anyhow!("No {} runtime was found on this worker", backend))
+            };
+            checks.push(BackendCheck {
+                backend: backend.to_string(),
+                passed: outcome.is_ok(),
+                duration_ms: started.elapsed().as_millis() as u64,
+                detail: outcome.unwrap_or_else(|e| Some(e.to_string())),
+            });
+        }
+        SelfTestReport {
+            worker_id: self.config.worker_id.clone(),
+            checks,
+        }
+    }
+
+    async fn probe(&self, backend: &str) -> Result<Option<String>> {
+        let Some(task_definition) = probe_task(backend) else {
+            return self.probe_docker().await;
+        };
+        let result = self.executor.execute_task(&task_definition, serde_json::json!({})).await?;
+        if result.status != TaskStatus::Completed {
+            anyhow::bail!("{}", result.error.unwrap_or_else(|| format!("Probe task ended {:?}", result.status)));
+        }
+        if result.outputs.get("ok") != Some(&serde_json::Value::Bool(true)) {
+            anyhow::bail!("Probe task returned unexpected outputs: {:?}", result.outputs);
+        }
+        Ok(None)
+    }
+
+    // A probe container would mean pulling an image at startup; checks that the
+    // daemon answers instead
+    #[cfg(feature = "docker")]
+    async fn probe_docker(&self) -> Result<Option<String>> {
+        let Some(containers) = self.executor.container_pool() else {
+            anyhow::bail!("The executor has no container pool");
+        };
+        containers.docker().ping().await.context("Docker daemon did not answer a ping")?;
+        Ok(Some(format!("Daemon reachable, warm pool for {} image(s)", self.config.docker.warm_pool.len())))
+    }
+
+    #[cfg(not(feature = "docker"))]
+    async fn probe_docker(&self) -> Result<Option<String>> {
+        anyhow::bail!("Built without the docker feature")
+    }
+
+    // Why this worker can't run `task_definition`, if it can't. Runtimes are only
+    // checked once they have been probed.
+    pub fn check_executable(&self, task_definition: &TaskDefinition) -> Result<()> {
+        if task_definition.isolation() == Isolation::Microvm
+            && !(self.config.backend_enabled("microvm") && self.config.microvm.is_configured())
+        {
+            anyhow::bail!("MicroVM isolation is not available on worker {}", self.worker_id());
+        }
+        let wants_dependencies = task_definition.requirements.as_ref().is_some_and(|r| r.install_dependencies);
+        if wants_dependencies && self.config.sandbox.dependency_policy == DependencyPolicy::Never {
+            anyhow::bail!("Worker {} does not install task dependencies", self.worker_id());
+        }
+        // The language of an "auto" task is only known once its source is fetched
+        if is_auto_language(task_definition.backend()) {
+            return Ok(());
+        }
+        if !self.config.backend_enabled(task_definition.backend()) {
+            anyhow::bail!("Backend '{}' is not enabled on worker {}", task_definition.backend(), self.worker_id());
+        }
+        if let Some(runtimes) = self.runtimes.get() {
+            runtimes.check(task_definition)?;
+        }
+        let failed_probe = self.self_test.get().is_some_and(|report| !report.passed(task_definition.backend()));
+        if failed_probe && task_definition.isolation() == Isolation::Process {
+            anyhow::bail!("Backend '{}' failed the self-test on worker {}", task_definition.backend(), self.worker_id());
+        }
+        Ok(())
+    }
+
+    pub fn can_execute(&self, task_definition: &TaskDefinition) -> bool {
+        self.check_executable(task_definition).is_ok()
+    }
+
+    fn accepts(&self, job: &Job) -> Result<()> {
+        match (&job.task_definition, &self.handler) {
+            (_, Some(_)) => Ok(()),
+            (Some(task_definition), None) => self.check_executable(task_definition),
+            (None, None) => anyhow::bail!("No task definition found"),
+        }
+    }
+
+    pub async fn execute(&self, job: &Job) -> Result<TaskResult> {
+        let task_definition = job.task_definition.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No task definition found"))?;
+        if let Some(pin) = &job.definition_pin {
+            pin.verify(task_definition)?;
+        }
+        // Submitters check too, but nothing stops a job from being published by hand.
+        // Child jobs are exempt here as they are at submission.
+        if job.parent_task_id.is_none() {
+            task_definition.check_inputs(&job.inputs)?;
+        }
+        self.runtimes().await;
+        self.check_executable(task_definition)?;
+
+        let task_definition = self.apply_sandbox_defaults(task_definition);
+        let mut result = self.executor.execute_task(&task_definition, job.inputs.clone()).await?;
+        result.task_id = job.task_id.clone();
+        result.worker_id = self.config.worker_id.clone();
+        self.stamp_provenance(&mut result);
+        if let Some(runtimes) = self.runtimes.get() {
+            DebugBundle::update(&mut result, |bundle| bundle.runtimes = runtimes.capabilities())?;
+        }
+        Ok(result)
+    }
+
+    // Like execute, but infrastructure errors are reported as a failed result
+    pub async fn run_job(&self, job: &Job) -> TaskResult {
+        match self.execute(job).await {
+            Ok(result) => result,
+            Err(e) => {
+                let mut result = TaskResult::failure(job.task_id.clone(), self.config.worker_id.clone(), e.to_string());
+                self.stamp_provenance(&mut result);
+                result
+            }
+        }
+    }
+
+    // Adds who ran the task to the result's provenance
+    fn stamp_provenance(&self, result: &mut TaskResult) {
+        let provenance = result.provenance.get_or_insert_with(Default::default);
+        provenance.worker_id = Some(self.config.worker_id.clone());
+        provenance.hostname = sysinfo::System::host_name();
+        provenance.worker_version = Some(env!("CARGO_PKG_VERSION").to_string());
+        if let Some(runtimes) = self.runtimes.get() {
+            provenance.runtimes = runtimes.capabilities();
+        }
+    }
+
+    fn apply_sandbox_defaults(&self, task_definition: &TaskDefinition) -> TaskDefinition {
+        let sandbox = &self.config.sandbox;
+        let mut task_definition = task_definition.clone();
+        let requirements = task_definition.requirements.get_or_insert_with(TaskRequirements::default);
+        requirements.timeout_seconds = requirements.timeout_seconds.or(sandbox.default_timeout_seconds);
+        requirements.disk_mb = requirements.disk_mb.or(sandbox.default_disk_mb);
+        task_definition
+    }
+
+    // Claim/assign/execute loop for one queue; returns once `shutdown` is cancelled.
+    // Up to max_concurrent_tasks jobs execute at once, and tasks that are already
+    // executing are allowed to finish.
+    pub async fn run(&self, session: &Session, keyspace: &Keyspace, shutdown: CancellationToken) -> Result<()> {
+        let worker_id = self.worker_id();
+        let announcements = session
+            .declare_subscriber(keyspace.announce())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to declare announce subscriber: {}", e))?;
+        summary!(self.config.verbosity, "👷 Worker {} started (claim delay: {}ms)", worker_id, self.claim_delay.as_millis());
+        let runtimes = self.runtimes().await;
+        if runtimes.capabilities().is_empty() {
+            summary!(self.config.verbosity, "⚠️  Worker {} found no execution runtimes", worker_id);
+        } else {
+            summary!(self.config.verbosity, "🧰 Worker {} runtimes: {}", worker_id, runtimes.capabilities().join(", "));
+        }
+        // Before the first heartbeat, so the assigner learns the outcome along with the worker
+        if self.config.self_test && self.handler.is_none() {
+            self.log_self_test(self.self_test().await);
+        }
+        if self.config.benchmark {
+            self.benchmark().await;
+        }
+        let mut publishers = PublisherCache::new(session.clone())
+            .with_qos_policy(self.qos)
+            .with_sender(worker_id)
+            .with_compression(self.config.zenoh.compression.clone());
+        if let Some(batching) = self.config.zenoh.batching {
+            publishers = publishers.with_batching(keyspace.batch(worker_id), batching);
+        }
+        let batching = publishers.batching().is_some();
+        let mut flush = tokio::time::interval(publishers.batching().unwrap_or_default().flush_interval());
+
+        let slots = Semaphore::new(self.config.concurrency.max_concurrent_tasks.max(1));
+        let mut in_flight = FuturesUnordered::new();
+        // Tasks being claimed or executed, so they aren't claimed twice
+        let mut handling = HashSet::new();
+        // Set when a job was skipped for want of an idle slot
+        let mut revisit = false;
+
+        // Jobs announced before we subscribed; a later announcement of the same
+        // attempt may still be in flight, so remember which ones were picked up
+        let mut recovered = HashSet::new();
+        for (job, metadata) in self.pending_jobs(session, keyspace, publishers.metadata()).await {
+            detail!(self.config.verbosity, "📥 Worker {} picked up waiting job: {} ({})", worker_id, job.task_id, job.name());
+            if let Some(backoff) = self.admit(&job, in_flight.len(), &mut revisit) {
+                recovered.insert((job.task_id.clone(), job.attempt));
+                handling.insert(job.task_id.clone());
+                in_flight.push(self.handle_job(&publishers, keyspace, job, metadata, backoff, &slots, &shutdown));
+            }
+        }
+
+        let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut host = HostMonitor::new(&self.config.sandbox.workspace_root);
+        #[cfg(feature = "docker")]
+        if let Some(containers) = self.executor.container_pool().cloned() {
+            let verbosity = self.config.verbosity;
+            tokio::spawn(async move {
+                if let Err(e) = containers.fill().await {
+                    summary!(verbosity, "⚠️  Failed to warm up containers: {}", e);
+                }
+            });
+        }
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => break,
+                _ = heartbeat.tick() => self.send_heartbeat(&publishers, keyspace, &mut host).await,
+                _ = flush.tick(), if batching => self.flush_batch(&publishers).await,
+                Some((task_id, outcome)) = in_flight.next(), if !in_flight.is_empty() => {
+                    handling.remove(&task_id);
+                    if let Err(e) = outcome {
+                        summary!(self.config.verbosity, "❌ Worker {} error: {}", worker_id, e);
+                    }
+                    // Skipped jobs are never re-announced, so ask for the ones still waiting
+                    if revisit && in_flight.len() < self.config.concurrency.max_concurrent_tasks.max(1) {
+                        revisit = false;
+                        for (job, metadata) in self.pending_jobs(session, keyspace, publishers.metadata()).await {
+                            if handling.contains(&job.task_id) {
+                                continue;
+                            }
+                            if let Some(backoff) = self.admit(&job, in_flight.len(), &mut revisit) {
+                                handling.insert(job.task_id.clone());
+                                in_flight.push(self.handle_job(&publishers, keyspace, job, metadata, backoff, &slots, &shutdown));
+                            }
+                        }
+                    }
+                }
+                sample = announcements.recv_async() => {
+                    let sample = match sample {
+                        Ok(sample) => sample,
+                        Err(e) => {
+                            summary!(self.config.verbosity, "❌ Worker {} announce subscriber closed: {}", worker_id, e);
+                            break;
+                        }
+                    };
+                    let job: Job = match deserialize_from_sample_with_context(&sample, "job") {
+                        Ok(job) => job,
+                        Err(e) => {
+                            summary!(self.config.verbosity, "⚠️  Worker {} skipping malformed job: {}", worker_id, e);
+                            continue;
+                        }
+                    };
+                    if recovered.remove(&(job.task_id.clone(), job.attempt)) || handling.contains(&job.task_id) {
+                        continue;
+                    }
+                    detail!(self.config.verbosity, "🔍 Worker {} sees job: {} ({})", worker_id, job.task_id, job.name());
+                    let Some(backoff) = self.admit(&job, in_flight.len(), &mut revisit) else {
+                        continue;
+                    };
+                    let metadata = publishers.metadata().continue_trace(&sample);
+                    handling.insert(job.task_id.clone());
+                    in_flight.push(self.handle_job(&publishers, keyspace, job, metadata, backoff, &slots, &shutdown));
+                }
+            }
+        }
+
+        // Keep heartbeating while in-flight tasks finish so their leases don't lapse
+        let grace = sleep(Duration::from_secs(self.config.concurrency.shutdown_grace_seconds));
+        tokio::pin!(grace);
+        let mut killed = false;
+        while !in_flight.is_empty() {
+            tokio::select! {
+                _ = &mut grace, if !killed => {
+                    summary!(self.config.verbosity, "🪓 Worker {} killing {} tasks still running after the shutdown grace period", worker_id, in_flight.len());
+                    killed = true;
+                    // Also reaps groups whose run_command is no longer polled to notice
+                    self.executor.cancellation_token().cancel();
+                    kill_all_process_groups();
+                }
+                _ = heartbeat.tick() => self.send_heartbeat(&publishers, keyspace, &mut host).await,
+                _ = flush.tick(), if batching => self.flush_batch(&publishers).await,
+                Some((_, outcome)) = in_flight.next() => {
+                    if let Err(e) = outcome {
+                        summary!(self.config.verbosity, "❌ Worker {} error: {}", worker_id, e);
+                    }
+                }
+            }
+        }
+        self.flush_batch(&publishers).await;
+        #[cfg(feature = "docker")]
+        if let Some(containers) = self.executor.container_pool() {
+            containers.shutdown().await;
+        }
+        summary!(self.config.verbosity, "👷 Worker {} stopped", worker_id);
+        Ok(())
+    }
+
+    // Whether to claim `job` given the claim policy, and after how long
+    fn admit(&self, job: &Job, in_flight: usize, revisit: &mut bool) -> Option<Duration> {
+        if let Err(e) = self.accepts(job) {
+            detail!(self.config.verbosity, "⏭️  Worker {} cannot execute job {}: {}", self.worker_id(), job.task_id, e);
+            return None;
+        }
+        // Calls that overran their timeout hold their pool thread until they return
+        let in_process = self.handler.is_none() && job.task_definition.as_ref().is_some_and(TaskDefinition::runs_in_process);
+        if in_process && self.executor.execution_pool().saturated() {
+            detail!(self.config.verbosity, "⏭️  Worker {} skips job {}: {} abandoned call(s) hold its execution pool", self.worker_id(), job.task_id, self.executor.execution_pool().abandoned());
+            return None;
+        }
+        let fleet = self.config.fleet.as_deref().unwrap_or(DEFAULT_FLEET);
+        if let Some(required) = job.task_definition.as_ref().and_then(TaskDefinition::fleet).filter(|required| *required != fleet) {
+            detail!(self.config.verbosity, "⏭️  Worker {} skips job {}: runs on the {} fleet, not {}", self.worker_id(), job.task_id, required, fleet);
+            return None;
+        }
+        let idle = in_flight < self.config.concurrency.max_concurrent_tasks.max(1);
+        match self.config.claims.decide(job, &self.config.capabilities, idle, rand::random()) {
+            ClaimDecision::Claim => Some(Duration::ZERO),
+            ClaimDecision::Defer(backoff) => {
+                detail!(self.config.verbosity, "🎲 Worker {} claims job {} after {}ms", self.worker_id(), job.task_id, backoff.as_millis());
+                Some(backoff)
+            }
+            ClaimDecision::Skip(reason) => {
+                detail!(self.config.verbosity, "⏭️  Worker {} skips job {}: {}", self.worker_id(), job.task_id, reason);
+                *revisit |= !idle;
+                None
+            }
+        }
+    }
+
+    // Resolves to the job's task ID along with the outcome
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_job(
+        &self,
+        publishers: &PublisherCache,
+        keyspace: &Keyspace,
+        job: Job,
+        metadata: MessageMetadata,
+        backoff: Duration,
+        slots: &Semaphore,
+        shutdown: &CancellationToken,
+    ) -> (String, Result<()>) {
+        let task_id = job.task_id.clone();
+        match self.claim(publishers, keyspace, &job, &metadata, backoff, shutdown).await {
+            Ok(true) => {}
+            Ok(false) => return (task_id, Ok(())),
+            Err(e) => return (task_id, Err(e)),
+        }
+
+        self.assigned_tasks_lock().insert(job.task_id.clone());
+        self.queued_tasks.fetch_add(1, Ordering::Relaxed);
+        // Waiting on the submitter or on stream samples doesn't hold up a slot
+        let resolved = tokio::select! {
+            resolved = resolve_inputs(publishers.session(), &job) => resolved,
+            // Left for its lease to lapse, so the assigner hands it to another worker
+            _ = shutdown.cancelled() => {
+                detail!(self.config.verbosity, "🛑 Worker {} dropped job {} while resolving its inputs", self.worker_id(), job.task_id);
+                self.queued_tasks.fetch_sub(1, Ordering::Relaxed);
+                self.assigned_tasks_lock().remove(&job.task_id);
+                return (task_id, Ok(()));
+            }
+        };
+        let permit = slots.acquire().await;
+        self.queued_tasks.fetch_sub(1, Ordering::Relaxed);
+
+        let outcome = match permit {
+            Ok(permit) => {
+                self.running_tasks.fetch_add(1, Ordering::Relaxed);
+                let outcome = self.process(publishers, keyspace, &job, resolved, &metadata, permit).await;
+                self.running_tasks.fetch_sub(1, Ordering::Relaxed);
+                outcome
+            }
+            Err(_) => Err(anyhow::anyhow!("Execution slots closed")),
+        };
+        self.assigned_tasks_lock().remove(&job.task_id);
+        (task_id, outcome)
+    }
+
+    // Submits the child jobs `result` asks for and waits for all of them, within the
+    // parent's timeout, before the parent's result is published
+    async fn await_children(
+        &self,
+        publishers: &PublisherCache,
+        keyspace: &Keyspace,
+        job: &Job,
+        result: &mut TaskResult,
+        metadata: &MessageMetadata,
+    ) -> Result<()> {
+        let worker_id = self.worker_id();
+        let children = match child_jobs(job, result, worker_id) {
+            Ok(children) if children.is_empty() => return Ok(()),
+            Ok(children) => children,
+            Err(e) => {
+                result.status = TaskStatus::Failed;
+                result.error = Some(format!("{:#}", e));
+                return Ok(());
+            }
+        };
+        detail!(self.config.verbosity, "🌱 Worker {} job {} spawned {} child tasks", worker_id, job.task_id, children.len());
+        let waiting = Status::new(
+            &job.task_id,
+            worker_id,
+            TaskStatus::Running,
+            &format!("Waiting for {} child tasks", children.len()),
+            0.6,
+        );
+        publishers
+            .publish_json_with_metadata(MessageClass::Status, &keyspace.status(&job.task_id), &self.sequencer.stamp(waiting), metadata)
+            .await?;
+
+        let client = TaskClient::for_keyspace(publishers.session().clone(), keyspace.clone())
+            .with_coordination(self.config.coordination)
+            .with_verbosity(self.config.verbosity);
+        let timeout = Duration::from_secs(job.timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+        let outcomes = futures::future::join_all(children.into_iter().map(|child| {
+            let client = &client;
+            async move {
+                let handle = match client.submit_job(child.clone()).await {
+                    Ok(handle) => handle,
+                    Err(e) => return ChildOutcome::lost(&child.task_id, format!("{:#}", e)),
+                };
+                match handle.await_result_timeout(timeout).await {
+                    Ok(child_result) => ChildOutcome::from_result(&child_result),
+                    Err(e) => ChildOutcome::lost(&child.task_id, format!("{:#}", e)),
+                }
+            }
+        }))
+        .await;
+        merge_children(result, outcomes);
+        Ok(())
+    }
+
+    fn log_self_test(&self, report: &SelfTestReport) {
+        for check in &report.checks {
+            if check.passed {
+                detail!(self.config.verbosity, "🩺 Worker {} {} self-test passed in {}ms", report.worker_id, check.backend, check.duration_ms);
+            } else {
+                summary!(
+                    self.config.verbosity,
+                    "⚠️  Worker {} {} self-test failed: {}",
+                    report.worker_id,
+                    check.backend,
+                    check.detail.as_deref().unwrap_or("unknown error")
+                );
+            }
+        }
+        if report.is_ready() {
+            summary!(self.config.verbosity, "🩺 Worker {} ready", report.worker_id);
+        } else {
+            summary!(self.config.verbosity, "🩺 Worker {} not ready for: {}", report.worker_id, report.failed_backends().join(", "));
+        }
+    }
+
+    fn emit(&self, event: LifecycleEvent) {
+        if let Some(events) = &self.events {
+            events.emit(event);
+        }
+    }
+
+    fn assigned_tasks_lock(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.assigned_tasks.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn flush_batch(&self, publishers: &PublisherCache) {
+        if let Err(e) = publishers.flush().await {
+            summary!(self.config.verbosity, "⚠️  Worker {} failed to publish batch: {}", self.worker_id(), e);
+        }
+    }
+
+    async fn send_heartbeat(&self, publishers: &PublisherCache, keyspace: &Keyspace, host: &mut HostMonitor) {
+        let status = if self.running_tasks.load(Ordering::Relaxed) as usize >= self.config.concurrency.max_concurrent_tasks {
+            WorkerStatus::Busy
+        } else {
+            WorkerStatus::Available
+        };
+        let mut info = self.info(status);
+        info.host = Some(host.sample());
+        let heartbeat = Heartbeat {
+            worker_id: self.worker_id().to_string(),
+            task_ids: self.assigned_tasks_lock().iter().cloned().collect(),
+            timestamp: chrono::Utc::now(),
+            info: Some(info),
+        };
+        if let Err(e) = publishers.publish_json(MessageClass::Control, &keyspace.heartbeat(self.worker_id()), &heartbeat).await {
+            summary!(self.config.verbosity, "⚠️  Worker {} failed to send heartbeat: {}", self.worker_id(), e);
+        }
+    }
+
+    // Asks the assigner for jobs that are still waiting for an assignee, along with
+    // `metadata` carrying on each job's trace
+    async fn pending_jobs(&self, session: &Session, keyspace: &Keyspace, metadata: &MessageMetadata) -> Vec<(Job, MessageMetadata)> {
+        let replies = match session.get(keyspace.all_pending()).await {
+            Ok(replies) => replies,
+            Err(e) => {
+                summary!(self.config.verbosity, "⚠️  Worker {} could not query pending jobs: {}", self.worker_id(), e);
+                return Vec::new();
+            }
+        };
+        let mut jobs = Vec::new();
+        while let Ok(reply) = replies.recv_async().await {
+            match reply.result() {
+                Ok(sample) => match deserialize_from_sample_with_context::<Job>(sample, "pending job") {
+                    Ok(job) => jobs.push((job, metadata.continue_trace(sample))),
+                    Err(e) => summary!(self.config.verbosity, "⚠️  Worker {} skipping malformed pending job: {}", self.worker_id(), e),
+                },
+                Err(e) => summary!(self.config.verbosity, "⚠️  Worker {} pending job query failed: {:?}", self.worker_id(), e),
+            }
+        }
+        jobs
+    }
+
+    // Publishes a claim after `backoff` plus jitter and waits for the assigner's
+    // decision; true if this worker won
+    async fn claim(
+        &self,
+        publishers: &PublisherCache,
+        keyspace: &Keyspace,
+        job: &Job,
+        metadata: &MessageMetadata,
+        backoff: Duration,
+        shutdown: &CancellationToken,
+    ) -> Result<bool> {
+        let worker_id = self.worker_id();
+        let delay = self.claim_delay + backoff + self.config.claims.jitter(rand::random());
+        if !self.wait_to_claim(publishers.session(), keyspace, job, delay, shutdown).await? {
+            return Ok(false);
+        }
+
+        let claim = Claim {
+            task_id: job.task_id.clone(),
+            worker_id: worker_id.to_string(),
+            claimed_at: chrono::Utc::now(),
+            estimated_duration_seconds: job
+                .task_definition
+                .as_ref()
+                .and_then(|td| td.requirements.as_ref())
+                .and_then(|requirements| requirements.timeout_seconds)
+                .or(job.timeout_seconds),
+            running_tasks: self.running_tasks.load(Ordering::Relaxed),
+            queue_depth: self.queued_tasks.load(Ordering::Relaxed),
+            fleet: self.config.fleet.clone(),
+            cpu_score: self.benchmark.get().and_then(Option::as_ref).map(|scores| scores.cpu_mb_per_second),
+        };
+        self.emit(LifecycleEvent::Claimed {
+            task_id: job.task_id.clone(),
+            worker_id: worker_id.to_string(),
+        });
+        if self.config.coordination == Coordination::WorkStealing {
+            return self.take_lock(publishers, keyspace, job, metadata, &claim, shutdown).await;
+        }
+
+        // Subscribe before claiming; the history query also picks up an assignment
+        // published before the subscription reached the assigner's session
+        let assignments = declare_history_subscriber(publishers.session(), keyspace.assign(&job.task_id)).await?;
+        publishers.publish_json_retained(MessageClass::Control, &keyspace.claim(&job.task_id), &claim, metadata).await?;
+        detail!(self.config.verbosity, "📝 Worker {} claimed job {}", worker_id, job.task_id);
+
+        let deadline = sleep(ASSIGNMENT_TIMEOUT);
+        tokio::pin!(deadline);
+        loop {
+            let sample = tokio::select! {
+                _ = shutdown.cancelled() => return Ok(false),
+                _ = &mut deadline => break,
+                sample = assignments.recv_async() => match sample {
+                    Ok(sample) => sample,
+                    Err(_) => break,
+                },
+            };
+            let assign: Assign = match deserialize_from_sample_with_context(&sample, "assign") {
+                Ok(assign) => assign,
+                Err(e) => {
+                    summary!(self.config.verbosity, "⚠️  Worker {} ignoring malformed assignment: {}", worker_id, e);
+                    continue;
+                }
+            };
+            if assign.attempt != job.attempt {
+                continue;
+            }
+            if assign.worker_id == worker_id {
+                let ack = AssignAck {
+                    task_id: job.task_id.clone(),
+                    worker_id: worker_id.to_string(),
+                    acked_at: chrono::Utc::now(),
+                };
+                publishers.publish_json_with_metadata(MessageClass::Control, &keyspace.ack(&job.task_id), &ack, metadata).await?;
+                detail!(self.config.verbosity, "✅ Worker {} assigned job {}", worker_id, job.task_id);
+                return Ok(true);
+            }
+            // Keep listening: the assigner falls back to us if the assignee never acks
+            detail!(self.config.verbosity, "🔄 Job {} went to {}", job.task_id, assign.worker_id);
+        }
+
+        detail!(self.config.verbosity, "⏰ Worker {} not assigned job {}", worker_id, job.task_id);
+        Ok(false)
+    }
+
+    // Work stealing: asks the job's submitter for it, which hands it to whoever asked
+    // first; true if that was this worker
+    async fn take_lock(
+        &self,
+        publishers: &PublisherCache,
+        keyspace: &Keyspace,
+        job: &Job,
+        metadata: &MessageMetadata,
+        claim: &Claim,
+        shutdown: &CancellationToken,
+    ) -> Result<bool> {
+        let worker_id = self.worker_id();
+        let key = keyspace.lock(&job.task_id);
+        let replies = publishers
+            .session()
+            .get(key.clone())
+            .payload(seal(publishers.sender(), claim)?)
+            .attachment(metadata.to_attachment()?)
+            .timeout(ASSIGNMENT_TIMEOUT)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to query {}: {}", key, e))?;
+        let reply = tokio::select! {
+            _ = shutdown.cancelled() => return Ok(false),
+            reply = replies.recv_async() => reply,
+        };
+        let Ok(reply) = reply else {
+            detail!(self.config.verbosity, "⏰ Worker {} got no answer for job {}; its submitter is gone", worker_id, job.task_id);
+            return Ok(false);
+        };
+        let grant: Assign = match reply.result() {
+            Ok(sample) => deserialize_from_sample_with_context(sample, "lock grant")?,
+            Err(e) => {
+                summary!(self.config.verbosity, "⚠️  Worker {} was refused job {}: {}", worker_id, job.task_id, String::from_utf8_lossy(&e.payload().to_bytes()));
+                return Ok(false);
+            }
+        };
+        if grant.worker_id != worker_id || grant.attempt != job.attempt {
+            detail!(self.config.verbosity, "🔄 Job {} went to {}", job.task_id, grant.worker_id);
+            return Ok(false);
+        }
+
+        // Nobody else reports the assignment without an assigner
+        let status = self.sequencer.stamp(Status::new(&job.task_id, worker_id, TaskStatus::Assigned, "Task taken by worker", 0.0));
+        publishers.publish_json_with_metadata(MessageClass::Status, &keyspace.status(&job.task_id), &status, metadata).await?;
+        detail!(self.config.verbosity, "🔒 Worker {} took job {}", worker_id, job.task_id);
+        self.emit(LifecycleEvent::Assigned {
+            task_id: job.task_id.clone(),
+            worker_id: worker_id.to_string(),
+        });
+        Ok(true)
+    }
+
+    // Waits out `delay`; false on shutdown or, with suppress_after, once enough other
+    // workers claimed the job in the meantime
+    async fn wait_to_claim(&self, session: &Session, keyspace: &Keyspace, job: &Job, delay: Duration, shutdown: &CancellationToken) -> Result<bool> {
+        let deadline = sleep(delay);
+        tokio::pin!(deadline);
+        let Some(limit) = self.config.claims.suppress_after.filter(|_| !delay.is_zero()) else {
+            tokio::select! {
+                _ = shutdown.cancelled() => return Ok(false),
+                _ = &mut deadline => return Ok(true),
+            }
+        };
+
+        // Claims are retained, so ones published before we subscribed count too
+        let claims = declare_history_subscriber(session, keyspace.claim(&job.task_id)).await?;
+        let announced_at = job.announced_at();
+        let mut claimants = HashSet::new();
+        loop {
+            let sample = tokio::select! {
+                _ = shutdown.cancelled() => return Ok(false),
+                _ = &mut deadline => return Ok(true),
+                sample = claims.recv_async() => match sample {
+                    Ok(sample) => sample,
+                    Err(_) => break,
+                },
+            };
+            let Ok(claim) = deserialize_from_sample_with_context::<Claim>(&sample, "claim") else {
+                continue;
+            };
+            // Earlier attempts' claims linger in the publishers' caches
+            if claim.worker_id != self.worker_id() && claim.claimed_at >= announced_at {
+                claimants.insert(claim.worker_id);
+            }
+            if claimants.len() >= limit {
+                detail!(self.config.verbosity, "🤫 Worker {} holds back its claim on job {}: {} others claimed it", self.worker_id(), job.task_id, claimants.len());
+                return Ok(false);
+            }
+        }
+        tokio::select! {
+            _ = shutdown.cancelled() => Ok(false),
+            _ = &mut deadline => Ok(true),
+        }
+    }
+
+    async fn process(
+        &self,
+        publishers: &PublisherCache,
+        keyspace: &Keyspace,
+        job: &Job,
+        resolved: Result<Cow<'_, Job>>,
+        metadata: &MessageMetadata,
+        permit: SemaphorePermit<'_>,
+    ) -> Result<()> {
+        let worker_id = self.worker_id();
+        let status_key = keyspace.status(&job.task_id);
+        let mut state = StateMachine::new(TaskStatus::Assigned);
+        detail!(self.config.verbosity, "⚙️  Worker {} executing job {} ({})", worker_id, job.task_id, job.name());
+        state.transition(TaskStatus::Running)?;
+        let running = self.sequencer.stamp(Status::new(&job.task_id, worker_id, TaskStatus::Running, "Task is running", 0.3));
+        publishers.publish_json_with_metadata(MessageClass::Status, &status_key, &running, metadata).await?;
+        self.emit(LifecycleEvent::Started {
+            task_id: job.task_id.clone(),
+            worker_id: worker_id.to_string(),
+        });
+
+        let mut result = match resolved {
+            Ok(resolved) => match &self.handler {
+                Some(handler) => {
+                    let mut result = handler(resolved.into_owned()).await;
+                    self.stamp_provenance(&mut result);
+                    result
+                }
+                None => self.run_job(&resolved).await,
+            },
+            Err(e) => {
+                let mut result = TaskResult::failure(job.task_id.clone(), worker_id.to_string(), format!("{:#}", e));
+                self.stamp_provenance(&mut result);
+                result
+            }
+        };
+        if result.status == TaskStatus::Completed {
+            // Children may need this worker's slot, so the parent gives it up while it waits
+            drop(permit);
+            self.await_children(publishers, keyspace, job, &mut result, metadata).await?;
+        }
+        // A handler reporting e.g. Pending here is a bug; don't let it leak out as a live task
+        if let Err(e) = state.transition(result.status.clone()) {
+            summary!(self.config.verbosity, "⚠️  Worker {} job {}: {}", worker_id, job.task_id, e);
+            result.error.get_or_insert_with(|| e.to_string());
+            result.status = TaskStatus::Failed;
+        }
+        publishers.publish_json_with_metadata(MessageClass::Data, &keyspace.result(&job.task_id), &result, metadata)
+            .await
+            .with_context(|| format!("Worker {} failed to publish result", worker_id))?;
+
+        let status = match result.status {
+            TaskStatus::Completed => Status::new(&job.task_id, worker_id, TaskStatus::Completed, "Task completed successfully", 1.0),
+            _ => Status::new(
+                &job.task_id,
+                worker_id,
+                result.status.clone(),
+                result.error.as_deref().unwrap_or("Task failed"),
+                1.0,
+            ),
+        };
+        publishers.publish_json_with_metadata(MessageClass::Status, &status_key, &self.sequencer.stamp(status), metadata).await?;
+        summary!(self.config.verbosity, "🎉 Worker {} finished job {}: {:?}", worker_id, job.task_id, result.status);
+        self.emit(match result.status {
+            TaskStatus::Completed => LifecycleEvent::Completed {
+                task_id: job.task_id.clone(),
+                worker_id: worker_id.to_string(),
+                execution_time_seconds: result.execution_time_seconds,
+            },
+            _ => LifecycleEvent::Failed {
+                task_id: job.task_id.clone(),
+                worker_id: worker_id.to_string(),
+                status: result.status.clone(),
+                error: result.error.clone(),
+            },
+        });
+        Ok(())
+    }
+}
+
+// The job with its offloaded inputs fetched from the submitter and its stream inputs
+// bound, if it had any
+async fn resolve_inputs<'a>(session: &Session, job: &'a Job) -> Result<Cow<'a, Job>> {
+    if job.inputs_ref.is_none() && job.stream_inputs.is_none() {
+        return Ok(Cow::Borrowed(job));
+    }
+    let mut inputs = match &job.inputs_ref {
+        Some(inputs_ref) => fetch_inputs(session, inputs_ref)
+            .await
+            .with_context(|| format!("Failed to fetch inputs of job {}", job.task_id))?,
+        None => job.inputs.clone(),
+    };
+    if let Some(stream_inputs) = &job.stream_inputs {
+        let samples = await_streams(session, stream_inputs)
+            .await
+            .with_context(|| format!("Failed to gather stream inputs of job {}", job.task_id))?;
+        inputs = bind_streams(inputs, samples);
+        // Submitters coerce everything but the streamed inputs; children are exempt
+        // there, and so here
+        if let Some(task_definition) = job.task_definition.as_ref().filter(|_| job.parent_task_id.is_none()) {
+            inputs = task_definition.coerce_deferred_inputs(inputs, &stream_inputs.names())?;
+        }
+    }
+    Ok(Cow::Owned(Job {
+        inputs,
+        inputs_ref: None,
+        stream_inputs: None,
+        ..job.clone()
+    }))
+}
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use tempfile::TempDir;
+use crate::summary;
+use crate::verbosity::Verbosity;
+
+// Directory names are "<prefix><owner pid>-<owner instance>-<random>" so the sweeper
+// can tell who owns them, even once the owner's PID has been reused
+const WORKSPACE_PREFIX: &str = "corebrum-task-";
+
+static INSTANCE: OnceLock<String> = OnceLock::new();
+
+// Random per process, so a workspace left by an earlier process with our PID (before
+// a reboot, say) isn't taken for one of ours
+fn instance() -> &'static str {
+    INSTANCE.get_or_init(|| uuid::Uuid::new_v4().simple().to_string()[..8].to_string())
+}
+
+pub fn default_workspace_root() -> PathBuf {
+    std::env::temp_dir().join("corebrum-workspaces")
+}
+
+// `path` as a path below some directory, rejecting anything that could point
+// outside of it
+pub fn safe_relative_path(path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    if path.is_empty() || !relative.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+        anyhow::bail!("Invalid path {:?}: must be relative and stay inside the source directory", path);
+    }
+    Ok(relative.to_path_buf())
+}
+
+// `name` as a single file name inside some directory: no separators, no `..`,
+// nothing absolute
+pub fn safe_file_name(name: &str) -> Result<&str> {
+    let mut components = Path::new(name).components();
+    let single = matches!(components.next(), Some(Component::Normal(file)) if file.to_str() == Some(name)) && components.next().is_none();
+    if !single || name.contains('\\') {
+        anyhow::bail!("Invalid file name {:?}: must be a single name without separators", name);
+    }
+    Ok(name)
+}
+
+// Scratch directory for a single execution, removed when dropped (including during unwinding)
+pub struct Workspace {
+    dir: TempDir,
+}
+
+impl Workspace {
+    pub fn create_in(root: &Path) -> Result<Self> {
+        fs::create_dir_all(root)
+            .with_context(|| format!("Failed to create workspace root {}", root.display()))?;
+        let dir = tempfile::Builder::new()
+            .prefix(&format!("{}{}-{}-", WORKSPACE_PREFIX, std::process::id(), instance()))
+            .tempdir_in(root)
+            .context("Failed to create task workspace")?;
+        Ok(Self { dir })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    pub fn disk_usage(&self) -> u64 {
+        disk_usage(self.path())
+    }
+}
+
+// Total size of regular files below path; symlinks are not followed
+pub fn disk_usage(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| disk_usage(&entry.path()))
+        .sum()
+}
+
+// Removes workspaces left behind by worker processes that crashed or were killed
+pub fn sweep_stale_workspaces(root: &Path, verbosity: Verbosity) -> Result<usize> {
+    if !root.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some((owner_pid, owner_instance)) = owner(&name.to_string_lossy()) else {
+            continue;
+        };
+        let ours = owner_pid == std::process::id() && owner_instance == instance();
+        // A live PID may belong to another worker sharing the root
+        if ours || (owner_pid != std::process::id() && process_alive(owner_pid)) {
+            continue;
+        }
+        match fs::remove_dir_all(entry.path()) {
+            Ok(()) => removed += 1,
+            Err(e) => summary!(verbosity, "⚠️  Failed to remove stale workspace {}: {}", entry.path().display(), e),
+        }
+    }
+    Ok(removed)
+}
+
+// Workspaces named before instances were recorded have their random suffix in the
+// instance's place, which never matches a live instance
+fn owner(dir_name: &str) -> Option<(u32, &str)> {
+    let mut parts = dir_name.strip_prefix(WORKSPACE_PREFIX)?.split('-');
+    let pid = parts.next()?.parse().ok()?;
+    Some((pid, parts.next()?))
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let rc = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
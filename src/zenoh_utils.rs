@@ -1,6 +1,21 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+use zenoh::bytes::ZBytes;
+use zenoh::handlers::FifoChannelHandler;
+use zenoh::pubsub::Publisher;
 use zenoh::Session;
+use zenoh::sample::Sample;
+use zenoh_ext::{
+    AdvancedPublisher, AdvancedPublisherBuilderExt, AdvancedSubscriber, AdvancedSubscriberBuilderExt, CacheConfig,
+    HistoryConfig,
+};
 use serde::{Deserialize, Serialize};
+use crate::batching::{common_codecs, Batch, BatchEntry, Batching, PendingBatch};
+use crate::envelope::{seal, unseal, Message};
+use crate::qos::{MessageClass, QosPolicy};
+use crate::schema::SCHEMA_VERSION;
 
 // Helper functions for Zenoh operations
 
@@ -10,6 +25,52 @@ pub async fn create_zenoh_session() -> Result<Session> {
     Ok(session)
 }
 
+pub const EMBEDDED_ROUTER_ENDPOINT: &str = "tcp/127.0.0.1:7447";
+
+// Router-mode session hosted inside the demo process, for machines where multicast
+// scouting is blocked and no external zenohd is running
+pub struct EmbeddedRouter {
+    session: Session,
+    endpoint: String,
+}
+
+impl EmbeddedRouter {
+    pub async fn start(endpoint: &str) -> Result<Self> {
+        let mut config = zenoh::Config::default();
+        insert_config(&mut config, "mode", "router")?;
+        insert_config(&mut config, "listen/endpoints", vec![endpoint])?;
+        insert_config(&mut config, "scouting/multicast/enabled", false)?;
+        let session = zenoh::open(config).await.map_err(|e| anyhow::anyhow!("Failed to start embedded Zenoh router on {}: {}", endpoint, e))?;
+        Ok(Self {
+            session,
+            endpoint: endpoint.to_string(),
+        })
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    // Config for sessions that should talk through this router instead of scouting
+    pub fn client_config(&self) -> Result<zenoh::Config> {
+        let mut config = zenoh::Config::default();
+        insert_config(&mut config, "mode", "client")?;
+        insert_config(&mut config, "connect/endpoints", vec![self.endpoint.as_str()])?;
+        insert_config(&mut config, "scouting/multicast/enabled", false)?;
+        Ok(config)
+    }
+
+    pub async fn close(self) -> Result<()> {
+        self.session.close().await.map_err(|e| anyhow::anyhow!("Failed to close embedded router: {}", e))
+    }
+}
+
+fn insert_config<T: Serialize>(config: &mut zenoh::Config, key: &str, value: T) -> Result<()> {
+    config
+        .insert_json5(key, &serde_json::to_string(&value)?)
+        .map_err(|e| anyhow::anyhow!("Invalid Zenoh config value for {}: {}", key, e))
+}
+
 pub fn serialize_to_string<T>(data: &T) -> Result<String>
 where
     T: Serialize,
@@ -18,6 +79,492 @@ where
     Ok(json)
 }
 
+pub const JSON_CONTENT_ENCODING: &str = "application/json";
+
+// Cross-cutting metadata sent as a Zenoh attachment next to the JSON payload, so
+// the message schemas stay free of transport concerns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_id: Option<String>,
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    pub content_encoding: String,
+    // Codec the payload was compressed with; absent for plain JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Codec>,
+    // Codecs everyone taking part in this trace can decode, advertised by whoever
+    // started it; payloads are only compressed with one of these
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accept_compression: Vec<Codec>,
+}
+
+impl Default for MessageMetadata {
+    fn default() -> Self {
+        Self {
+            trace_id: None,
+            schema_version: SCHEMA_VERSION,
+            tenant: None,
+            content_encoding: JSON_CONTENT_ENCODING.to_string(),
+            compression: None,
+            accept_compression: Vec::new(),
+        }
+    }
+}
+
+impl MessageMetadata {
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    pub fn with_accept_compression(mut self, codecs: Vec<Codec>) -> Self {
+        self.accept_compression = codecs;
+        self
+    }
+
+    // This metadata, carrying on the trace of `sample` and the codecs it accepts.
+    // A sample from a peer that attaches nothing accepts no compression.
+    pub fn continue_trace(&self, sample: &Sample) -> Self {
+        let mut metadata = self.clone();
+        metadata.accept_compression = Vec::new();
+        match sample_metadata(sample) {
+            Some(incoming) => {
+                metadata.accept_compression = incoming.accept_compression;
+                match incoming.trace_id {
+                    Some(trace_id) => metadata.with_trace_id(trace_id),
+                    None => metadata,
+                }
+            }
+            None => metadata,
+        }
+    }
+
+    // Fresh metadata for a new trace, e.g. one per submitted job
+    pub fn new_trace() -> Self {
+        Self::default().with_trace_id(uuid::Uuid::new_v4().to_string())
+    }
+
+    pub fn to_attachment(&self) -> Result<ZBytes> {
+        Ok(ZBytes::from(serialize_to_string(self)?))
+    }
+
+    pub fn from_attachment(attachment: &ZBytes) -> Result<Self> {
+        let json = attachment
+            .try_to_string()
+            .map_err(|e| anyhow::anyhow!("Invalid UTF-8 in message metadata: {}", e))?;
+        serde_json::from_str(&json).context("Failed to deserialize message metadata")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Gzip,
+}
+
+// Decompressed payloads larger than this are rejected rather than inflated
+const MAX_DECOMPRESSED_BYTES: u64 = 64 * 1024 * 1024;
+
+impl Codec {
+    pub fn compress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Codec::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    pub fn decompress(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let mut decompressed = Vec::new();
+        match self {
+            Codec::Gzip => flate2::read::GzDecoder::new(bytes)
+                .take(MAX_DECOMPRESSED_BYTES + 1)
+                .read_to_end(&mut decompressed)
+                .context("Invalid gzip payload")?,
+        };
+        if decompressed.len() as u64 > MAX_DECOMPRESSED_BYTES {
+            anyhow::bail!("Payload inflates to more than {} bytes", MAX_DECOMPRESSED_BYTES);
+        }
+        Ok(decompressed)
+    }
+}
+
+// Which codecs a publisher may use, and the payload size below which compressing
+// isn't worth it. A payload is only compressed if its trace accepts the codec, so
+// peers that don't advertise any still get plain JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Compression {
+    pub codecs: Vec<Codec>,
+    pub threshold_bytes: usize,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            codecs: vec![Codec::Gzip],
+            threshold_bytes: 16 * 1024,
+        }
+    }
+}
+
+impl Compression {
+    pub fn disabled() -> Self {
+        Self {
+            codecs: Vec::new(),
+            ..Self::default()
+        }
+    }
+
+    // The payload as it should go out under `metadata`, with the metadata recording
+    // the codec that was applied
+    pub fn apply(&self, payload: String, metadata: &MessageMetadata) -> Result<(ZBytes, MessageMetadata)> {
+        let codec = self.codecs.iter().copied().find(|codec| metadata.accept_compression.contains(codec));
+        match codec {
+            Some(codec) if payload.len() >= self.threshold_bytes => {
+                let compressed = codec.compress(payload.as_bytes())?;
+                let mut metadata = metadata.clone();
+                metadata.compression = Some(codec);
+                Ok((ZBytes::from(compressed), metadata))
+            }
+            _ => Ok((ZBytes::from(payload), metadata.clone())),
+        }
+    }
+}
+
+// Metadata attached to `sample`; None for senders that attach nothing (or garbage)
+pub fn sample_metadata(sample: &Sample) -> Option<MessageMetadata> {
+    sample
+        .attachment()
+        .and_then(|attachment| MessageMetadata::from_attachment(attachment).ok())
+}
+
+pub async fn publish_json<T>(session: &Session, key: &str, data: &T) -> Result<()>
+where
+    T: Message,
+{
+    publish_json_with_metadata(session, key, data, &MessageMetadata::default()).await
+}
+
+// Sent in an Envelope from the session's Zenoh ID
+pub async fn publish_json_with_metadata<T>(session: &Session, key: &str, data: &T, metadata: &MessageMetadata) -> Result<()>
+where
+    T: Message,
+{
+    session
+        .put(key, seal(&session.zid().to_string(), data)?)
+        .attachment(metadata.to_attachment()?)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to publish on {}: {}", key, e))
+}
+
+// Declared publishers keyed by key expression, so hot paths don't re-declare one per
+// message. Per-task keys make the key set unbounded, so the oldest entries are
+// undeclared once the cache is full.
+pub const DEFAULT_PUBLISHER_CACHE_CAPACITY: usize = 256;
+
+pub struct PublisherCache {
+    session: Session,
+    capacity: usize,
+    qos: QosPolicy,
+    metadata: MessageMetadata,
+    sender: String,
+    compression: Compression,
+    batch: Option<PendingBatch>,
+    publishers: Mutex<CachedPublishers>,
+}
+
+#[derive(Default)]
+struct CachedPublishers {
+    by_key: HashMap<String, Arc<CachedPublisher>>,
+    order: VecDeque<String>,
+}
+
+enum CachedPublisher {
+    Plain(Publisher<'static>),
+    // Keeps its last sample for subscribers declared after it was published
+    Retained(AdvancedPublisher<'static>),
+}
+
+impl CachedPublisher {
+    async fn put(&self, payload: ZBytes, attachment: ZBytes) -> zenoh::Result<()> {
+        match self {
+            CachedPublisher::Plain(publisher) => publisher.put(payload).attachment(attachment).await,
+            CachedPublisher::Retained(publisher) => publisher.put(payload).attachment(attachment).await,
+        }
+    }
+}
+
+impl PublisherCache {
+    pub fn new(session: Session) -> Self {
+        Self::with_capacity(session, DEFAULT_PUBLISHER_CACHE_CAPACITY)
+    }
+
+    pub fn with_capacity(session: Session, capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            qos: QosPolicy::default(),
+            metadata: MessageMetadata::default(),
+            sender: session.zid().to_string(),
+            compression: Compression::default(),
+            batch: None,
+            publishers: Mutex::new(CachedPublishers::default()),
+            session,
+        }
+    }
+
+    // Only affects publishers declared afterwards
+    pub fn with_qos_policy(mut self, qos: QosPolicy) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    // Attached to every message published without explicit metadata
+    pub fn with_metadata(mut self, metadata: MessageMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn metadata(&self) -> &MessageMetadata {
+        &self.metadata
+    }
+
+    // Named as the sender of every envelope; defaults to the session's Zenoh ID
+    pub fn with_sender(mut self, sender: impl Into<String>) -> Self {
+        self.sender = sender.into();
+        self
+    }
+
+    pub fn sender(&self) -> &str {
+        &self.sender
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    // Status and data messages published with explicit metadata are held back and
+    // sent together on `key`; callers flush every `settings.flush_interval()`
+    pub fn with_batching(mut self, key: String, settings: Batching) -> Self {
+        self.batch = Some(PendingBatch::new(key, settings));
+        self
+    }
+
+    pub fn batching(&self) -> Option<Batching> {
+        self.batch.as_ref().map(|batch| batch.settings)
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CachedPublishers> {
+        self.publishers.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // A key is expected to always be published with the same class and retention
+    async fn publisher(&self, key: &str, class: MessageClass, retained: bool) -> Result<Arc<CachedPublisher>> {
+        if let Some(publisher) = self.lock().by_key.get(key) {
+            return Ok(publisher.clone());
+        }
+
+        let qos = self.qos.get(class);
+        let builder = self
+            .session
+            .declare_publisher(key.to_string())
+            .priority(qos.priority)
+            .congestion_control(qos.congestion_control)
+            .express(qos.express);
+        let publisher = if retained {
+            builder
+                .cache(CacheConfig::default().max_samples(1))
+                .publisher_detection()
+                .await
+                .map(CachedPublisher::Retained)
+        } else {
+            builder.await.map(CachedPublisher::Plain)
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to declare publisher on {}: {}", key, e))?;
+        let publisher = Arc::new(publisher);
+
+        let mut cached = self.lock();
+        // Another task may have declared the same key while we were awaiting
+        if let Some(existing) = cached.by_key.get(key) {
+            return Ok(existing.clone());
+        }
+        if cached.order.len() >= self.capacity {
+            if let Some(oldest) = cached.order.pop_front() {
+                cached.by_key.remove(&oldest);
+            }
+        }
+        cached.order.push_back(key.to_string());
+        cached.by_key.insert(key.to_string(), publisher.clone());
+        Ok(publisher)
+    }
+
+    pub async fn publish_json<T>(&self, class: MessageClass, key: &str, data: &T) -> Result<()>
+    where
+        T: Message,
+    {
+        self.put_json(class, key, data, false, &self.metadata).await
+    }
+
+    pub async fn publish_json_with_metadata<T>(
+        &self,
+        class: MessageClass,
+        key: &str,
+        data: &T,
+        metadata: &MessageMetadata,
+    ) -> Result<()>
+    where
+        T: Message,
+    {
+        if let Some(batch) = self.batch.as_ref().filter(|_| class != MessageClass::Control) {
+            let entry = BatchEntry {
+                key: key.to_string(),
+                metadata: metadata.clone(),
+                payload: seal(&self.sender, data)?,
+            };
+            if batch.push(entry) {
+                self.flush().await?;
+            }
+            return Ok(());
+        }
+        self.put_json(class, key, data, false, metadata).await
+    }
+
+    // Publishes whatever is waiting in the batch; a no-op without batching
+    pub async fn flush(&self) -> Result<()> {
+        let Some(batch) = &self.batch else {
+            return Ok(());
+        };
+        let entries = batch.take();
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let metadata = MessageMetadata {
+            accept_compression: common_codecs(&entries),
+            ..self.metadata.clone()
+        };
+        self.put_json(MessageClass::Data, &batch.key, &Batch { entries }, false, &metadata).await
+    }
+
+    // Like publish_json, but a subscriber declared with `declare_history_subscriber`
+    // shortly afterwards still receives the message
+    pub async fn publish_json_retained<T>(
+        &self,
+        class: MessageClass,
+        key: &str,
+        data: &T,
+        metadata: &MessageMetadata,
+    ) -> Result<()>
+    where
+        T: Message,
+    {
+        self.put_json(class, key, data, true, metadata).await
+    }
+
+    async fn put_json<T>(
+        &self,
+        class: MessageClass,
+        key: &str,
+        data: &T,
+        retained: bool,
+        metadata: &MessageMetadata,
+    ) -> Result<()>
+    where
+        T: Message,
+    {
+        let (payload, metadata) = self.compression.apply(seal(&self.sender, data)?, metadata)?;
+        self.publisher(key, class, retained)
+            .await?
+            .put(payload, metadata.to_attachment()?)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to publish on {}: {}", key, e))
+    }
+}
+
+// Subscribes to `key` and, on declaration, fetches what retained publishers on
+// matching keys have already sent, so a message published just before the
+// subscriber existed is not lost. Publishers that appear later are queried too.
+pub async fn declare_history_subscriber(
+    session: &Session,
+    key: String,
+) -> Result<AdvancedSubscriber<FifoChannelHandler<Sample>>> {
+    session
+        .declare_subscriber(key.clone())
+        .history(HistoryConfig::default().detect_late_publishers())
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to declare history subscriber on {}: {}", key, e))
+}
+
+// Samples already waiting on a subscriber, up to `limit`, without blocking
+pub fn ready_samples(handler: &FifoChannelHandler<Sample>, limit: usize) -> Vec<Sample> {
+    let mut samples = Vec::new();
+    while samples.len() < limit {
+        match handler.try_recv() {
+            Ok(Some(sample)) => samples.push(sample),
+            _ => break,
+        }
+    }
+    samples
+}
+
+pub fn deserialize_from_sample_with_context<T>(sample: &Sample, context: &str) -> Result<T>
+where
+    T: Message,
+{
+    deserialize_payload_with_context(sample.key_expr().as_str(), sample.payload(), sample.attachment(), context)
+}
+
+// The checks behind deserialize_from_sample_with_context, on the parts of a sample
+// received on `key`. Payloads come from the network, so any bytes must come back as
+// an error rather than a panic; the fuzz targets hold it to that.
+pub fn deserialize_payload_with_context<T>(key: &str, payload: &ZBytes, attachment: Option<&ZBytes>, context: &str) -> Result<T>
+where
+    T: Message,
+{
+    let metadata = attachment.and_then(|attachment| MessageMetadata::from_attachment(attachment).ok());
+    if let Some(metadata) = &metadata {
+        if metadata.content_encoding != JSON_CONTENT_ENCODING {
+            anyhow::bail!("Unsupported content encoding {} for {} on {}", metadata.content_encoding, context, key);
+        }
+    }
+    let decompressed = match metadata.and_then(|metadata| metadata.compression) {
+        Some(codec) => ZBytes::from(
+            codec
+                .decompress(&payload.to_bytes())
+                .with_context(|| format!("Failed to decompress {} from {}", context, key))?,
+        ),
+        None => payload.clone(),
+    };
+    let payload = decompressed
+        .try_to_string()
+        .map_err(|e| anyhow::anyhow!("Invalid UTF-8 in {} payload on {}: {}", context, key, e))?;
+    unseal(&payload)
+        .and_then(|envelope| envelope.open::<T>())
+        .map(|envelope| envelope.body)
+        .with_context(|| format!("Failed to deserialize {} from {}", context, key))
+}
+
 // Extension trait to add .into_anyhow() method for compatibility
 pub trait IntoAnyhow<T> {
     fn into_anyhow(self) -> Result<T>;
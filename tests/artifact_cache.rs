@@ -0,0 +1,19 @@
+#![cfg(feature = "plugin")]
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::{TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::worker::Worker;
+
+#[tokio::test]
+async fn malformed_hashes_fail_the_task_before_any_download() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    // Multi-byte characters used to panic when the hash was cut into a cache file name
+    for sha256 in ["é".repeat(32), format!("../{}", "a".repeat(61))] {
+        let task = TaskDefinition {
+            source: TaskSource::Plugin { url: "http://127.0.0.1:9/libtask.so".to_string(), sha256 },
+            ..TaskDefinition::inline("plugin", "plugin", "")
+        };
+        let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+        assert_eq!(result.status, TaskStatus::Failed);
+        assert!(result.error.as_deref().unwrap_or_default().contains("not a hex SHA-256"), "{:?}", result.error);
+    }
+}
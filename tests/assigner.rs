@@ -0,0 +1,228 @@
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use zenoh::handlers::FifoChannelHandler;
+use zenoh::pubsub::Subscriber;
+use zenoh::sample::Sample;
+use corebrum_examples::assigner::Assigner;
+use corebrum_examples::events::{EventBus, LifecycleEvent};
+use corebrum_examples::keyspace::Keyspace;
+use corebrum_examples::schema::{AssignAck, Claim, Heartbeat, Job, Result as TaskResult, TaskStatus};
+use corebrum_examples::zenoh_utils::{deserialize_from_sample_with_context, publish_json};
+
+const SETTLE: Duration = Duration::from_millis(500);
+const EVENT_TIMEOUT: Duration = Duration::from_secs(10);
+
+async fn open_session() -> zenoh::Session {
+    zenoh::open(zenoh::Config::default()).await.unwrap()
+}
+
+// An assigner on a queue of its own, with a session standing in for the workers:
+// claims and acknowledgments are published by hand
+struct Mesh {
+    keyspace: Keyspace,
+    session: zenoh::Session,
+    events: broadcast::Receiver<LifecycleEvent>,
+    shutdown: CancellationToken,
+}
+
+impl Mesh {
+    async fn start(configure: impl FnOnce(Assigner) -> Assigner) -> Self {
+        let keyspace = Keyspace::for_queue(&format!("assigner-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+        let bus = EventBus::new();
+        let events = bus.subscribe();
+        let assigner = configure(Assigner::new(keyspace.clone()).with_events(bus));
+        let shutdown = CancellationToken::new();
+        let assigner_session = open_session().await;
+        let assigner_shutdown = shutdown.clone();
+        tokio::spawn(async move { assigner.run(&assigner_session, assigner_shutdown).await });
+        tokio::time::sleep(SETTLE).await;
+        Self { keyspace, session: open_session().await, events, shutdown }
+    }
+
+    async fn announce(&self) -> Job {
+        let job = Job::new(self.keyspace.queue().to_string(), serde_json::json!({}));
+        publish_json(&self.session, &self.keyspace.announce(), &job).await.unwrap();
+        job
+    }
+
+    async fn claim(&self, job: &Job, worker_id: &str, estimated_duration_seconds: u64, running_tasks: u32) {
+        let claim = Claim {
+            task_id: job.task_id.clone(),
+            worker_id: worker_id.to_string(),
+            claimed_at: chrono::Utc::now(),
+            estimated_duration_seconds: Some(estimated_duration_seconds),
+            running_tasks,
+            queue_depth: 0,
+            fleet: None,
+            cpu_score: None,
+        };
+        publish_json(&self.session, &self.keyspace.claim(&job.task_id), &claim).await.unwrap();
+    }
+
+    async fn ack(&self, job: &Job, worker_id: &str) {
+        let ack = AssignAck { task_id: job.task_id.clone(), worker_id: worker_id.to_string(), acked_at: chrono::Utc::now() };
+        publish_json(&self.session, &self.keyspace.ack(&job.task_id), &ack).await.unwrap();
+    }
+
+    async fn heartbeat(&self, worker_id: &str, task_ids: &[&str]) {
+        let heartbeat = Heartbeat {
+            worker_id: worker_id.to_string(),
+            task_ids: task_ids.iter().map(|task_id| task_id.to_string()).collect(),
+            timestamp: chrono::Utc::now(),
+            info: None,
+        };
+        publish_json(&self.session, &self.keyspace.heartbeat(worker_id), &heartbeat).await.unwrap();
+    }
+
+    // The next event `pick` accepts, skipping the rest
+    async fn next_event<T>(&mut self, mut pick: impl FnMut(LifecycleEvent) -> Option<T>) -> T {
+        tokio::time::timeout(EVENT_TIMEOUT, async {
+            loop {
+                if let Some(picked) = pick(self.events.recv().await.unwrap()) {
+                    return picked;
+                }
+            }
+        })
+        .await
+        .expect("timed out waiting for a lifecycle event")
+    }
+
+    async fn next_assignee(&mut self, task_id: &str) -> String {
+        self.next_event(|event| match event {
+            LifecycleEvent::Assigned { task_id: assigned, worker_id } if assigned == task_id => Some(worker_id),
+            _ => None,
+        })
+        .await
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        self.shutdown.cancel();
+    }
+}
+
+// Re-announcements of `job` by the assigner, past the original announcement
+async fn next_attempt(announcements: &Subscriber<FifoChannelHandler<Sample>>, job: &Job) -> Job {
+    tokio::time::timeout(EVENT_TIMEOUT, async {
+        loop {
+            let sample = announcements.recv_async().await.unwrap();
+            let announced: Job = deserialize_from_sample_with_context(&sample, "job").unwrap();
+            if announced.task_id == job.task_id && announced.attempt > job.attempt {
+                return announced;
+            }
+        }
+    })
+    .await
+    .expect("timed out waiting for a re-announcement")
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_claimant_that_never_acknowledges_costs_an_attempt_not_the_job() {
+    let mut mesh = Mesh::start(|assigner| {
+        assigner
+            .with_claim_window(Duration::from_millis(50))
+            .with_ack_timeout(Duration::from_millis(200))
+            .with_max_attempts(2)
+    })
+    .await;
+    let announcements = mesh.session.declare_subscriber(mesh.keyspace.announce()).await.unwrap();
+    let results = mesh.session.declare_subscriber(mesh.keyspace.all_results()).await.unwrap();
+
+    let job = mesh.announce().await;
+    mesh.claim(&job, "ghost", 1, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "ghost");
+
+    // Nobody is left to fall back to, so the job goes out again as its next attempt
+    let retry = next_attempt(&announcements, &job).await;
+    assert_eq!(retry.attempt, job.attempt + 1);
+    assert!(retry.failed_on("ghost"));
+    assert!(retry.audit_trail.iter().any(|entry| entry.event.contains("never acknowledged")), "{:?}", retry.audit_trail);
+
+    // Out of attempts, the job fails with a result rather than vanishing
+    mesh.claim(&job, "ghost-2", 1, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "ghost-2");
+    let sample = tokio::time::timeout(EVENT_TIMEOUT, results.recv_async()).await.unwrap().unwrap();
+    let result: TaskResult = deserialize_from_sample_with_context(&sample, "result").unwrap();
+    assert_eq!(result.task_id, job.task_id);
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.error.as_deref().unwrap_or_default().contains("Giving up after 2 attempts"), "{:?}", result.error);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_best_claim_within_the_window_wins_not_the_first() {
+    let mut mesh = Mesh::start(|assigner| assigner.with_claim_window(Duration::from_millis(300))).await;
+
+    let job = mesh.announce().await;
+    mesh.claim(&job, "slow", 30, 0).await;
+    mesh.claim(&job, "fast", 2, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "fast");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_busy_fast_worker_loses_to_an_idle_slower_one() {
+    let mut mesh = Mesh::start(|assigner| assigner.with_claim_window(Duration::from_millis(300))).await;
+
+    // 2s behind four running tasks is 10s of wait; 5s on an idle worker is less
+    let job = mesh.announce().await;
+    mesh.claim(&job, "busy-fast", 2, 4).await;
+    mesh.claim(&job, "idle-slow", 5, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "idle-slow");
+
+    // Idle, the fast worker wins again
+    let job = mesh.announce().await;
+    mesh.claim(&job, "busy-fast", 2, 0).await;
+    mesh.claim(&job, "idle-slow", 5, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "busy-fast");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn an_unacknowledged_assignment_falls_back_to_the_next_claimant() {
+    let mut mesh = Mesh::start(|assigner| {
+        assigner
+            .with_claim_window(Duration::from_millis(100))
+            .with_ack_timeout(Duration::from_millis(300))
+    })
+    .await;
+
+    let job = mesh.announce().await;
+    let announcements = mesh.session.declare_subscriber(mesh.keyspace.announce()).await.unwrap();
+    mesh.claim(&job, "ghost", 1, 0).await;
+    mesh.claim(&job, "backup", 5, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "ghost");
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "backup");
+
+    // Acknowledged by the fallback, the job stays on its first attempt
+    mesh.ack(&job, "backup").await;
+    assert!(tokio::time::timeout(Duration::from_secs(1), announcements.recv_async()).await.is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_worker_that_goes_quiet_after_acknowledging_loses_the_job() {
+    let mut mesh = Mesh::start(|assigner| {
+        assigner
+            .with_claim_window(Duration::from_millis(50))
+            .with_lease_duration(Duration::from_millis(600))
+    })
+    .await;
+    let announcements = mesh.session.declare_subscriber(mesh.keyspace.announce()).await.unwrap();
+
+    let job = mesh.announce().await;
+    mesh.claim(&job, "worker-1", 1, 0).await;
+    assert_eq!(mesh.next_assignee(&job.task_id).await, "worker-1");
+    mesh.ack(&job, "worker-1").await;
+
+    // Heartbeats naming the job keep the lease alive past its duration
+    for _ in 0..4 {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        mesh.heartbeat("worker-1", &[&job.task_id]).await;
+    }
+    assert!(tokio::time::timeout(Duration::from_millis(100), next_attempt(&announcements, &job)).await.is_err());
+
+    // Then the worker dies: the job goes out again as its next attempt
+    let retry = next_attempt(&announcements, &job).await;
+    assert_eq!(retry.attempt, job.attempt + 1);
+    assert!(retry.failed_on("worker-1"));
+    assert!(retry.audit_trail.iter().any(|entry| entry.event.contains("lease expired on worker-1")), "{:?}", retry.audit_trail);
+}
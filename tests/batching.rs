@@ -0,0 +1,50 @@
+use corebrum_examples::batching::{BatchEntry, Batching};
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::envelope::seal;
+use corebrum_examples::keyspace::Keyspace;
+use corebrum_examples::schema::{Result as TaskResult, Status, TaskStatus};
+use corebrum_examples::zenoh_utils::MessageMetadata;
+
+fn keyspace() -> Keyspace {
+    Keyspace::new("corebrum", "default")
+}
+
+fn status_entry(task_id: &str) -> BatchEntry {
+    let status = Status::new(task_id, "worker-1", TaskStatus::Running, "Executing task", 0.5);
+    BatchEntry {
+        key: keyspace().status(task_id),
+        metadata: MessageMetadata::new_trace(),
+        payload: seal("worker-1", &status).unwrap(),
+    }
+}
+
+#[test]
+fn entries_decode_as_the_message_they_carry() {
+    let entry = status_entry("task-1");
+    let status: Status = entry.decode("status").unwrap();
+
+    assert_eq!(status.task_id, "task-1");
+    assert!(entry.decode::<TaskResult>("result").is_err());
+}
+
+#[test]
+fn entries_match_the_keys_they_would_have_been_published_on() {
+    let keyspace = keyspace();
+    let entry = status_entry("task-1");
+
+    assert!(entry.matches(&keyspace.all_statuses()));
+    assert!(entry.matches(&keyspace.status("task-1")));
+    assert!(!entry.matches(&keyspace.status("task-2")));
+    assert!(!entry.matches(&keyspace.all_results()));
+}
+
+#[test]
+fn batching_is_off_unless_configured() {
+    assert_eq!(WorkerConfig::default().zenoh.batching, None);
+
+    let config: WorkerConfig = toml::from_str("[zenoh.batching]\nmax_messages = 8\n").unwrap();
+    assert_eq!(
+        config.zenoh.batching,
+        Some(Batching { max_messages: 8, ..Batching::default() })
+    );
+}
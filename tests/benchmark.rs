@@ -0,0 +1,54 @@
+use corebrum_examples::benchmark::{benchmark_outputs, run_benchmark, scores_from_outputs};
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::scheduling::SchedulingStrategy;
+use corebrum_examples::schema::{Claim, WorkerStatus};
+use corebrum_examples::worker::Worker;
+
+fn claim(worker_id: &str, cpu_score: Option<f64>, running_tasks: u32) -> Claim {
+    Claim {
+        task_id: "task-1".to_string(),
+        worker_id: worker_id.to_string(),
+        claimed_at: chrono::Utc::now(),
+        estimated_duration_seconds: None,
+        running_tasks,
+        queue_depth: 0,
+        fleet: None,
+        cpu_score,
+    }
+}
+
+#[test]
+fn benchmarks_measure_every_resource() {
+    let dir = tempfile::tempdir().unwrap();
+    let scores = run_benchmark(dir.path(), 4).unwrap();
+    assert!(scores.cpu_mb_per_second > 0.0);
+    assert!(scores.memory_gb_per_second > 0.0);
+    assert!(scores.disk_write_mb_per_second > 0.0);
+    assert!(scores.disk_read_mb_per_second > 0.0);
+    // The disk test cleans up after itself
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    assert_eq!(scores_from_outputs(&benchmark_outputs(&scores).unwrap()).unwrap(), scores);
+
+    assert!(run_benchmark(dir.path(), 0).is_err());
+    assert!(run_benchmark(dir.path(), 4096).is_err());
+}
+
+#[tokio::test]
+async fn workers_report_their_scores() {
+    let worker = Worker::from_config(WorkerConfig { benchmark: true, ..Default::default() }).unwrap();
+    assert!(worker.info(WorkerStatus::Available).benchmark.is_none());
+    let scores = worker.benchmark().await.cloned().expect("benchmark failed");
+    assert_eq!(worker.info(WorkerStatus::Available).benchmark, Some(scores));
+}
+
+#[test]
+fn the_fastest_strategy_prefers_benchmarked_idle_workers() {
+    let winner = |claims: &[Claim]| SchedulingStrategy::Fastest.select(claims).map(|claim| claim.worker_id.clone());
+    assert_eq!(winner(&[claim("pi", Some(200.0), 0), claim("server", Some(900.0), 0)]), Some("server".to_string()));
+    // Four tasks deep, the server is worth less than the idle Pi
+    assert_eq!(winner(&[claim("pi", Some(200.0), 0), claim("server", Some(900.0), 4)]), Some("pi".to_string()));
+    assert_eq!(winner(&[claim("unknown", None, 0), claim("pi", Some(200.0), 0)]), Some("pi".to_string()));
+    // Ties go to the earliest claimant
+    assert_eq!(winner(&[claim("a", None, 0), claim("b", None, 0)]), Some("a".to_string()));
+    assert_eq!(winner(&[]), None);
+}
@@ -0,0 +1,49 @@
+use corebrum_examples::dynamic_executor::{bundle_inputs, cached_build, depfile_inputs, hash_inputs, store_build};
+
+#[test]
+fn esbuild_metafiles_list_the_bundled_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let metafile = dir.path().join("esbuild-meta.json");
+    let meta = serde_json::json!({
+        "inputs": {"task.ts": {"bytes": 120, "imports": []}, "lib/util.ts": {"bytes": 40, "imports": []}},
+        "outputs": {"task.corebrum.cjs": {"bytes": 300}},
+    });
+    std::fs::write(&metafile, meta.to_string()).unwrap();
+    let mut inputs = bundle_inputs(&metafile).unwrap();
+    inputs.sort();
+    assert_eq!(inputs, ["lib/util.ts", "task.ts"]);
+
+    std::fs::write(&metafile, r#"{"outputs": {}}"#).unwrap();
+    assert!(bundle_inputs(&metafile).is_err());
+}
+
+#[test]
+fn depfiles_list_the_source_and_its_local_headers() {
+    let deps = "/work/task: task.c include/util.h \\\n  include/more.h\n";
+    assert_eq!(depfile_inputs(deps), ["task.c", "include/util.h", "include/more.h"]);
+    assert!(depfile_inputs("").is_empty());
+}
+
+#[test]
+fn builds_are_reused_until_a_source_changes() {
+    let source_dir = tempfile::tempdir().unwrap();
+    let cache_dir = tempfile::tempdir().unwrap();
+    let cache = cache_dir.path().join("native").join("0123456789abcdef");
+    std::fs::write(source_dir.path().join("task.c"), "#include \"util.h\"\nint main() { return 0; }\n").unwrap();
+    std::fs::write(source_dir.path().join("util.h"), "#define ANSWER 42\n").unwrap();
+    let binary = source_dir.path().join("task");
+    std::fs::write(&binary, b"\x7fELF").unwrap();
+
+    assert!(cached_build(&cache, "binary", source_dir.path()).is_none());
+    let inputs = hash_inputs(vec!["task.c".to_string(), "util.h".to_string()], source_dir.path()).unwrap();
+    store_build(&cache, "binary", &binary, &inputs).unwrap();
+    let cached = cached_build(&cache, "binary", source_dir.path()).unwrap();
+    assert_eq!(std::fs::read(cached).unwrap(), b"\x7fELF");
+
+    // A header edit is enough to rebuild, even though the source itself is the same
+    std::fs::write(source_dir.path().join("util.h"), "#define ANSWER 43\n").unwrap();
+    assert!(cached_build(&cache, "binary", source_dir.path()).is_none());
+
+    // Inputs that can't be read aren't cached at all
+    assert!(hash_inputs(vec!["missing.h".to_string()], source_dir.path()).is_err());
+}
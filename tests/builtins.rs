@@ -0,0 +1,63 @@
+use corebrum_examples::builtins::{builtin_task, run_builtin, sleep_duration, BUILTIN_TASKS};
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::TaskStatus;
+use corebrum_examples::worker::Worker;
+use std::time::Duration;
+
+#[test]
+fn every_builtin_has_a_definition() {
+    for name in BUILTIN_TASKS {
+        let task = builtin_task(name).unwrap();
+        assert_eq!(task.name, format!("builtin/{}", name));
+        assert_eq!(task.backend(), "builtin");
+        assert!(task.source.check().is_ok());
+    }
+    assert!(builtin_task("rm").is_none());
+}
+
+#[test]
+fn builtins_compute_what_their_scripts_would() {
+    let outputs = run_builtin("factorial", &serde_json::json!({"number": 5})).unwrap();
+    assert_eq!(outputs["factorial"], 120);
+    assert_eq!(outputs["input_number"], 5);
+    // Past u64, as digits
+    let outputs = run_builtin("factorial", &serde_json::json!({"number": 25})).unwrap();
+    assert_eq!(outputs["factorial"], "15511210043330985984000000");
+
+    let outputs = run_builtin("fibonacci", &serde_json::json!({"terms": 8})).unwrap();
+    assert_eq!(outputs["sequence"], serde_json::json!([0, 1, 1, 2, 3, 5, 8, 13]));
+    let outputs = run_builtin("fibonacci", &serde_json::json!({"terms": 94})).unwrap();
+    assert_eq!(outputs["sequence"][93], 12200160415121876738u64);
+
+    let outputs = run_builtin("hash", &serde_json::json!({"data": "abc"})).unwrap();
+    assert_eq!(outputs["sha256"], "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    let twice = run_builtin("hash", &serde_json::json!({"data": "abc", "rounds": 2})).unwrap();
+    assert_ne!(twice["sha256"], outputs["sha256"]);
+
+    let outputs = run_builtin("echo", &serde_json::json!({"a": 1, "b": [true]})).unwrap();
+    assert_eq!(serde_json::to_value(outputs).unwrap(), serde_json::json!({"a": 1, "b": [true]}));
+}
+
+#[test]
+fn builtin_inputs_are_bounded() {
+    assert!(run_builtin("factorial", &serde_json::json!({"number": 1_000_000})).is_err());
+    assert!(run_builtin("factorial", &serde_json::json!({"number": -1})).is_err());
+    assert!(run_builtin("fibonacci", &serde_json::json!({"terms": 95})).is_err());
+    assert!(run_builtin("nope", &serde_json::json!({})).is_err());
+    assert_eq!(sleep_duration(&serde_json::json!({"seconds": 0.25})).unwrap(), Duration::from_millis(250));
+    assert!(sleep_duration(&serde_json::json!({"seconds": 86400})).is_err());
+}
+
+#[tokio::test]
+async fn workers_run_builtins_without_an_interpreter() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let result = worker.executor().execute_task(&builtin_task("factorial").unwrap(), serde_json::json!({"number": 6})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Completed, "{:?}", result.error);
+    assert_eq!(result.outputs["factorial"], 720);
+
+    let mut sleep = builtin_task("sleep").unwrap();
+    sleep.requirements = Some(corebrum_examples::schema::TaskRequirements { timeout_seconds: Some(1), ..Default::default() });
+    let result = worker.executor().execute_task(&sleep, serde_json::json!({"seconds": 5})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.metadata.timed_out);
+}
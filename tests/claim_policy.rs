@@ -0,0 +1,88 @@
+use std::time::Duration;
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::scheduling::{ClaimDecision, ClaimPolicy};
+use corebrum_examples::schema::{Job, TaskDefinition, TaskRequirements, TaskSource};
+
+fn job(labels: &[&str]) -> Job {
+    let task_definition = TaskDefinition {
+        name: "labelled".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: Some(TaskRequirements {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            ..Default::default()
+        }),
+        checks: Vec::new(),
+    };
+    Job::new_user_task("tasks".to_string(), task_definition, serde_json::json!({}))
+}
+
+#[test]
+fn default_policy_claims_everything_it_can_run() {
+    let policy = WorkerConfig::default().claims;
+    assert_eq!(policy.decide(&job(&[]), &[], false, 0.99), ClaimDecision::Claim);
+}
+
+#[test]
+fn idle_only_workers_skip_while_busy() {
+    let policy = ClaimPolicy { only_when_idle: true, ..Default::default() };
+    assert!(matches!(policy.decide(&job(&[]), &[], false, 0.0), ClaimDecision::Skip(_)));
+    assert_eq!(policy.decide(&job(&[]), &[], true, 0.0), ClaimDecision::Claim);
+}
+
+#[test]
+fn labels_must_all_be_among_capabilities() {
+    let policy = ClaimPolicy::default();
+    let capabilities = vec!["gpu".to_string()];
+    assert_eq!(policy.decide(&job(&["gpu"]), &capabilities, true, 0.0), ClaimDecision::Claim);
+    match policy.decide(&job(&["gpu", "camera"]), &capabilities, true, 0.0) {
+        ClaimDecision::Skip(reason) => assert!(reason.contains("camera")),
+        other => panic!("expected a skip, got {:?}", other),
+    }
+    let unmatched = ClaimPolicy { match_labels: false, ..Default::default() };
+    assert_eq!(unmatched.decide(&job(&["camera"]), &capabilities, true, 0.0), ClaimDecision::Claim);
+}
+
+#[test]
+fn losing_the_roll_defers_the_claim() {
+    let policy = ClaimPolicy { probability: 0.25, backoff_ms: 300, ..Default::default() };
+    assert_eq!(policy.decide(&job(&[]), &[], true, 0.1), ClaimDecision::Claim);
+    assert_eq!(policy.decide(&job(&[]), &[], true, 0.5), ClaimDecision::Defer(Duration::from_millis(300)));
+}
+
+#[test]
+fn claim_policy_is_read_from_worker_config() {
+    let config: WorkerConfig = toml::from_str("[claims]\nonly_when_idle = true\nprobability = 0.5\n").unwrap();
+    assert!(config.claims.only_when_idle);
+    assert!(config.claims.match_labels);
+    assert_eq!(config.claims.probability, 0.5);
+}
+
+#[test]
+fn jitter_spreads_claims_over_the_configured_range() {
+    let policy = ClaimPolicy { jitter_ms: 200, ..Default::default() };
+    assert_eq!(policy.jitter(0.0), Duration::ZERO);
+    assert_eq!(policy.jitter(0.5), Duration::from_millis(100));
+    assert!(policy.jitter(0.999) < Duration::from_millis(200));
+    assert_eq!(ClaimPolicy::default().jitter(0.9), Duration::ZERO);
+}
+
+#[test]
+fn suppression_is_opt_in() {
+    assert_eq!(ClaimPolicy::default().suppress_after, None);
+    let config: WorkerConfig = toml::from_str("[claims]\njitter_ms = 250\nsuppress_after = 3\n").unwrap();
+    assert_eq!(config.claims.jitter_ms, 250);
+    assert_eq!(config.claims.suppress_after, Some(3));
+}
+
+#[test]
+fn retried_jobs_count_claims_from_their_re_announcement() {
+    let first = job(&[]);
+    assert_eq!(first.announced_at(), first.created_at);
+    let retried = first.retry("worker-1", "lease expired on worker-1");
+    assert!(retried.announced_at() >= first.created_at);
+    assert_eq!(retried.announced_at(), retried.audit_trail[0].timestamp);
+}
@@ -0,0 +1,51 @@
+use zenoh::bytes::ZBytes;
+use corebrum_examples::envelope::seal;
+use corebrum_examples::schema::Job;
+use corebrum_examples::zenoh_utils::{deserialize_payload_with_context, Codec, Compression, MessageMetadata};
+
+fn large_job() -> Job {
+    Job::new("perception".to_string(), serde_json::json!({"frame": "x".repeat(64 * 1024)}))
+}
+
+#[test]
+fn large_payload_is_compressed_when_the_trace_accepts_the_codec() {
+    let job = large_job();
+    let payload = seal("worker-1", &job).unwrap();
+    let metadata = MessageMetadata::new_trace().with_accept_compression(vec![Codec::Gzip]);
+    let (bytes, sent) = Compression::default().apply(payload.clone(), &metadata).unwrap();
+
+    assert_eq!(sent.compression, Some(Codec::Gzip));
+    assert!(bytes.len() < payload.len() / 10);
+    let attachment = sent.to_attachment().unwrap();
+    let received: Job = deserialize_payload_with_context("test", &bytes, Some(&attachment), "job").unwrap();
+    assert_eq!(received, job);
+}
+
+#[test]
+fn peers_that_advertise_nothing_get_plain_json() {
+    let payload = seal("worker-1", &large_job()).unwrap();
+    let (bytes, sent) = Compression::default().apply(payload.clone(), &MessageMetadata::new_trace()).unwrap();
+
+    assert_eq!(sent.compression, None);
+    assert_eq!(bytes.to_bytes().as_ref(), payload.as_bytes());
+}
+
+#[test]
+fn small_payload_is_sent_as_is() {
+    let job = Job::new("perception".to_string(), serde_json::json!({"rgb": "rt/cam/rgb"}));
+    let payload = seal("worker-1", &job).unwrap();
+    let metadata = MessageMetadata::new_trace().with_accept_compression(vec![Codec::Gzip]);
+    let (_, sent) = Compression::default().apply(payload, &metadata).unwrap();
+
+    assert_eq!(sent.compression, None);
+}
+
+#[test]
+fn oversized_inflation_is_rejected() {
+    let bomb = Codec::Gzip.compress(&vec![0; 65 * 1024 * 1024]).unwrap();
+    let metadata = MessageMetadata { compression: Some(Codec::Gzip), ..MessageMetadata::default() };
+    let attachment = metadata.to_attachment().unwrap();
+
+    let error = deserialize_payload_with_context::<Job>("test", &ZBytes::from(bomb), Some(&attachment), "job").unwrap_err();
+    assert!(format!("{:#}", error).contains("inflates"), "{:#}", error);
+}
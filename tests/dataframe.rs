@@ -0,0 +1,67 @@
+#![cfg(feature = "dataframe")]
+use corebrum_examples::dataframe::transform_dataset;
+use corebrum_examples::schema::{AggFunc, Aggregation, Comparison, DatasetFormat, FrameOperation};
+
+fn operations(value: serde_json::Value) -> Vec<FrameOperation> {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn operations_are_parsed_by_their_op_tag() {
+    let parsed = operations(serde_json::json!([
+        {"op": "filter", "column": "speed", "cmp": "gt", "value": 2.5},
+        {"op": "drop_nulls"},
+        {"op": "group_by", "by": ["city"], "aggs": [{"column": "speed", "func": "n_unique"}]},
+        {"op": "sort", "by": ["city"]},
+        {"op": "limit", "n": 10},
+    ]));
+    assert_eq!(
+        parsed,
+        [
+            FrameOperation::Filter { column: "speed".to_string(), cmp: Comparison::Gt, value: serde_json::json!(2.5) },
+            FrameOperation::DropNulls { columns: Vec::new() },
+            FrameOperation::GroupBy {
+                by: vec!["city".to_string()],
+                aggs: vec![Aggregation { column: "speed".to_string(), func: AggFunc::NUnique, alias: None }],
+            },
+            FrameOperation::Sort { by: vec!["city".to_string()], descending: false },
+            FrameOperation::Limit { n: 10 },
+        ]
+    );
+
+    let refused = [
+        serde_json::json!({"op": "pivot", "columns": ["a"]}),
+        serde_json::json!({"op": "filter", "column": "speed", "cmp": "like", "value": "a%"}),
+        serde_json::json!({"op": "limit", "n": -1}),
+        serde_json::json!({"column": "speed"}),
+    ];
+    for value in refused {
+        assert!(serde_json::from_value::<FrameOperation>(value.clone()).is_err(), "{} was accepted", value);
+    }
+}
+
+#[test]
+fn operations_apply_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("rides.csv");
+    let output = dir.path().join("result.json");
+    std::fs::write(&input, "city,speed\nparis,3.0\nlyon,12.5\nparis,8.0\nnice,1.0\n").unwrap();
+    let ops = operations(serde_json::json!([
+        {"op": "filter", "column": "speed", "cmp": "ge", "value": 2},
+        {"op": "group_by", "by": ["city"], "aggs": [{"column": "speed", "func": "sum"}, {"column": "speed", "func": "count", "alias": "rides"}]},
+        {"op": "sort", "by": ["speed_sum"], "descending": true},
+    ]));
+    let summary = transform_dataset(&input, DatasetFormat::Csv, &ops, &output, DatasetFormat::Json).unwrap();
+    assert_eq!(summary.rows, 2);
+    assert_eq!(summary.columns, ["city", "speed_sum", "rides"]);
+    let rows: serde_json::Value = serde_json::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+    assert_eq!(rows[0]["city"], "lyon");
+    assert_eq!(rows[1]["speed_sum"], 11.0);
+
+    // Problems only found against the data name the operation
+    let ops = operations(serde_json::json!([{"op": "filter", "column": "speed", "cmp": "gt", "value": null}]));
+    let error = transform_dataset(&input, DatasetFormat::Csv, &ops, &output, DatasetFormat::Json).unwrap_err();
+    assert!(format!("{:#}", error).contains("Invalid operation 1"), "{:#}", error);
+    let ops = operations(serde_json::json!([{"op": "group_by", "by": ["city"], "aggs": []}]));
+    assert!(transform_dataset(&input, DatasetFormat::Csv, &ops, &output, DatasetFormat::Json).is_err());
+}
@@ -0,0 +1,61 @@
+use corebrum_examples::determinism::{deterministic, ReproducibilityReport};
+use corebrum_examples::schema::{Determinism, Result as TaskResult, TaskDefinition, TaskRequirements, TaskSource, TaskStatus};
+
+fn result(outputs: serde_json::Value) -> TaskResult {
+    let mut result = TaskResult::failure("task-1".to_string(), "worker-1".to_string(), String::new());
+    result.status = TaskStatus::Completed;
+    result.error = None;
+    result.outputs = serde_json::from_value(outputs).unwrap();
+    result
+}
+
+#[test]
+fn identical_outputs_are_reproducible() {
+    let runs = [
+        result(serde_json::json!({"sample": [3, 1, 2], "meta": {"a": 1, "b": 2}})),
+        result(serde_json::json!({"meta": {"b": 2, "a": 1}, "sample": [3, 1, 2]})),
+    ];
+    let report = ReproducibilityReport::from_results(Determinism::default(), &runs);
+    assert!(report.is_reproducible());
+    assert!(report.differing_outputs.is_empty());
+}
+
+#[test]
+fn differing_and_missing_outputs_are_named() {
+    let runs = [
+        result(serde_json::json!({"sample": 0.25, "now": "2000-01-01", "extra": true})),
+        result(serde_json::json!({"sample": 0.75, "now": "2000-01-01"})),
+    ];
+    let report = ReproducibilityReport::from_results(Determinism::default(), &runs);
+    assert!(!report.is_reproducible());
+    assert_eq!(report.differing_outputs, ["extra", "sample"]);
+
+    let failed = [result(serde_json::json!({})), TaskResult::failure("task-1".to_string(), "worker-1".to_string(), "boom".to_string())];
+    let report = ReproducibilityReport::from_results(Determinism::default(), &failed);
+    assert_eq!(report.failed_runs(), 1);
+    assert!(!report.is_reproducible());
+}
+
+#[test]
+fn tasks_keep_their_own_seed_unless_overridden() {
+    let task = TaskDefinition {
+        name: "sample".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    assert_eq!(deterministic(&task, None).determinism(), Some(Determinism::default()));
+
+    let mut seeded = task.clone();
+    seeded.requirements = Some(TaskRequirements {
+        deterministic: Some(Determinism { seed: 7, ..Default::default() }),
+        ..Default::default()
+    });
+    assert_eq!(deterministic(&seeded, None).determinism().unwrap().seed, 7);
+    let overridden = Determinism { seed: 9, clock: 0 };
+    assert_eq!(deterministic(&seeded, Some(overridden)).determinism(), Some(overridden));
+}
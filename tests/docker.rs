@@ -0,0 +1,85 @@
+#![cfg(feature = "docker")]
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use bollard::container::InspectContainerOptions;
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::container_pool::{ContainerPool, ContainerResources};
+use corebrum_examples::schema::{TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::worker::Worker;
+
+fn docker_task(command: &[&str]) -> TaskDefinition {
+    TaskDefinition {
+        name: "docker-shell".to_string(),
+        description: None,
+        language: "docker".to_string(),
+        source: TaskSource::Docker {
+            image: "alpine:3.19".to_string(),
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+        },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs a Docker daemon"]
+async fn cold_started_containers_read_inputs_and_report_exit_codes() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let inputs = serde_json::json!({"data": [1, 2, 3]});
+    let result = worker.executor().execute_task(&docker_task(&["sh", "-c", "cat \"$COREBRUM_INPUTS\""]), inputs.clone()).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Completed, "{:?}", result.error);
+    assert_eq!(serde_json::to_value(&result.outputs).unwrap(), inputs);
+
+    let result = worker.executor().execute_task(&docker_task(&["sh", "-c", "echo nope >&2; exit 3"]), inputs).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert_eq!(result.metadata.exit_code, Some(3));
+    assert!(result.error.unwrap().contains("nope"));
+}
+
+#[tokio::test]
+#[ignore = "needs a Docker daemon"]
+async fn warm_containers_dropped_mid_task_are_removed() {
+    let root = tempfile::tempdir().unwrap();
+    let targets = HashMap::from([("alpine:3.19".to_string(), 1)]);
+    let pool = Arc::new(ContainerPool::connect(root.path().to_path_buf(), targets).unwrap());
+    pool.fill().await.unwrap();
+    // As when a cancelled task's run is dropped while it holds the container
+    let container = pool.take("alpine:3.19").unwrap();
+    let id = container.id().to_string();
+    drop(container);
+
+    let inspect = || pool.docker().inspect_container(&id, None::<InspectContainerOptions>);
+    for _ in 0..50 {
+        if inspect().await.is_err() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(inspect().await.is_err(), "container {} is still there", id);
+    pool.shutdown().await;
+}
+
+#[tokio::test]
+#[ignore = "needs a Docker daemon"]
+async fn capped_tasks_never_get_an_uncapped_warm_container() {
+    let root = tempfile::tempdir().unwrap();
+    let targets = HashMap::from([("alpine:3.19".to_string(), 1)]);
+    let capped = ContainerResources { memory_mb: Some(64), cpu_cores: Some(1) };
+    let pool = Arc::new(ContainerPool::connect(root.path().to_path_buf(), targets.clone()).unwrap());
+    pool.fill().await.unwrap();
+    // Left to a cold start, which applies the caps
+    assert!(pool.acquire("alpine:3.19", &[], capped).await.unwrap().is_none());
+    assert_eq!(pool.idle_count("alpine:3.19"), 1);
+    pool.shutdown().await;
+
+    let reusing = Arc::new(ContainerPool::connect(root.path().to_path_buf(), targets).unwrap().with_reuse(Duration::from_secs(5)));
+    let container = reusing.acquire("alpine:3.19", &[], capped).await.unwrap().unwrap();
+    let details = reusing.docker().inspect_container(container.id(), None::<InspectContainerOptions>).await.unwrap();
+    let host_config = details.host_config.unwrap();
+    assert_eq!(host_config.memory, Some(64 * 1024 * 1024));
+    assert_eq!(host_config.nano_cpus, Some(1_000_000_000));
+    reusing.release(container, &[], false).await;
+}
@@ -0,0 +1,84 @@
+use corebrum_examples::dry_run::worker_unfit;
+use corebrum_examples::schema::{TaskDefinition, TaskRequirements, TaskSource, WorkerInfo, WorkerStatus};
+
+fn task(language: &str, labels: &[&str]) -> TaskDefinition {
+    TaskDefinition {
+        name: "detect".to_string(),
+        description: None,
+        language: language.to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: Some(TaskRequirements {
+            labels: labels.iter().map(|label| label.to_string()).collect(),
+            ..Default::default()
+        }),
+        checks: Vec::new(),
+    }
+}
+
+fn worker(capabilities: &[&str], failed_probes: &[&str]) -> WorkerInfo {
+    WorkerInfo {
+        worker_id: "worker-1".to_string(),
+        capabilities: capabilities.iter().map(|capability| capability.to_string()).collect(),
+        status: WorkerStatus::Available,
+        last_heartbeat: chrono::Utc::now(),
+        host: None,
+        ready: None,
+        failed_probes: failed_probes.iter().map(|probe| probe.to_string()).collect(),
+        fleet: None,
+        benchmark: None,
+    }
+}
+
+#[test]
+fn workers_are_judged_by_their_advertised_capabilities() {
+    let gpu_worker = worker(&["python", "gpu"], &[]);
+    assert_eq!(worker_unfit(Some(&gpu_worker), Some(&task("python", &["gpu"]))), None);
+    assert_eq!(
+        worker_unfit(Some(&gpu_worker), Some(&task("javascript", &[]))),
+        Some("backend 'javascript' not enabled".to_string())
+    );
+    assert_eq!(
+        worker_unfit(Some(&worker(&["python"], &[])), Some(&task("python", &["gpu", "cuda"]))),
+        Some("missing label(s) gpu, cuda".to_string())
+    );
+    assert_eq!(
+        worker_unfit(Some(&worker(&["python"], &["python"])), Some(&task("python", &[]))),
+        Some("backend 'python' failed its self-test".to_string())
+    );
+    let mut canary_task = task("python", &[]);
+    canary_task.requirements.as_mut().unwrap().fleet = Some("canary".to_string());
+    assert_eq!(worker_unfit(Some(&gpu_worker), Some(&canary_task)), Some("in the stable fleet, not canary".to_string()));
+    // Auto-detected languages aren't known until the source is fetched
+    assert_eq!(worker_unfit(Some(&worker(&[], &[])), Some(&task("auto", &[]))), None);
+}
+
+#[test]
+fn unknown_workers_and_handler_jobs_get_the_benefit_of_the_doubt() {
+    assert_eq!(worker_unfit(None, Some(&task("r", &[]))), None);
+    assert_eq!(worker_unfit(Some(&worker(&[], &[])), None), None);
+}
+
+#[test]
+fn malformed_sources_are_caught_before_submission() {
+    let digest = "a".repeat(64);
+    assert!(TaskSource::Url { url: "https://example.com/task.py".to_string() }.check().is_ok());
+    assert!(TaskSource::Url { url: "ftp://example.com/task.py".to_string() }.check().is_err());
+    assert!(TaskSource::Inline { code: "  \n".to_string() }.check().is_err());
+    assert!(TaskSource::Gist { id: "abc".to_string(), filename: String::new() }.check().is_err());
+    let archive = |sha256: &str| TaskSource::Archive {
+        url: "https://example.com/project.tar.gz".to_string(),
+        sha256: sha256.to_string(),
+        entrypoint: "main.py".to_string(),
+    };
+    assert!(archive(&digest).check().is_ok());
+    assert!(archive("not-a-digest").check().is_err());
+    assert!(TaskSource::Plugin { url: "https://example.com/libtask.so".to_string(), sha256: digest.to_uppercase() }.check().is_ok());
+    // 64 bytes, but not 64 hex digits
+    assert!(TaskSource::Plugin { url: "https://example.com/libtask.so".to_string(), sha256: "é".repeat(32) }.check().is_err());
+    let onnx = |sha256: &str| TaskSource::Onnx { url: "https://example.com/model.onnx".to_string(), sha256: sha256.to_string(), batch_size: None };
+    assert!(onnx(&digest).check().is_ok());
+    assert!(onnx(&digest[..63]).check().is_err());
+    assert!(onnx(&format!("../../{}", &digest[..58])).check().is_err());
+}
@@ -0,0 +1,454 @@
+use std::time::Duration;
+use corebrum_examples::ab_test::{compare_versions, AbOptions};
+use corebrum_examples::client::TaskSubmitter;
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::debug_bundle::DebugBundle;
+use corebrum_examples::determinism::check_reproducible;
+use corebrum_examples::dry_run::WORKER_DISCOVERY_WINDOW;
+use corebrum_examples::demo_runtime::DemoRuntime;
+use corebrum_examples::envelope::seal;
+use corebrum_examples::events::LifecycleEvent;
+use corebrum_examples::local_runtime::LocalRuntime;
+use corebrum_examples::scheduling::{Coordination, FleetRouting};
+use corebrum_examples::schema::{
+    Determinism, InputType, Job, Result as TaskResult, Status, StreamInputs, SyncPolicy, TaskDefinition, TaskInput, TaskOutput, TaskRequirements,
+    TaskSource, TaskStatus,
+};
+use corebrum_examples::worker::Worker;
+use corebrum_examples::zenoh_utils::deserialize_from_sample_with_context;
+
+const RESULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const FACTORIAL_PY: &str = r#"import json, math, sys
+inputs = json.load(open(sys.argv[1]))
+n = int(inputs.get("number", 10))
+print(json.dumps({"factorial": math.factorial(n), "input_number": n}))
+"#;
+
+fn factorial_task() -> TaskDefinition {
+    TaskDefinition {
+        name: "factorial_computation".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: FACTORIAL_PY.to_string() },
+        inputs: vec![TaskInput {
+            name: "number".to_string(),
+            description: None,
+            required: true,
+            default_value: None,
+            data_type: Some(InputType::Integer),
+            choices: Vec::new(),
+        }],
+        outputs: vec![TaskOutput { name: "factorial".to_string(), description: None, data_type: "json".to_string() }],
+        requirements: None,
+        checks: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn local_runtime_computes_factorial() {
+    let runtime = LocalRuntime::new(WorkerConfig::default()).unwrap();
+    let handle = runtime.submit(factorial_task(), serde_json::json!({"number": 10})).await.unwrap();
+    let result = handle.await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+
+    assert_eq!(result.status, TaskStatus::Completed, "task failed: {:?}", result.error);
+    assert_eq!(result.outputs["factorial"], serde_json::json!(3628800));
+    assert_eq!(result.outputs["input_number"], serde_json::json!(10));
+}
+
+#[tokio::test]
+async fn failed_tasks_carry_a_debug_bundle_when_enabled() {
+    std::env::set_var("COREBRUM_E2E_API_TOKEN", "hunter2");
+    let mut config = WorkerConfig::default();
+    config.sandbox.debug_bundles = true;
+    let runtime = LocalRuntime::new(config).unwrap();
+    let mut task = factorial_task();
+    task.source = TaskSource::Inline {
+        code: "import sys\nopen('partial.txt', 'w').write('half done')\nsys.stderr.write('about to fail\\n')\nsys.exit(3)\n".to_string(),
+    };
+    let handle = runtime.submit(task, serde_json::json!({"number": 10})).await.unwrap();
+    let result = handle.await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+
+    assert_eq!(result.status, TaskStatus::Failed);
+    let bundle = DebugBundle::from_result(&result).expect("no debug bundle").unwrap();
+    assert_eq!(bundle.language, "python");
+    assert!(bundle.stderr_tail.contains("about to fail"), "{}", bundle.stderr_tail);
+    assert!(bundle.workspace_files.iter().any(|file| file.path.ends_with("partial.txt")), "{:?}", bundle.workspace_files);
+    assert!(bundle.inputs.contains("\"number\":10"), "{}", bundle.inputs);
+    assert_eq!(bundle.env["COREBRUM_E2E_API_TOKEN"], "<redacted>");
+    assert!(bundle.runtimes.iter().any(|runtime| runtime.starts_with("python=")), "{:?}", bundle.runtimes);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn mesh_reports_status_transitions_and_result() {
+    // A queue of its own, so concurrent test runs on the same host don't see each other's jobs
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue).with_shutdown_timeout(Duration::from_secs(2));
+    runtime.start(vec![DemoRuntime::worker("e2e-worker", Duration::ZERO).unwrap()]).await;
+
+    let observer = runtime.open_session().await.unwrap();
+    let statuses = observer.declare_subscriber(runtime.keyspace().all_statuses()).await.unwrap();
+    let mut events = runtime.events().subscribe();
+    let client = runtime.client().await.unwrap();
+    let job = Job::new_user_task(queue.clone(), factorial_task(), serde_json::json!({"number": 12}));
+    let task_id = job.task_id.clone();
+    let handle = client.submit_job(job).await.unwrap();
+    let receipt = handle.receipt().expect("the assigner sent no receipt").clone();
+    assert_eq!(receipt.task_id, task_id);
+    assert_eq!(receipt.queue, queue);
+    assert_eq!(receipt.queue_position, 0);
+    let result = handle.await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+
+    assert_eq!(result.task_id, task_id);
+    assert_eq!(result.status, TaskStatus::Completed, "task failed: {:?}", result.error);
+    assert_eq!(result.worker_id, "e2e-worker");
+    assert_eq!(result.outputs["factorial"], serde_json::json!(479001600));
+
+    // The result can overtake the final status update, so wait for it
+    let mut transitions = Vec::new();
+    let collect = async {
+        while let Ok(sample) = statuses.recv_async().await {
+            let Ok(status) = deserialize_from_sample_with_context::<Status>(&sample, "status") else {
+                continue;
+            };
+            if status.task_id != task_id || transitions.last() == Some(&status.status) {
+                continue;
+            }
+            transitions.push(status.status.clone());
+            if status.status == TaskStatus::Completed {
+                break;
+            }
+        }
+    };
+    tokio::time::timeout(RESULT_TIMEOUT, collect).await.expect("no Completed status was published");
+    assert_eq!(transitions, vec![TaskStatus::Assigned, TaskStatus::Running, TaskStatus::Completed]);
+
+    let mut lifecycle = Vec::new();
+    let collect = async {
+        while let Ok(event) = events.recv().await {
+            if event.task_id() != task_id {
+                continue;
+            }
+            let completed = matches!(event, LifecycleEvent::Completed { .. });
+            lifecycle.push(event);
+            if completed {
+                break;
+            }
+        }
+    };
+    tokio::time::timeout(RESULT_TIMEOUT, collect).await.expect("no Completed event was emitted");
+    let kinds: Vec<&str> = lifecycle
+        .iter()
+        .map(|event| match event {
+            LifecycleEvent::JobAnnounced { .. } => "announced",
+            LifecycleEvent::Claimed { .. } => "claimed",
+            LifecycleEvent::Assigned { .. } => "assigned",
+            LifecycleEvent::Started { .. } => "started",
+            LifecycleEvent::Completed { .. } => "completed",
+            LifecycleEvent::Failed { .. } => "failed",
+        })
+        .collect();
+    // The assigner and the worker see the announcement independently
+    assert_eq!(kinds.len(), 5, "{:?}", kinds);
+    let mut seen = kinds[..2].to_vec();
+    seen.sort();
+    assert_eq!(seen, vec!["announced", "claimed"]);
+    assert_eq!(kinds[2..], ["assigned", "started", "completed"]);
+
+    client.close().await.unwrap();
+    observer.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn attached_handle_fetches_an_already_published_result() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let runtime = DemoRuntime::new(&queue);
+    let key = runtime.keyspace().result("finished-task");
+
+    // Stands in for a storage that kept the result while no client was listening
+    let store = runtime.open_session().await.unwrap();
+    let queryable = store.declare_queryable(key.clone()).await.unwrap();
+    let stored = TaskResult::failure("finished-task".to_string(), "e2e-worker".to_string(), "boom".to_string());
+    tokio::spawn(async move {
+        while let Ok(query) = queryable.recv_async().await {
+            let _ = query.reply(key.clone(), seal("store", &stored).unwrap()).await;
+        }
+    });
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let client = runtime.client().await.unwrap();
+    let result = client.attach("finished-task").await.unwrap().await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    assert_eq!(result.task_id, "finished-task");
+    assert_eq!(result.error.as_deref(), Some("boom"));
+
+    client.close().await.unwrap();
+    store.close().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn work_stealing_runs_each_job_once_without_an_assigner() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue)
+        .with_coordination(Coordination::WorkStealing)
+        .with_shutdown_timeout(Duration::from_secs(2));
+    runtime
+        .start(vec![
+            DemoRuntime::worker("thief-1", Duration::ZERO).unwrap(),
+            DemoRuntime::worker("thief-2", Duration::ZERO).unwrap(),
+        ])
+        .await;
+
+    let mut events = runtime.events().subscribe();
+    let client = runtime.client().await.unwrap();
+    let job = Job::new_user_task(queue.clone(), factorial_task(), serde_json::json!({"number": 5}));
+    let task_id = job.task_id.clone();
+    let handle = client.submit_job(job).await.unwrap();
+    assert!(handle.receipt().is_none());
+    let result = handle.await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Completed, "task failed: {:?}", result.error);
+    assert_eq!(result.outputs["factorial"], serde_json::json!(120));
+
+    // Both workers ask for the job, only one gets it
+    let mut assigned = Vec::new();
+    let collect = async {
+        while let Ok(event) = events.recv().await {
+            match event {
+                LifecycleEvent::Assigned { task_id: id, worker_id } if id == task_id => assigned.push(worker_id),
+                LifecycleEvent::Completed { task_id: id, .. } if id == task_id => break,
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(RESULT_TIMEOUT, collect).await.expect("no Completed event was emitted");
+    assert_eq!(assigned, vec![result.worker_id.clone()]);
+
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dry_runs_find_eligible_workers_without_announcing() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue).with_shutdown_timeout(Duration::from_secs(2));
+    runtime.start(vec![DemoRuntime::worker("dry-worker", Duration::ZERO).unwrap()]).await;
+    let mut events = runtime.events().subscribe();
+    let client = runtime.client().await.unwrap();
+
+    let job = Job::new_user_task(queue.clone(), factorial_task(), serde_json::json!({"number": "12"}));
+    let report = client.dry_run(job, WORKER_DISCOVERY_WINDOW).await.unwrap();
+    assert!(report.is_ok(), "{:?}", report.problems);
+    assert_eq!(report.inputs["number"], 12);
+    assert_eq!(report.eligible_workers().map(|worker| worker.worker_id.as_str()).collect::<Vec<_>>(), ["dry-worker"]);
+
+    let mut r_task = factorial_task();
+    r_task.language = "r".to_string();
+    let report = client
+        .dry_run(Job::new_user_task(queue.clone(), r_task, serde_json::json!({})), WORKER_DISCOVERY_WINDOW)
+        .await
+        .unwrap();
+    assert_eq!(report.workers[0].unfit.as_deref(), Some("backend 'r' not enabled"));
+    assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+    assert!(report.problems[0].contains("missing required input 'number'"));
+
+    while let Ok(event) = events.try_recv() {
+        assert!(!matches!(event, LifecycleEvent::JobAnnounced { .. }), "{:?} was announced", event.task_id());
+    }
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn estimates_learn_from_completed_runs() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue).with_shutdown_timeout(Duration::from_secs(2));
+    runtime.start(vec![DemoRuntime::worker("estimate-worker", Duration::ZERO).unwrap()]).await;
+    let client = runtime.client().await.unwrap();
+    // Long enough for the worker to have heartbeated
+    tokio::time::sleep(WORKER_DISCOVERY_WINDOW).await;
+
+    let job = || Job::new_user_task(queue.clone(), factorial_task(), serde_json::json!({"number": 5}));
+    let estimate = client.estimate(&job()).await.unwrap();
+    assert_eq!(estimate.candidates, ["estimate-worker"]);
+    assert_eq!(estimate.expected_duration_seconds, None);
+    assert_eq!(estimate.estimated_wait_seconds, Some(0.0));
+
+    let result = client.submit_job(job()).await.unwrap().await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Completed, "task failed: {:?}", result.error);
+    // The assigner hears the result independently of the client
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let estimate = client.estimate(&job()).await.unwrap();
+    assert_eq!(estimate.duration_samples, 1);
+    assert_eq!(estimate.expected_duration_seconds, result.execution_time_seconds);
+
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[tokio::test]
+async fn task_versions_are_compared_on_the_same_inputs() {
+    let runtime = LocalRuntime::new(WorkerConfig::default()).unwrap();
+    let current = factorial_task();
+    let mut ported = factorial_task();
+    ported.source = TaskSource::Inline { code: FACTORIAL_PY.replace("math.factorial(n)", "float(math.factorial(n))") };
+    let inputs = [serde_json::json!({"number": 5}), serde_json::json!({"number": 0})];
+
+    let options = AbOptions { timeout: RESULT_TIMEOUT, ..AbOptions::default() };
+    let report = compare_versions(&runtime, &current, &ported, &inputs, &options).await;
+    assert_eq!(report.a, "factorial_computation (python)");
+    assert_eq!(report.differing(), 2);
+    assert_eq!(report.cases[0].inputs, inputs[0]);
+    assert!(report.cases[0].a.execution_time_seconds().is_some());
+
+    let options = AbOptions { tolerance: Some(0.0), ..options };
+    let report = compare_versions(&runtime, &current, &ported, &inputs, &options).await;
+    assert!(report.is_clean(), "{:?}", report.cases);
+    assert!(report.mean_execution_seconds().is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn fleet_routing_steers_jobs_to_canary_workers() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue)
+        .with_shutdown_timeout(Duration::from_secs(2))
+        .with_fleet_routing(FleetRouting::new([("canary", 1.0)]).unwrap());
+    let in_fleet = |worker_id: &str, fleet: Option<&str>| {
+        let config = WorkerConfig { worker_id: worker_id.to_string(), fleet: fleet.map(str::to_string), ..WorkerConfig::default() };
+        Worker::from_config(config).unwrap()
+    };
+    runtime.start(vec![in_fleet("blue", None), in_fleet("green", Some("canary"))]).await;
+    let client = runtime.client().await.unwrap();
+
+    let job = Job::new_user_task(queue.clone(), factorial_task(), serde_json::json!({"number": 5}));
+    let result = client.submit_job(job).await.unwrap().await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    assert_eq!(result.worker_id, "green");
+
+    // Tasks that name a fleet override the routing
+    let mut task = factorial_task();
+    task.requirements = Some(TaskRequirements { fleet: Some("stable".to_string()), ..Default::default() });
+    let job = Job::new_user_task(queue.clone(), task, serde_json::json!({"number": 5}));
+    let result = client.submit_job(job).await.unwrap().await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    assert_eq!(result.worker_id, "blue");
+
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[tokio::test]
+async fn deterministic_runs_pin_randomness_and_the_clock() {
+    let code = r#"import datetime, os, random, time
+def handler(inputs):
+    return {
+        "sample": [random.random() for _ in range(3)],
+        "now": datetime.datetime.now().isoformat(),
+        "time": time.time(),
+        "order": list({"pear", "apple", "fig"}),
+        "tz": os.environ.get("TZ"),
+    }
+"#;
+    let task = TaskDefinition { source: TaskSource::Inline { code: code.to_string() }, inputs: Vec::new(), outputs: Vec::new(), ..factorial_task() };
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let inputs = serde_json::json!({});
+
+    let report = check_reproducible(worker.executor(), &task, &inputs, 3, None).await.unwrap();
+    assert!(report.is_reproducible(), "{:?}", report);
+    assert_eq!(report.outputs["now"], "2000-01-01T00:00:00");
+    assert_eq!(report.outputs["time"], 946_684_800.0);
+    assert_eq!(report.outputs["tz"], "UTC");
+
+    // Another seed draws other numbers, the same ones every time
+    let reseeded = check_reproducible(worker.executor(), &task, &inputs, 2, Some(Determinism { seed: 42, ..Default::default() })).await.unwrap();
+    assert!(reseeded.is_reproducible(), "{:?}", reseeded);
+    assert_ne!(reseeded.outputs["sample"], report.outputs["sample"]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn jobs_wait_for_an_aligned_pair_of_stream_samples() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue).with_shutdown_timeout(Duration::from_secs(2));
+    runtime.start(vec![DemoRuntime::worker("e2e-worker", Duration::ZERO).unwrap()]).await;
+
+    // A camera publishing raw rgb frames and JSON depth frames together
+    let camera = runtime.open_session().await.unwrap();
+    let (rgb_key, depth_key) = (format!("{}/cam/rgb", queue), format!("{}/cam/depth", queue));
+    let (rgb, depth) = (rgb_key.clone(), depth_key.clone());
+    let frames = tokio::spawn(async move {
+        for frame in 0u64.. {
+            camera.put(rgb.as_str(), vec![0xff, 0x00, 0x80]).await.unwrap();
+            camera.put(depth.as_str(), serde_json::json!({"frame": frame}).to_string()).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(30)).await;
+        }
+    });
+
+    let code = "def handler(inputs):\n    return {\"rgb\": inputs[\"rgb\"], \"frame\": inputs[\"depth\"][\"frame\"], \"model\": inputs[\"model\"]}\n";
+    // The frames are required but only arrive on the worker, where they are coerced
+    let input = |name: &str, data_type: InputType| TaskInput {
+        name: name.to_string(),
+        description: None,
+        required: true,
+        default_value: None,
+        data_type: Some(data_type),
+        choices: Vec::new(),
+    };
+    let inputs = vec![input("rgb", InputType::String), input("depth", InputType::Object), input("model", InputType::String)];
+    let task = TaskDefinition { source: TaskSource::Inline { code: code.to_string() }, inputs, outputs: Vec::new(), ..factorial_task() };
+    let job = Job::new_user_task(queue.clone(), task, serde_json::json!({"model": "vlm-x"})).with_stream_inputs(StreamInputs {
+        keys: [("rgb".to_string(), rgb_key), ("depth".to_string(), depth_key)].into(),
+        sync: SyncPolicy::Aligned { tolerance_ms: 20 },
+        timeout_ms: Some(5000),
+    });
+    let client = runtime.client().await.unwrap();
+    let result = client.submit_job(job).await.unwrap().await_result_timeout(RESULT_TIMEOUT).await.unwrap();
+    frames.abort();
+
+    assert_eq!(result.status, TaskStatus::Completed, "task failed: {:?}", result.error);
+    // Not JSON and not text, so base64
+    assert_eq!(result.outputs["rgb"], "/wCA");
+    assert!(result.outputs["frame"].is_u64());
+    assert_eq!(result.outputs["model"], "vlm-x");
+
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread")]
+async fn shutdown_kills_what_tasks_spawned_once_the_grace_period_is_over() {
+    let queue = format!("e2e-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    let mut runtime = DemoRuntime::new(&queue).with_shutdown_timeout(Duration::from_secs(20));
+    let mut config = WorkerConfig { worker_id: "e2e-worker".to_string(), ..Default::default() };
+    config.concurrency.shutdown_grace_seconds = 1;
+    runtime.start(vec![Worker::from_config(config).unwrap()]).await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let pid_file = dir.path().join("grandchild.pid");
+    let code = "import json, subprocess, sys, time\ninputs = json.load(open(sys.argv[1]))\nchild = subprocess.Popen([\"sleep\", \"300\"])\nopen(inputs[\"pid_file\"], \"w\").write(str(child.pid))\ntime.sleep(300)\n";
+    let task = TaskDefinition { source: TaskSource::Inline { code: code.to_string() }, inputs: Vec::new(), outputs: Vec::new(), ..factorial_task() };
+    let client = runtime.client().await.unwrap();
+    let _handle = client.submit_job(Job::new_user_task(queue.clone(), task, serde_json::json!({"pid_file": pid_file}))).await.unwrap();
+    let pid = tokio::time::timeout(RESULT_TIMEOUT, async {
+        loop {
+            if let Ok(pid) = std::fs::read_to_string(&pid_file) {
+                if let Ok(pid) = pid.trim().parse::<u32>() {
+                    return pid;
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("the task never started its grandchild");
+
+    let started = std::time::Instant::now();
+    client.close().await.unwrap();
+    runtime.shutdown().await;
+    assert!(started.elapsed() < Duration::from_secs(20), "the worker was aborted rather than stopping");
+    // Reparented to init once its parent dies, which reaps it shortly after
+    let proc = std::path::PathBuf::from(format!("/proc/{}", pid));
+    let gone = async {
+        while proc.exists() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(5), gone).await.expect("the grandchild outlived the worker");
+}
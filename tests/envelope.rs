@@ -0,0 +1,44 @@
+use corebrum_examples::envelope::{seal, unseal, Message};
+use corebrum_examples::schema::{Claim, Job, SCHEMA_VERSION};
+
+fn job() -> Job {
+    Job::new("perception".to_string(), serde_json::json!({"rgb": "rt/cam/rgb"}))
+}
+
+#[test]
+fn sealed_message_opens_as_its_type() {
+    let job = job();
+    let envelope = unseal(&seal("worker-1", &job).unwrap()).unwrap();
+
+    assert_eq!(envelope.msg_type, Job::MSG_TYPE);
+    assert_eq!(envelope.schema_version, SCHEMA_VERSION);
+    assert_eq!(envelope.sender, "worker-1");
+    assert_eq!(envelope.open::<Job>().unwrap().body, job);
+}
+
+#[test]
+fn message_of_another_type_is_rejected() {
+    let envelope = unseal(&seal("worker-1", &job()).unwrap()).unwrap();
+    let error = envelope.open::<Claim>().unwrap_err();
+    assert!(error.to_string().contains("got job"), "{}", error);
+}
+
+#[test]
+fn bare_body_from_an_older_peer_is_accepted() {
+    let job = job();
+    let envelope = unseal(&serde_json::to_string(&job).unwrap()).unwrap();
+
+    assert!(envelope.msg_type.is_empty());
+    assert_eq!(envelope.schema_version, 0);
+    assert_eq!(envelope.open::<Job>().unwrap().body, job);
+}
+
+#[test]
+fn unknown_message_types_can_be_skipped() {
+    let payload = r#"{"msg_type": "telemetry_v9", "schema_version": 9, "sender": "robot-3", "ts": "2026-01-01T00:00:00Z", "body": {"speed": 1.5}}"#;
+    let envelope = unseal(payload).unwrap();
+
+    assert_eq!(envelope.msg_type, "telemetry_v9");
+    assert_eq!(envelope.body["speed"], 1.5);
+    assert!(envelope.open::<Job>().is_err());
+}
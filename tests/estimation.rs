@@ -0,0 +1,86 @@
+use corebrum_examples::estimation::{estimate, DurationHistory};
+use corebrum_examples::schema::{Job, TaskDefinition, TaskSource, WorkerInfo, WorkerStatus};
+
+fn job(language: &str) -> Job {
+    let task_definition = TaskDefinition {
+        name: "detect".to_string(),
+        description: None,
+        language: language.to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    Job::new_user_task("tasks".to_string(), task_definition, serde_json::Value::Null)
+}
+
+fn worker(worker_id: &str, status: WorkerStatus) -> WorkerInfo {
+    WorkerInfo {
+        worker_id: worker_id.to_string(),
+        capabilities: vec!["python".to_string()],
+        status,
+        last_heartbeat: chrono::Utc::now(),
+        host: None,
+        ready: None,
+        failed_probes: Vec::new(),
+        fleet: None,
+        benchmark: None,
+    }
+}
+
+#[test]
+fn history_keeps_the_latest_runs_per_task() {
+    let mut history = DurationHistory::new();
+    assert_eq!(history.expected("detect"), None);
+    for seconds in 0..60 {
+        history.record("detect", seconds as f64);
+    }
+    history.record("resize", 100.0);
+    history.record("resize", f64::NAN);
+    // Runs 10 to 59 are kept
+    assert_eq!(history.expected("detect"), Some((34.5, 50)));
+    assert_eq!(history.expected("resize"), Some((100.0, 1)));
+    assert_eq!(history.overall().unwrap().1, 51);
+}
+
+#[test]
+fn idle_candidates_start_the_job_right_away() {
+    let now = chrono::Utc::now();
+    let workers = [worker("busy", WorkerStatus::Busy), worker("idle", WorkerStatus::Available), worker("gone", WorkerStatus::Offline)];
+    let estimate = estimate(&job("python"), &DurationHistory::new(), 0, 1, &workers, now);
+    assert_eq!(estimate.candidates, ["busy", "idle"]);
+    assert_eq!(estimate.estimated_wait_seconds, Some(0.0));
+    assert_eq!(estimate.estimated_start_at, Some(now));
+    assert_eq!(estimate.expected_duration_seconds, None);
+}
+
+#[test]
+fn queued_jobs_are_spread_over_the_candidates() {
+    let now = chrono::Utc::now();
+    let mut history = DurationHistory::new();
+    history.record("detect", 4.0);
+    history.record("resize", 2.0);
+    let workers = [worker("a", WorkerStatus::Busy), worker("b", WorkerStatus::Busy)];
+    // Five jobs ahead on two workers: the job starts in the third round
+    let queued = estimate(&job("python"), &history, 5, 2, &workers, now);
+    assert_eq!(queued.queue_depth, 5);
+    assert_eq!(queued.expected_duration_seconds, Some(4.0));
+    assert_eq!(queued.duration_samples, 1);
+    assert_eq!(queued.estimated_wait_seconds, Some(9.0));
+    assert_eq!(queued.estimated_start_at, Some(now + chrono::Duration::seconds(9)));
+
+    // Nothing to go by without history
+    let unknown = estimate(&job("python"), &DurationHistory::new(), 5, 2, &workers, now);
+    assert_eq!(unknown.estimated_wait_seconds, None);
+    assert_eq!(unknown.estimated_start_at, None);
+}
+
+#[test]
+fn jobs_no_worker_can_take_have_no_start() {
+    let mut history = DurationHistory::new();
+    history.record("detect", 4.0);
+    let estimate = estimate(&job("r"), &history, 0, 0, &[worker("a", WorkerStatus::Available)], chrono::Utc::now());
+    assert!(estimate.candidates.is_empty());
+    assert_eq!(estimate.estimated_wait_seconds, None);
+}
@@ -0,0 +1,22 @@
+use corebrum_examples::events::{EventBus, LifecycleEvent};
+use corebrum_examples::schema::TaskStatus;
+
+#[tokio::test]
+async fn subscribers_receive_events_emitted_after_subscribing() {
+    let bus = EventBus::new();
+    bus.emit(LifecycleEvent::Claimed { task_id: "early".to_string(), worker_id: "worker-1".to_string() });
+
+    let mut first = bus.subscribe();
+    let mut second = bus.clone().subscribe();
+    let failed = LifecycleEvent::Failed {
+        task_id: "task-1".to_string(),
+        worker_id: "worker-1".to_string(),
+        status: TaskStatus::Timeout,
+        error: Some("timed out".to_string()),
+    };
+    bus.emit(failed.clone());
+
+    assert_eq!(first.recv().await.unwrap(), failed);
+    assert_eq!(second.recv().await.unwrap(), failed);
+    assert_eq!(failed.task_id(), "task-1");
+}
@@ -0,0 +1,38 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use corebrum_examples::execution_pool::ExecutionPool;
+use corebrum_examples::schema::{TaskDefinition, TaskSource};
+
+#[tokio::test]
+async fn abandoned_jobs_hold_the_pool_until_they_return() {
+    let pool = ExecutionPool::new(1).unwrap();
+    assert_eq!(pool.run(|| 42).await.unwrap(), 42);
+    assert_eq!(pool.abandoned(), 0);
+
+    let stuck = pool.run(|| std::thread::sleep(Duration::from_millis(400)));
+    assert!(tokio::time::timeout(Duration::from_millis(50), stuck).await.is_err());
+    assert_eq!(pool.abandoned(), 1);
+    assert!(pool.saturated());
+
+    // Queued behind the stuck job and given up on before it started, so it never runs
+    let ran = Arc::new(AtomicBool::new(false));
+    let queued = pool.run({
+        let ran = ran.clone();
+        move || ran.store(true, Ordering::SeqCst)
+    });
+    assert!(tokio::time::timeout(Duration::from_millis(50), queued).await.is_err());
+    assert_eq!(pool.abandoned(), 2);
+
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    assert_eq!(pool.abandoned(), 0);
+    assert!(!pool.saturated());
+    assert!(!ran.load(Ordering::SeqCst));
+}
+
+#[test]
+fn only_pool_backends_count_as_in_process() {
+    assert!(TaskDefinition::new("sleep", "builtin", TaskSource::Builtin { name: "sleep".to_string() }).runs_in_process());
+    assert!(TaskDefinition::inline("query", "sql", "SELECT 1").runs_in_process());
+    assert!(!TaskDefinition::inline("script", "python", "def handler(inputs):\n    return {}\n").runs_in_process());
+}
@@ -0,0 +1,31 @@
+#![cfg(feature = "expression")]
+
+use corebrum_examples::expression::{evaluate, MAX_EXPRESSION_BYTES};
+
+#[test]
+fn assigned_variables_are_the_outputs() {
+    let outputs = evaluate("area = width * height; perimeter = 2 * (width + height)", &serde_json::json!({"width": 3, "height": 4})).unwrap();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs["area"], 12);
+    assert_eq!(outputs["perimeter"], 14);
+}
+
+#[test]
+fn a_final_value_is_the_result() {
+    let outputs = evaluate("n / 2 + x", &serde_json::json!({"n": 7, "x": 0.5})).unwrap();
+    assert_eq!(outputs["result"], 3.5);
+    let outputs = evaluate("if(ok, name, \"none\")", &serde_json::json!({"ok": true, "name": "robot1"})).unwrap();
+    assert_eq!(outputs["result"], "robot1");
+    let outputs = evaluate("(a, b)", &serde_json::json!({"a": [1, 2], "b": null})).unwrap();
+    assert_eq!(outputs["result"], serde_json::json!([[1, 2], null]));
+}
+
+#[test]
+fn bad_expressions_fail_cleanly() {
+    assert!(evaluate("missing + 1", &serde_json::json!({})).is_err());
+    assert!(evaluate("1 +", &serde_json::json!({})).is_err());
+    // Objects aren't bound
+    assert!(evaluate("config + 1", &serde_json::json!({"config": {"a": 1}})).is_err());
+    assert!(evaluate(&"1 + ".repeat(MAX_EXPRESSION_BYTES), &serde_json::json!({})).is_err());
+    assert!(evaluate("x / 0.0", &serde_json::json!({"x": 1.0})).is_err());
+}
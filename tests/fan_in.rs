@@ -0,0 +1,87 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use corebrum_examples::fan_in::{bind_streams, fan_in_timeout, validate_stream_inputs, FanIn, StreamSample, DEFAULT_FAN_IN_TIMEOUT, MAX_ALIGNMENT_TOLERANCE_MS, MAX_FAN_IN_TIMEOUT_MS};
+use corebrum_examples::schema::{StreamInputs, SyncPolicy};
+
+fn stream_inputs(sync: SyncPolicy) -> StreamInputs {
+    StreamInputs {
+        keys: BTreeMap::from([("rgb".to_string(), "rt/cam/rgb".to_string()), ("depth".to_string(), "rt/cam/depth".to_string())]),
+        sync,
+        timeout_ms: None,
+    }
+}
+
+fn sample(frame: u64, at_ms: i64) -> StreamSample {
+    StreamSample {
+        value: serde_json::json!(frame),
+        timestamp: chrono::DateTime::from_timestamp_millis(1_700_000_000_000 + at_ms).unwrap(),
+    }
+}
+
+fn frames(set: BTreeMap<String, StreamSample>) -> Vec<(String, serde_json::Value)> {
+    set.into_iter().map(|(name, sample)| (name, sample.value)).collect()
+}
+
+#[test]
+fn latest_of_each_waits_for_every_key_then_takes_the_newest() {
+    let mut fan_in = FanIn::new(&stream_inputs(SyncPolicy::LatestOfEach));
+    assert!(fan_in.push("rgb", sample(1, 0)).is_none());
+    assert!(fan_in.push("rgb", sample(2, 33)).is_none());
+    assert!(fan_in.push("lidar", sample(9, 40)).is_none());
+    let set = fan_in.push("depth", sample(7, 5000)).unwrap();
+    assert_eq!(frames(set), [("depth".to_string(), serde_json::json!(7)), ("rgb".to_string(), serde_json::json!(2))]);
+}
+
+#[test]
+fn aligned_sets_are_within_the_tolerance() {
+    let mut fan_in = FanIn::new(&stream_inputs(SyncPolicy::Aligned { tolerance_ms: 10 }));
+    assert!(fan_in.push("rgb", sample(1, 0)).is_none());
+    assert!(fan_in.push("rgb", sample(2, 33)).is_none());
+    // Too far from either rgb frame
+    assert!(fan_in.push("depth", sample(1, 16)).is_none());
+    // Out of order, but close to the first rgb frame
+    let set = fan_in.push("depth", sample(2, 4)).unwrap();
+    assert_eq!(frames(set), [("depth".to_string(), serde_json::json!(2)), ("rgb".to_string(), serde_json::json!(1))]);
+}
+
+#[test]
+fn out_of_range_tolerances_are_refused_and_never_panic() {
+    for tolerance_ms in [u64::MAX, 1 << 63, MAX_ALIGNMENT_TOLERANCE_MS + 1] {
+        let sync = SyncPolicy::Aligned { tolerance_ms };
+        assert!(validate_stream_inputs(&stream_inputs(sync)).is_err());
+        // Announced by hand, they are clamped on the worker
+        let mut fan_in = FanIn::new(&stream_inputs(sync));
+        assert!(fan_in.push("rgb", sample(1, 0)).is_none());
+        assert!(fan_in.push("depth", sample(1, 60_000)).is_some());
+    }
+    assert!(validate_stream_inputs(&stream_inputs(SyncPolicy::Aligned { tolerance_ms: 20 })).is_ok());
+}
+
+#[test]
+fn out_of_range_timeouts_are_refused_and_clamped() {
+    let timeout = |timeout_ms: Option<u64>| StreamInputs { timeout_ms, ..stream_inputs(SyncPolicy::LatestOfEach) };
+    assert!(validate_stream_inputs(&timeout(Some(MAX_FAN_IN_TIMEOUT_MS))).is_ok());
+    assert!(validate_stream_inputs(&timeout(Some(u64::MAX))).is_err());
+    assert_eq!(fan_in_timeout(&timeout(Some(u64::MAX))), Duration::from_millis(MAX_FAN_IN_TIMEOUT_MS));
+    assert_eq!(fan_in_timeout(&timeout(Some(250))), Duration::from_millis(250));
+    assert_eq!(fan_in_timeout(&timeout(None)), DEFAULT_FAN_IN_TIMEOUT);
+}
+
+#[test]
+fn invalid_keys_are_refused() {
+    for key in ["rt/cam//rgb", "rt/cam/rgb/", "rt/cam/**/**", ""] {
+        let mut inputs = stream_inputs(SyncPolicy::LatestOfEach);
+        inputs.keys.insert("rgb".to_string(), key.to_string());
+        assert!(validate_stream_inputs(&inputs).is_err(), "{:?}", key);
+    }
+    let mut inputs = stream_inputs(SyncPolicy::LatestOfEach);
+    inputs.keys.insert("rgb".to_string(), "rt/cam/*/rgb".to_string());
+    assert!(validate_stream_inputs(&inputs).is_ok());
+}
+
+#[test]
+fn samples_are_bound_to_inputs_of_the_same_name() {
+    let samples = BTreeMap::from([("rgb".to_string(), sample(3, 0))]);
+    assert_eq!(bind_streams(serde_json::json!({"model": "vlm-x", "rgb": "rt/cam/rgb"}), samples.clone()), serde_json::json!({"model": "vlm-x", "rgb": 3}));
+    assert_eq!(bind_streams(serde_json::Value::Null, samples), serde_json::json!({"rgb": 3}));
+}
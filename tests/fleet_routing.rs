@@ -0,0 +1,64 @@
+use corebrum_examples::scheduling::{FleetChoice, FleetRouting};
+use corebrum_examples::schema::{Claim, Job, TaskDefinition, TaskRequirements, TaskSource};
+
+fn job(fleet: Option<&str>) -> Job {
+    let task_definition = TaskDefinition {
+        name: "detect".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: Some(TaskRequirements { fleet: fleet.map(str::to_string), ..Default::default() }),
+        checks: Vec::new(),
+    };
+    Job::new_user_task("tasks".to_string(), task_definition, serde_json::json!({}))
+}
+
+fn claim(worker_id: &str, fleet: Option<&str>) -> Claim {
+    Claim {
+        task_id: "task-1".to_string(),
+        worker_id: worker_id.to_string(),
+        claimed_at: chrono::Utc::now(),
+        estimated_duration_seconds: None,
+        running_tasks: 0,
+        queue_depth: 0,
+        fleet: fleet.map(str::to_string),
+        cpu_score: None,
+    }
+}
+
+#[test]
+fn jobs_are_routed_to_fleets_by_weight() {
+    let routing = FleetRouting::new([("stable", 95.0), ("canary", 5.0)]).unwrap();
+    // Fleets are laid out in name order: canary takes the first 5%
+    assert_eq!(routing.choose(&job(None), 0.01), FleetChoice::Preferred("canary".to_string()));
+    assert_eq!(routing.choose(&job(None), 0.05), FleetChoice::Preferred("stable".to_string()));
+    assert_eq!(routing.choose(&job(None), 0.999), FleetChoice::Preferred("stable".to_string()));
+    // A fleet the task requires always wins
+    assert_eq!(routing.choose(&job(Some("canary")), 0.5), FleetChoice::Required("canary".to_string()));
+    assert_eq!(FleetRouting::default().choose(&job(None), 0.5), FleetChoice::Any);
+
+    assert!(FleetRouting::new([("canary", -1.0)]).is_err());
+    assert!(FleetRouting::new([("stable", 0.0), ("canary", 0.0)]).is_err());
+}
+
+#[test]
+fn preferred_fleets_fall_back_to_any_claimant() {
+    let claims = [claim("old", None), claim("new", Some("canary"))];
+    let winners = |choice: FleetChoice| choice.narrow(&claims).into_iter().map(|claim| claim.worker_id).collect::<Vec<_>>();
+    assert_eq!(winners(FleetChoice::Preferred("canary".to_string())), ["new"]);
+    // Workers without a fleet are in the stable one
+    assert_eq!(winners(FleetChoice::Required("stable".to_string())), ["old"]);
+    assert_eq!(winners(FleetChoice::Preferred("green".to_string())), ["old", "new"]);
+    assert!(winners(FleetChoice::Required("green".to_string())).is_empty());
+
+    assert!(!FleetChoice::Required("canary".to_string()).admits(&claims[0]));
+    assert!(FleetChoice::Preferred("canary".to_string()).admits(&claims[0]));
+}
+
+#[test]
+fn routing_reads_as_a_map_of_weights() {
+    let routing: FleetRouting = toml::from_str("stable = 90\ncanary = 10").unwrap();
+    assert_eq!(routing, FleetRouting::new([("stable", 90.0), ("canary", 10.0)]).unwrap());
+}
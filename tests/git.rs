@@ -0,0 +1,60 @@
+#![cfg(feature = "git")]
+use std::path::Path;
+use std::process::Command;
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::{TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::worker::Worker;
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git").arg("-C").arg(dir).args(args).status().unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+fn git_task(repo: &str, branch: Option<&str>, rev: Option<&str>) -> TaskDefinition {
+    let source = TaskSource::Git {
+        repo: repo.to_string(),
+        path: "task.py".to_string(),
+        branch: branch.map(str::to_string),
+        rev: rev.map(str::to_string),
+        submodules: false,
+    };
+    TaskDefinition { source, ..TaskDefinition::inline("from-git", "python", "") }
+}
+
+#[test]
+fn option_like_repos_and_refs_are_refused() {
+    assert!(git_task("https://example.com/tasks.git", Some("main"), Some("0123abc")).source.check().is_ok());
+    assert!(git_task("-c core.sshCommand=touch /tmp/pwned", None, None).source.check().is_err());
+    assert!(git_task("https://example.com/tasks.git", Some("--upload-pack=touch /tmp/pwned"), None).source.check().is_err());
+    assert!(git_task("https://example.com/tasks.git", None, Some("--upload-pack=touch /tmp/pwned")).source.check().is_err());
+}
+
+#[tokio::test]
+async fn option_like_refs_never_reach_git() {
+    let scratch = tempfile::tempdir().unwrap();
+    let pwned = scratch.path().join("pwned");
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let injection = format!("--upload-pack=touch {}", pwned.display());
+    for task in [git_task(&format!("-c core.sshCommand=touch {}", pwned.display()), None, None), git_task("https://example.com/tasks.git", None, Some(&injection))] {
+        let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+        assert_eq!(result.status, TaskStatus::Failed);
+        assert!(result.error.as_deref().unwrap_or_default().contains("can't start with '-'"), "{:?}", result.error);
+    }
+    assert!(!pwned.exists());
+}
+
+#[tokio::test]
+async fn pinned_branches_are_checked_out() {
+    let repo = tempfile::tempdir().unwrap();
+    git(repo.path(), &["init", "--quiet", "--initial-branch=main"]);
+    std::fs::write(repo.path().join("task.py"), "def handler(inputs):\n    return {\"answer\": 42}\n").unwrap();
+    git(repo.path(), &["add", "task.py"]);
+    git(repo.path(), &["-c", "user.name=test", "-c", "user.email=test@example.com", "commit", "--quiet", "-m", "task"]);
+
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let task = git_task(&repo.path().to_string_lossy(), Some("main"), None);
+    let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Completed, "{:?}", result.error);
+    assert_eq!(result.outputs["answer"], serde_json::json!(42));
+    assert!(result.provenance.as_ref().and_then(|provenance| provenance.git_commit.as_ref()).is_some());
+}
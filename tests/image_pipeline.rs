@@ -0,0 +1,21 @@
+#![cfg(feature = "image")]
+use corebrum_examples::image_pipeline::{preprocess_image, MAX_FRAME_PIXELS};
+use corebrum_examples::schema::{ImageOutput, ImageStep, ResizeFilter};
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    image::RgbImage::new(width, height).write_to(&mut encoded, image::ImageFormat::Png).unwrap();
+    encoded.into_inner()
+}
+
+#[test]
+fn resizes_are_capped_at_the_frame_pixel_limit() {
+    let resize = |width: u32, height: u32| ImageStep::Resize { width, height, filter: ResizeFilter::Nearest };
+    let frame = preprocess_image(&png(4, 4), &[resize(8, 2)], ImageOutput::Png).unwrap();
+    assert_eq!((frame.width, frame.height), (8, 2));
+
+    let side = (MAX_FRAME_PIXELS as f64).sqrt() as u32 + 1;
+    let error = preprocess_image(&png(4, 4), &[resize(side, side)], ImageOutput::Png).unwrap_err();
+    assert!(format!("{:#}", error).contains("pixel limit"), "{:#}", error);
+    assert!(preprocess_image(&png(4, 4), &[resize(u32::MAX, u32::MAX)], ImageOutput::Png).is_err());
+}
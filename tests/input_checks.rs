@@ -0,0 +1,104 @@
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::input_types::{FailedCheck, InputValidationError};
+use corebrum_examples::schema::{Expression, InputCheck, InputType, Job, TaskDefinition, TaskInput, TaskStatus};
+use corebrum_examples::worker::Worker;
+use serde_json::json;
+
+fn holds(expression: &str, inputs: serde_json::Value) -> bool {
+    Expression::parse(expression).unwrap().evaluate(&inputs).unwrap()
+}
+
+fn check(expression: &str, message: Option<&str>) -> InputCheck {
+    InputCheck { expression: expression.to_string(), message: message.map(str::to_string) }
+}
+
+fn definition(checks: Vec<InputCheck>) -> TaskDefinition {
+    TaskDefinition {
+        inputs: vec![TaskInput {
+            name: "window".to_string(),
+            description: None,
+            required: false,
+            default_value: None,
+            data_type: Some(InputType::Duration),
+            choices: Vec::new(),
+        }],
+        checks,
+        ..TaskDefinition::inline("detect", "python", "def handler(inputs):\n    return {}\n")
+    }
+}
+
+#[test]
+fn comparisons_and_logic() {
+    assert!(holds("end > start", json!({"start": 1, "end": 2})));
+    assert!(!holds("end > start", json!({"start": 2, "end": 2})));
+    assert!(holds("a + b * 2 == 7 and not (a > b)", json!({"a": 1, "b": 3})));
+    assert!(holds("n % 2 == 0 || n == -1", json!({"n": -1})));
+    assert!(holds("mode in [\"fast\", 'accurate']", json!({"mode": "accurate"})));
+    assert!(holds("\"cam\" in topic", json!({"topic": "robot/cam/front"})));
+    assert!(holds("scale == 2", json!({"scale": 2.0})));
+    assert!(holds("camera.width <= 1920 and missing == null", json!({"camera": {"width": 1280}})));
+}
+
+#[test]
+fn when_only_applies_if_its_condition_holds() {
+    let expression = r#"max_objs <= 128 when model == "vlm-x""#;
+    assert!(holds(expression, json!({"model": "vlm-x", "max_objs": 64})));
+    assert!(!holds(expression, json!({"model": "vlm-x", "max_objs": 256})));
+    assert!(holds(expression, json!({"model": "yolo", "max_objs": 256})));
+    // Not evaluated at all when the condition fails
+    assert!(holds("max_objs / 0 > 1 when false", json!({"max_objs": 1})));
+}
+
+#[test]
+fn timestamps_compare_as_times() {
+    // 03:04:05Z sorts after 03:04:05.5Z as a string
+    assert!(holds("end > start", json!({"start": "2026-01-02T03:04:05Z", "end": "2026-01-02T03:04:05.5Z"})));
+    assert!(!holds("end > start", json!({"start": "2026-01-02T03:04:05Z", "end": "2026-01-02T04:00:00+01:00"})));
+}
+
+#[test]
+fn malformed_or_ill_typed_expressions_are_errors() {
+    for invalid in ["", "a =", "a = 1", "(a > 1", "a > 1 b", "a.", "\"open", "[1, 2", "when", "a ^ b"] {
+        assert!(Expression::parse(invalid).is_err(), "{:?} should not parse", invalid);
+    }
+    let nested = format!("{}1{}", "(".repeat(64), ")".repeat(64));
+    assert!(Expression::parse(&nested).is_err());
+    assert!(Expression::parse(&"x + ".repeat(300)).is_err());
+
+    let evaluate = |expression: &str| Expression::parse(expression).unwrap().evaluate(&json!({"s": "text", "n": 1}));
+    assert!(evaluate("n").is_err());
+    assert!(evaluate("s > n").is_err());
+    assert!(evaluate("missing > n").is_err());
+    assert!(evaluate("n / 0 > 1").is_err());
+}
+
+#[test]
+fn failed_checks_are_reported_after_coercion() {
+    let task_definition = definition(vec![check("window <= 60", Some("window is at most a minute")), check("window > 0", None)]);
+    assert_eq!(task_definition.coerce_inputs(json!({"window": "30s"})).unwrap(), json!({"window": 30}));
+
+    let error = task_definition.coerce_inputs(json!({"window": "2m"})).unwrap_err();
+    let error = error.downcast::<InputValidationError>().unwrap();
+    assert!(error.errors.is_empty());
+    assert_eq!(
+        error.failed_checks,
+        vec![FailedCheck { expression: "window <= 60".to_string(), message: Some("window is at most a minute".to_string()) }]
+    );
+    assert_eq!(error.to_string(), "Invalid inputs for detect: check `window <= 60` failed: window is at most a minute");
+
+    // Checks that can't be evaluated fail with the reason
+    let error = task_definition.coerce_inputs(json!({})).unwrap_err();
+    let error = error.downcast::<InputValidationError>().unwrap();
+    assert_eq!(error.failed_checks.len(), 2);
+    assert!(error.failed_checks[0].message.as_ref().unwrap().contains("Cannot compare"));
+}
+
+#[tokio::test]
+async fn workers_refuse_jobs_that_fail_the_checks() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let task_definition = definition(vec![check("window <= 60", None)]);
+    let job = Job::new_user_task("tasks".to_string(), task_definition, json!({"window": 120}));
+    let result = worker.run_job(&job).await;
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.error.unwrap().contains("check `window <= 60` failed"));
+}
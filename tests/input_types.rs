@@ -0,0 +1,189 @@
+use corebrum_examples::input_types::{parse_bytesize, parse_duration, InputError, InputProblem, InputValidationError};
+use corebrum_examples::schema::{InputType, TaskDefinition, TaskInput, TaskSource};
+use serde_json::json;
+
+fn definition(inputs: Vec<TaskInput>) -> TaskDefinition {
+    TaskDefinition {
+        name: "resize".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs,
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    }
+}
+
+fn input(name: &str, data_type: &str) -> TaskInput {
+    TaskInput {
+        name: name.to_string(),
+        description: None,
+        required: true,
+        default_value: None,
+        data_type: Some(InputType::from(data_type.to_string())),
+        choices: Vec::new(),
+    }
+}
+
+#[test]
+fn durations_parse_to_seconds() {
+    assert_eq!(parse_duration("30s").unwrap(), 30.0);
+    assert_eq!(parse_duration("1h30m").unwrap(), 5400.0);
+    assert_eq!(parse_duration("1h 30m").unwrap(), 5400.0);
+    assert_eq!(parse_duration("250ms").unwrap(), 0.25);
+    assert_eq!(parse_duration("1.5d").unwrap(), 129600.0);
+    assert_eq!(parse_duration("90").unwrap(), 90.0);
+    for invalid in ["", "s", "30 parsecs", "-5s", "1h30"] {
+        assert!(parse_duration(invalid).is_err(), "{:?} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn byte_sizes_parse_decimal_and_binary_units() {
+    assert_eq!(parse_bytesize("512MB").unwrap(), 512_000_000);
+    assert_eq!(parse_bytesize("512MiB").unwrap(), 512 << 20);
+    assert_eq!(parse_bytesize("64k").unwrap(), 64 << 10);
+    assert_eq!(parse_bytesize("1.5 GiB").unwrap(), 3 << 29);
+    assert_eq!(parse_bytesize("4096").unwrap(), 4096);
+    for invalid in ["", "MB", "12 parsecs", "-1KB"] {
+        assert!(parse_bytesize(invalid).is_err(), "{:?} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn task_files_spell_the_type_as_type() {
+    let task_file = r#"{
+        "task_definition": {
+            "name": "resize",
+            "language": "python",
+            "source": {"Inline": {"code": "def handler(inputs):\n    return {}\n"}},
+            "inputs": [
+                {"name": "timeout", "type": "Duration", "required": true},
+                {"name": "topic", "type": "zenoh_topic", "required": true},
+                {"name": "mode", "type": "enum", "choices": ["fast", "accurate"], "required": false}
+            ],
+            "outputs": []
+        }
+    }"#;
+    let task_definition = TaskDefinition::from_task_file(task_file, true).unwrap();
+    assert_eq!(task_definition.inputs[0].data_type, Some(InputType::Duration));
+    assert_eq!(task_definition.inputs[1].data_type, Some(InputType::Other("zenoh_topic".to_string())));
+    assert_eq!(task_definition.inputs[2].choices, [json!("fast"), json!("accurate")]);
+    let json = serde_json::to_value(&task_definition.inputs[1]).unwrap();
+    assert_eq!(json["data_type"], "zenoh_topic");
+}
+
+#[test]
+fn submitted_inputs_are_coerced_to_their_declared_types() {
+    let mut mode = input("mode", "enum");
+    mode.choices = vec![json!("fast"), json!("accurate")];
+    let task_definition = definition(vec![
+        input("timeout", "duration"),
+        input("memory", "bytesize"),
+        input("since", "timestamp"),
+        input("count", "int"),
+        input("verbose", "bool"),
+        mode,
+    ]);
+    let inputs = task_definition
+        .coerce_inputs(json!({
+            "timeout": "30s",
+            "memory": "512MB",
+            "since": "2026-01-02T03:04:05+02:00",
+            "count": "7",
+            "verbose": "yes",
+            "mode": "fast",
+            "extra": [1, 2],
+        }))
+        .unwrap();
+    assert_eq!(
+        inputs,
+        json!({
+            "timeout": 30,
+            "memory": 512_000_000,
+            "since": "2026-01-02T01:04:05Z",
+            "count": 7,
+            "verbose": true,
+            "mode": "fast",
+            "extra": [1, 2],
+        })
+    );
+}
+
+fn validation_errors(task_definition: &TaskDefinition, inputs: serde_json::Value) -> Vec<InputError> {
+    let error = task_definition.coerce_inputs(inputs).unwrap_err();
+    error.downcast::<InputValidationError>().unwrap().errors
+}
+
+#[test]
+fn every_invalid_input_is_reported() {
+    let mut mode = input("mode", "enum");
+    mode.choices = vec![json!("fast"), json!("accurate")];
+    let task_definition = definition(vec![input("memory", "bytesize"), mode, input("count", "integer")]);
+    let errors = validation_errors(&task_definition, json!({"memory": "lots", "mode": "slow"}));
+    assert_eq!(errors.len(), 3);
+    assert_eq!(errors[0].input, "memory");
+    assert!(matches!(errors[0].problem, InputProblem::Invalid { .. }));
+    assert_eq!(
+        errors[1].problem,
+        InputProblem::NotAllowed { value: json!("slow"), choices: vec![json!("fast"), json!("accurate")] }
+    );
+    assert_eq!(errors[2], InputError { input: "count".to_string(), problem: InputProblem::Missing });
+    assert_eq!(errors[1].to_string(), r#"input 'mode' must be one of "fast", "accurate", got "slow""#);
+    assert_eq!(errors[2].to_string(), "missing required input 'count'");
+    assert!(task_definition.coerce_inputs(json!([1024, "fast"])).is_err());
+}
+
+#[test]
+fn choices_apply_to_coerced_values_of_any_type() {
+    let mut threads = input("threads", "integer");
+    threads.choices = vec![json!(1), json!(2), json!(4)];
+    let task_definition = definition(vec![threads]);
+    assert_eq!(task_definition.coerce_inputs(json!({"threads": "4"})).unwrap(), json!({"threads": 4}));
+    let errors = validation_errors(&task_definition, json!({"threads": 3}));
+    assert_eq!(errors[0].problem, InputProblem::NotAllowed { value: json!(3), choices: vec![json!(1), json!(2), json!(4)] });
+}
+
+#[test]
+fn validation_errors_serialize_for_clients() {
+    let error = InputValidationError {
+        task: "resize".to_string(),
+        errors: vec![InputError {
+            input: "mode".to_string(),
+            problem: InputProblem::NotAllowed { value: json!("slow"), choices: vec![json!("fast")] },
+        }],
+        failed_checks: Vec::new(),
+    };
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({"task": "resize", "errors": [{"input": "mode", "problem": "not_allowed", "value": "slow", "choices": ["fast"]}]})
+    );
+}
+
+#[test]
+fn defaults_are_filled_in_and_coerced() {
+    let mut timeout = input("timeout", "duration");
+    timeout.required = false;
+    timeout.default_value = Some(json!("2m"));
+    let mut label = input("label", "string");
+    label.required = false;
+    let task_definition = definition(vec![timeout, label]);
+    assert_eq!(task_definition.coerce_inputs(serde_json::Value::Null).unwrap(), json!({"timeout": 120}));
+    assert_eq!(task_definition.coerce_inputs(json!({"timeout": 5})).unwrap(), json!({"timeout": 5}));
+}
+
+#[test]
+fn deferred_inputs_are_coerced_once_they_arrive() {
+    let task_definition = definition(vec![input("depth", "object"), input("scale", "number")]);
+    // At submission only `scale` is there, and checked
+    let submitted = task_definition.coerce_inputs_except(json!({"scale": "0.5"}), &["depth"]).unwrap();
+    assert_eq!(submitted, json!({"scale": 0.5}));
+    assert!(task_definition.coerce_inputs_except(json!({"scale": "big"}), &["depth"]).is_err());
+    // On the worker the streamed value gets its declared type applied
+    let bound = json!({"scale": 0.5, "depth": "not an object"});
+    assert!(task_definition.coerce_deferred_inputs(bound, &["depth"]).is_err());
+    let bound = json!({"scale": 0.5, "depth": {"frame": 3}});
+    assert_eq!(task_definition.coerce_deferred_inputs(bound.clone(), &["depth"]).unwrap(), bound);
+    assert!(task_definition.coerce_deferred_inputs(json!({"scale": 0.5}), &["depth"]).is_err());
+}
@@ -0,0 +1,31 @@
+use corebrum_examples::keyspace::{escape_key_segment, validate_key_segment, Keyspace};
+use zenoh::key_expr::KeyExpr;
+
+#[test]
+fn escaped_segments_stay_within_one_chunk() {
+    assert_eq!(escape_key_segment("task-1"), "task-1");
+    assert_eq!(escape_key_segment("a/b"), "a%2Fb");
+    assert_eq!(escape_key_segment("**"), "%2A%2A");
+    assert_eq!(escape_key_segment("50%"), "50%25");
+    assert_eq!(escape_key_segment("@admin"), "%40admin");
+    // Only a leading '@' starts an admin chunk
+    assert_eq!(escape_key_segment("user@host"), "user@host");
+    assert_eq!(escape_key_segment(""), "%");
+    assert_eq!(escape_key_segment("line\nbreak"), "line%0Abreak");
+
+    let keyspace = Keyspace::for_queue("@/queue");
+    for task_id in ["", "@", "a/b", "$*", "x?y#z", " ", "é"] {
+        let key = keyspace.status(task_id);
+        assert!(KeyExpr::try_from(key.as_str()).is_ok(), "{:?}", key);
+        assert_eq!(key.split('/').count(), keyspace.status("task-1").split('/').count(), "{:?}", key);
+    }
+}
+
+#[test]
+fn segments_that_would_need_escaping_are_refused() {
+    assert!(validate_key_segment("Task ID", "task-1").is_ok());
+    assert!(validate_key_segment("Task ID", "user@host").is_ok());
+    for value in ["", "@admin", "a/b", "*", "$x", "a?b", "a#b", "50%", "a b", "a\tb"] {
+        assert!(validate_key_segment("Task ID", value).is_err(), "{:?}", value);
+    }
+}
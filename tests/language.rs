@@ -0,0 +1,41 @@
+use corebrum_examples::language::{detect_language, language_from_extension, resolve_language};
+
+#[test]
+fn typescript_is_detected_by_extension_and_shebang() {
+    for path in ["task.ts", "https://example.com/src/task.mts?raw=1", "task.cts"] {
+        assert_eq!(language_from_extension(path), Some("typescript"), "{}", path);
+    }
+    assert_eq!(detect_language(None, None, "#!/usr/bin/env ts-node\nconsole.log('{}');\n"), Some("typescript"));
+    assert_eq!(detect_language(None, Some("TypeScript"), ""), Some("typescript"));
+    assert_eq!(resolve_language("auto", Some("task.ts"), None, ""), "typescript");
+}
+
+#[test]
+fn r_is_detected_by_extension_and_shebang() {
+    assert_eq!(language_from_extension("analysis.R"), Some("r"));
+    assert_eq!(language_from_extension("analysis.r"), Some("r"));
+    assert_eq!(detect_language(None, None, "#!/usr/bin/env Rscript\nprint(1)\n"), Some("r"));
+    assert_eq!(resolve_language("auto", Some("https://example.com/fit.R"), None, ""), "r");
+}
+
+#[test]
+fn julia_is_detected_by_extension_and_shebang() {
+    assert_eq!(language_from_extension("simulate.jl"), Some("julia"));
+    assert_eq!(detect_language(None, None, "#!/usr/local/bin/julia1.10 --project\nprintln(1)\n"), Some("julia"));
+    assert_eq!(detect_language(None, Some("Julia"), ""), Some("julia"));
+}
+
+#[test]
+fn jars_are_detected_by_extension() {
+    assert_eq!(language_from_extension("https://repo.example.com/task-1.0.jar"), Some("java"));
+    assert_eq!(resolve_language("auto", Some("build/libs/task.JAR"), None, ""), "java");
+}
+
+#[test]
+fn c_and_cpp_are_detected_by_extension() {
+    assert_eq!(language_from_extension("kernel.c"), Some("c"));
+    for path in ["solver.cpp", "solver.cc", "solver.cxx", "solver.C++"] {
+        assert_eq!(language_from_extension(path), Some("cpp"), "{}", path);
+    }
+    assert_eq!(detect_language(None, Some("C++"), ""), Some("cpp"));
+}
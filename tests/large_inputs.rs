@@ -0,0 +1,74 @@
+use corebrum_examples::keyspace::Keyspace;
+use corebrum_examples::large_inputs::{decode_inputs, InputsOffload, DEFAULT_INLINE_INPUTS_BYTES};
+use corebrum_examples::schema::{Job, TaskDefinition, TaskSource};
+use corebrum_examples::zenoh_utils::Codec;
+
+fn job(inputs: serde_json::Value) -> Job {
+    let task_definition = TaskDefinition {
+        name: "sum".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    Job::new_user_task("tasks".to_string(), task_definition, inputs)
+}
+
+fn large_inputs() -> serde_json::Value {
+    serde_json::json!({ "values": vec![42; DEFAULT_INLINE_INPUTS_BYTES] })
+}
+
+#[test]
+fn small_inputs_stay_inline() {
+    let mut job = job(serde_json::json!({"values": [1, 2, 3]}));
+    let offloaded = InputsOffload::default().offload(&mut job, &Keyspace::for_queue("tasks")).unwrap();
+    assert!(offloaded.is_none());
+    assert!(job.inputs_ref.is_none());
+    assert_eq!(job.inputs["values"][2], 3);
+}
+
+#[test]
+fn large_inputs_are_swapped_for_a_reference_that_decodes_back() {
+    let keyspace = Keyspace::for_queue("tasks");
+    let mut job = job(large_inputs());
+    let payload = InputsOffload::default().offload(&mut job, &keyspace).unwrap().unwrap();
+    assert!(job.inputs.is_null());
+    let inputs_ref = job.inputs_ref.clone().unwrap();
+    assert_eq!(inputs_ref.key, keyspace.inputs(&job.task_id));
+    assert_eq!(inputs_ref.codec, Some(Codec::Gzip));
+    assert!((payload.len() as u64) < inputs_ref.size_bytes);
+    assert_eq!(decode_inputs(&inputs_ref, &payload).unwrap(), large_inputs());
+
+    // Offloading an already offloaded job leaves it alone
+    assert!(InputsOffload::default().offload(&mut job, &keyspace).unwrap().is_none());
+    // So does announcing it inline
+    assert!(serde_json::to_string(&job).unwrap().len() < DEFAULT_INLINE_INPUTS_BYTES);
+}
+
+#[test]
+fn offloaded_inputs_can_skip_compression() {
+    let offload = InputsOffload { threshold_bytes: Some(16), codec: None };
+    let mut job = job(serde_json::json!({"text": "more than sixteen bytes of JSON"}));
+    let payload = offload.offload(&mut job, &Keyspace::for_queue("tasks")).unwrap().unwrap();
+    let inputs_ref = job.inputs_ref.unwrap();
+    assert_eq!(inputs_ref.size_bytes, payload.len() as u64);
+    assert_eq!(decode_inputs(&inputs_ref, &payload).unwrap()["text"], "more than sixteen bytes of JSON");
+}
+
+#[test]
+fn tampered_payloads_are_rejected() {
+    let offload = InputsOffload { threshold_bytes: Some(0), codec: None };
+    let mut job = job(serde_json::json!({"n": 1}));
+    offload.offload(&mut job, &Keyspace::for_queue("tasks")).unwrap().unwrap();
+    assert!(decode_inputs(job.inputs_ref.as_ref().unwrap(), br#"{"n":2}"#).is_err());
+}
+
+#[test]
+fn disabled_offload_announces_everything_inline() {
+    let mut job = job(large_inputs());
+    assert!(InputsOffload::disabled().offload(&mut job, &Keyspace::for_queue("tasks")).unwrap().is_none());
+    assert!(job.inputs_ref.is_none());
+}
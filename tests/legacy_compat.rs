@@ -0,0 +1,48 @@
+use corebrum_examples::schema::{Claim, Status, TaskStatus};
+
+#[test]
+fn legacy_claim_normalizes_to_current_shape() {
+    let claim: Claim = serde_json::from_str(r#"{"task_id": "t-1", "peer": "worker-7", "eta_ms": 1500, "lease_until_ms": 1718000000200}"#).unwrap();
+
+    assert_eq!(claim.task_id, "t-1");
+    assert_eq!(claim.worker_id, "worker-7");
+    assert_eq!(claim.estimated_duration_seconds, Some(2));
+    assert_eq!(claim.load(), 0);
+
+    // Re-published in the current shape only
+    let value = serde_json::to_value(&claim).unwrap();
+    assert!(value.get("peer").is_none());
+    assert_eq!(value["worker_id"], "worker-7");
+}
+
+#[test]
+fn current_claim_is_unaffected() {
+    let json = r#"{"task_id": "t-1", "worker_id": "worker-7", "claimed_at": "2024-06-10T12:00:00Z", "estimated_duration_seconds": 3, "running_tasks": 1, "queue_depth": 2}"#;
+    let claim: Claim = serde_json::from_str(json).unwrap();
+
+    assert_eq!(claim.worker_id, "worker-7");
+    assert_eq!(claim.estimated_duration_seconds, Some(3));
+    assert_eq!(claim.load(), 3);
+}
+
+#[test]
+fn legacy_status_normalizes_to_current_shape() {
+    let status: Status = serde_json::from_str(r#"{"task_id": "t-1", "state": "assigned", "progress": 0.1}"#).unwrap();
+
+    assert_eq!(status.task_id, "t-1");
+    assert_eq!(status.status, TaskStatus::Assigned);
+    assert_eq!(status.progress, Some(0.1));
+    assert_eq!(status.epoch, 0);
+}
+
+#[test]
+fn unknown_legacy_state_is_rejected() {
+    let error = serde_json::from_str::<Status>(r#"{"task_id": "t-1", "state": "exploded"}"#).unwrap_err();
+    assert!(error.to_string().contains("exploded"), "{}", error);
+}
+
+#[test]
+fn malformed_claim_is_an_error() {
+    assert!(serde_json::from_str::<Claim>(r#"{"task_id": "t-1", "peer": "worker-7"}"#).is_err());
+    assert!(serde_json::from_str::<Claim>(r#"{"task_id": "t-1"}"#).is_err());
+}
@@ -0,0 +1,40 @@
+use corebrum_examples::metrics::{render_prometheus, GcCounters, QueueGauges, VersionCounters};
+
+#[test]
+fn gc_counters_are_rendered_per_queue() {
+    let gauges = QueueGauges::new("perception");
+    let gc = GcCounters::new("perception");
+    gc.record_reclaimed();
+    gc.record_reclaimed();
+    gc.record_failed_delete();
+    gc.set_tracked(7);
+
+    let body = render_prometheus(&[gauges], &[gc], &[]);
+    assert!(body.contains("corebrum_gc_reclaimed_tasks_total{queue=\"perception\"} 2\n"), "{}", body);
+    assert!(body.contains("corebrum_gc_failed_deletes_total{queue=\"perception\"} 1\n"), "{}", body);
+    assert!(body.contains("corebrum_gc_tracked_tasks{queue=\"perception\"} 7\n"), "{}", body);
+}
+
+#[test]
+fn gc_metrics_are_omitted_without_a_collector() {
+    let body = render_prometheus(&[QueueGauges::new("perception")], &[], &[]);
+    assert!(!body.contains("corebrum_gc_"), "{}", body);
+}
+
+#[test]
+fn results_are_counted_per_task_version() {
+    let versions = VersionCounters::new("perception");
+    assert_eq!(versions.success_rate("vision/resize@1.1"), None);
+    versions.record("vision/resize@1.0", true);
+    versions.record("vision/resize@1.1", true);
+    versions.record("vision/resize@1.1", false);
+    assert_eq!(versions.success_rate("vision/resize@1.1"), Some(0.5));
+
+    let body = render_prometheus(&[], &[], &[versions]);
+    assert!(
+        body.contains("corebrum_task_version_results_total{queue=\"perception\",task=\"vision/resize\",version=\"1.1\",outcome=\"failed\"} 1\n"),
+        "{}",
+        body
+    );
+    assert!(!render_prometheus(&[], &[], &[VersionCounters::new("perception")]).contains("corebrum_task_version_"));
+}
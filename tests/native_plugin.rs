@@ -0,0 +1,49 @@
+#![cfg(feature = "plugin")]
+use std::process::Command;
+use corebrum_examples::native_plugin::NativePlugin;
+use corebrum_examples::runtimes::c_compiler;
+
+const ECHO_PLUGIN_C: &str = r#"#include <stdlib.h>
+#include <string.h>
+
+char *corebrum_run(const char *inputs_json) {
+    if (strcmp(inputs_json, "{}") == 0) {
+        return NULL;
+    }
+    return strdup(inputs_json);
+}
+
+void corebrum_free(char *outputs_json) {
+    free(outputs_json);
+}
+"#;
+
+#[test]
+fn files_that_are_not_libraries_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("libtask.so");
+    std::fs::write(&path, b"not a shared library").unwrap();
+    let error = NativePlugin::load(&path, &"e".repeat(64)).err().unwrap();
+    assert!(error.to_string().contains("Failed to load plugin"), "{}", error);
+}
+
+#[test]
+#[ignore = "needs a C compiler"]
+fn plugins_are_called_through_the_c_abi() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("echo.c");
+    let library = dir.path().join(format!("libecho.{}", std::env::consts::DLL_EXTENSION));
+    std::fs::write(&source, ECHO_PLUGIN_C).unwrap();
+    let status = Command::new(c_compiler("c").unwrap())
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(&source)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let plugin = NativePlugin::load(&library, &"f".repeat(64)).unwrap();
+    assert_eq!(plugin.run(r#"{"n": 1}"#).unwrap(), r#"{"n": 1}"#);
+    assert!(plugin.run("{}").unwrap_err().to_string().contains("no outputs"));
+    assert!(plugin.run("nul\0byte").is_err());
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "onnx")]
+use corebrum_examples::onnx::{Tensor, TensorData, TensorSpec};
+
+fn spec(value: serde_json::Value) -> TensorSpec {
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn inline_tensors_are_parsed_and_checked_against_their_shape() {
+    let tensor = Tensor::from_spec(&spec(serde_json::json!({"shape": [1, 3], "data": [0.5, 1, 2.5]}))).unwrap();
+    assert_eq!(tensor.data, TensorData::Float32(vec![0.5, 1.0, 2.5]));
+    assert_eq!(tensor.to_json(), serde_json::json!({"dtype": "float32", "shape": [1, 3], "data": [0.5, 1.0, 2.5]}));
+
+    let tensor = Tensor::from_spec(&spec(serde_json::json!({"dtype": "int64", "shape": [2], "data": [-1, 7]}))).unwrap();
+    assert_eq!(tensor.data, TensorData::Int64(vec![-1, 7]));
+
+    let refused = [
+        serde_json::json!({"shape": [2, 2], "data": [1, 2, 3]}),
+        serde_json::json!({"dtype": "uint8", "shape": [1], "data": [256]}),
+        serde_json::json!({"dtype": "int32", "shape": [1], "data": [1.5]}),
+        serde_json::json!({"shape": [1], "data": ["one"]}),
+        serde_json::json!({"dtype": "float16", "shape": [1], "data": [1]}),
+        serde_json::json!({"shape": [1], "url": "https://example.com/tensor.bin"}),
+    ];
+    for value in refused {
+        assert!(Tensor::from_spec(&spec(value.clone())).is_err(), "{} was accepted", value);
+    }
+}
+
+#[test]
+fn referenced_tensors_are_little_endian() {
+    let bytes: Vec<u8> = [1.5f32, -2.0].iter().flat_map(|value| value.to_le_bytes()).collect();
+    assert_eq!(Tensor::from_bytes("float32", vec![2], &bytes).unwrap().data, TensorData::Float32(vec![1.5, -2.0]));
+    assert_eq!(Tensor::from_bytes("uint8", vec![2, 2], &[1, 2, 3, 4]).unwrap().data, TensorData::Uint8(vec![1, 2, 3, 4]));
+    // A trailing partial value doesn't make up for a missing one
+    assert!(Tensor::from_bytes("int32", vec![2], &bytes[..7]).is_err());
+}
+
+#[test]
+fn batches_split_and_join_along_the_first_dimension() {
+    let tensor = Tensor::new(vec![5, 2], TensorData::Int32((0..10).collect())).unwrap();
+    let batches = tensor.split(2);
+    assert_eq!(batches.iter().map(|batch| batch.shape.clone()).collect::<Vec<_>>(), [vec![2, 2], vec![2, 2], vec![1, 2]]);
+    assert_eq!(batches[2].data, TensorData::Int32(vec![8, 9]));
+    assert_eq!(Tensor::concat(batches).unwrap(), tensor);
+
+    let mismatched = vec![tensor.clone(), Tensor::new(vec![1, 10], TensorData::Int32((0..10).collect())).unwrap()];
+    assert!(Tensor::concat(mismatched).is_err());
+    let mixed = vec![tensor, Tensor::new(vec![1, 2], TensorData::Uint8(vec![0, 1])).unwrap()];
+    assert!(Tensor::concat(mixed).is_err());
+}
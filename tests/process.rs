@@ -0,0 +1,31 @@
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+use corebrum_examples::process::{run_command, CappedOutput, ProcessLimits, MAX_PROCESS_OUTPUT_BYTES};
+
+#[test]
+fn capped_output_keeps_only_the_start() {
+    let mut output = CappedOutput::new(4);
+    output.push(b"abc");
+    output.push(b"");
+    assert_eq!(output.finish(), (b"abc".to_vec(), false));
+
+    let mut output = CappedOutput::new(4);
+    output.push(b"abc");
+    output.push(b"def");
+    output.push(b"ghi");
+    assert_eq!(output.finish(), (b"abcd".to_vec(), true));
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn chatty_children_are_drained_but_not_kept() {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(format!("head -c {} /dev/zero; echo done >&2", MAX_PROCESS_OUTPUT_BYTES * 2));
+    let output = run_command(command, &ProcessLimits::default(), &CancellationToken::new()).await.unwrap();
+    // Still read to the end: the child neither blocked nor died of a broken pipe
+    assert!(output.success(), "{}", output.failure_reason());
+    assert_eq!(output.stdout.len(), MAX_PROCESS_OUTPUT_BYTES);
+    assert!(output.stdout_truncated);
+    assert_eq!(output.stderr, b"done\n");
+    assert!(!output.stderr_truncated);
+}
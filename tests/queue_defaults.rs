@@ -0,0 +1,86 @@
+use corebrum_examples::config::{QueueDefaults, QueueSettings};
+use corebrum_examples::schema::{DefinitionPin, Job, TaskDefinition, TaskRequirements, TaskSource, DEFAULT_TIMEOUT_SECONDS};
+
+fn job(requirements: Option<TaskRequirements>) -> Job {
+    let task_definition = TaskDefinition {
+        name: "sum".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements,
+        checks: Vec::new(),
+    };
+    Job::new_user_task("user_tasks".to_string(), task_definition, serde_json::json!({}))
+}
+
+fn requirements(job: &Job) -> &TaskRequirements {
+    job.task_definition.as_ref().unwrap().requirements.as_ref().unwrap()
+}
+
+const SETTINGS: &str = r#"
+[defaults]
+timeout_seconds = 300
+max_attempts = 3
+
+[queues.user_tasks]
+timeout_seconds = 600
+memory_mb = 1024
+"#;
+
+#[test]
+fn queue_entries_override_the_defaults_field_by_field() {
+    let settings: QueueSettings = toml::from_str(SETTINGS).unwrap();
+    let user_tasks = settings.for_queue("user_tasks");
+    assert_eq!(user_tasks.timeout_seconds, Some(600));
+    assert_eq!(user_tasks.memory_mb, Some(1024));
+    assert_eq!(user_tasks.max_attempts, Some(3));
+    assert_eq!(settings.for_queue("other"), settings.defaults);
+}
+
+#[test]
+fn defaults_fill_in_what_the_task_leaves_unset() {
+    let defaults = toml::from_str::<QueueSettings>(SETTINGS).unwrap().for_queue("user_tasks");
+    let mut job = job(None);
+    assert_eq!(job.timeout_seconds, Some(DEFAULT_TIMEOUT_SECONDS));
+    defaults.apply(&mut job);
+    assert_eq!(requirements(&job).timeout_seconds, Some(600));
+    assert_eq!(requirements(&job).memory_mb, Some(1024));
+    assert_eq!(job.timeout_seconds, Some(600));
+    assert_eq!(job.max_attempts(), Some(3));
+}
+
+#[test]
+fn task_values_win_over_queue_defaults() {
+    let defaults = QueueDefaults { timeout_seconds: Some(600), memory_mb: Some(1024), ..Default::default() };
+    let mut job = job(Some(TaskRequirements { timeout_seconds: Some(30), ..Default::default() }));
+    assert_eq!(job.timeout_seconds, Some(30));
+    defaults.apply(&mut job);
+    assert_eq!(requirements(&job).timeout_seconds, Some(30));
+    assert_eq!(requirements(&job).memory_mb, Some(1024));
+    assert_eq!(job.timeout_seconds, Some(30));
+}
+
+#[test]
+fn pinned_and_handler_jobs_are_left_alone() {
+    let defaults = QueueDefaults { memory_mb: Some(1024), ..Default::default() };
+    let mut pinned = job(None);
+    pinned.definition_pin = Some(DefinitionPin {
+        reference: "sum@1.0".to_string(),
+        sha256: pinned.task_definition.as_ref().unwrap().sha256(),
+    });
+    defaults.apply(&mut pinned);
+    assert!(pinned.task_definition.as_ref().unwrap().requirements.is_none());
+
+    let mut handler_job = Job::new("user_tasks".to_string(), serde_json::json!({}));
+    defaults.apply(&mut handler_job);
+    assert!(handler_job.task_definition.is_none());
+}
+
+#[test]
+fn empty_defaults_change_nothing() {
+    let mut job = job(None);
+    QueueDefaults::default().apply(&mut job);
+    assert!(job.task_definition.as_ref().unwrap().requirements.is_none());
+}
@@ -0,0 +1,39 @@
+use corebrum_examples::receipts::ReceiptSigner;
+use corebrum_examples::schema::Receipt;
+
+fn receipt() -> Receipt {
+    Receipt {
+        task_id: "task-1".to_string(),
+        queue: "user_tasks".to_string(),
+        accepted_at: chrono::Utc::now(),
+        queue_position: 3,
+        assigner_id: "assigner-1".to_string(),
+        signature: None,
+    }
+}
+
+#[test]
+fn signed_receipts_verify_after_a_round_trip() {
+    let signer = ReceiptSigner::new("shared secret");
+    let mut receipt = receipt();
+    signer.sign(&mut receipt);
+
+    let received: Receipt = serde_json::from_str(&serde_json::to_string(&receipt).unwrap()).unwrap();
+    signer.verify(&received).unwrap();
+}
+
+#[test]
+fn tampered_unsigned_or_foreign_receipts_are_rejected() {
+    let signer = ReceiptSigner::new("shared secret");
+    let mut receipt = receipt();
+    assert!(signer.verify(&receipt).is_err());
+
+    signer.sign(&mut receipt);
+    assert!(ReceiptSigner::new("other secret").verify(&receipt).is_err());
+
+    receipt.queue_position = 0;
+    assert!(signer.verify(&receipt).is_err());
+
+    receipt.signature = Some("not hex".to_string());
+    assert!(signer.verify(&receipt).is_err());
+}
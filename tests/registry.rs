@@ -0,0 +1,119 @@
+use corebrum_examples::registry::{DefinitionRef, Rollout, TaskRegistry};
+use corebrum_examples::schema::{DefinitionPin, TaskDefinition, TaskInput, TaskSource};
+
+fn definition(code: &str) -> TaskDefinition {
+    TaskDefinition {
+        name: "resize".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: code.to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    }
+}
+
+#[test]
+fn references_parse_as_name_at_version() {
+    let reference: DefinitionRef = "vision/resize@1.2.0".parse().unwrap();
+    assert_eq!(reference.name, "vision/resize");
+    assert_eq!(reference.version, "1.2.0");
+    assert_eq!(reference.to_string(), "vision/resize@1.2.0");
+    for invalid in ["resize", "resize@", "@1.0", "../resize@1.0", "resize@1.0/..", "re size@1.0"] {
+        assert!(invalid.parse::<DefinitionRef>().is_err(), "{} should be rejected", invalid);
+    }
+}
+
+#[test]
+fn urls_fill_in_the_template() {
+    let registry = TaskRegistry::new("https://tasks.example.com/{name}/{version}.yaml");
+    let reference: DefinitionRef = "vision/resize@1.2.0".parse().unwrap();
+    assert_eq!(registry.url(&reference), "https://tasks.example.com/vision/resize/1.2.0.yaml");
+}
+
+#[tokio::test]
+async fn pinned_definitions_resolve_without_the_registry() {
+    let pins = tempfile::tempdir().unwrap();
+    let reference: DefinitionRef = "resize@1.0".parse().unwrap();
+    let pinned = definition("def handler(inputs):\n    return {}\n");
+    std::fs::create_dir_all(pins.path().join("resize")).unwrap();
+    std::fs::write(pins.path().join("resize").join("1.0.json"), serde_json::to_string(&pinned).unwrap()).unwrap();
+
+    // Nothing listens here; only the pin can answer
+    let registry = TaskRegistry::new("http://127.0.0.1:9/{name}/{version}.json").with_pins_dir(pins.path().to_path_buf());
+    let (task_definition, pin) = registry.resolve(&reference).await.unwrap();
+    assert_eq!(task_definition, pinned);
+    assert_eq!(pin.reference, "resize@1.0");
+    assert_eq!(pin.sha256, pinned.sha256());
+
+    registry.forget(&reference).unwrap();
+    assert!(registry.resolve(&reference).await.is_err());
+    // Forgetting twice is fine
+    registry.forget(&reference).unwrap();
+}
+
+#[tokio::test]
+async fn builtins_are_always_published() {
+    let pins = tempfile::tempdir().unwrap();
+    let registry = TaskRegistry::new("http://127.0.0.1:9/{name}/{version}.json").with_pins_dir(pins.path().to_path_buf());
+    let (task_definition, pin) = registry.resolve(&"builtin/fibonacci@1".parse().unwrap()).await.unwrap();
+    assert_eq!(task_definition.source, TaskSource::Builtin { name: "fibonacci".to_string() });
+    assert_eq!(pin.sha256, task_definition.sha256());
+    assert!(registry.resolve(&"builtin/fibonacci@2".parse().unwrap()).await.is_err());
+    assert!(registry.resolve(&"builtin/rm@1".parse().unwrap()).await.is_err());
+}
+
+#[test]
+fn pins_reject_any_other_definition() {
+    let reviewed = definition("def handler(inputs):\n    return {}\n");
+    let pin = DefinitionPin { reference: "resize@1.0".to_string(), sha256: reviewed.sha256() };
+    assert!(pin.verify(&reviewed).is_ok());
+    assert!(pin.verify(&definition("def handler(inputs):\n    return {'evil': True}\n")).is_err());
+}
+
+#[test]
+fn definition_hash_ignores_key_order() {
+    let with_default = |default_value: &str| {
+        let mut task_definition = definition("");
+        task_definition.inputs = vec![TaskInput {
+            name: "options".to_string(),
+            description: None,
+            required: false,
+            default_value: Some(serde_json::from_str(default_value).unwrap()),
+            data_type: None,
+            choices: Vec::new(),
+        }];
+        task_definition
+    };
+    let yaml_order = with_default(r#"{"width": 640, "height": 480, "filter": {"kind": "lanczos", "radius": 3}}"#);
+    let json_order = with_default(r#"{"filter": {"radius": 3, "kind": "lanczos"}, "height": 480, "width": 640}"#);
+    assert_eq!(yaml_order.sha256(), json_order.sha256());
+    assert_ne!(yaml_order.sha256(), with_default(r#"{"width": 320}"#).sha256());
+}
+
+#[test]
+fn rollouts_route_bare_names_by_weight() {
+    let rollout = Rollout::new("1.0", "1.1", 5.0).unwrap();
+    assert_eq!(rollout.pick(0.0), "1.1");
+    assert_eq!(rollout.pick(0.049), "1.1");
+    assert_eq!(rollout.pick(0.05), "1.0");
+    assert_eq!(rollout.pick(0.99), "1.0");
+    assert!(Rollout::new("1.0", "1.1", 120.0).is_err());
+
+    let registry = TaskRegistry::new("https://tasks.example.com/{name}/{version}.yaml")
+        .with_rollout("vision/resize", rollout)
+        .unwrap();
+    assert_eq!(registry.route("vision/resize", 0.01).unwrap().to_string(), "vision/resize@1.1");
+    assert_eq!(registry.route("vision/resize", 0.5).unwrap().to_string(), "vision/resize@1.0");
+    // Explicit versions bypass the rollout
+    assert_eq!(registry.route("vision/resize@0.9", 0.01).unwrap().to_string(), "vision/resize@0.9");
+
+    // Promoting the canary
+    registry.set_rollout("vision/resize", Rollout::new("1.0", "1.1", 100.0).unwrap()).unwrap();
+    assert_eq!(registry.route("vision/resize", 0.99).unwrap().version, "1.1");
+    assert!(registry.set_rollout("vision/resize", Rollout::new("1.0", "../1.1", 5.0).unwrap()).is_err());
+
+    registry.end_rollout("vision/resize");
+    assert!(registry.route("vision/resize", 0.5).is_err());
+}
@@ -0,0 +1,82 @@
+use corebrum_examples::replay::{pinned_source, OutputChange, ResultDiff};
+use corebrum_examples::schema::{Provenance, Result as TaskResult, TaskDefinition, TaskSource, TaskStatus};
+
+fn result(outputs: serde_json::Value) -> TaskResult {
+    let mut result = TaskResult::failure("task-1".to_string(), "worker-1".to_string(), String::new());
+    result.status = TaskStatus::Completed;
+    result.error = None;
+    result.outputs = serde_json::from_value(outputs).unwrap();
+    result
+}
+
+#[test]
+fn identical_results_do_not_differ() {
+    let archived = result(serde_json::json!({"factorial": 120}));
+    let replayed = result(serde_json::json!({"factorial": 120}));
+    assert!(ResultDiff::between(&archived, &replayed, &[]).is_empty());
+}
+
+#[test]
+fn output_changes_are_reported_by_name() {
+    let archived = result(serde_json::json!({"factorial": 120, "host": "a", "legacy": true}));
+    let mut replayed = result(serde_json::json!({"factorial": 121, "host": "b", "extra": 1}));
+    replayed.status = TaskStatus::Failed;
+
+    let diff = ResultDiff::between(&archived, &replayed, &["host".to_string()]);
+    assert_eq!(diff.status, Some((TaskStatus::Completed, TaskStatus::Failed)));
+    assert_eq!(
+        diff.outputs,
+        vec![
+            OutputChange::Added { name: "extra".to_string(), replayed: serde_json::json!(1) },
+            OutputChange::Changed {
+                name: "factorial".to_string(),
+                archived: serde_json::json!(120),
+                replayed: serde_json::json!(121),
+            },
+            OutputChange::Missing { name: "legacy".to_string(), archived: serde_json::json!(true) },
+        ]
+    );
+}
+
+#[test]
+fn numbers_can_match_within_a_tolerance() {
+    let archived = result(serde_json::json!({"factorial": 120, "scores": [0.5, {"p": 0.25}]}));
+    let ported = result(serde_json::json!({"factorial": 120.0, "scores": [0.5000001, {"p": 0.25}]}));
+    assert_eq!(ResultDiff::between(&archived, &ported, &[]).outputs.len(), 2);
+    assert!(ResultDiff::between_within(&archived, &ported, &[], Some(1e-6)).is_empty());
+    assert!(!ResultDiff::between_within(&archived, &ported, &[], Some(1e-9)).is_empty());
+}
+
+#[test]
+fn replayed_sources_are_pinned_to_what_the_worker_ran() {
+    let definition = |source: TaskSource| TaskDefinition {
+        name: "detect".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source,
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    let provenance = Provenance {
+        source_url: Some("https://gist.githubusercontent.com/someone/abc/raw/f00d/detect.py".to_string()),
+        git_commit: Some("0123abc".to_string()),
+        ..Default::default()
+    };
+    let git = definition(TaskSource::Git {
+        repo: "https://github.com/someone/tasks".to_string(),
+        path: "detect.py".to_string(),
+        branch: Some("main".to_string()),
+        rev: None,
+        submodules: false,
+    });
+    match pinned_source(&git, Some(&provenance)).source {
+        TaskSource::Git { rev, .. } => assert_eq!(rev.as_deref(), Some("0123abc")),
+        other => panic!("unexpected source {:?}", other),
+    }
+    let gist = definition(TaskSource::Gist { id: "abc".to_string(), filename: "detect.py".to_string() });
+    assert_eq!(pinned_source(&gist, Some(&provenance)).source, TaskSource::Url { url: provenance.source_url.clone().unwrap() });
+    // Nothing to pin without provenance
+    assert_eq!(pinned_source(&gist, None), gist);
+}
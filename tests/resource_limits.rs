@@ -0,0 +1,34 @@
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::{TaskDefinition, TaskRequirements, TaskStatus, MAX_REQUIREMENT_MB};
+use corebrum_examples::worker::Worker;
+
+const FILL_DISK_PY: &str = "import time\nwith open('filler.bin', 'wb') as f:\n    for _ in range(400):\n        f.write(b'\\0' * 1024 * 1024)\n        f.flush()\n        time.sleep(0.01)\nprint('{}')\n";
+
+#[test]
+fn impossible_sizes_are_refused() {
+    assert!(TaskRequirements { memory_mb: Some(MAX_REQUIREMENT_MB), disk_mb: Some(1024), ..Default::default() }.check().is_ok());
+    assert!(TaskRequirements { memory_mb: Some(u64::MAX), ..Default::default() }.check().is_err());
+    assert!(TaskRequirements { disk_mb: Some(MAX_REQUIREMENT_MB + 1), ..Default::default() }.check().is_err());
+}
+
+#[tokio::test]
+async fn tasks_writing_past_their_disk_quota_are_killed() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let task = TaskDefinition {
+        requirements: Some(TaskRequirements { disk_mb: Some(8), timeout_seconds: Some(60), ..Default::default() }),
+        ..TaskDefinition::inline("fill-disk", "python", FILL_DISK_PY)
+    };
+    let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(!result.metadata.timed_out);
+    assert!(result.error.as_deref().unwrap_or_default().contains("disk quota"), "{:?}", result.error);
+
+    // Refused before anything runs rather than overflowing the byte count
+    let task = TaskDefinition {
+        requirements: Some(TaskRequirements { disk_mb: Some(u64::MAX), ..Default::default() }),
+        ..TaskDefinition::inline("fill-disk", "python", FILL_DISK_PY)
+    };
+    let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.error.as_deref().unwrap_or_default().contains("disk_mb"), "{:?}", result.error);
+}
@@ -0,0 +1,174 @@
+use std::path::Path;
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::dynamic_executor::{deno_permission_flags, jvm_heap_mb};
+use corebrum_examples::runners::{RunnerResult, JS_RUNNER, JULIA_RUNNER, PYTHON_RUNNER, R_RUNNER};
+use corebrum_examples::schema::{Permissions, TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::worker::Worker;
+
+fn task(language: &str, code: &str) -> TaskDefinition {
+    TaskDefinition {
+        name: "handler".to_string(),
+        description: None,
+        language: language.to_string(),
+        source: TaskSource::Inline { code: code.to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    }
+}
+
+async fn run(language: &str, code: &str, inputs: serde_json::Value) -> corebrum_examples::schema::Result {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    worker.executor().execute_task(&task(language, code), inputs).await.unwrap()
+}
+
+#[test]
+fn result_files_carry_outputs_or_an_error() {
+    let outputs: RunnerResult = serde_json::from_str(r#"{"outputs": {"answer": 42}}"#).unwrap();
+    assert!(matches!(outputs, RunnerResult::Outputs(outputs) if outputs["answer"] == serde_json::json!(42)));
+
+    let error: RunnerResult = serde_json::from_str(r#"{"error": {"kind": "contract", "message": "handler must return a dict, got list", "details": null}}"#).unwrap();
+    let RunnerResult::Error(error) = error else { panic!("expected an error") };
+    assert_eq!(error.kind, "contract");
+    assert!(error.details.is_none());
+
+    assert!(serde_json::from_str::<RunnerResult>(r#"{"answer": 42}"#).is_err());
+}
+
+#[test]
+fn python_runner_takes_script_inputs_and_result_paths() {
+    assert!(PYTHON_RUNNER.contains("script_path, inputs_path, result_path = sys.argv[1:4]"));
+    for kind in ["\"load\"", "\"handler\"", "\"contract\""] {
+        assert!(PYTHON_RUNNER.contains(kind), "no {} failures", kind);
+    }
+}
+
+#[test]
+fn js_runner_is_commonjs_and_takes_the_same_paths() {
+    // Loaded with node's CommonJS loader whatever package.json says
+    assert!(JS_RUNNER.starts_with("'use strict';"));
+    assert!(!JS_RUNNER.lines().any(|line| line.starts_with("import ") || line.starts_with("export ")));
+    assert!(JS_RUNNER.contains("const [scriptPath, inputsPath, resultPath] = process.argv.slice(2);"));
+    // Handlers exported either way are found
+    assert!(JS_RUNNER.contains("exported.handler || (exported.default && exported.default.handler)"));
+    for kind in ["'load'", "'handler'", "'contract'"] {
+        assert!(JS_RUNNER.contains(kind), "no {} failures", kind);
+    }
+}
+
+#[test]
+fn r_runner_writes_errors_the_worker_can_read() {
+    assert!(R_RUNNER.starts_with("args <- commandArgs(trailingOnly = TRUE)\nscript_path <- args[1]\ninputs_path <- args[2]\nresult_path <- args[3]\n"));
+    // Errors are written by hand so they survive a missing jsonlite; fill the
+    // template the way sprintf does and read it back
+    let template = R_RUNNER
+        .split_once("sprintf(\"")
+        .and_then(|(_, rest)| rest.split_once("\", kind"))
+        .map(|(template, _)| template.replace("\\\"", "\""))
+        .unwrap();
+    let mut filled = template;
+    for value in ["contract", "\"handler must be a function, got numeric\"", "null"] {
+        filled = filled.replacen("%s", value, 1);
+    }
+    let RunnerResult::Error(error) = serde_json::from_str(&filled).unwrap() else { panic!("expected an error: {}", filled) };
+    assert_eq!(error.kind, "contract");
+    assert_eq!(error.message, "handler must be a function, got numeric");
+    for kind in ["\"load\"", "\"handler\"", "\"contract\""] {
+        assert!(R_RUNNER.contains(kind), "no {} failures", kind);
+    }
+}
+
+#[test]
+fn julia_runner_takes_the_same_paths_and_checks_the_contract() {
+    assert!(JULIA_RUNNER.starts_with("script_path, inputs_path, result_path = ARGS[1:3]\n"));
+    // The script gets a module of its own, with `inputs` defined before it loads
+    assert!(JULIA_RUNNER.contains("Core.eval(task, :(const inputs = $inputs))"));
+    assert!(JULIA_RUNNER.contains("outputs isa AbstractDict || outputs isa NamedTuple"));
+    for kind in ["fail(\"load\"", "fail(\"handler\"", "fail(\"contract\""] {
+        assert!(JULIA_RUNNER.contains(kind), "no {} failures", kind);
+    }
+}
+
+#[test]
+fn deno_gets_only_the_permissions_a_task_declares() {
+    let workdir = Path::new("/work");
+    let result_path = Path::new("/work/corebrum-result.json");
+    let flags = deno_permission_flags(&Permissions::default(), workdir, result_path).unwrap();
+    assert_eq!(flags, ["--no-prompt", "--allow-read=/work", "--allow-write=/work/corebrum-result.json"]);
+
+    let permissions = Permissions {
+        net: vec!["api.example.com".to_string(), "localhost:8080".to_string()],
+        env: vec!["HOME".to_string()],
+        write: true,
+    };
+    let flags = deno_permission_flags(&permissions, workdir, result_path).unwrap();
+    assert_eq!(flags, ["--no-prompt", "--allow-read=/work", "--allow-write=/work", "--allow-net=api.example.com,localhost:8080", "--allow-env=HOME"]);
+
+    // Entries that would widen or smuggle a permission
+    for net in ["", "a.com,b.com", "a.com --allow-all", "=a.com"] {
+        let permissions = Permissions { net: vec![net.to_string()], ..Default::default() };
+        assert!(deno_permission_flags(&permissions, workdir, result_path).is_err(), "{:?} was accepted", net);
+    }
+    let permissions = Permissions { env: vec![String::new()], ..Default::default() };
+    assert!(deno_permission_flags(&permissions, workdir, result_path).is_err());
+}
+
+#[test]
+fn jvm_heaps_leave_room_for_the_rest_of_the_jvm() {
+    assert_eq!(jvm_heap_mb(1024), 768);
+    assert_eq!(jvm_heap_mb(100), 75);
+    // Never below what the JVM needs to start
+    assert_eq!(jvm_heap_mb(16), 32);
+    assert_eq!(jvm_heap_mb(0), 32);
+    assert_eq!(jvm_heap_mb(u64::MAX), u64::MAX / 4);
+}
+
+#[tokio::test]
+async fn handlers_get_inputs_and_return_outputs() {
+    let result = run("python", "def handler(inputs):\n    return {\"doubled\": inputs[\"n\"] * 2}\n", serde_json::json!({"n": 21})).await;
+    assert_eq!(result.status, TaskStatus::Completed, "{:?}", result.error);
+    assert_eq!(result.outputs["doubled"], serde_json::json!(42));
+
+    // Scripts without a handler still print their outputs
+    let result = run("python", "import json\nprint(json.dumps({\"n\": inputs[\"n\"]}))\n", serde_json::json!({"n": 7})).await;
+    assert_eq!(result.status, TaskStatus::Completed, "{:?}", result.error);
+    assert_eq!(result.outputs["n"], serde_json::json!(7));
+}
+
+#[tokio::test]
+async fn broken_handlers_fail_with_the_reason() {
+    let cases = [
+        ("def handler(inputs):\n    return [1, 2]\n", "broke the handler contract: handler must return a dict, got list"),
+        ("handler = 3\n", "broke the handler contract: `handler` must be a function, got int"),
+        ("def handler(inputs):\n    return {\"when\": object()}\n", "broke the handler contract: handler returned outputs that are not JSON serializable"),
+        ("def handler(inputs):\n    raise ValueError(\"bad n\")\n", "Python task failed: handler raised ValueError: bad n"),
+        ("import no_such_module\n", "Python task failed: loading the script raised ModuleNotFoundError"),
+    ];
+    for (code, expected) in cases {
+        let result = run("python", code, serde_json::json!({})).await;
+        assert_eq!(result.status, TaskStatus::Failed);
+        assert!(result.error.as_deref().unwrap_or_default().contains(expected), "{:?} for {:?}", result.error, code);
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs Node.js"]
+async fn broken_js_handlers_fail_with_the_reason() {
+    let cases = [
+        ("exports.handler = async (inputs) => ({ doubled: inputs.n * 2 });\n", None),
+        ("exports.handler = () => [1, 2];\n", Some("broke the handler contract: handler must return an object, got array")),
+        ("exports.handler = 3;\n", Some("broke the handler contract: handler must be a function, got number")),
+        ("exports.handler = () => { throw new RangeError('bad n'); };\n", Some("JavaScript task failed: handler threw RangeError: bad n")),
+    ];
+    for (code, expected) in cases {
+        let result = run("javascript", code, serde_json::json!({"n": 21})).await;
+        match expected {
+            None => assert_eq!(result.outputs["doubled"], serde_json::json!(42), "{:?}", result.error),
+            Some(expected) => {
+                assert_eq!(result.status, TaskStatus::Failed);
+                assert!(result.error.as_deref().unwrap_or_default().contains(expected), "{:?} for {:?}", result.error, code);
+            }
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, TimeZone, Utc};
+use proptest::collection::{btree_map, hash_map, vec};
+use proptest::option;
+use proptest::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use corebrum_examples::schema::{
+    AggFunc, Aggregation, Assign, AuditEntry, Claim, Comparison, DatasetFormat, DefinitionPin, Determinism, ExecutionMetadata, FrameOperation, ImageOutput,
+    ImageStep, InputCheck, InputType, InputsRef, Isolation, Job, JsRuntime, Permissions, Provenance, ResizeFilter, ResourceUsage, Result as TaskResult, Status,
+    StreamInputs, SyncPolicy, TaskDefinition, TaskInput, TaskOutput, TaskRequirements, TaskSource, TaskStatus, TensorLayout,
+};
+use corebrum_examples::zenoh_utils::Codec;
+
+// Every wire format a message may travel in; each must give back an equal value
+fn assert_round_trips<T>(value: &T)
+where
+    T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug,
+{
+    let bytes = serde_json::to_vec(value).unwrap();
+    let decoded: T = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(&decoded, value, "JSON: {}", String::from_utf8_lossy(&bytes));
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9_-]{0,11}"
+}
+
+fn timestamp() -> impl Strategy<Value = DateTime<Utc>> {
+    (0i64..4_102_444_800, 0u32..1_000_000_000).prop_map(|(secs, nanos)| Utc.timestamp_opt(secs, nanos).unwrap())
+}
+
+// Dyadic fractions, which survive a trip through decimal text exactly
+fn fraction() -> impl Strategy<Value = f64> {
+    (-4096i32..4096).prop_map(|n| n as f64 / 64.0)
+}
+
+fn fraction32() -> impl Strategy<Value = f32> {
+    fraction().prop_map(|f| f as f32)
+}
+
+fn json() -> impl Strategy<Value = serde_json::Value> {
+    let leaf = prop_oneof![
+        Just(serde_json::Value::Null),
+        any::<bool>().prop_map(serde_json::Value::from),
+        any::<i64>().prop_map(serde_json::Value::from),
+        fraction().prop_map(serde_json::Value::from),
+        any::<String>().prop_map(serde_json::Value::from),
+    ];
+    leaf.prop_recursive(3, 24, 4, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..4).prop_map(serde_json::Value::Array),
+            btree_map(any::<String>(), inner, 0..4).prop_map(|map| serde_json::Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+// A missing `Option<Value>` and a null one both read back as None
+fn json_not_null() -> impl Strategy<Value = serde_json::Value> {
+    json().prop_filter("null", |value| !value.is_null())
+}
+
+fn dataset_format() -> impl Strategy<Value = DatasetFormat> {
+    prop_oneof![Just(DatasetFormat::Csv), Just(DatasetFormat::Parquet), Just(DatasetFormat::Json)]
+}
+
+fn frame_operation() -> impl Strategy<Value = FrameOperation> {
+    let comparison = prop_oneof![
+        Just(Comparison::Eq),
+        Just(Comparison::Ne),
+        Just(Comparison::Gt),
+        Just(Comparison::Ge),
+        Just(Comparison::Lt),
+        Just(Comparison::Le),
+    ];
+    let func = prop_oneof![
+        Just(AggFunc::Sum),
+        Just(AggFunc::Mean),
+        Just(AggFunc::Min),
+        Just(AggFunc::Max),
+        Just(AggFunc::Count),
+        Just(AggFunc::First),
+        Just(AggFunc::Last),
+        Just(AggFunc::NUnique),
+    ];
+    let aggregation = (name(), func, option::of(name())).prop_map(|(column, func, alias)| Aggregation { column, func, alias });
+    prop_oneof![
+        vec(name(), 0..3).prop_map(|columns| FrameOperation::Select { columns }),
+        vec(name(), 0..3).prop_map(|columns| FrameOperation::Drop { columns }),
+        btree_map(name(), name(), 0..3).prop_map(|columns: BTreeMap<_, _>| FrameOperation::Rename { columns }),
+        (name(), comparison, json()).prop_map(|(column, cmp, value)| FrameOperation::Filter { column, cmp, value }),
+        vec(name(), 0..3).prop_map(|columns| FrameOperation::DropNulls { columns }),
+        (vec(name(), 0..3), vec(aggregation, 0..3)).prop_map(|(by, aggs)| FrameOperation::GroupBy { by, aggs }),
+        (vec(name(), 0..3), any::<bool>()).prop_map(|(by, descending)| FrameOperation::Sort { by, descending }),
+        any::<u32>().prop_map(|n| FrameOperation::Limit { n }),
+    ]
+}
+
+fn image_step() -> impl Strategy<Value = ImageStep> {
+    let filter = prop_oneof![
+        Just(ResizeFilter::Nearest),
+        Just(ResizeFilter::Bilinear),
+        Just(ResizeFilter::Bicubic),
+        Just(ResizeFilter::Lanczos),
+    ];
+    prop_oneof![
+        (any::<u32>(), any::<u32>(), filter).prop_map(|(width, height, filter)| ImageStep::Resize { width, height, filter }),
+        (any::<u32>(), any::<u32>(), any::<u32>(), any::<u32>()).prop_map(|(x, y, width, height)| ImageStep::Crop { x, y, width, height }),
+        (any::<u32>(), any::<u32>()).prop_map(|(width, height)| ImageStep::CenterCrop { width, height }),
+        (prop::array::uniform3(fraction32()), prop::array::uniform3(fraction32())).prop_map(|(mean, std)| ImageStep::Normalize { mean, std }),
+    ]
+}
+
+fn image_output() -> impl Strategy<Value = ImageOutput> {
+    prop_oneof![
+        Just(ImageOutput::Tensor { layout: TensorLayout::Nchw }),
+        Just(ImageOutput::Tensor { layout: TensorLayout::Nhwc }),
+        Just(ImageOutput::Png),
+        any::<u8>().prop_map(|quality| ImageOutput::Jpeg { quality }),
+    ]
+}
+
+fn task_source() -> impl Strategy<Value = TaskSource> {
+    prop_oneof![
+        any::<String>().prop_map(|code| TaskSource::Inline { code }),
+        name().prop_map(|url| TaskSource::Url { url }),
+        (name(), name(), option::of(name()), option::of(name()), any::<bool>())
+            .prop_map(|(repo, path, branch, rev, submodules)| TaskSource::Git { repo, path, branch, rev, submodules }),
+        (name(), name()).prop_map(|(id, filename)| TaskSource::Gist { id, filename }),
+        (name(), name(), name()).prop_map(|(url, sha256, entrypoint)| TaskSource::Archive { url, sha256, entrypoint }),
+        vec(any::<u8>(), 0..32).prop_map(|wasm_bytes| TaskSource::Wasm { wasm_bytes }),
+        (name(), vec(any::<String>(), 0..4)).prop_map(|(image, command)| TaskSource::Docker { image, command }),
+        (name(), name()).prop_map(|(url, sha256)| TaskSource::Plugin { url, sha256 }),
+        (name(), name(), option::of(1usize..64)).prop_map(|(url, sha256, batch_size)| TaskSource::Onnx { url, sha256, batch_size }),
+        (name(), vec(frame_operation(), 0..4), dataset_format())
+            .prop_map(|(dataset, operations, output_format)| TaskSource::DataFrame { dataset, operations, output_format }),
+        (name(), vec(image_step(), 0..4), image_output()).prop_map(|(frame, steps, output)| TaskSource::Image { frame, steps, output }),
+        name().prop_map(|name| TaskSource::Builtin { name }),
+    ]
+}
+
+fn requirements() -> impl Strategy<Value = TaskRequirements> {
+    let isolation = prop_oneof![Just(Isolation::Process), Just(Isolation::Microvm)];
+    let runtime = prop_oneof![Just(JsRuntime::Node), Just(JsRuntime::Deno)];
+    let permissions = (vec(name(), 0..3), vec(name(), 0..3), any::<bool>()).prop_map(|(net, env, write)| Permissions { net, env, write });
+    (
+        (option::of(any::<u64>()), option::of(any::<u32>()), option::of(any::<u64>()), option::of(vec(name(), 0..3)), option::of(1u32..10), option::of(name())),
+        (option::of(any::<u64>()), option::of(isolation), any::<bool>(), option::of(runtime), option::of(permissions), vec(name(), 0..3)),
+        option::of((any::<u64>(), any::<i64>()).prop_map(|(seed, clock)| Determinism { seed, clock })),
+    )
+        .prop_map(
+            |(
+                (memory_mb, cpu_cores, timeout_seconds, dependencies, max_attempts, fleet),
+                (disk_mb, isolation, install_dependencies, runtime, permissions, labels),
+                deterministic,
+            )| {
+                TaskRequirements {
+                    memory_mb,
+                    cpu_cores,
+                    timeout_seconds,
+                    dependencies,
+                    disk_mb,
+                    isolation,
+                    install_dependencies,
+                    runtime,
+                    permissions,
+                    labels,
+                    max_attempts,
+                    fleet,
+                    deterministic,
+                }
+            },
+        )
+}
+
+fn task_definition() -> impl Strategy<Value = TaskDefinition> {
+    let data_type = prop_oneof![
+        Just(InputType::Integer),
+        Just(InputType::Duration),
+        Just(InputType::Bytesize),
+        Just(InputType::Enum),
+        Just(InputType::Other("zenoh_topic".to_string())),
+    ];
+    let input = (name(), option::of(any::<String>()), any::<bool>(), option::of(json_not_null()), option::of(data_type), vec(name(), 0..3))
+        .prop_map(|(name, description, required, default_value, data_type, choices)| TaskInput {
+            name,
+            description,
+            required,
+            default_value,
+            data_type,
+            choices,
+        });
+    let output = (name(), option::of(any::<String>()), name())
+        .prop_map(|(name, description, data_type)| TaskOutput { name, description, data_type });
+    let check = (name(), option::of(any::<String>())).prop_map(|(expression, message)| InputCheck { expression, message });
+    let definition = (name(), option::of(any::<String>()), name(), task_source(), vec(input, 0..3), vec(output, 0..3));
+    (definition, option::of(requirements()), vec(check, 0..2))
+        .prop_map(|((name, description, language, source, inputs, outputs), requirements, checks)| TaskDefinition {
+            name,
+            description,
+            language,
+            source,
+            inputs,
+            outputs,
+            requirements,
+            checks,
+        })
+}
+
+fn task_status() -> impl Strategy<Value = TaskStatus> {
+    prop_oneof![
+        Just(TaskStatus::Pending),
+        Just(TaskStatus::Claimed),
+        Just(TaskStatus::Assigned),
+        Just(TaskStatus::Running),
+        Just(TaskStatus::Completed),
+        Just(TaskStatus::Failed),
+        Just(TaskStatus::Timeout),
+        Just(TaskStatus::Cancelled),
+    ]
+}
+
+prop_compose! {
+    fn job()(
+        task_id in name(),
+        queue in name(),
+        task_definition in option::of(task_definition()),
+        inputs in json(),
+        priority in option::of(any::<i32>()),
+        created_at in timestamp(),
+        timeout_seconds in option::of(any::<u64>()),
+        attempt in 1u32..10,
+        audit_trail in vec(
+            (1u32..10, option::of(name()), any::<String>(), timestamp())
+                .prop_map(|(attempt, worker_id, event, timestamp)| AuditEntry { attempt, worker_id, event, timestamp }),
+            0..3,
+        ),
+        parent_task_id in option::of(name()),
+        inputs_ref in option::of((name(), any::<u64>(), "[0-9a-f]{64}", option::of(Just(Codec::Gzip)))),
+        definition_pin in option::of((name(), "[0-9a-f]{64}")),
+        stream_inputs in option::of(stream_inputs()),
+    ) -> Job {
+        let inputs_ref = inputs_ref.map(|(key, size_bytes, sha256, codec)| InputsRef { key, size_bytes, sha256, codec });
+        let definition_pin = definition_pin.map(|(reference, sha256)| DefinitionPin { reference, sha256 });
+        Job {
+            task_id, queue, task_definition, inputs, priority, created_at, timeout_seconds, attempt, audit_trail, parent_task_id, inputs_ref,
+            definition_pin, stream_inputs,
+        }
+    }
+}
+
+fn stream_inputs() -> impl Strategy<Value = StreamInputs> {
+    let sync = prop_oneof![Just(SyncPolicy::LatestOfEach), any::<u64>().prop_map(|tolerance_ms| SyncPolicy::Aligned { tolerance_ms })];
+    (btree_map(name(), name(), 0..3), sync, option::of(any::<u64>())).prop_map(|(keys, sync, timeout_ms)| StreamInputs { keys, sync, timeout_ms })
+}
+
+prop_compose! {
+    fn claim()(
+        task_id in name(),
+        worker_id in name(),
+        claimed_at in timestamp(),
+        estimated_duration_seconds in option::of(any::<u64>()),
+        running_tasks in any::<u32>(),
+        queue_depth in any::<u32>(),
+        fleet in option::of(name()),
+        cpu_score in option::of(fraction()),
+    ) -> Claim {
+        Claim { task_id, worker_id, claimed_at, estimated_duration_seconds, running_tasks, queue_depth, fleet, cpu_score }
+    }
+}
+
+prop_compose! {
+    fn assign()(
+        task_id in name(),
+        worker_id in name(),
+        assigned_at in timestamp(),
+        task_definition in option::of(task_definition()),
+        inputs in json(),
+        attempt in 1u32..10,
+    ) -> Assign {
+        Assign { task_id, worker_id, assigned_at, task_definition, inputs, attempt }
+    }
+}
+
+prop_compose! {
+    fn status()(
+        task_id in name(),
+        worker_id in name(),
+        status in task_status(),
+        message in option::of(any::<String>()),
+        progress in option::of(fraction()),
+        timestamp in timestamp(),
+        epoch in any::<u64>(),
+        sequence in any::<u64>(),
+    ) -> Status {
+        Status { task_id, worker_id, status, message, progress, timestamp, epoch, sequence }
+    }
+}
+
+fn provenance() -> impl Strategy<Value = Provenance> {
+    (
+        (name(), option::of(name()), option::of(name()), option::of(name()), option::of(name()), option::of(name())),
+        (vec(name(), 0..3), option::of(name()), option::of(name()), option::of(name())),
+    )
+        .prop_map(
+            |((source_kind, source_url, git_commit, gist_revision, image_digest, code_sha256), (runtimes, worker_id, hostname, worker_version))| {
+                Provenance {
+                    source_kind,
+                    source_url,
+                    git_commit,
+                    gist_revision,
+                    image_digest,
+                    code_sha256,
+                    runtimes,
+                    worker_id,
+                    hostname,
+                    worker_version,
+                }
+            },
+        )
+}
+
+fn metadata() -> impl Strategy<Value = ExecutionMetadata> {
+    let resources = (any::<u64>(), fraction(), any::<u64>(), any::<u64>()).prop_map(
+        |(peak_rss_bytes, cpu_time_seconds, read_bytes, write_bytes)| ResourceUsage { peak_rss_bytes, cpu_time_seconds, read_bytes, write_bytes },
+    );
+    (option::of(any::<i32>()), option::of(any::<i32>()), any::<bool>(), option::of(resources))
+        .prop_map(|(exit_code, signal, timed_out, resources)| ExecutionMetadata { exit_code, signal, timed_out, resources })
+}
+
+prop_compose! {
+    fn result()(
+        task_id in name(),
+        worker_id in name(),
+        status in task_status(),
+        outputs in hash_map(name(), json(), 0..4),
+        error in option::of(any::<String>()),
+        execution_time_seconds in option::of(fraction()),
+        completed_at in timestamp(),
+        artifacts in hash_map(name(), any::<String>(), 0..3),
+        metadata in metadata(),
+        provenance in option::of(provenance()),
+    ) -> TaskResult {
+        TaskResult { task_id, worker_id, status, outputs, error, execution_time_seconds, completed_at, artifacts, metadata, provenance }
+    }
+}
+
+proptest! {
+    #[test]
+    fn task_definition_round_trips(value in task_definition()) {
+        assert_round_trips(&value);
+    }
+
+    #[test]
+    fn job_round_trips(value in job()) {
+        assert_round_trips(&value);
+    }
+
+    #[test]
+    fn claim_round_trips(value in claim()) {
+        assert_round_trips(&value);
+    }
+
+    #[test]
+    fn assign_round_trips(value in assign()) {
+        assert_round_trips(&value);
+    }
+
+    #[test]
+    fn status_round_trips(value in status()) {
+        assert_round_trips(&value);
+    }
+
+    #[test]
+    fn result_round_trips(value in result()) {
+        assert_round_trips(&value);
+    }
+}
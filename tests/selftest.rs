@@ -0,0 +1,57 @@
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::{TaskSource, WorkerInfo, WorkerStatus};
+use corebrum_examples::selftest::{probe_task, BackendCheck, SelfTestReport};
+use corebrum_examples::worker::Worker;
+
+fn check(backend: &str, passed: bool) -> BackendCheck {
+    BackendCheck {
+        backend: backend.to_string(),
+        passed,
+        duration_ms: 1,
+        detail: None,
+    }
+}
+
+#[test]
+fn report_is_ready_only_when_every_probe_passed() {
+    let report = SelfTestReport {
+        worker_id: "worker-1".to_string(),
+        checks: vec![check("python", true), check("docker", false)],
+    };
+    assert!(!report.is_ready());
+    assert_eq!(report.failed_backends(), vec!["docker".to_string()]);
+    assert!(report.passed("python"));
+    // Backends that weren't probed don't hold anything back
+    assert!(report.passed("r"));
+    assert!(SelfTestReport::default().is_ready());
+}
+
+#[test]
+fn probe_tasks_exist_for_runnable_backends() {
+    assert!(matches!(probe_task("python").unwrap().source, TaskSource::Inline { .. }));
+    assert!(matches!(probe_task("javascript").unwrap().source, TaskSource::Inline { .. }));
+    assert_eq!(probe_task("wasm").unwrap().backend(), "wasm");
+    assert_eq!(probe_task("expression").unwrap().backend(), "expression");
+    assert!(probe_task("docker").is_none());
+}
+
+#[test]
+fn worker_info_without_self_test_fields_still_parses() {
+    let info: WorkerInfo = serde_json::from_str(
+        r#"{"worker_id":"worker-1","capabilities":[],"status":"Available","last_heartbeat":"2024-01-01T00:00:00Z"}"#,
+    )
+    .unwrap();
+    assert_eq!(info.ready, None);
+    assert!(info.failed_probes.is_empty());
+}
+
+#[tokio::test]
+async fn worker_without_enabled_backends_is_ready() {
+    let mut config = WorkerConfig::default();
+    config.backends.enabled.clear();
+    let worker = Worker::from_config(config).unwrap();
+    let report = worker.self_test().await;
+    assert!(report.checks.is_empty());
+    assert!(report.is_ready());
+    assert_eq!(worker.info(WorkerStatus::Available).ready, Some(true));
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "sql")]
+use corebrum_examples::schema::DatasetFormat;
+use corebrum_examples::sql::{check_identifier, open_sandboxed, run_query, SqlTable};
+
+#[test]
+fn only_plain_identifiers_name_tables_and_variables() {
+    for name in ["rides", "_tmp", "Trips2024", "a_b_c"] {
+        assert!(check_identifier(name).is_ok(), "{} was refused", name);
+    }
+    for name in ["", "2024", "rides\"; DROP TABLE x; --", "my table", "café", "a-b", "../rides"] {
+        assert!(check_identifier(name).is_err(), "{:?} was accepted", name);
+    }
+}
+
+#[test]
+fn queries_see_staged_tables_and_scalar_inputs() {
+    let dir = tempfile::tempdir().unwrap();
+    let csv = dir.path().join("rides.csv");
+    std::fs::write(&csv, "city,km\nparis,3\nlyon,12\nparis,8\n").unwrap();
+    let output = dir.path().join("result.json");
+    let conn = open_sandboxed(dir.path(), Some(256), Some(1)).unwrap();
+    let tables = [SqlTable { name: "rides".to_string(), path: csv, format: DatasetFormat::Csv }];
+    let variables = [("min_km".to_string(), serde_json::json!(5)), ("city".to_string(), serde_json::json!("o'brien"))];
+    let query = "SELECT city, sum(km) AS km, getvariable('city') AS quoted FROM rides WHERE km >= getvariable('min_km') GROUP BY city ORDER BY city;";
+    run_query(&conn, &tables, &variables, query, &output, DatasetFormat::Json).unwrap();
+    let rows: serde_json::Value = serde_json::from_slice(&std::fs::read(&output).unwrap()).unwrap();
+    assert_eq!(rows, serde_json::json!([{"city": "lyon", "km": 12, "quoted": "o'brien"}, {"city": "paris", "km": 8, "quoted": "o'brien"}]));
+
+    // Input names become identifiers, so they are checked before any SQL is built
+    let bad = [("x\" = 1; --".to_string(), serde_json::json!(1))];
+    assert!(run_query(&conn, &[], &bad, "SELECT 1", &output, DatasetFormat::Json).is_err());
+}
+
+#[test]
+fn sandboxed_queries_stay_inside_the_workspace() {
+    let workdir = tempfile::tempdir().unwrap();
+    let outside = tempfile::tempdir().unwrap();
+    let secret = outside.path().join("secret.csv");
+    std::fs::write(&secret, "token\nhunter2\n").unwrap();
+    let conn = open_sandboxed(workdir.path(), None, None).unwrap();
+    let query = format!("SELECT * FROM read_csv_auto('{}')", secret.display());
+    assert!(run_query(&conn, &[], &[], &query, &workdir.path().join("result.json"), DatasetFormat::Json).is_err());
+    // Settings are locked before task SQL runs
+    assert!(conn.execute_batch("SET enable_external_access = true").is_err());
+}
@@ -0,0 +1,98 @@
+use corebrum_examples::schema::{Status, TaskStatus};
+use corebrum_examples::status::StatusAggregator;
+use TaskStatus::*;
+
+// (status, epoch, sequence, milliseconds after the first update)
+type Update = (TaskStatus, u64, u64, i64);
+
+// What apply said about each update: accepted, ignored as stale, or refused
+#[derive(Debug, PartialEq)]
+enum Applied {
+    Accepted,
+    Stale,
+    Illegal,
+}
+use Applied::*;
+
+fn status((status, epoch, sequence, at_ms): Update) -> Status {
+    let mut update = Status::new("task-1", "worker-1", status, "", 0.0);
+    update.timestamp = chrono::DateTime::from_timestamp_millis(1_700_000_000_000 + at_ms).unwrap();
+    update.epoch = epoch;
+    update.sequence = sequence;
+    update
+}
+
+#[test]
+fn updates_are_ordered_by_sequence_within_an_epoch_and_by_time_across_them() {
+    let cases: &[(&str, &[Update], &[Applied], (TaskStatus, u64, u64))] = &[
+        (
+            "in order",
+            &[(Assigned, 1, 1, 0), (Running, 1, 2, 10), (Completed, 1, 3, 20)],
+            &[Accepted, Accepted, Accepted],
+            (Completed, 1, 3),
+        ),
+        (
+            "an older sequence arriving late is stale",
+            &[(Assigned, 1, 1, 0), (Running, 1, 3, 20), (Running, 1, 2, 10)],
+            &[Accepted, Accepted, Stale],
+            (Running, 1, 3),
+        ),
+        (
+            "within an epoch the sequence beats a skewed clock",
+            &[(Running, 1, 2, 100), (Completed, 1, 3, 50)],
+            &[Accepted, Accepted],
+            (Completed, 1, 3),
+        ),
+        (
+            "a restarted publisher's sequence starts over in a new epoch",
+            &[(Assigned, 1, 5, 0), (Running, 1, 6, 10), (Assigned, 2, 1, 20), (Running, 2, 2, 30)],
+            &[Accepted, Accepted, Accepted, Accepted],
+            (Running, 2, 2),
+        ),
+        (
+            "the previous epoch's updates are stale once a newer one is seen",
+            &[(Running, 2, 1, 30), (Running, 1, 9, 10)],
+            &[Accepted, Stale],
+            (Running, 2, 1),
+        ),
+        (
+            "legacy epoch-0 updates are ordered by timestamp",
+            &[(Running, 0, 0, 10), (Assigned, 0, 0, 5), (Completed, 0, 0, 20)],
+            &[Accepted, Stale, Accepted],
+            (Completed, 0, 0),
+        ),
+        (
+            "epoch 0 never matches, even with a higher sequence",
+            &[(Running, 0, 7, 10), (Assigned, 0, 8, 5)],
+            &[Accepted, Stale],
+            (Running, 0, 7),
+        ),
+        (
+            "a newer legacy update supersedes a sequenced one",
+            &[(Running, 1, 1, 10), (Completed, 0, 0, 20)],
+            &[Accepted, Accepted],
+            (Completed, 0, 0),
+        ),
+        (
+            "terminal states are final",
+            &[(Completed, 1, 1, 0), (Running, 1, 2, 10)],
+            &[Accepted, Illegal],
+            (Completed, 1, 1),
+        ),
+    ];
+
+    for (name, updates, expected, (last_status, last_epoch, last_sequence)) in cases {
+        let mut store = StatusAggregator::new();
+        let applied: Vec<Applied> = updates
+            .iter()
+            .map(|update| match store.apply(status(update.clone())) {
+                Ok(true) => Accepted,
+                Ok(false) => Stale,
+                Err(_) => Illegal,
+            })
+            .collect();
+        assert_eq!(&applied, expected, "{}", name);
+        let latest = store.get("task-1").unwrap();
+        assert_eq!((&latest.status, latest.epoch, latest.sequence), (last_status, *last_epoch, *last_sequence), "{}", name);
+    }
+}
@@ -0,0 +1,93 @@
+use corebrum_examples::schema::{Job, Result as TaskResult, TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::subtasks::{child_jobs, merge_children, ChildOutcome, CHILDREN_OUTPUT, CHILD_JOBS_OUTPUT, MAX_CHILD_JOBS};
+
+fn parent() -> Job {
+    let task_definition = TaskDefinition {
+        name: "map".to_string(),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    Job::new_user_task("tasks".to_string(), task_definition, serde_json::json!({}))
+}
+
+fn completed(task_id: &str, outputs: serde_json::Value) -> TaskResult {
+    let mut result = TaskResult::failure(task_id.to_string(), "worker-1".to_string(), String::new());
+    result.status = TaskStatus::Completed;
+    result.error = None;
+    result.outputs = serde_json::from_value(outputs).unwrap();
+    result
+}
+
+#[test]
+fn children_inherit_the_parent_and_link_back_to_it() {
+    let parent = parent();
+    let mut result = completed(&parent.task_id, serde_json::json!({
+        CHILD_JOBS_OUTPUT: [{"inputs": {"chunk": 0}}, {"inputs": {"chunk": 1}}],
+        "total": 2,
+    }));
+    let children = child_jobs(&parent, &mut result, "worker-1").unwrap();
+    assert_eq!(children.len(), 2);
+    assert!(!result.outputs.contains_key(CHILD_JOBS_OUTPUT));
+    assert!(result.outputs.contains_key("total"));
+    for (chunk, child) in children.iter().enumerate() {
+        assert_ne!(child.task_id, parent.task_id);
+        assert_eq!(child.queue, parent.queue);
+        assert_eq!(child.parent_task_id.as_deref(), Some(parent.task_id.as_str()));
+        assert_eq!(child.task_definition, parent.task_definition);
+        assert_eq!(child.inputs, serde_json::json!({"chunk": chunk}));
+        assert_eq!(child.attempt, 1);
+        assert!(child.audit_trail[0].event.contains(&parent.task_id));
+        assert_eq!(child.audit_trail[0].worker_id.as_deref(), Some("worker-1"));
+    }
+}
+
+#[test]
+fn tasks_without_children_spawn_nothing() {
+    let parent = parent();
+    let mut result = completed(&parent.task_id, serde_json::json!({"total": 0}));
+    assert!(child_jobs(&parent, &mut result, "worker-1").unwrap().is_empty());
+}
+
+#[test]
+fn too_many_or_malformed_children_are_rejected() {
+    let parent = parent();
+    let specs = vec![serde_json::json!({"inputs": {}}); MAX_CHILD_JOBS + 1];
+    let mut result = completed(&parent.task_id, serde_json::json!({ CHILD_JOBS_OUTPUT: specs }));
+    assert!(child_jobs(&parent, &mut result, "worker-1").is_err());
+    let mut result = completed(&parent.task_id, serde_json::json!({ CHILD_JOBS_OUTPUT: "not a list" }));
+    assert!(child_jobs(&parent, &mut result, "worker-1").is_err());
+}
+
+#[test]
+fn parent_fails_when_a_child_does() {
+    let mut result = completed("parent", serde_json::json!({}));
+    merge_children(&mut result, vec![
+        ChildOutcome::from_result(&completed("child-1", serde_json::json!({"sum": 3}))),
+        ChildOutcome::lost("child-2", "Timed out waiting for result of task child-2".to_string()),
+    ]);
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.error.as_deref().unwrap().contains("child-2"));
+    let children = result.outputs[CHILDREN_OUTPUT].as_array().unwrap();
+    assert_eq!(children.len(), 2);
+    assert_eq!(children[0]["outputs"]["sum"], 3);
+}
+
+#[test]
+fn parent_completes_when_every_child_did() {
+    let mut result = completed("parent", serde_json::json!({}));
+    merge_children(&mut result, vec![ChildOutcome::from_result(&completed("child-1", serde_json::json!({})))]);
+    assert_eq!(result.status, TaskStatus::Completed);
+    assert_eq!(result.outputs[CHILDREN_OUTPUT][0]["task_id"], "child-1");
+}
+
+#[test]
+fn jobs_without_a_parent_still_parse() {
+    let job: Job = serde_json::to_value(parent()).and_then(serde_json::from_value).unwrap();
+    assert_eq!(job.parent_task_id, None);
+    assert!(!serde_json::to_string(&job).unwrap().contains("parent_task_id"));
+}
@@ -0,0 +1,58 @@
+use corebrum_examples::schema::{Result as TaskResult, TaskStatus};
+use corebrum_examples::task_archive::{ArchiveFormat, ArchivedTask, TaskArchive};
+use std::path::Path;
+
+fn archived(task_id: &str) -> ArchivedTask {
+    let mut result = TaskResult::failure(task_id.to_string(), "worker-1".to_string(), "boom".to_string());
+    result.execution_time_seconds = Some(0.25);
+    let finished_at = result.completed_at;
+    ArchivedTask {
+        task_id: task_id.to_string(),
+        queue: "user_tasks".to_string(),
+        attempt: 1,
+        status: TaskStatus::Failed,
+        worker_id: Some("worker-1".to_string()),
+        task_definition: None,
+        inputs: serde_json::json!({"n": 5}),
+        result: Some(result),
+        submitted_at: Some(finished_at - chrono::Duration::seconds(2)),
+        started_at: None,
+        finished_at,
+    }
+}
+
+#[test]
+fn jsonl_archive_appends_across_opens() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("tasks.jsonl");
+    {
+        let mut archive = TaskArchive::open(&path).unwrap();
+        archive.append(archived("task-1")).unwrap();
+    }
+    let mut archive = TaskArchive::open(&path).unwrap();
+    archive.append(archived("task-2")).unwrap();
+    archive.flush().unwrap();
+
+    let tasks = ArchivedTask::read_jsonl(&path).unwrap();
+    assert_eq!(tasks, vec![archived_like(&tasks[0], "task-1"), archived_like(&tasks[1], "task-2")]);
+    assert_eq!(tasks[0].total_seconds(), Some(2.0));
+    assert_eq!(tasks[0].execution_seconds(), Some(0.25));
+}
+
+// The fixture's timestamps differ between calls, so compare against the read-back ones
+fn archived_like(read: &ArchivedTask, task_id: &str) -> ArchivedTask {
+    let mut expected = archived(task_id);
+    expected.finished_at = read.finished_at;
+    expected.submitted_at = read.submitted_at;
+    if let (Some(expected), Some(read)) = (&mut expected.result, &read.result) {
+        expected.completed_at = read.completed_at;
+    }
+    expected
+}
+
+#[test]
+fn format_follows_the_extension() {
+    assert_eq!(ArchiveFormat::from_path(Path::new("out/tasks.jsonl")).unwrap(), ArchiveFormat::Jsonl);
+    assert_eq!(ArchiveFormat::from_path(Path::new("tasks.parquet")).unwrap(), ArchiveFormat::Parquet);
+    assert!(ArchiveFormat::from_path(Path::new("tasks.csv")).is_err());
+}
@@ -0,0 +1,49 @@
+use corebrum_examples::schema::{Status, TaskFilter, TaskListRequest, TaskStatus};
+use corebrum_examples::status::StatusAggregator;
+
+fn store() -> StatusAggregator {
+    let mut store = StatusAggregator::new();
+    for i in 0..25 {
+        let worker_id = if i % 2 == 0 { "worker-even" } else { "worker-odd" };
+        let status = if i < 10 { TaskStatus::Running } else { TaskStatus::Assigned };
+        store.apply(Status::new(&format!("task-{:02}", i), worker_id, status, "", 0.0)).unwrap();
+    }
+    store
+}
+
+#[test]
+fn pages_cover_every_task_once_in_id_order() {
+    let store = store();
+    let mut request = TaskListRequest { page_size: 10, ..TaskListRequest::default() };
+    let mut seen = Vec::new();
+    loop {
+        let page = store.list(&request);
+        seen.extend(page.tasks.into_iter().map(|task| task.task_id));
+        match page.next_page_token {
+            Some(token) => request.page_token = Some(token),
+            None => break,
+        }
+    }
+
+    let expected: Vec<String> = (0..25).map(|i| format!("task-{:02}", i)).collect();
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn filters_apply_before_paging() {
+    let store = store();
+    let request = TaskListRequest {
+        filter: TaskFilter {
+            statuses: vec![TaskStatus::Running],
+            worker_id: Some("worker-odd".to_string()),
+            ..TaskFilter::default()
+        },
+        page_size: 5,
+        ..TaskListRequest::default()
+    };
+    let page = store.list(&request);
+
+    let ids: Vec<&str> = page.tasks.iter().map(|task| task.task_id.as_str()).collect();
+    assert_eq!(ids, ["task-01", "task-03", "task-05", "task-07", "task-09"]);
+    assert_eq!(page.next_page_token, None);
+}
@@ -0,0 +1,97 @@
+use corebrum_examples::schema::{Job, Status, TaskDefinition, TaskSource, TaskStatus};
+use corebrum_examples::status::StatusAggregator;
+
+fn job(task_id: &str, parent: Option<&str>) -> Job {
+    let task_definition = TaskDefinition {
+        name: format!("task {}", task_id),
+        description: None,
+        language: "python".to_string(),
+        source: TaskSource::Inline { code: "def handler(inputs):\n    return {}\n".to_string() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: None,
+        checks: Vec::new(),
+    };
+    let mut job = Job::new_user_task("tasks".to_string(), task_definition, serde_json::json!({}));
+    job.task_id = task_id.to_string();
+    job.parent_task_id = parent.map(str::to_string);
+    job
+}
+
+fn run(store: &mut StatusAggregator, task_id: &str, outcome: TaskStatus) {
+    store.apply(Status::new(task_id, "worker-1", TaskStatus::Running, "", 0.3)).unwrap();
+    store.apply(Status::new(task_id, "worker-1", outcome, "", 1.0)).unwrap();
+}
+
+#[test]
+fn delegated_children_hang_off_their_parent() {
+    let mut store = StatusAggregator::new();
+    store.record_job(&job("map", None));
+    store.record_job(&job("chunk-a", Some("map")));
+    store.record_job(&job("chunk-b", Some("map")));
+    store.record_job(&job("chunk-a1", Some("chunk-a")));
+    run(&mut store, "map", TaskStatus::Completed);
+    run(&mut store, "chunk-a", TaskStatus::Completed);
+    run(&mut store, "chunk-b", TaskStatus::Failed);
+
+    // Any task of the tree returns the whole of it
+    let tree = store.tree("chunk-a1").unwrap();
+    assert!(!tree.truncated);
+    assert_eq!(tree.root.task_id, "map");
+    assert_eq!(tree.root.name.as_deref(), Some("task map"));
+    assert_eq!(tree.root.status, Some(TaskStatus::Completed));
+    assert!(tree.root.duration_seconds().unwrap() >= 0.0);
+    let children: Vec<&str> = tree.root.children.iter().map(|child| child.task_id.as_str()).collect();
+    assert_eq!(children, vec!["chunk-a", "chunk-b"]);
+    assert_eq!(tree.root.children[1].status, Some(TaskStatus::Failed));
+    let grandchild = &tree.root.children[0].children[0];
+    assert_eq!(grandchild.task_id, "chunk-a1");
+    assert_eq!(grandchild.status, None);
+    assert_eq!(grandchild.started_at, None);
+    assert_eq!(tree.root.nodes().len(), 4);
+}
+
+#[test]
+fn only_announced_parents_make_a_tree() {
+    let mut store = StatusAggregator::new();
+    // IDs that look like numbered steps say nothing about where a task came from
+    for step in 0..3 {
+        run(&mut store, &format!("pipeline-{}", step), TaskStatus::Completed);
+    }
+    let tree = store.tree("pipeline-1").unwrap();
+    assert_eq!(tree.root.task_id, "pipeline-1");
+    assert!(tree.root.children.is_empty());
+    assert!(store.tree("pipeline").is_none());
+
+    store.record_job(&job("pipeline-2", Some("pipeline-1")));
+    let tree = store.tree("pipeline-2").unwrap();
+    assert_eq!(tree.root.task_id, "pipeline-1");
+    assert_eq!(tree.root.nodes().len(), 2);
+}
+
+#[test]
+fn unknown_tasks_have_no_tree() {
+    assert!(StatusAggregator::new().tree("missing").is_none());
+}
+
+#[test]
+fn parent_loops_do_not_hang() {
+    let mut store = StatusAggregator::new();
+    store.record_job(&job("a", Some("b")));
+    store.record_job(&job("b", Some("a")));
+    let tree = store.tree("a").unwrap();
+    assert_eq!(tree.root.nodes().len(), 2);
+}
+
+#[test]
+fn dot_output_has_a_node_per_task_and_an_edge_per_child() {
+    let mut store = StatusAggregator::new();
+    store.record_job(&job("map", None));
+    store.record_job(&job("chunk \"a\"", Some("map")));
+    run(&mut store, "map", TaskStatus::Completed);
+    let dot = store.tree("map").unwrap().to_dot();
+    assert!(dot.starts_with("digraph tasks {"));
+    assert!(dot.contains("\"map\" -> \"chunk \\\"a\\\"\";"));
+    assert!(dot.contains("Completed in"));
+    assert_eq!(dot.matches("[label=").count(), 2);
+}
@@ -0,0 +1,25 @@
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::verbosity::Verbosity;
+
+#[test]
+fn library_components_are_quiet_by_default() {
+    assert_eq!(Verbosity::default(), Verbosity::Off);
+    assert_eq!(WorkerConfig::default().verbosity, Verbosity::Off);
+    assert!(!Verbosity::Off.shows_summary());
+}
+
+#[test]
+fn debug_includes_summary_output() {
+    assert!(Verbosity::Summary.shows_summary());
+    assert!(!Verbosity::Summary.shows_debug());
+    assert!(Verbosity::Debug.shows_summary());
+    assert!(Verbosity::Debug.shows_debug());
+}
+
+#[test]
+fn verbosity_is_read_from_worker_config() {
+    let config: WorkerConfig = toml::from_str("worker_id = \"worker-1\"\nverbosity = \"debug\"\n").unwrap();
+    assert_eq!(config.verbosity, Verbosity::Debug);
+    assert_eq!("quiet".parse::<Verbosity>().unwrap(), Verbosity::Off);
+    assert!("loud".parse::<Verbosity>().is_err());
+}
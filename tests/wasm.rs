@@ -0,0 +1,41 @@
+#![cfg(feature = "wasm")]
+use corebrum_examples::config::WorkerConfig;
+use corebrum_examples::schema::{TaskDefinition, TaskRequirements, TaskSource, TaskStatus};
+use corebrum_examples::worker::Worker;
+
+// Modules as WAT text, which wasmtime compiles just like a binary
+fn wasm_task(wat: &str, requirements: TaskRequirements) -> TaskDefinition {
+    TaskDefinition {
+        name: "wasm-guest".to_string(),
+        description: None,
+        language: "wasm".to_string(),
+        source: TaskSource::Wasm { wasm_bytes: wat.as_bytes().to_vec() },
+        inputs: Vec::new(),
+        outputs: Vec::new(),
+        requirements: Some(requirements),
+        checks: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn looping_guests_are_stopped_at_the_timeout() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    let spin = r#"(module (func (export "_start") (loop $spin (br $spin))))"#;
+    let task = wasm_task(spin, TaskRequirements { timeout_seconds: Some(1), ..Default::default() });
+    let started = std::time::Instant::now();
+    let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.metadata.timed_out);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+}
+
+#[tokio::test]
+async fn guests_cannot_grow_past_the_memory_limit() {
+    let worker = Worker::from_config(WorkerConfig::default()).unwrap();
+    // 1024 pages of 64KiB is 64MB
+    let grow = r#"(module (memory 1) (func (export "_start") (drop (memory.grow (i32.const 1024)))))"#;
+    let task = wasm_task(grow, TaskRequirements { memory_mb: Some(16), ..Default::default() });
+    let result = worker.executor().execute_task(&task, serde_json::json!({})).await.unwrap();
+    assert_eq!(result.status, TaskStatus::Failed);
+    assert!(result.error.unwrap().contains("trapped"));
+}
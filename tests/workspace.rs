@@ -0,0 +1,34 @@
+use corebrum_examples::verbosity::Verbosity;
+use corebrum_examples::workspace::{safe_file_name, sweep_stale_workspaces, Workspace};
+
+#[cfg(unix)]
+#[test]
+fn sweeps_remove_only_workspaces_of_dead_or_earlier_processes() {
+    let root = tempfile::tempdir().unwrap();
+    let ours = Workspace::create_in(root.path()).unwrap();
+    let pid = std::process::id();
+    // An earlier process that had our PID, one long gone, and pid 1 which is alive
+    let stale = [format!("corebrum-task-{}-0badf00d-a1b2c3", pid), "corebrum-task-4194305-0badf00d-a1b2c3".to_string()];
+    let alive = root.path().join("corebrum-task-1-0badf00d-a1b2c3");
+    for name in &stale {
+        std::fs::create_dir(root.path().join(name)).unwrap();
+    }
+    std::fs::create_dir(&alive).unwrap();
+    let unrelated = root.path().join("notes");
+    std::fs::create_dir(&unrelated).unwrap();
+
+    assert_eq!(sweep_stale_workspaces(root.path(), Verbosity::default()).unwrap(), 2);
+    assert!(ours.path().exists());
+    assert!(alive.exists());
+    assert!(unrelated.exists());
+    assert!(stale.iter().all(|name| !root.path().join(name).exists()));
+}
+
+#[test]
+fn file_names_cannot_leave_their_directory() {
+    assert_eq!(safe_file_name("trips").unwrap(), "trips");
+    assert_eq!(safe_file_name("trips.2024").unwrap(), "trips.2024");
+    for name in ["", ".", "..", "../../x", "/etc/foo", "data/trips", "trips/", "..\\x"] {
+        assert!(safe_file_name(name).is_err(), "{:?}", name);
+    }
+}